@@ -1,15 +1,24 @@
-use clap::{ArgAction, Parser, Subcommand};
+use anyhow::Context;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use indicatif::ProgressStyle;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::path::PathBuf;
-use tracing::info;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
 use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::EnvFilter;
 
-use fractal_image::image::Image;
-use fractal_image::model::Compressed;
-use fractal_image::preprocessing::{SafeableImage, SquaredGrayscaleImage};
-use fractal_image::{compress, decompress};
+use fractal_image::compress::Compressor;
+use fractal_image::image::{Image, IntoOwnedImage, OwnedImage, PadMode, Size};
+use fractal_image::model::{Compressed, PartitionStats};
+use fractal_image::persistence::dump::DumpFormat;
+use fractal_image::preprocessing::{FromDynamicImage, LoadOptions, LumaWeights, SafeableImage, Sizing, SquaredGrayscaleImage};
+use image::imageops::FilterType;
+use image::ImageFormat;
+use fractal_image::{compress, decompress, metrics, visualize};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -18,6 +27,129 @@ struct Cli {
     command: Commands,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Algorithm {
+    /// Recursively splits blocks into four quadrants until a mapping is found
+    Quadtree,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Preset {
+    /// Compresses quickly at the cost of quality
+    Fast,
+    /// Balances compression speed and quality
+    Balanced,
+    /// Compresses with the best quality at the cost of speed
+    Best,
+}
+
+impl From<Preset> for compress::quadtree::CompressionPreset {
+    fn from(value: Preset) -> Self {
+        match value {
+            Preset::Fast => compress::quadtree::CompressionPreset::Fast,
+            Preset::Balanced => compress::quadtree::CompressionPreset::Balanced,
+            Preset::Best => compress::quadtree::CompressionPreset::Best,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SizingMode {
+    /// Downscales the image to the largest square power of two fitting into it
+    Downscale,
+    /// Pads the image to the next square power of two by repeating its edges, keeping every pixel
+    Pad,
+    /// Keeps the largest square power of two in the center of the image
+    Crop,
+}
+
+impl From<SizingMode> for Sizing {
+    fn from(value: SizingMode) -> Self {
+        match value {
+            SizingMode::Downscale => Sizing::DownscaleToPreviousPow2,
+            SizingMode::Pad => Sizing::PadToNextPow2(PadMode::Edge),
+            SizingMode::Crop => Sizing::CenterCropToPreviousPow2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Lanczos3,
+    Gaussian,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(value: ResizeFilter) -> Self {
+        match value {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Luma {
+    /// The weights of ITU-R BT.601, as used by NTSC
+    Ntsc,
+    /// The weights of ITU-R BT.709, which match sRGB
+    Bt709,
+    /// The mean of the red, green and blue channel
+    Average,
+}
+
+impl From<Luma> for LumaWeights {
+    fn from(value: Luma) -> Self {
+        match value {
+            Luma::Ntsc => LumaWeights::Ntsc,
+            Luma::Bt709 => LumaWeights::Bt709,
+            Luma::Average => LumaWeights::Average,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Webp,
+}
+
+impl From<OutputFormat> for ImageFormat {
+    fn from(value: OutputFormat) -> Self {
+        match value {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::Webp => ImageFormat::WebP,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ListingFormat {
+    /// Aligned columns
+    Table,
+    /// Comma separated values, which can be edited
+    Csv,
+}
+
+impl From<ListingFormat> for DumpFormat {
+    fn from(value: ListingFormat) -> Self {
+        match value {
+            ListingFormat::Table => DumpFormat::Table,
+            ListingFormat::Csv => DumpFormat::Csv,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     Compress {
@@ -28,6 +160,9 @@ enum Commands {
         #[arg(short, long, action = ArgAction::SetTrue, help = "Reports progress" , default_value_t = false)]
         progress: bool,
 
+        #[arg(short, long, action = ArgAction::SetTrue, help = "Prints statistics after compressing", default_value_t = false)]
+        stats: bool,
+
         #[arg(
             short,
             long,
@@ -35,8 +170,38 @@ enum Commands {
             help = "Sets the root mean squared error threshold for acceptable block mappings"
         )]
         rms_error_threshold: Option<f64>,
+
+        #[arg(
+            short,
+            long,
+            required = false,
+            conflicts_with = "rms_error_threshold",
+            help = "Searches for an error threshold such that the compressed file has roughly this size in bytes"
+        )]
+        target_size: Option<u64>,
+
+        #[arg(short, long, value_enum, default_value_t = Algorithm::Quadtree, help = "The compression algorithm")]
+        algorithm: Algorithm,
+
+        #[arg(long, value_enum, required = false, help = "Trades compression speed for quality, other options take precedence")]
+        preset: Option<Preset>,
+
+        #[arg(long, required = false, help = "Saves the image with the boundaries of the range blocks drawn onto it as PNG")]
+        debug_partition: Option<PathBuf>,
+
+        #[arg(long, value_enum, default_value_t = SizingMode::Downscale, help = "How the image is turned into a square whose size is a power of two")]
+        sizing: SizingMode,
+
+        #[arg(long, value_enum, default_value_t = ResizeFilter::Gaussian, help = "The filter with which the image is downscaled")]
+        filter: ResizeFilter,
+
+        #[arg(long, value_enum, default_value_t = Luma::Ntsc, help = "The weights with which colors are converted to gray")]
+        luma: Luma,
+
+        #[arg(long, required = false, help = "Downscales the image to at most this width and height, which needs to be a power of two")]
+        max_size: Option<u32>,
     },
-    /// Decompresses a compressed image as a PNG file.
+    /// Decompresses a compressed image.
     Decompress {
         /// The path (including a file name) of the compressed image.
         input_path: PathBuf,
@@ -51,7 +216,112 @@ enum Commands {
         /// Stores the intermediate decompression results for each iteration.
         #[arg(short, long, default_value_t = false)]
         keep: bool,
+
+        /// Decompresses the image at this multiple of its size. Needs to be a power of two.
+        #[arg(long, default_value_t = 1)]
+        scale: u32,
+
+        /// The image format of the decompressed image and of the intermediate results.
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Png)]
+        format: OutputFormat,
     },
+    /// Prints how the range blocks of a compressed image partition it.
+    Info {
+        /// The path of the compressed image.
+        input_path: PathBuf,
+
+        /// Prints the information as a JSON object.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Prints the transformations of a compressed image, one per line.
+    Dump {
+        /// The path of the compressed image.
+        input_path: PathBuf,
+
+        /// The format of the listing.
+        #[arg(short, long, value_enum, default_value_t = ListingFormat::Table)]
+        format: ListingFormat,
+    },
+    /// Prints quality metrics of an image compared to the original.
+    Compare {
+        /// The path of the original image.
+        original_path: PathBuf,
+
+        /// The path of the image to compare, such as a decompressed image. Files which are no
+        /// images are decompressed.
+        image_path: PathBuf,
+
+        /// Saves the RMS error per block as a grayscale PNG, where the worst block is white.
+        #[arg(long, required = false)]
+        heatmap: Option<PathBuf>,
+
+        /// The size of the blocks of the heatmap.
+        #[arg(long, default_value_t = 8)]
+        heatmap_block_size: u32,
+
+        /// Uses the range blocks of this compressed file as the blocks of the heatmap.
+        #[arg(long, required = false, conflicts_with = "heatmap_block_size")]
+        partition: Option<PathBuf>,
+
+        /// Also prints the square window of this size with the largest error, to find localized
+        /// artifacts which barely affect the metrics of the whole image.
+        #[arg(long, required = false)]
+        worst_window: Option<u32>,
+
+        /// Fails if the PSNR is below this value in dB, such as for quality gates in CI.
+        #[arg(long, required = false)]
+        min_psnr: Option<f64>,
+    },
+}
+
+/// Reads an image, or decompresses a compressed image if the extension of `path` is no image
+/// format.
+fn read_grayscale(path: &PathBuf) -> anyhow::Result<OwnedImage> {
+    if ImageFormat::from_path(path).is_err() {
+        let compressed = Compressed::read_from_path(path)
+            .with_context(|| format!("Could not read the compressed file {:?}", path))?;
+        return Ok(decompress::decompress(compressed, decompress::Options::default())?.image);
+    }
+    let image = image::open(path).with_context(|| format!("Could not read the image {:?}", path))?;
+    Ok(OwnedImage::from_dynamic_image(&image)?)
+}
+
+/// The information printed by `info --json`. Fields which are unknown without transformations
+/// are `null`.
+#[derive(Serialize)]
+struct Info {
+    width: u32,
+    height: u32,
+    file_size: u64,
+    transformations: usize,
+    /// The amount of range blocks by their block size
+    blocks_per_size: BTreeMap<u32, usize>,
+    min_block_size: Option<u32>,
+    max_block_size: Option<u32>,
+    mean_block_size: Option<f64>,
+    quadtree_depth: Option<u32>,
+    covered_fraction: f64,
+    /// The amount of pairs of overlapping range blocks
+    overlaps: usize,
+}
+
+impl Info {
+    fn new(size: Size, file_size: u64, stats: &PartitionStats) -> Self {
+        Self {
+            width: size.get_width(),
+            height: size.get_height(),
+            file_size,
+            transformations: stats.transformations,
+            blocks_per_size: stats.blocks_per_size.iter().copied().collect(),
+            min_block_size: stats.min_block_size,
+            max_block_size: stats.max_block_size,
+            mean_block_size: stats.mean_block_size,
+            quadtree_depth: stats.quadtree_depth,
+            covered_fraction: stats.covered_fraction,
+            overlaps: stats.overlaps.len(),
+        }
+    }
 }
 
 fn main() -> anyhow::Result<()> {
@@ -67,43 +337,81 @@ fn main() -> anyhow::Result<()> {
             input_path,
             output_path,
             progress,
+            stats,
             rms_error_threshold,
+            target_size,
+            algorithm,
+            preset,
+            debug_partition,
+            sizing,
+            filter,
+            luma,
+            max_size,
         } => {
-            let image = SquaredGrayscaleImage::read_from(&input_path);
+            let options = LoadOptions {
+                sizing: sizing.into(),
+                filter: filter.into(),
+                luma: luma.into(),
+                max_dimension: max_size,
+                ..Default::default()
+            };
+            let loaded = SquaredGrayscaleImage::read_with(&input_path, options)
+                .with_context(|| format!("Could not read the image {:?}", input_path))?;
+            if loaded.original_size != loaded.image.get_size() {
+                info!("Resized the image from {} to {}", loaded.original_size, loaded.image.get_size());
+            }
+            let image = loaded.image;
             info!("Image width: {}", image.get_width());
             info!("Image height: {}", image.get_height());
+            let original = debug_partition.as_ref().map(|_| image.to_owned_image());
+
+            let mut compressor: Box<dyn Compressor> = match algorithm {
+                Algorithm::Quadtree => {
+                    let compressor = compress::quadtree::Compressor::new(image);
+                    let compressor = if let Some(preset) = preset {
+                        compressor.with_preset(preset.into())
+                    } else {
+                        compressor
+                    };
+                    let compressor = if let Some(target_size) = target_size {
+                        compressor.with_target_size_bytes(target_size)
+                    } else {
+                        compressor
+                    };
+                    Box::new(compressor)
+                }
+            };
 
-            let compressor = compress::quadtree::Compressor::new(image);
-            let compressor = if progress {
+            if progress {
                 let progress_bar = indicatif::ProgressBar::new(100)
                     .with_message("Mapping blocks")
                     .with_style(ProgressStyle::with_template("{spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {human_pos}/{human_len}")
                         .unwrap()
                         .progress_chars("#>-"));
 
-                compressor.with_progress_reporter(move |progress| {
+                compressor.set_progress_reporter_throttled(Arc::new(move |progress| {
                     progress_bar.set_length(progress.total_area as u64);
                     if progress.finished() {
                         progress_bar.finish();
                     }
                     progress_bar.set_position(progress.area_covered as u64)
-                })
-            } else {
-                compressor
-            };
+                }), Duration::from_millis(50));
+            }
 
-            let compressor = if let Some(rms_error_threshold) = rms_error_threshold {
-                compressor.with_error_threshold(
+            if let Some(rms_error_threshold) = rms_error_threshold {
+                compressor.set_error_threshold(
                     compress::quadtree::ErrorThreshold::AnyBlockBelowRms(rms_error_threshold),
-                )
-            } else {
-                compressor
-            };
+                );
+            }
 
-            let compressed = compressor.compress()?;
+            let (compressed, compression_stats) = compressor.compress_with_stats()?;
+            if progress || stats {
+                println!("{}", compression_stats);
+                println!("{}", compressed.partition_stats());
+            }
 
             let size_of_file = compressed
-                .persist_as_binary_v1(&output_path)
+                .persist_as_binary_v2(&output_path)
                 .expect("Could not save compression");
 
             info!(
@@ -111,6 +419,13 @@ fn main() -> anyhow::Result<()> {
                 indicatif::HumanBytes(size_of_file)
             );
 
+            if let (Some(path), Some(original)) = (debug_partition, original) {
+                visualize::draw_partition(&original, &compressed)
+                    .save_image_as_png(&path)
+                    .with_context(|| format!("Could not save the partition to {:?}", path))?;
+                info!("Saved the partition to {:?}", path);
+            }
+
             Ok(())
         }
         Commands::Decompress {
@@ -118,45 +433,138 @@ fn main() -> anyhow::Result<()> {
             output_path,
             iterations,
             keep,
+            scale,
+            format,
         } => {
-            let compressed =
-                Compressed::read_from_binary_v1(&input_path).expect("Could not read compressed file");
-            let decompressed = decompress::decompress(
-                compressed,
-                decompress::Options {
-                    iterations,
-                    keep_each_iteration: keep,
-                },
-            );
+            let format = ImageFormat::from(format);
+            let compressed = Compressed::read_from_path(&input_path)
+                .with_context(|| format!("Could not read the compressed file {:?}", input_path))?;
 
-            if let Some(iterations) = &decompressed.iterations {
+            let on_iteration = keep.then(|| {
                 let original_file_name = output_path
                     .file_stem()
                     .unwrap_or(OsStr::new("decompressed"))
                     .to_str()
-                    .expect("Unable to process this file name");
+                    .expect("Unable to process this file name")
+                    .to_owned();
                 let extension = output_path
                     .extension()
-                    .unwrap_or(OsStr::new("png"))
+                    .unwrap_or(OsStr::new(format.extensions_str()[0]))
                     .to_str()
-                    .expect("Unable to process this file extension");
-                iterations
-                    .iter()
-                    .enumerate()
-                    .map(|(index, image)| {
-                        (
-                            format!("{}.{}.{}", original_file_name, index, extension),
-                            image,
-                        )
-                    })
-                    .map(|(new_file_name, image)| {
-                        (output_path.with_file_name(new_file_name), image)
-                    })
-                    .for_each(|(new_file_path, image)| image.save_image_as_png(&new_file_path))
+                    .expect("Unable to process this file extension")
+                    .to_owned();
+                let output_path = output_path.clone();
+                Box::new(move |index: u8, image: &OwnedImage| {
+                    let new_file_name = format!("{}.{}.{}", original_file_name, index, extension);
+                    let path = output_path.with_file_name(new_file_name);
+                    if let Err(error) = image.save_image(&path, format) {
+                        warn!("Could not save iteration {} to {:?}: {}", index, path, error);
+                    }
+                }) as decompress::IterationCallback
+            });
+
+            let decompressed = decompress::decompress_scaled(
+                compressed,
+                decompress::Options {
+                    iterations,
+                    on_iteration,
+                    track_coverage: true,
+                    ..Default::default()
+                },
+                scale,
+            )?;
+
+            if let Some(coverage) = decompressed.coverage.as_ref().filter(|coverage| !coverage.is_complete()) {
+                warn!(
+                    "{} pixels ({:.2}%) are not covered by any block and keep their initial value",
+                    coverage.uncovered_pixels(),
+                    100.0 * (1.0 - coverage.ratio())
+                );
+            }
+
+            decompressed.save(&output_path, format)?;
+
+            Ok(())
+        }
+        Commands::Info { input_path, json } => {
+            let compressed = Compressed::read_from_path(&input_path)
+                .with_context(|| format!("Could not read the compressed file {:?}", input_path))?;
+            let file_size = std::fs::metadata(&input_path)?.len();
+            let stats = compressed.partition_stats();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&Info::new(compressed.size, file_size, &stats))?);
+                return Ok(());
+            }
+
+            println!("Image size: {}", compressed.size);
+            println!("File size: {}", indicatif::HumanBytes(file_size));
+            println!("{}", stats);
+            if !stats.overlaps.is_empty() {
+                warn!("{} pairs of range blocks overlap", stats.overlaps.len());
+            }
+
+            Ok(())
+        }
+        Commands::Dump { input_path, format } => {
+            let compressed = Compressed::read_from_path(&input_path)
+                .with_context(|| format!("Could not read the compressed file {:?}", input_path))?;
+            info!("Image size: {}", compressed.size);
+            compressed.dump(std::io::stdout().lock(), format.into())?;
+
+            Ok(())
+        }
+        Commands::Compare {
+            original_path,
+            image_path,
+            heatmap,
+            heatmap_block_size,
+            partition,
+            worst_window,
+            min_psnr,
+        } => {
+            let original = read_grayscale(&original_path)?;
+            let image = read_grayscale(&image_path)?;
+            let report = metrics::compare(&original, &image)?;
+            println!("{}", report);
+
+            if let Some(window_size) = worst_window {
+                anyhow::ensure!(window_size > 0, "The window size needs to be positive");
+                let (window, mse) = metrics::worst_window(&original, &image, window_size)?;
+                println!(
+                    "Worst window: {} at ({}, {}) with MSE {:.2} and PSNR {:.2} dB",
+                    window.size,
+                    window.origin.x,
+                    window.origin.y,
+                    mse,
+                    metrics::psnr_region(&original, &image, window)?
+                );
+            }
+
+            if let Some(path) = heatmap {
+                let map = match partition {
+                    Some(partition) => {
+                        let compressed = Compressed::read_from_path(&partition)
+                            .with_context(|| format!("Could not read the compressed file {:?}", partition))?;
+                        metrics::error_map_of_partition(&original, &image, &compressed)?
+                    }
+                    None => {
+                        anyhow::ensure!(heatmap_block_size > 0, "The heatmap block size needs to be positive");
+                        metrics::error_map(&original, &image, heatmap_block_size)?
+                    }
+                };
+                map.save_image_as_png(&path).with_context(|| format!("Could not save the heatmap to {:?}", path))?;
+                info!("Saved the heatmap to {:?}", path);
+            }
+
+            if let Some(min_psnr) = min_psnr {
+                anyhow::ensure!(
+                    report.psnr >= min_psnr,
+                    "The PSNR of {:.2} dB is below the minimum of {:.2} dB",
+                    report.psnr,
+                    min_psnr
+                );
             }
 
-            decompressed.image.save_image_as_png(&output_path);
-            
             Ok(())
         }
     }
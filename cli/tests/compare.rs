@@ -0,0 +1,78 @@
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use image::{GrayImage, Luma};
+
+fn frim<S: AsRef<OsStr>>(arguments: &[S]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_frim")).args(arguments).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// Saves a generated image with a diagonal gradient as PNG.
+fn image_file(name: &str, size: u32) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("frim-compare-{}.png", name));
+    GrayImage::from_fn(size, size, |x, y| Luma([(4 * (x + y)) as u8])).save(&path).unwrap();
+    path
+}
+
+#[test]
+fn identical_images_pass() {
+    let path = image_file("identical", 32);
+
+    let output = frim(&[
+        OsStr::new("compare"),
+        path.as_os_str(),
+        path.as_os_str(),
+        OsStr::new("--min-psnr"),
+        OsStr::new("60"),
+    ]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("MSE: 0.00"), "{}", stdout(&output));
+}
+
+#[test]
+fn compressed_files_are_decompressed_and_compared() {
+    let original = image_file("compressed", 32);
+    let compressed = std::env::temp_dir().join("frim-compare-compressed.qfic");
+    assert!(frim(&[OsStr::new("compress"), original.as_os_str(), compressed.as_os_str()]).status.success());
+
+    let compare = |min_psnr: &str| {
+        frim(&[
+            OsStr::new("compare"),
+            original.as_os_str(),
+            compressed.as_os_str(),
+            OsStr::new("--min-psnr"),
+            OsStr::new(min_psnr),
+        ])
+    };
+    let passed = compare("10");
+    let failed = compare("200");
+    std::fs::remove_file(&original).unwrap();
+    std::fs::remove_file(&compressed).unwrap();
+
+    assert!(passed.status.success(), "{}", stderr(&passed));
+    assert!(stdout(&passed).contains("PSNR"), "{}", stdout(&passed));
+    assert!(!failed.status.success());
+    assert!(stderr(&failed).contains("below the minimum of 200.00 dB"), "{}", stderr(&failed));
+}
+
+#[test]
+fn images_of_different_sizes_fail() {
+    let (small, large) = (image_file("small", 16), image_file("large", 32));
+
+    let output = frim(&[OsStr::new("compare"), small.as_os_str(), large.as_os_str()]);
+    std::fs::remove_file(&small).unwrap();
+    std::fs::remove_file(&large).unwrap();
+
+    assert!(!output.status.success());
+}
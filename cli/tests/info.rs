@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fractal_image::model::Compressed;
+use image::{GrayImage, Luma};
+
+fn frim(arguments: &[&Path]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_frim")).args(arguments).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Compresses a generated image with a bright square in its top left quadrant.
+fn compressed_file(name: &str) -> PathBuf {
+    let image_path = std::env::temp_dir().join(format!("frim-{}.png", name));
+    let compressed_path = std::env::temp_dir().join(format!("frim-{}.qfic", name));
+    GrayImage::from_fn(32, 32, |x, y| Luma([if x < 16 && y < 16 { 200 } else { 30 }]))
+        .save(&image_path)
+        .unwrap();
+
+    frim(&[Path::new("compress"), &image_path, &compressed_path]);
+    std::fs::remove_file(&image_path).unwrap();
+    compressed_path
+}
+
+#[test]
+fn info_prints_the_partition() {
+    let path = compressed_file("info");
+
+    let output = frim(&[Path::new("info"), &path]);
+    let stats = Compressed::read_from_path(&path).unwrap().partition_stats();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.contains("Image size: 32x32"), "{}", output);
+    assert!(output.contains(&format!("Transformations: {}\n", stats.transformations)), "{}", output);
+    for (block_size, count) in stats.blocks_per_size {
+        assert!(output.contains(&format!("{}x{}: {}", block_size, block_size, count)), "{}", output);
+    }
+    assert!(output.contains("Covered: 100.00%"), "{}", output);
+    assert!(output.contains("File size: "), "{}", output);
+}
+
+#[test]
+fn info_prints_json() {
+    let path = compressed_file("info-json");
+
+    let output = frim(&[Path::new("info"), &path, Path::new("--json")]);
+    let stats = Compressed::read_from_path(&path).unwrap().partition_stats();
+    let file_size = std::fs::metadata(&path).unwrap().len();
+    std::fs::remove_file(&path).unwrap();
+
+    let info: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(info["width"], 32);
+    assert_eq!(info["height"], 32);
+    assert_eq!(info["file_size"], file_size);
+    assert_eq!(info["transformations"], stats.transformations);
+    for (block_size, count) in stats.blocks_per_size {
+        assert_eq!(info["blocks_per_size"][block_size.to_string()], count, "{}", output);
+    }
+    assert_eq!(info["covered_fraction"], 1.0);
+    assert_eq!(info["overlaps"], 0);
+}
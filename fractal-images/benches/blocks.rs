@@ -0,0 +1,37 @@
+//! Compares reading the pixels of a nested block, i.e. a block of a block of a block, with
+//! reading them from the equivalent flattened block.
+//!
+//! Run with `cargo bench --features bench-support --bench blocks`.
+
+use std::hint::black_box;
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fractal_image::bench_support::noise;
+use fractal_image::image::{Coords, Image, IntoSquaredBlocks, SquaredBlock};
+
+fn sum<I: Image>(image: &I) -> u64 {
+    let mut sum = 0;
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            sum += image.pixel(x, y) as u64;
+        }
+    }
+    sum
+}
+
+fn nested_blocks(c: &mut Criterion) {
+    let image = noise(256);
+    let outer = image.as_inner().squared_blocks(128).unwrap().remove(3);
+    let middle = SquaredBlock { image: Arc::new(outer), size: 64, origin: Coords { x: 32, y: 32 } };
+    let nested = SquaredBlock { image: Arc::new(middle), size: 32, origin: Coords { x: 16, y: 16 } };
+    let flattened = nested.flatten().flatten();
+
+    let mut group = c.benchmark_group("pixels of block 32x32");
+    group.bench_function("nested", |b| b.iter(|| sum(black_box(&nested))));
+    group.bench_function("flattened", |b| b.iter(|| sum(black_box(&flattened))));
+    group.finish();
+}
+
+criterion_group!(benches, nested_blocks);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+//! Measures the compression of a generated image.
+//!
+//! Run with `cargo bench --features generators --bench compression`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fractal_image::compress;
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::image::gen::GenCircle;
+use fractal_image::image::PowerOfTwo;
+
+fn compression(c: &mut Criterion) {
+    let circle = || PowerOfTwo::new(GenCircle::new(256, 128.0)).unwrap();
+
+    for rotations in [true, false] {
+        let (_, stats) = compress::quadtree::Compressor::new(circle())
+            .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(1.0))
+            .with_rotations(rotations)
+            .compress_with_stats()
+            .unwrap();
+        println!("{:<40} {:>12} mappings", format!("rotations {}", if rotations { "enabled" } else { "disabled" }), stats.mappings_computed);
+    }
+
+    let mut group = c.benchmark_group("compress circle 256x256");
+    group.sample_size(10);
+    group.bench_function("default threshold", |b| {
+        b.iter(|| compress::quadtree::Compressor::new(circle()).compress().unwrap())
+    });
+    group.bench_function("strict threshold", |b| {
+        b.iter(|| {
+            compress::quadtree::Compressor::new(circle())
+                .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(1.0))
+                .compress()
+                .unwrap()
+        })
+    });
+    group.bench_function("no rotations", |b| {
+        b.iter(|| {
+            compress::quadtree::Compressor::new(circle())
+                .with_rotations(false)
+                .compress()
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, compression);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! Compares the conversion to a `DynamicImage` of images exposing their rows with images
+//! which only provide single pixels.
+//!
+//! Run with `cargo bench --features bench-support --bench conversion`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fractal_image::bench_support::WithoutRows;
+use fractal_image::image::{OwnedImage, Size};
+use fractal_image::preprocessing::AsDynamicImage;
+
+fn conversion(c: &mut Criterion) {
+    let image = OwnedImage::random_with_seed(Size::squared(2048), 42);
+    let without_rows = WithoutRows(image.clone());
+
+    let mut group = c.benchmark_group("dynamic image 2048x2048");
+    group.bench_function("rows", |b| b.iter(|| black_box(&image).as_dynamic_image()));
+    group.bench_function("pixels", |b| b.iter(|| black_box(&without_rows).as_dynamic_image()));
+    group.finish();
+}
+
+criterion_group!(benches, conversion);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+//! Measures the decompression of a generated image and the memory it allocates.
+//!
+//! Run with `cargo bench --features generators --bench decompression`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fractal_image::image::gen::GenCircle;
+use fractal_image::image::PowerOfTwo;
+use fractal_image::{compress, decompress};
+
+/// Counts the bytes allocated by the benchmark.
+struct CountingAllocator;
+
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn decompression(c: &mut Criterion) {
+    let circle = PowerOfTwo::new(GenCircle::new(512, 200.0)).unwrap();
+    let compressed = compress::quadtree::Compressor::new(circle).compress().unwrap();
+
+    let before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    decompress::decompress(compressed.clone(), decompress::Options::default()).unwrap();
+    let allocated = ALLOCATED_BYTES.load(Ordering::Relaxed) - before;
+    println!("{:<40} {:>12} bytes allocated", "decompress circle 512x512", allocated);
+
+    let mut group = c.benchmark_group("decompress circle 512x512");
+    group.sample_size(20);
+    group.bench_function("default options", |b| {
+        b.iter(|| decompress::decompress(compressed.clone(), decompress::Options::default()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decompression);
+criterion_main!(benches);
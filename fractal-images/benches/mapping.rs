@@ -0,0 +1,34 @@
+//! Compares the computation of mappings between images exposing their rows and
+//! images which only provide single pixels.
+//!
+//! Run with `cargo bench --features bench-support --bench mapping`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fractal_image::bench_support::{noise, WithoutRows};
+use fractal_image::compress::Mapping;
+use fractal_image::image::IntoSquaredBlocks;
+
+fn mapping(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mapping");
+    for block_size in [8, 16, 32] {
+        let image = noise(2 * block_size);
+        let blocks = image.as_inner().squared_blocks(block_size).unwrap();
+        let (domain, range) = (&blocks[0], &blocks[3]);
+        let parameter = format!("{}x{}", block_size, block_size);
+
+        group.bench_function(BenchmarkId::new("rows", &parameter), |b| {
+            b.iter(|| Mapping::compute(black_box(domain), black_box(range)))
+        });
+
+        let (domain, range) = (WithoutRows(domain.clone()), WithoutRows(range.clone()));
+        group.bench_function(BenchmarkId::new("pixels", &parameter), |b| {
+            b.iter(|| Mapping::compute(black_box(&domain), black_box(&range)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, mapping);
+criterion_main!(benches);
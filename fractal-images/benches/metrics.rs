@@ -0,0 +1,46 @@
+//! Compares the computation of metrics between images exposing their rows and
+//! images which only provide single pixels, and between the serial and parallel metrics.
+//!
+//! Run with `cargo bench --features bench-support --bench metrics`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fractal_image::bench_support::{noise, WithoutRows};
+use fractal_image::metrics;
+
+fn metrics_1024(c: &mut Criterion) {
+    let (first, second) = (noise(1024), noise(1024));
+    let (first_pixels, second_pixels) = (WithoutRows(first.clone()), WithoutRows(second.clone()));
+
+    let mut group = c.benchmark_group("mse 1024x1024");
+    group.bench_function("rows", |b| b.iter(|| metrics::mse(black_box(&first), black_box(&second)).unwrap()));
+    group.bench_function("rows parallel", |b| b.iter(|| metrics::mse_par(black_box(&first), black_box(&second)).unwrap()));
+    group.bench_function("pixels", |b| {
+        b.iter(|| metrics::mse(black_box(&first_pixels), black_box(&second_pixels)).unwrap())
+    });
+    group.bench_function("pixels parallel", |b| {
+        b.iter(|| metrics::mse_par(black_box(&first_pixels), black_box(&second_pixels)).unwrap())
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("psnr 1024x1024");
+    group.bench_function("rows", |b| b.iter(|| metrics::psnr(black_box(&first), black_box(&second)).unwrap()));
+    group.bench_function("rows parallel", |b| b.iter(|| metrics::psnr_par(black_box(&first), black_box(&second)).unwrap()));
+    group.bench_function("pixels", |b| {
+        b.iter(|| metrics::psnr(black_box(&first_pixels), black_box(&second_pixels)).unwrap())
+    });
+    group.finish();
+}
+
+fn metrics_4096(c: &mut Criterion) {
+    let (first, second) = (noise(4096), noise(4096));
+
+    let mut group = c.benchmark_group("mse 4096x4096");
+    group.bench_function("rows", |b| b.iter(|| metrics::mse(black_box(&first), black_box(&second)).unwrap()));
+    group.bench_function("rows parallel", |b| b.iter(|| metrics::mse_par(black_box(&first), black_box(&second)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, metrics_1024, metrics_4096);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! Measures the partitioning of an image into squared blocks.
+//!
+//! Run with `cargo bench --features bench-support --bench partitioning`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fractal_image::bench_support::noise;
+use fractal_image::image::IntoSquaredBlocks;
+
+fn partitioning(c: &mut Criterion) {
+    let image = noise(1024).as_inner();
+
+    let mut group = c.benchmark_group("squared_blocks 1024x1024");
+    for block_size in [4, 16, 64] {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", block_size, block_size)), &block_size, |b, &block_size| {
+            b.iter(|| black_box(&image).squared_blocks(block_size).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, partitioning);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+//! Compares the decompression time of plain and pyramid decoding for the same quality.
+//!
+//! Run with `cargo bench --features generators --bench pyramid`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fractal_image::image::gen::GenCircle;
+use fractal_image::image::{Image, PowerOfTwo};
+use fractal_image::model::Compressed;
+use fractal_image::{compress, decompress, metrics};
+
+/// The fewest iterations for which decompressing with `pyramid` reaches `target_psnr`.
+fn iterations_for<R: Image>(compressed: &Compressed, reference: &R, pyramid: bool, target_psnr: f64) -> u8 {
+    (1..=30)
+        .find(|&iterations| {
+            let options = decompress::Options { iterations, pyramid, ..Default::default() };
+            let decompressed = decompress::decompress(compressed.clone(), options).unwrap();
+            metrics::psnr(reference, &decompressed.image).unwrap() >= target_psnr
+        })
+        .expect("Target PSNR is reached within 30 iterations")
+}
+
+fn pyramid(c: &mut Criterion) {
+    let circle = GenCircle::new(1024, 400.0);
+    let compressed = compress::quadtree::Compressor::new(PowerOfTwo::new(GenCircle::new(1024, 400.0)).unwrap()).compress().unwrap();
+
+    let converged = decompress::decompress(compressed.clone(), decompress::Options { iterations: 30, ..Default::default() }).unwrap();
+    let target_psnr = metrics::psnr(&circle, &converged.image).unwrap() - 0.5;
+    println!("Target PSNR: {:.2} dB", target_psnr);
+
+    let mut group = c.benchmark_group("decompress circle 1024x1024");
+    group.sample_size(10);
+    for pyramid in [false, true] {
+        let iterations = iterations_for(&compressed, &circle, pyramid, target_psnr);
+        let id = BenchmarkId::new(format!("pyramid: {}", pyramid), format!("{} iterations", iterations));
+        group.bench_with_input(id, &iterations, |b, &iterations| {
+            b.iter(|| {
+                let options = decompress::Options { iterations, pyramid, ..Default::default() };
+                decompress::decompress(compressed.clone(), options).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, pyramid);
+criterion_main!(benches);
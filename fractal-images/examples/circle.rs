@@ -14,7 +14,8 @@ fn main() {
         .compress()
         .expect("Error while compressing image");
 
-    let decompressed = decompress::decompress(compressed, decompress::Options::default());
+    let decompressed = decompress::decompress(compressed, decompress::Options::default())
+        .expect("Error while decompressing image");
 
-    decompressed.image.save_image_as_png("out.png");
+    decompressed.image.save_image_as_png("out.png").expect("Could not save the decompressed image");
 }
\ No newline at end of file
@@ -3,11 +3,18 @@ mod ex_module;
 use cli_table::{print_stdout, WithTitle};
 use fractal_image::image::gen::GenCircle;
 
+/// Pass `--antialiased` to soften the edges of the circles, such that the errors are not
+/// dominated by single edge pixels.
 fn main() {
+    let antialiased = std::env::args().any(|argument| argument == "--antialiased");
     let compressions = vec![8, 16, 32, 64, 128, 256, 512].into_iter()
         .map(|image_size| {
             let radius = image_size as f64 / 2.0;
-            GenCircle::new(image_size, radius)
+            if antialiased {
+                GenCircle::new_aa(image_size, radius, 1.0)
+            } else {
+                GenCircle::new(image_size, radius)
+            }
         })
         .map(ex_module::compare_to_png_compression)
         .collect::<Vec<_>>();
@@ -2,7 +2,7 @@ use std::fmt::Debug;
 
 use cli_table::Table;
 
-use fractal_image::{compress, decompress};
+use fractal_image::{compress, decompress, metrics};
 use fractal_image::image::{Image, PowerOfTwo, Size, Square};
 use fractal_image::preprocessing::SafeableImage;
 
@@ -17,7 +17,9 @@ pub struct Comparison {
     #[table(title = "PNG size [Bytes]")]
     png_file_size_bytes: u64,
     #[table(title = "Ratio")]
-    compression_ratio: f32,
+    compression_ratio: f64,
+    #[table(title = "Bits per pixel")]
+    bits_per_pixel: f64,
 }
 
 pub fn compare_to_png_compression<I: Image + Debug>(image: I) -> Comparison {
@@ -34,7 +36,7 @@ pub fn compare_to_png_compression<I: Image + Debug>(image: I) -> Comparison {
     let file_name_png = |prefix: &str| format!("{}.png", file_name(prefix));
 
     let original_file_name = file_name_png("orig");
-    image.save_image_as_png(&original_file_name);
+    image.save_image_as_png(&original_file_name).expect("Could not save the original image");
     let png_file_size = std::fs::metadata(&original_file_name).unwrap().len();
 
     let compressed = compress::quadtree::Compressor::new(image)
@@ -42,16 +44,19 @@ pub fn compare_to_png_compression<I: Image + Debug>(image: I) -> Comparison {
         .expect("Error while compressing image");
 
     let compressed_file_size = compressed.persist_as_binary_v1(file_name("cmp")).expect("Could not persist compressed image");
-    let decompressed = decompress::decompress(compressed, decompress::Options::default());
+    let report = metrics::compression_report(&compressed, compressed_file_size).with_reference(png_file_size);
+    let decompressed = decompress::decompress(compressed, decompress::Options::default())
+        .expect("Error while decompressing image");
 
     let out_file_name = file_name_png("out");
-    decompressed.image.save_image_as_png(&out_file_name);
+    decompressed.image.save_image_as_png(&out_file_name).expect("Could not save the decompressed image");
 
     Comparison {
         image_size,
         file_name: out_file_name,
-        compressed_file_size_bytes: compressed_file_size,
+        compressed_file_size_bytes: report.serialized_bytes,
         png_file_size_bytes: png_file_size,
-        compression_ratio: compressed_file_size as f32 / png_file_size as f32,
+        compression_ratio: report.ratio().expect("The PNG is the reference"),
+        bits_per_pixel: report.bits_per_pixel,
     }
 }
\ No newline at end of file
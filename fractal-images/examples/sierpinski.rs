@@ -0,0 +1,18 @@
+use fractal_image::compress;
+use fractal_image::decompress;
+use fractal_image::image::gen::GenSierpinski;
+use fractal_image::preprocessing::SafeableImage;
+
+fn main() {
+    let triangle = GenSierpinski::new(512, 7);
+
+    let compressed = compress::quadtree::Compressor::new(triangle)
+        .compress()
+        .expect("Error while compressing image");
+    println!("Compressed the triangle into {} transformations", compressed.transformations.len());
+
+    let decompressed = decompress::decompress(compressed, decompress::Options::default())
+        .expect("Error while decompressing image");
+
+    decompressed.image.save_image_as_png("sierpinski.png").expect("Could not save the decompressed image");
+}
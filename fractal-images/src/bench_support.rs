@@ -0,0 +1,11 @@
+//! Helpers for the benchmarks in `benches/`, not meant to be used otherwise.
+
+use crate::image::{OwnedImage, PowerOfTwo, Size, Square};
+
+pub use crate::image::WithoutRows;
+
+/// Returns a deterministic noise image of `size`x`size` pixels.
+pub fn noise(size: u32) -> PowerOfTwo<Square<OwnedImage>> {
+    let image = OwnedImage::random_with_seed(Size::squared(size), 42);
+    PowerOfTwo::new(Square::new(image).unwrap()).expect("Size needs to be a power of two")
+}
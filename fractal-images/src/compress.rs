@@ -1,17 +1,82 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::compress::quadtree::{CompressionError, CompressionStats, ErrorThreshold, StatsReporting};
 use crate::image::Image;
+use crate::model::Compressed;
 use tracing::trace;
 
+pub mod color;
+pub(crate) mod index;
 pub mod quadtree;
 
-#[derive(Debug, Clone, Copy)]
-struct Mapping {
-    error: f64,
+/// A compression algorithm, which allows callers to abstract over the concrete algorithm.
+///
+/// The trait is object safe, a `Box<dyn Compressor>` is a [Compressor] itself.
+pub trait Compressor {
+    /// Compresses the image.
+    fn compress(self) -> Result<Compressed, CompressionError>
+    where
+        Self: Sized,
+    {
+        self.compress_with_stats().map(|(compressed, _)| compressed)
+    }
+
+    /// Compresses the image, additionally returning [statistics](CompressionStats) about the compression.
+    fn compress_with_stats(self) -> Result<(Compressed, CompressionStats), CompressionError>
+    where
+        Self: Sized;
+
+    /// Same as [Compressor::compress_with_stats] for boxed compressors.
+    fn compress_boxed_with_stats(self: Box<Self>) -> Result<(Compressed, CompressionStats), CompressionError>;
+
+    /// Sets the threshold which decides whether a mapping between two blocks is acceptable.
+    fn set_error_threshold(&mut self, error_threshold: ErrorThreshold);
+
+    /// Sets a function which is called whenever the compression progresses.
+    fn set_progress_reporter(&mut self, progress_fn: Arc<dyn Fn(StatsReporting) + Send + Sync>);
+
+    /// Like [Compressor::set_progress_reporter], but calls `progress_fn` at most once per
+    /// `min_interval`. The final report is always delivered.
+    fn set_progress_reporter_throttled(&mut self, progress_fn: Arc<dyn Fn(StatsReporting) + Send + Sync>, min_interval: Duration);
+}
+
+impl<C: Compressor + ?Sized> Compressor for Box<C> {
+    fn compress_with_stats(self) -> Result<(Compressed, CompressionStats), CompressionError> {
+        C::compress_boxed_with_stats(self)
+    }
+
+    fn compress_boxed_with_stats(self: Box<Self>) -> Result<(Compressed, CompressionStats), CompressionError> {
+        C::compress_boxed_with_stats(*self)
+    }
+
+    fn set_error_threshold(&mut self, error_threshold: ErrorThreshold) {
+        (**self).set_error_threshold(error_threshold)
+    }
+
+    fn set_progress_reporter(&mut self, progress_fn: Arc<dyn Fn(StatsReporting) + Send + Sync>) {
+        (**self).set_progress_reporter(progress_fn)
+    }
+
+    fn set_progress_reporter_throttled(&mut self, progress_fn: Arc<dyn Fn(StatsReporting) + Send + Sync>, min_interval: Duration) {
+        (**self).set_progress_reporter_throttled(progress_fn, min_interval)
+    }
+}
+
+/// The brightness and saturation which map a domain block to a range block with the least
+/// squared error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mapping {
+    /// The RMS error between the mapped domain block and the range block
+    pub error: f64,
     brightness: i16,
     saturation: f64,
 }
 
 impl Mapping {
-    fn compute<A, B>(domain: &A, range: &B) -> Option<Self>
+    /// Computes the mapping between two blocks of the same size. Returns `None` if the
+    /// mapping would not be contractive.
+    pub fn compute<A, B>(domain: &A, range: &B) -> Option<Self>
     where
         A: Image,
         B: Image,
@@ -21,17 +86,8 @@ impl Mapping {
 
         let n: f64 = (domain.get_width() * domain.get_height()) as f64; // amount of pixels
 
-        let (mut domain_times_range_sum, mut domain_squared_sum, mut range_squared_sum, mut domain_sum, mut range_sum) =
-            (0.0, 0.0, 0.0, 0.0, 0.0);
-        for (dp, rp) in domain.pixels().zip(range.pixels()) {
-            let dp = dp as f64;
-            let rp = rp as f64;
-            domain_times_range_sum += dp * rp;
-            domain_squared_sum += dp * dp;
-            range_squared_sum += rp * rp;
-            domain_sum += dp;
-            range_sum += rp;
-        }
+        let Sums { domain_times_range_sum, domain_squared_sum, range_squared_sum, domain_sum, range_sum } =
+            Sums::from_rows(domain, range).unwrap_or_else(|| Sums::from_pixels(domain, range));
         let domain_sum_squared = domain_sum * domain_sum;
 
         // Compute s (saturation)
@@ -69,4 +125,132 @@ impl Mapping {
             saturation,
         })
     }
-}
\ No newline at end of file
+}
+
+/// The sums over all pixel pairs of a domain and a range block needed to compute a [Mapping].
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sums {
+    domain_times_range_sum: f64,
+    domain_squared_sum: f64,
+    range_squared_sum: f64,
+    domain_sum: f64,
+    range_sum: f64,
+}
+
+impl Sums {
+    /// The amount of pixels accumulated at once on the fast path, sized such that the
+    /// compiler can vectorize the accumulation.
+    const CHUNK_SIZE: usize = 8;
+
+    /// Accumulates the sums pixel by pixel, works for any two images.
+    fn from_pixels<A: Image, B: Image>(domain: &A, range: &B) -> Self {
+        let (mut domain_times_range_sum, mut domain_squared_sum, mut range_squared_sum, mut domain_sum, mut range_sum) =
+            (0.0, 0.0, 0.0, 0.0, 0.0);
+        for (dp, rp) in domain.pixels().zip(range.pixels()) {
+            let dp = dp as f64;
+            let rp = rp as f64;
+            domain_times_range_sum += dp * rp;
+            domain_squared_sum += dp * dp;
+            range_squared_sum += rp * rp;
+            domain_sum += dp;
+            range_sum += rp;
+        }
+
+        Self { domain_times_range_sum, domain_squared_sum, range_squared_sum, domain_sum, range_sum }
+    }
+
+    /// Accumulates the sums row by row with integer arithmetic. Returns `None` if one of the
+    /// images does not expose its [rows](Image::pixel_row).
+    ///
+    /// Since all sums are integers, the result is identical to [Sums::from_pixels].
+    fn from_rows<A: Image, B: Image>(domain: &A, range: &B) -> Option<Self> {
+        let (mut domain_times_range_sum, mut domain_squared_sum, mut range_squared_sum, mut domain_sum, mut range_sum) =
+            (0u64, 0u64, 0u64, 0u64, 0u64);
+
+        for y in 0..domain.get_height() {
+            let domain_row = domain.pixel_row(y)?;
+            let range_row = range.pixel_row(y)?;
+
+            let domain_chunks = domain_row.chunks_exact(Self::CHUNK_SIZE);
+            let range_chunks = range_row.chunks_exact(Self::CHUNK_SIZE);
+            let remainder = domain_chunks.remainder().iter().zip(range_chunks.remainder());
+
+            // Within a chunk, the sums can not overflow an u32
+            for (dc, rc) in domain_chunks.zip(range_chunks) {
+                let (mut dr, mut dd, mut rr, mut d, mut r) = (0u32, 0u32, 0u32, 0u32, 0u32);
+                for i in 0..Self::CHUNK_SIZE {
+                    let dp = dc[i] as u32;
+                    let rp = rc[i] as u32;
+                    dr += dp * rp;
+                    dd += dp * dp;
+                    rr += rp * rp;
+                    d += dp;
+                    r += rp;
+                }
+                domain_times_range_sum += dr as u64;
+                domain_squared_sum += dd as u64;
+                range_squared_sum += rr as u64;
+                domain_sum += d as u64;
+                range_sum += r as u64;
+            }
+
+            for (&dp, &rp) in remainder {
+                let dp = dp as u64;
+                let rp = rp as u64;
+                domain_times_range_sum += dp * rp;
+                domain_squared_sum += dp * dp;
+                range_squared_sum += rp * rp;
+                domain_sum += dp;
+                range_sum += rp;
+            }
+        }
+
+        Some(Self {
+            domain_times_range_sum: domain_times_range_sum as f64,
+            domain_squared_sum: domain_squared_sum as f64,
+            range_squared_sum: range_squared_sum as f64,
+            domain_sum: domain_sum as f64,
+            range_sum: range_sum as f64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{IntoSquaredBlocks, OwnedImage, Size, Square, WithoutRows};
+
+    use super::*;
+
+    #[test]
+    fn row_sums_equal_pixel_sums() {
+        let domain = OwnedImage::random_with_seed(Size::squared(13), 1);
+        let range = OwnedImage::random_with_seed(Size::squared(13), 2);
+
+        assert_eq!(
+            Sums::from_rows(&domain, &range),
+            Some(Sums::from_pixels(&domain, &range))
+        );
+    }
+
+    #[test]
+    fn row_sums_are_not_available_without_rows() {
+        let domain = OwnedImage::random_with_seed(Size::squared(4), 1);
+        let range = WithoutRows(OwnedImage::random_with_seed(Size::squared(4), 2));
+
+        assert_eq!(Sums::from_rows(&domain, &range), None);
+    }
+
+    #[test]
+    fn mapping_of_blocks_is_identical_on_both_paths() {
+        let image = Square::new(OwnedImage::random_with_seed(Size::squared(32), 3)).unwrap();
+        let blocks = image.squared_blocks(16).unwrap();
+
+        for (domain, range) in blocks.iter().zip(blocks.iter().rev()) {
+            assert!(Sums::from_rows(domain, range).is_some());
+            assert_eq!(
+                Mapping::compute(domain, range),
+                Mapping::compute(&WithoutRows(domain.clone()), &WithoutRows(range.clone()))
+            );
+        }
+    }
+}
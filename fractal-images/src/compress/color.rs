@@ -0,0 +1,181 @@
+//! Compression of color images.
+//!
+//! The image is converted to the YCbCr color space (as used by JPEG) and each plane is
+//! compressed separately with the [quadtree compressor](crate::compress::quadtree::Compressor).
+//! As the human eye is less sensitive to color than to brightness, the chroma planes can be
+//! subsampled to half of the width and height of the image.
+
+use image::RgbImage;
+use tracing::instrument;
+
+use crate::compress::quadtree::{CompressionError, Compressor, ErrorThreshold};
+use crate::image::{Image, OwnedImage, Pixel, PowerOfTwo, Size, Square};
+use crate::model::{ColorCompressed, Compressed};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Whether the chroma planes are compressed with half the width and height of the image.
+    pub chroma_subsampling: bool,
+
+    /// The error threshold used for each plane. If `None`, the default threshold of the
+    /// [Compressor] is used.
+    pub error_threshold: Option<ErrorThreshold>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            chroma_subsampling: true,
+            error_threshold: None,
+        }
+    }
+}
+
+/// Compresses a color image, whose width and height are the same power of two.
+#[instrument(level = "debug", skip(image))]
+pub fn compress_rgb(image: &RgbImage, options: Options) -> Result<ColorCompressed, CompressionError> {
+    let size = Size::new(image.width(), image.height());
+    if !size.is_squared() {
+        return Err(CompressionError::NotSquare(size));
+    }
+
+    let planes = YCbCrPlanes::from_rgb(image);
+
+    // A single pixel can not be subsampled any further
+    let chroma_subsampled = options.chroma_subsampling && size.get_width() > 1;
+    let (blue_chroma, red_chroma) = match chroma_subsampled {
+        true => (subsample(&planes.blue_chroma), subsample(&planes.red_chroma)),
+        false => (planes.blue_chroma, planes.red_chroma),
+    };
+
+    Ok(ColorCompressed {
+        chroma_subsampled,
+        luma: compress_plane(planes.luma, &options)?,
+        blue_chroma: compress_plane(blue_chroma, &options)?,
+        red_chroma: compress_plane(red_chroma, &options)?,
+    })
+}
+
+fn compress_plane(plane: OwnedImage, options: &Options) -> Result<Compressed, CompressionError> {
+    let plane = Square::new(plane).expect("Planes of a square image are squares");
+    let compressor = Compressor::new(PowerOfTwo::new(plane)?);
+    match &options.error_threshold {
+        None => compressor.compress(),
+        Some(error_threshold) => compressor.with_error_threshold(error_threshold.clone()).compress(),
+    }
+}
+
+/// Halves the width and height of `plane` by averaging 2x2 pixels.
+pub(crate) fn subsample(plane: &OwnedImage) -> OwnedImage {
+    let size = Size::new(plane.get_width() / 2, plane.get_height() / 2);
+    let mut pixels = Vec::with_capacity(size.area() as usize);
+    for y in 0..size.get_height() {
+        for x in 0..size.get_width() {
+            let sum = plane.pixel(2 * x, 2 * y) as u32
+                + plane.pixel(2 * x + 1, 2 * y) as u32
+                + plane.pixel(2 * x, 2 * y + 1) as u32
+                + plane.pixel(2 * x + 1, 2 * y + 1) as u32;
+            pixels.push(((sum + 2) / 4) as Pixel);
+        }
+    }
+    OwnedImage::from_pixels(size, pixels).expect("One pixel per 2x2 pixels is computed")
+}
+
+/// The planes of an image in the YCbCr color space.
+pub(crate) struct YCbCrPlanes {
+    pub luma: OwnedImage,
+    pub blue_chroma: OwnedImage,
+    pub red_chroma: OwnedImage,
+}
+
+impl YCbCrPlanes {
+    pub fn from_rgb(image: &RgbImage) -> Self {
+        let size = Size::new(image.width(), image.height());
+        let mut luma = Vec::with_capacity(size.area() as usize);
+        let mut blue_chroma = Vec::with_capacity(size.area() as usize);
+        let mut red_chroma = Vec::with_capacity(size.area() as usize);
+
+        for pixel in image.pixels() {
+            let [r, g, b] = pixel.0.map(|channel| channel as f64);
+            luma.push(to_pixel(0.299 * r + 0.587 * g + 0.114 * b));
+            blue_chroma.push(to_pixel(128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b));
+            red_chroma.push(to_pixel(128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b));
+        }
+
+        Self {
+            luma: OwnedImage::from_pixels(size, luma).expect("One luma value per pixel"),
+            blue_chroma: OwnedImage::from_pixels(size, blue_chroma).expect("One chroma value per pixel"),
+            red_chroma: OwnedImage::from_pixels(size, red_chroma).expect("One chroma value per pixel"),
+        }
+    }
+
+    /// Converts the planes back to RGB. Chroma planes which are smaller than the luma plane
+    /// are upscaled by repeating their pixels.
+    pub fn into_rgb(self) -> RgbImage {
+        let (width, height) = (self.luma.get_width(), self.luma.get_height());
+        let scale_x = width / self.blue_chroma.get_width();
+        let scale_y = height / self.blue_chroma.get_height();
+
+        RgbImage::from_fn(width, height, |x, y| {
+            let luma = self.luma.pixel(x, y) as f64;
+            let cb = self.blue_chroma.pixel(x / scale_x, y / scale_y) as f64 - 128.0;
+            let cr = self.red_chroma.pixel(x / scale_x, y / scale_y) as f64 - 128.0;
+            image::Rgb([
+                to_pixel(luma + 1.402 * cr),
+                to_pixel(luma - 0.344136 * cb - 0.714136 * cr),
+                to_pixel(luma + 1.772 * cb),
+            ])
+        })
+    }
+}
+
+fn to_pixel(value: f64) -> Pixel {
+    value.round().clamp(0.0, 255.0) as Pixel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversion_to_ycbcr_and_back_is_almost_lossless() {
+        let image = RgbImage::from_fn(8, 8, |x, y| image::Rgb([(x * 32) as u8, (y * 32) as u8, 200]));
+
+        let converted = YCbCrPlanes::from_rgb(&image).into_rgb();
+
+        for (original, converted) in image.pixels().zip(converted.pixels()) {
+            for channel in 0..3 {
+                let difference = (original.0[channel] as i16 - converted.0[channel] as i16).abs();
+                assert!(difference <= 2, "Expected {:?}, was {:?}", original, converted);
+            }
+        }
+    }
+
+    #[test]
+    fn gray_pixels_have_neutral_chroma() {
+        let image = RgbImage::from_pixel(2, 2, image::Rgb([77, 77, 77]));
+
+        let planes = YCbCrPlanes::from_rgb(&image);
+
+        assert_eq!(planes.luma.pixel(0, 0), 77);
+        assert_eq!(planes.blue_chroma.pixel(0, 0), 128);
+        assert_eq!(planes.red_chroma.pixel(0, 0), 128);
+    }
+
+    #[test]
+    fn subsampling_averages_2x2_pixels() {
+        let plane = OwnedImage::from_pixels(Size::squared(2), vec![10, 20, 30, 40]).unwrap();
+
+        let subsampled = subsample(&plane);
+
+        assert_eq!(subsampled.get_size(), Size::squared(1));
+        assert_eq!(subsampled.pixel(0, 0), 25);
+    }
+
+    #[test]
+    fn non_square_images_are_rejected() {
+        let image = RgbImage::new(4, 8);
+        let result = compress_rgb(&image, Options::default());
+        assert_eq!(result.unwrap_err(), CompressionError::NotSquare(Size::new(4, 8)));
+    }
+}
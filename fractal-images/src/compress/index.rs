@@ -0,0 +1,193 @@
+//! An index to search for the domain blocks which are most similar to a range block,
+//! instead of comparing a range block with all domain blocks.
+
+use crate::image::Image;
+
+/// The maximal width and height of the downsampled image [Features] are computed from.
+const FEATURE_SIZE: u32 = 4;
+
+/// Describes the structure of an image: its pixels downsampled to at most
+/// [FEATURE_SIZE]x[FEATURE_SIZE] values, shifted to a mean of zero and scaled to a length
+/// of one. Thus, images related by a positive saturation and any brightness have equal features.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Features(Vec<f64>);
+
+impl Features {
+    /// Computes the features of a squared image whose size is a power of two.
+    pub(crate) fn of<I: Image>(image: &I) -> Self {
+        let size = image.get_width();
+        let cells = size.min(FEATURE_SIZE);
+        let cell_size = size / cells;
+
+        let mut values = vec![0.0; (cells * cells) as usize];
+        for y in 0..size {
+            for x in 0..size {
+                values[((y / cell_size) * cells + x / cell_size) as usize] += image.pixel(x, y) as f64;
+            }
+        }
+
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter_mut().for_each(|value| *value -= mean);
+        let length = values.iter().map(|value| value * value).sum::<f64>().sqrt();
+        if length > 0.0 {
+            values.iter_mut().for_each(|value| *value /= length);
+        }
+
+        Self(values)
+    }
+
+    fn distance(&self, other: &Self) -> f64 {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+/// A [vantage-point tree](https://en.wikipedia.org/wiki/Vantage-point_tree) which stores
+/// items by their [Features] and finds the items with the nearest features.
+pub(crate) struct VpTree<T> {
+    nodes: Vec<Node<T>>,
+}
+
+struct Node<T> {
+    features: Features,
+    item: T,
+    /// The median distance of the items below this node to its features
+    radius: f64,
+    /// The subtree of items within `radius`
+    inside: Option<usize>,
+    /// The subtree of items outside of `radius`
+    outside: Option<usize>,
+}
+
+impl<T> VpTree<T> {
+    pub(crate) fn new(items: Vec<(Features, T)>) -> Self {
+        let mut tree = Self {
+            nodes: Vec::with_capacity(items.len()),
+        };
+        tree.insert(items);
+        tree
+    }
+
+    /// Inserts `items` as a subtree and returns the index of its root node.
+    fn insert(&mut self, mut items: Vec<(Features, T)>) -> Option<usize> {
+        let (features, item) = items.pop()?;
+
+        let mut items = items
+            .into_iter()
+            .map(|(f, i)| (features.distance(&f), f, i))
+            .collect::<Vec<_>>();
+        items.sort_by(|(a, _, _), (b, _, _)| a.total_cmp(b));
+
+        let outside = items.split_off(items.len() / 2);
+        let radius = items.last().map_or(0.0, |(distance, _, _)| *distance);
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            features,
+            item,
+            radius,
+            inside: None,
+            outside: None,
+        });
+
+        let inside = self.insert(items.into_iter().map(|(_, f, i)| (f, i)).collect());
+        let outside = self.insert(outside.into_iter().map(|(_, f, i)| (f, i)).collect());
+        self.nodes[index].inside = inside;
+        self.nodes[index].outside = outside;
+
+        Some(index)
+    }
+
+    /// Returns the (up to) `k` items whose features are nearest to `features`, the nearest first.
+    pub(crate) fn nearest(&self, features: &Features, k: usize) -> Vec<&T> {
+        let mut nearest: Vec<(f64, usize)> = Vec::with_capacity(k + 1);
+        if !self.nodes.is_empty() && k > 0 {
+            self.search(0, features, k, &mut nearest);
+        }
+
+        nearest.into_iter().map(|(_, index)| &self.nodes[index].item).collect()
+    }
+
+    fn search(&self, index: usize, features: &Features, k: usize, nearest: &mut Vec<(f64, usize)>) {
+        let node = &self.nodes[index];
+        let distance = node.features.distance(features);
+
+        if nearest.len() < k || distance < nearest[nearest.len() - 1].0 {
+            let position = nearest.partition_point(|(d, _)| *d <= distance);
+            nearest.insert(position, (distance, index));
+            nearest.truncate(k);
+        }
+
+        // The distance of the k-th nearest item found so far
+        let bound = |nearest: &Vec<(f64, usize)>| match nearest.len() < k {
+            true => f64::INFINITY,
+            false => nearest[nearest.len() - 1].0,
+        };
+
+        // Visits the more promising subtree first
+        let (first, second) = match distance <= node.radius {
+            true => (node.inside, node.outside),
+            false => (node.outside, node.inside),
+        };
+        if let Some(first) = first {
+            self.search(first, features, k, nearest);
+        }
+        if let Some(second) = second {
+            if (distance - node.radius).abs() <= bound(nearest) {
+                self.search(second, features, k, nearest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{FakeImage, OwnedImage, Size};
+
+    use super::*;
+
+    #[test]
+    fn features_are_normalized() {
+        let features = Features::of(&FakeImage::squared(16));
+        let length = features.0.iter().map(|value| value * value).sum::<f64>().sqrt();
+        let mean = features.0.iter().sum::<f64>() / features.0.len() as f64;
+
+        assert_eq!(features.0.len(), 16);
+        assert!((length - 1.0).abs() < 1e-9);
+        assert!(mean.abs() < 1e-9);
+    }
+
+    #[test]
+    fn features_of_small_images_use_all_pixels() {
+        assert_eq!(Features::of(&FakeImage::squared(2)).0.len(), 4);
+        assert_eq!(Features::of(&FakeImage::squared(1)).0, vec![0.0]);
+    }
+
+    #[test]
+    fn nearest_items_equal_brute_force() {
+        let features = (0..200)
+            .map(|seed| Features::of(&OwnedImage::random_with_seed(Size::squared(4), seed)))
+            .collect::<Vec<_>>();
+        let tree = VpTree::new(features.iter().cloned().zip(0..).collect());
+
+        for query in features.iter().take(20) {
+            let mut expected = (0..features.len()).collect::<Vec<_>>();
+            expected.sort_by(|a, b| features[*a].distance(query).total_cmp(&features[*b].distance(query)));
+            expected.truncate(5);
+
+            let nearest = tree.nearest(query, 5).into_iter().copied().collect::<Vec<usize>>();
+            assert_eq!(nearest, expected);
+        }
+    }
+
+    #[test]
+    fn nearest_returns_at_most_all_items() {
+        let tree = VpTree::new(vec![(Features(vec![0.0]), 'a'), (Features(vec![1.0]), 'b')]);
+        assert_eq!(tree.nearest(&Features(vec![0.9]), 5), vec![&'b', &'a']);
+        assert!(tree.nearest(&Features(vec![0.9]), 0).is_empty());
+    }
+}
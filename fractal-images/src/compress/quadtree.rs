@@ -1,22 +1,182 @@
+use crate::compress::index::{Features, VpTree};
 use crate::compress::Mapping;
 use crate::image::{IntoSquaredBlocks, NoPowerOfTwo, PowerOfTwo, Square, SquaredBlock, SquareSizeDoesNotDivideImageSize};
-use crate::image::IntoDownscaled;
-use crate::image::Image;
+use crate::image::{ExtendedBlock, IntoDownscaled};
+use crate::image::{Image, IntoOwnedImage, Size};
 use crate::image::IntoRotated;
-use crate::model::{Block, Compressed, Transformation};
+use crate::image::stats::{variance, Stats as PixelStats};
+use crate::model::{Block, Compressed, LosslessCompressed, Rotation, Transformation};
+use crate::{decompress, metrics};
 use log::warn;
+use rayon::iter::Either;
 use rayon::prelude::*;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tracing::{debug, info, instrument};
 
+pub use stats::{CompressionStats, StatsReporting};
+
 pub struct Compressor<I> {
     image: Arc<I>,
     error_threshold: ErrorThreshold,
+    target: Option<Target>,
+    search: SearchOptions,
+    /// The margin by which range blocks are extended, see [Compressor::with_overlap]
+    overlap: u32,
+    /// The size below which range blocks are not split, see [Compressor::with_min_block_size]
+    min_block_size: Option<u32>,
     progress_fn: Option<Arc<dyn Fn(stats::StatsReporting) + Send + Sync>>,
     stats: Arc<stats::Stats>,
+    /// The indices over the domain blocks per domain block size and scale, see [Compressor::with_ann_search]
+    domain_indices: Mutex<HashMap<(u32, u32), Arc<DomainIndex>>>,
+    /// The pixel statistics of the downscaled domain blocks per domain block size and scale,
+    /// see [Compressor::with_variance_prefilter]
+    domain_stats: Mutex<HashMap<(u32, u32), Arc<DomainStats>>>,
+}
+
+/// Indexes the domain blocks of one size by their position in the list of domain blocks
+/// and their rotation.
+type DomainIndex = VpTree<(usize, Rotation)>;
+
+/// The pixel statistics of the downscaled domain blocks of one size, in the order of the
+/// list of domain blocks.
+type DomainStats = Vec<PixelStats>;
+
+/// The domain blocks of one size which are searched for a range block.
+struct DomainPool<I> {
+    blocks: Vec<SquaredBlock<I>>,
+
+    /// The factor by which the domain blocks are larger than the range block
+    scale: u32,
+
+    index: Option<Arc<DomainIndex>>,
+
+    /// The pixel statistics of each downscaled domain block, if the variance prefilter is enabled
+    stats: Option<Arc<DomainStats>>,
+}
+
+/// A target the [Compressor] aims for instead of a fixed [ErrorThreshold].
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Target {
+    TransformationCount(usize),
+    #[cfg(feature = "persist-as-binary-v1")]
+    SizeBytes(u64),
+    Psnr(f64),
+}
+
+impl Target {
+    fn value(&self) -> f64 {
+        match self {
+            Target::TransformationCount(count) => *count as f64,
+            #[cfg(feature = "persist-as-binary-v1")]
+            Target::SizeBytes(bytes) => *bytes as f64,
+            Target::Psnr(db) => *db,
+        }
+    }
+
+    /// The relative deviation from the target which is considered good enough.
+    fn tolerance(&self) -> f64 {
+        match self {
+            Target::Psnr(db) => TARGET_PSNR_TOLERANCE_DB / db,
+            _ => TARGET_TOLERANCE,
+        }
+    }
+
+    /// Measures `compressed`, which is a compression of `original` with range blocks
+    /// extended by `overlap`.
+    fn measure<I: Image>(&self, compressed: &Compressed, original: &I, overlap: u32) -> f64 {
+        match self {
+            Target::TransformationCount(_) => compressed.transformations.len() as f64,
+            #[cfg(feature = "persist-as-binary-v1")]
+            Target::SizeBytes(_) => compressed
+                .estimated_size_binary_v1()
+                .expect("Quadtree compressions are always serializable") as f64,
+            Target::Psnr(_) => {
+                let options = decompress::Options {
+                    iterations: TARGET_PSNR_ITERATIONS,
+                    overlap,
+                    ..Default::default()
+                };
+                let decompressed = decompress::decompress(compressed.clone(), options)
+                    .expect("The default initial image has the size of the compressed image");
+                metrics::psnr(original, &decompressed.image).expect("Decompressed image has the original size")
+            }
+        }
+    }
+}
+
+/// A combination of settings which trades compression speed for quality, see
+/// [Compressor::with_preset].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CompressionPreset {
+    /// A lenient error threshold, range blocks of at least 4x4 pixels and an approximate
+    /// search without rotations
+    Fast,
+
+    /// The default error threshold, range blocks of at least 2x2 pixels and an approximate
+    /// search over more domain blocks, including rotations
+    Balanced,
+
+    /// A strict error threshold, range blocks down to single pixels and an exhaustive search
+    /// of all domain blocks
+    Best,
 }
 
+/// Options which restrict the domain blocks searched for a range block.
+#[derive(Clone, Debug, PartialEq)]
+struct SearchOptions {
+    /// Whether rotated domain blocks are searched
+    rotations: bool,
+
+    /// If set, domain blocks are skipped if their variance and the variance of the range
+    /// block differ by more than this factor
+    variance_prefilter: Option<f64>,
+
+    /// If set, only this many domain blocks with the most similar [Features] are searched
+    ann_search: Option<usize>,
+
+    /// The factors by which searched domain blocks are larger than the range block
+    domain_scales: Vec<u32>,
+}
+
+impl SearchOptions {
+    /// The rotations of domain blocks which are searched.
+    fn rotations(&self) -> Vec<Rotation> {
+        match self.rotations {
+            true => vec![Rotation::By0, Rotation::By90, Rotation::By180, Rotation::By270],
+            false => vec![Rotation::By0],
+        }
+    }
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            rotations: true,
+            variance_prefilter: None,
+            ann_search: None,
+            domain_scales: vec![2],
+        }
+    }
+}
+
+/// The relative deviation from a [Target] which is considered good enough.
+const TARGET_TOLERANCE: f64 = 0.05;
+
+/// The deviation from a [Target::Psnr] in dB which is considered good enough.
+const TARGET_PSNR_TOLERANCE_DB: f64 = 0.5;
+
+/// The amount of iterations to decompress with while aiming for a [Target::Psnr].
+const TARGET_PSNR_ITERATIONS: u8 = 8;
+
+/// The maximal amount of compressions while searching for a [Target].
+const TARGET_MAX_ATTEMPTS: u32 = 16;
+
+/// The range of RMS thresholds which is searched while aiming for a [Target].
+const TARGET_RMS_RANGE: (f64, f64) = (0.0, 128.0);
+
 #[derive(Error, Debug, Eq, PartialEq)]
 pub enum CompressionError {
     #[error(transparent)]
@@ -24,6 +184,12 @@ pub enum CompressionError {
 
     #[error(transparent)]
     NoPowerOfTwo(#[from] NoPowerOfTwo),
+
+    #[error("The provided image is not a square ({0})")]
+    NotSquare(Size),
+
+    #[error("A target size in bytes requires domain blocks which are twice the size of range blocks")]
+    UnsupportedTargetSize,
 }
 
 impl<I> Compressor<PowerOfTwo<Square<I>>>
@@ -33,14 +199,115 @@ where
     pub fn new(image: PowerOfTwo<Square<I>>) -> Self {
         Self {
             error_threshold: ErrorThreshold::AnyBlockBelowRms((image.get_height() as f64).powf(0.5)),
+            target: None,
+            search: SearchOptions::default(),
+            overlap: 0,
+            min_block_size: None,
             progress_fn: None,
             stats: Arc::new(stats::Stats::new(image.get_height())),
+            domain_indices: Mutex::new(HashMap::new()),
+            domain_stats: Mutex::new(HashMap::new()),
             image: Arc::new(image),
         }
     }
 
     #[instrument(level = "debug", skip(self))]
     pub fn compress(self) -> Result<Compressed, CompressionError> {
+        self.compress_with_stats().map(|(compressed, _)| compressed)
+    }
+
+    /// Compresses the image like [Compressor::compress], additionally returning
+    /// [statistics](CompressionStats) about the search.
+    #[instrument(level = "debug", skip(self))]
+    pub fn compress_with_stats(self) -> Result<(Compressed, CompressionStats), CompressionError> {
+        let start = Instant::now();
+        let (compressed, psnr) = self.compress_with_target()?;
+        let mut stats = self.stats.summary(&compressed, start.elapsed());
+        stats.psnr = psnr;
+        stats.source_entropy = metrics::entropy(&*self.image);
+        info!("{}", stats);
+        Ok((compressed, stats))
+    }
+
+    /// Compresses the image like [Compressor::compress], additionally storing the residual
+    /// between the image and its decompression. This allows to reproduce the image exactly.
+    #[instrument(level = "debug", skip(self))]
+    pub fn compress_lossless(self) -> Result<LosslessCompressed, CompressionError> {
+        let image = self.image.clone();
+        let options = decompress::Options { overlap: self.overlap, ..Default::default() };
+        let (iterations, overlap) = (options.iterations, options.overlap);
+        let compressed = self.compress()?;
+
+        let decompressed = decompress::decompress(compressed.clone(), options)
+            .expect("The default initial image has the size of the compressed image")
+            .image;
+        let residual = image.pixels()
+            .zip(decompressed.pixels())
+            .map(|(original, decompressed)| original as i16 - decompressed as i16)
+            .collect();
+
+        Ok(LosslessCompressed {
+            compressed,
+            iterations,
+            overlap,
+            residual,
+        })
+    }
+
+    /// Compresses the image, returning the PSNR of the decompressed image if it was measured
+    /// while aiming for a [Target::Psnr].
+    fn compress_with_target(&self) -> Result<(Compressed, Option<f64>), CompressionError> {
+        // The size is estimated with the binary v1 format, which only supports a domain scale of 2
+        #[cfg(feature = "persist-as-binary-v1")]
+        if matches!(self.target, Some(Target::SizeBytes(_))) && self.search.domain_scales != [2] {
+            return Err(CompressionError::UnsupportedTargetSize);
+        }
+
+        match self.target {
+            None => Ok((self.compress_with_threshold(&self.error_threshold)?, None)),
+            Some(target @ Target::Psnr(_)) => self.compress_towards(target).map(|(compressed, psnr)| (compressed, Some(psnr))),
+            Some(target) => self.compress_towards(target).map(|(compressed, _)| (compressed, None)),
+        }
+    }
+
+    /// Bisects the RMS error threshold until the compression hits `target` (within
+    /// [Target::tolerance]) or [TARGET_MAX_ATTEMPTS] compressions were made.
+    /// Returns the compression closest to the target and its measured value.
+    fn compress_towards(&self, target: Target) -> Result<(Compressed, f64), CompressionError> {
+        let (mut lower, mut upper) = TARGET_RMS_RANGE;
+        let mut best: Option<(f64, f64, Compressed)> = None;
+
+        for attempt in 0..TARGET_MAX_ATTEMPTS {
+            let rms = (lower + upper) / 2.0;
+            self.stats.reset();
+            let compressed = self.compress_with_threshold(&ErrorThreshold::AnyBlockBelowRms(rms))?;
+            let measured = target.measure(&compressed, self.image.as_ref(), self.overlap);
+            let deviation = (measured - target.value()).abs() / target.value();
+            debug!("Attempt {}: RMS threshold {} yields {} (target {:?})", attempt, rms, measured, target);
+
+            // A larger threshold accepts more mappings, i.e. yields fewer transformations
+            // and a lower quality
+            if measured > target.value() {
+                lower = rms;
+            } else {
+                upper = rms;
+            }
+
+            if best.as_ref().is_none_or(|(best_deviation, _, _)| deviation < *best_deviation) {
+                best = Some((deviation, measured, compressed));
+            }
+
+            if deviation <= target.tolerance() {
+                break;
+            }
+        }
+
+        let (deviation, measured, compressed) = best.expect("At least one compression attempt is made");
+        info!("Closest compression deviates {:.1}% from target {:?}", 100.0 * deviation, target);
+        Ok((compressed, measured))
+    }
+
+    fn compress_with_threshold(&self, error_threshold: &ErrorThreshold) -> Result<Compressed, CompressionError> {
         let size = self.image.get_size();
         info!("Compressing image size {size}", size=size);
 
@@ -51,8 +318,7 @@ where
         let range_blocks = self
             .image
             .as_inner()
-            .squared_blocks(range_block_size)?
-            .into_iter()
+            .squared_blocks_iter(range_block_size)?
             .map(PowerOfTwo::new)
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -71,7 +337,7 @@ where
 
         let transformations = range_blocks
             .into_par_iter()
-            .flat_map(|rb| self.find_transformations_recursive(Arc::new(rb)))
+            .flat_map(|rb| self.find_transformations_recursive(Arc::new(rb), error_threshold))
             .flatten()
             .collect::<Vec<_>>();
 
@@ -81,36 +347,57 @@ where
         })
     }
 
-    fn find_transformations_recursive(&self, rb: Arc<PowerOfTwo<SquaredBlock<I>>>) -> Result<Vec<Transformation>, CompressionError> {
+    fn find_transformations_recursive(&self, rb: Arc<PowerOfTwo<SquaredBlock<I>>>, error_threshold: &ErrorThreshold) -> Result<Vec<Transformation>, CompressionError> {
         debug!("Finding transformation for range block {}", rb);
         let rb = rb.as_inner();
 
         // Partition image into suitable domain blocks
-        let domain_blocks = self.image.as_inner().squared_blocks(2 * rb.size)?;
+        let domains = self.search.domain_scales
+            .iter()
+            .filter(|&&scale| scale * rb.size <= self.image.get_height())
+            .map(|&scale| -> Result<DomainPool<I>, CompressionError> {
+                let blocks = self.image.as_inner().squared_blocks(scale * rb.size)?;
+                let index = self.domain_index(&blocks, scale);
+                let stats = self.domain_stats(&blocks, scale);
+                Ok(DomainPool { blocks, scale, index, stats })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The smallest range blocks are mapped by any domain block, as they can not be split
+        let at_min_block_size = self.min_block_size.is_some_and(|min| rb.get_height() <= min);
+        let (error_threshold, search) = match at_min_block_size {
+            true => (
+                &ErrorThreshold::AnyBlockBelowRms(f64::INFINITY),
+                &SearchOptions { variance_prefilter: None, ..self.search.clone() },
+            ),
+            false => (error_threshold, &self.search),
+        };
 
-        match Transformation::find(domain_blocks, rb.as_ref(), self.error_threshold) {
+        match Transformation::find(&domains, rb.as_ref(), error_threshold, search, &self.stats) {
             Some(transformation) => {
                 debug!("For range block {}, found best matching domain block", rb);
+                let transformation = self.fit_overlap(transformation, rb.as_ref());
 
-                if let Some(progress_fn) = self.progress_fn.clone() {
-                    self.stats.report_block_mapped(rb.get_height());
-                    progress_fn(self.stats.report());
+                let progress = self.stats.report_block_mapped(rb.get_height());
+                if let Some(progress_fn) = self.progress_fn.as_ref() {
+                    if self.stats.should_deliver(&progress) {
+                        progress_fn(progress);
+                    }
                 }
 
                 Ok(vec![transformation])
             }
             None => {
                 debug!("For range block {}, found no matching domain block", rb);
-                if rb.get_height() <= 1 {
+                if rb.get_height() <= 1 || at_min_block_size {
                     warn!("Unable to map range block {}", rb);
                     Ok(vec![]) // TODO: Should this really be an Ok?
                 } else {
-                    let res = rb.squared_blocks((rb.size as f64 / 2.0) as u32)?
-                        .into_par_iter()
+                    let res = rb.squared_blocks_iter((rb.size as f64 / 2.0) as u32)?
                         .map(PowerOfTwo::new)
                         .collect::<Result<Vec<_>, _>>()?
                         .into_iter()
-                        .flat_map(|nrb| self.find_transformations_recursive(Arc::new(nrb)))
+                        .flat_map(|nrb| self.find_transformations_recursive(Arc::new(nrb), error_threshold))
                         .flatten()
                         .collect::<Vec<_>>();
 
@@ -120,11 +407,191 @@ where
         }
     }
 
+    /// Fits brightness and saturation of `transformation` to the range block extended by
+    /// the [overlap](Compressor::with_overlap), keeping the transformation if no valid mapping exists.
+    fn fit_overlap(&self, transformation: Transformation, range_block: &SquaredBlock<I>) -> Transformation {
+        if self.overlap == 0 {
+            return transformation;
+        }
+
+        let scale = transformation.domain.block_size / transformation.range.block_size;
+        let domain_block = SquaredBlock {
+            image: range_block.image.clone(),
+            size: transformation.domain.block_size,
+            origin: transformation.domain.origin,
+        };
+        let domain_block = ExtendedBlock::around(&domain_block, scale * self.overlap)
+            .downscale_by(scale)
+            .rot(transformation.rotation);
+        let range_block = ExtendedBlock::around(range_block, self.overlap);
+
+        match Mapping::compute(&domain_block, &range_block) {
+            Some(mapping) => Transformation {
+                brightness: mapping.brightness,
+                saturation: mapping.saturation,
+                ..transformation
+            },
+            None => transformation,
+        }
+    }
+
+    /// Returns the index over `domain_blocks` downscaled by `scale`, if the
+    /// [approximate search](Compressor::with_ann_search) is enabled. The index is built on first use.
+    fn domain_index(&self, domain_blocks: &[SquaredBlock<I>], scale: u32) -> Option<Arc<DomainIndex>> {
+        self.search.ann_search?;
+
+        let domain_block_size = domain_blocks.first()?.size;
+        let mut domain_indices = self.domain_indices.lock().expect("Domain index lock is poisoned");
+        let index = domain_indices.entry((domain_block_size, scale)).or_insert_with(|| {
+            debug!("Indexing {} domain blocks with size {}x{}", domain_blocks.len(), domain_block_size, domain_block_size);
+            let items = domain_blocks
+                .iter()
+                .enumerate()
+                .flat_map(|(i, d)| {
+                    // Every rotation reads the downscaled block, which is hence only computed once
+                    let d = Arc::new(d.downscale_by(scale).to_owned_image());
+                    self.search
+                        .rotations()
+                        .into_iter()
+                        .map(move |rotation| (Features::of(&d.clone().rot(rotation)), (i, rotation)))
+                })
+                .collect();
+            Arc::new(VpTree::new(items))
+        });
+
+        Some(index.clone())
+    }
+
+    /// Returns the pixel statistics of `domain_blocks` downscaled by `scale`, if the
+    /// [variance prefilter](Compressor::with_variance_prefilter) is enabled. The statistics
+    /// are computed on first use.
+    fn domain_stats(&self, domain_blocks: &[SquaredBlock<I>], scale: u32) -> Option<Arc<DomainStats>> {
+        self.search.variance_prefilter?;
+
+        let domain_block_size = domain_blocks.first()?.size;
+        let mut domain_stats = self.domain_stats.lock().expect("Domain stats lock is poisoned");
+        let stats = domain_stats.entry((domain_block_size, scale)).or_insert_with(|| {
+            Arc::new(domain_blocks.iter().map(|d| PixelStats::of(&d.downscale_by(scale))).collect())
+        });
+
+        Some(stats.clone())
+    }
+
+    /// Applies the settings of `preset`, i.e. the error threshold, the minimal block size and
+    /// the domain search. Settings can still be overridden by calling the individual `with_*`
+    /// methods afterwards.
+    pub fn with_preset(mut self, preset: CompressionPreset) -> Self {
+        let default_rms = (self.image.get_height() as f64).powf(0.5);
+        let (rms, min_block_size, search) = match preset {
+            CompressionPreset::Fast => (2.0 * default_rms, Some(4), SearchOptions {
+                rotations: false,
+                variance_prefilter: Some(4.0),
+                ann_search: Some(16),
+                ..SearchOptions::default()
+            }),
+            CompressionPreset::Balanced => (default_rms, Some(2), SearchOptions {
+                rotations: true,
+                variance_prefilter: Some(16.0),
+                ann_search: Some(64),
+                ..SearchOptions::default()
+            }),
+            CompressionPreset::Best => (0.5 * default_rms, None, SearchOptions::default()),
+        };
+        self.error_threshold = ErrorThreshold::AnyBlockBelowRms(rms);
+        self.min_block_size = min_block_size;
+        self.search = search;
+        self
+    }
+
     pub fn with_error_threshold(mut self, error_threshold: ErrorThreshold) -> Self {
         self.error_threshold = error_threshold;
         self
     }
 
+    /// Searches for an error threshold such that the compression consists of roughly
+    /// `count` transformations. Overrides [Compressor::with_error_threshold].
+    pub fn with_target_transformation_count(mut self, count: usize) -> Self {
+        self.target = Some(Target::TransformationCount(count));
+        self
+    }
+
+    /// Searches for an error threshold such that the compression persisted with
+    /// [Compressed::persist_as_binary_v1] takes roughly `bytes` bytes.
+    /// Overrides [Compressor::with_error_threshold].
+    #[cfg(feature = "persist-as-binary-v1")]
+    pub fn with_target_size_bytes(mut self, bytes: u64) -> Self {
+        self.target = Some(Target::SizeBytes(bytes));
+        self
+    }
+
+    /// Enables or disables the search for rotated domain blocks. Without rotations, only a
+    /// quarter of the mappings is computed and every transformation uses [Rotation::By0](crate::model::Rotation::By0).
+    pub fn with_rotations(mut self, enabled: bool) -> Self {
+        self.search.rotations = enabled;
+        self
+    }
+
+    /// Skips domain blocks whose variance lies outside of the variance of the range block
+    /// divided and multiplied by `tolerance`, without computing their mapping. A larger
+    /// `tolerance` skips fewer domain blocks. Variances below one gray level squared are
+    /// considered equal, such that flat range blocks are still mapped by flat domain blocks.
+    pub fn with_variance_prefilter(mut self, tolerance: f64) -> Self {
+        self.search.variance_prefilter = Some(tolerance);
+        self
+    }
+
+    /// Only searches the `k` domain blocks whose downsampled and normalized pixels are most
+    /// similar to the range block, instead of all domain blocks. Speeds up the compression
+    /// of large images considerably, at the cost of possibly missing better mappings.
+    pub fn with_ann_search(mut self, k: usize) -> Self {
+        self.search.ann_search = Some(k);
+        self
+    }
+
+    /// Searches domain blocks which are larger than the range block by each of `scales`,
+    /// instead of only domain blocks twice the size of the range block.
+    ///
+    /// Compressions with scales other than two can not be persisted with
+    /// [Compressed::persist_as_binary_v1] or [Compressed::persist_as_binary_v2], which fail with
+    /// an [InvalidBlockSize](crate::persistence::binary_v1::SerializationError::InvalidBlockSize) error.
+    ///
+    /// # Panics
+    /// If `scales` is empty or contains a scale which is not a power of two larger than one.
+    pub fn with_domain_scales(mut self, scales: &[u32]) -> Self {
+        assert!(!scales.is_empty(), "At least one domain scale is required");
+        assert!(
+            scales.iter().all(|scale| *scale > 1 && scale.is_power_of_two()),
+            "Domain scales need to be powers of two larger than one, were {:?}", scales
+        );
+        self.search.domain_scales = scales.to_vec();
+        self
+    }
+
+    /// Does not split range blocks of `size` or smaller any further. Instead, they are mapped
+    /// by the first domain block found, regardless of the error threshold and the
+    /// [variance prefilter](Compressor::with_variance_prefilter). Fewer and larger blocks make
+    /// the compression faster and smaller, but less accurate.
+    pub fn with_min_block_size(mut self, size: u32) -> Self {
+        self.min_block_size = Some(size);
+        self
+    }
+
+    /// Extends each range block by `margin` pixels on each side when fitting brightness and
+    /// saturation, such that neighbouring blocks overlap. Decompress with the same
+    /// [overlap](crate::decompress::Options::overlap) to blend the overlapping regions.
+    pub fn with_overlap(mut self, margin: u32) -> Self {
+        self.overlap = margin;
+        self
+    }
+
+    /// Searches for an error threshold such that the decompressed image has roughly a
+    /// [PSNR](metrics::psnr) of `db`. The achieved PSNR is part of the [CompressionStats].
+    /// Overrides [Compressor::with_error_threshold].
+    pub fn with_target_psnr(mut self, db: f64) -> Self {
+        self.target = Some(Target::Psnr(db));
+        self
+    }
+
     pub fn with_progress_reporter<F: Fn(stats::StatsReporting) + Send + Sync + 'static>(
         mut self,
         progress_fn: F,
@@ -132,31 +599,102 @@ where
         self.progress_fn = Some(Arc::new(progress_fn));
         self
     }
+
+    /// Like [Compressor::with_progress_reporter], but calls `progress_fn` at most once
+    /// per `min_interval`. The final report, i.e. when the compression is
+    /// [finished](StatsReporting::finished), is always delivered.
+    pub fn with_progress_reporter_throttled<F: Fn(stats::StatsReporting) + Send + Sync + 'static>(
+        mut self,
+        progress_fn: F,
+        min_interval: Duration,
+    ) -> Self {
+        self.stats = Arc::new(stats::Stats::with_min_report_interval(self.image.get_height(), min_interval));
+        self.with_progress_reporter(progress_fn)
+    }
 }
 
+impl<I> crate::compress::Compressor for Compressor<PowerOfTwo<Square<I>>>
+where
+    I: Image + Send,
+{
+    fn compress_with_stats(self) -> Result<(Compressed, CompressionStats), CompressionError> {
+        Compressor::compress_with_stats(self)
+    }
+
+    fn compress_boxed_with_stats(self: Box<Self>) -> Result<(Compressed, CompressionStats), CompressionError> {
+        Compressor::compress_with_stats(*self)
+    }
+
+    fn set_error_threshold(&mut self, error_threshold: ErrorThreshold) {
+        self.error_threshold = error_threshold;
+    }
+
+    fn set_progress_reporter(&mut self, progress_fn: Arc<dyn Fn(StatsReporting) + Send + Sync>) {
+        self.progress_fn = Some(progress_fn);
+    }
+
+    fn set_progress_reporter_throttled(&mut self, progress_fn: Arc<dyn Fn(StatsReporting) + Send + Sync>, min_interval: Duration) {
+        self.stats = Arc::new(stats::Stats::with_min_report_interval(self.image.get_height(), min_interval));
+        self.progress_fn = Some(progress_fn);
+    }
+}
+
+/// The variance below which blocks are considered flat by the variance prefilter.
+const MIN_PREFILTER_VARIANCE: f64 = 1.0;
+
 impl Transformation {
     fn find<I: Image + Send>(
-        domain_blocks: Vec<SquaredBlock<I>>,
+        domains: &[DomainPool<I>],
         range_block: &SquaredBlock<I>,
-        error_threshold: ErrorThreshold,
+        error_threshold: &ErrorThreshold,
+        search: &SearchOptions,
+        stats: &stats::Stats,
     ) -> Option<Self> {
-        let mapping = domain_blocks
-            .into_par_iter()
-            .map(|d| d.downscale_2x2())
-            .map(|d| d.all_rotations())
-            .flatten()
+        let acceptable_error = error_threshold.rms_for(range_block);
+        let variance_prefilter = search.variance_prefilter
+            .map(|tolerance| (tolerance, variance(range_block).max(MIN_PREFILTER_VARIANCE)));
+        let passes_prefilter = |domain: &DomainPool<I>, i: usize| match (variance_prefilter, &domain.stats) {
+            // Since the saturation is at most 1, a domain block can not reproduce a range block
+            // with a much higher variance. A domain block with a much higher variance needs a
+            // small saturation, which rarely beats a domain block of similar variance.
+            (Some((tolerance, range_variance)), Some(stats)) => {
+                let domain_variance = stats[i].variance.max(MIN_PREFILTER_VARIANCE);
+                (range_variance / tolerance..=range_variance * tolerance).contains(&domain_variance)
+            }
+            _ => true,
+        };
+        let passes_prefilter = &passes_prefilter;
+        let range_features = search.ann_search.map(|_| Features::of(range_block));
+        let range_features = range_features.as_ref();
+
+        let candidates = domains.par_iter().flat_map(move |domain| {
+            let scale = domain.scale;
+            match (&domain.index, range_features, search.ann_search) {
+                (Some(index), Some(range_features), Some(k)) => Either::Left(index
+                    .nearest(range_features, k)
+                    .into_par_iter()
+                    .filter(move |&&(i, _)| passes_prefilter(domain, i))
+                    .map(move |&(i, rotation)| domain.blocks[i].downscale_by(scale).rot(rotation))),
+                _ => Either::Right(domain.blocks
+                    .par_iter()
+                    .enumerate()
+                    .filter(move |&(i, _)| passes_prefilter(domain, i))
+                    .map(move |(_, d)| d.downscale_by(scale))
+                    .map(move |d| if search.rotations { d.all_rotations() } else { vec![d.rot_0()] })
+                    .flatten()),
+            }
+        });
+
+        let mapping = candidates
             .map(|db| {
                 let mapping = Mapping::compute(&db, range_block);
                 debug!("Mapping: {:?}", mapping);
+                stats.report_mapping_computed(mapping.is_some());
                 (db, mapping)
             })
             .filter(|(_, mapping)| mapping.is_some())
             .map(|(db, mapping)| (db, mapping.unwrap()))
-            .find_any(|(_, mapping)| match error_threshold {
-                ErrorThreshold::AnyBlockBelowRms(acceptable_error) => {
-                    mapping.error <= acceptable_error
-                }
-            });
+            .find_any(|(_, mapping)| mapping.error <= acceptable_error);
 
         if let Some((db, mapping)) = mapping {
             debug!("Using mapping: {:?}", mapping);
@@ -179,18 +717,53 @@ impl Transformation {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ErrorThreshold {
     AnyBlockBelowRms(f64),
+
+    /// Uses a separate RMS threshold per range block size, e.g. to be strict on large
+    /// blocks and lenient on small ones. Block sizes without an entry in `thresholds`
+    /// use the `default` threshold.
+    PerBlockSize {
+        thresholds: Vec<(u32, f64)>,
+        default: f64,
+    },
+
+    /// Accepts a mapping if its RMS error is at most `factor` times the standard deviation
+    /// of the range block, i.e. is lenient on noisy and strict on flat blocks.
+    RelativeToVariance(f64),
+}
+
+impl ErrorThreshold {
+    /// The RMS error a mapping onto `range_block` may have at most.
+    pub fn rms_for<I: Image>(&self, range_block: &I) -> f64 {
+        match self {
+            ErrorThreshold::AnyBlockBelowRms(rms) => *rms,
+            ErrorThreshold::PerBlockSize { thresholds, default } => thresholds
+                .iter()
+                .find(|(size, _)| *size == range_block.get_width())
+                .map_or(*default, |(_, rms)| *rms),
+            ErrorThreshold::RelativeToVariance(factor) => factor * variance(range_block).sqrt(),
+        }
+    }
 }
 
 mod stats {
-    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::fmt::{Display, Formatter};
+    use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+    use std::time::{Duration, Instant};
+
+    use itertools::Itertools;
+
+    use crate::model::Compressed;
 
     #[derive(Clone, Copy, Debug)]
     pub struct StatsReporting {
         pub area_covered: u32,
         pub total_area: u32,
+
+        /// The amount of mappings between domain and range blocks computed so far
+        pub mappings_computed: u64,
     }
 
     impl StatsReporting {
@@ -199,10 +772,57 @@ mod stats {
         }
     }
 
+    /// A summary of a finished compression.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct CompressionStats {
+        /// The wall-clock duration of the compression
+        pub duration: Duration,
+
+        /// The amount of mappings computed between a domain and a range block
+        pub mappings_computed: u64,
+
+        /// The amount of domain block candidates which were rejected because
+        /// their saturation exceeded the allowed bounds
+        pub saturation_rejections: u64,
+
+        /// The amount of mapped range blocks per range block size, ordered from the
+        /// largest to the smallest block size
+        pub blocks_per_level: Vec<(u32, u64)>,
+
+        /// The PSNR of the decompressed image, if it was measured while aiming for a target PSNR
+        pub psnr: Option<f64>,
+
+        /// The [entropy](crate::metrics::entropy) of the compressed image in bits per pixel
+        pub source_entropy: f64,
+    }
+
+    impl Display for CompressionStats {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            writeln!(f, "Duration: {:.2?}", self.duration)?;
+            writeln!(f, "Mappings computed: {}", self.mappings_computed)?;
+            writeln!(f, "Rejected by saturation: {}", self.saturation_rejections)?;
+            write!(f, "Blocks per level: {}", self.blocks_per_level
+                .iter()
+                .map(|(block_size, count)| format!("{}x{}: {}", block_size, block_size, count))
+                .join(", "))?;
+            write!(f, "\nSource entropy: {:.3} bits per pixel", self.source_entropy)?;
+            if let Some(psnr) = self.psnr {
+                write!(f, "\nPSNR: {:.2} dB", psnr)?;
+            }
+            Ok(())
+        }
+    }
+
     /// Records the area of the image that has already been mapped
     pub struct Stats {
         pub image_size_squared: u32,
         pub area_covered: AtomicU32,
+        pub mappings_computed: AtomicU64,
+        pub saturation_rejections: AtomicU64,
+        min_report_interval: Option<Duration>,
+        created: Instant,
+        /// The time of the last delivered report, in nanoseconds since `created`
+        last_report: AtomicU64,
     }
 
     impl Stats {
@@ -210,19 +830,126 @@ mod stats {
             Self {
                 image_size_squared: image_size * image_size,
                 area_covered: AtomicU32::new(0),
+                mappings_computed: AtomicU64::new(0),
+                saturation_rejections: AtomicU64::new(0),
+                min_report_interval: None,
+                created: Instant::now(),
+                last_report: AtomicU64::new(0),
             }
         }
 
-        pub fn report_block_mapped(&self, range_block_size: u32) {
-            self.area_covered
-                .fetch_add(range_block_size * range_block_size, Ordering::SeqCst);
+        /// Creates stats which [deliver](Stats::should_deliver) reports at most once per `min_interval`.
+        pub fn with_min_report_interval(image_size: u32, min_interval: Duration) -> Self {
+            Self {
+                min_report_interval: Some(min_interval),
+                ..Self::new(image_size)
+            }
         }
 
-        pub fn report(&self) -> StatsReporting {
+        /// Records a mapped range block and returns the progress including that block.
+        pub fn report_block_mapped(&self, range_block_size: u32) -> StatsReporting {
+            let block_area = range_block_size * range_block_size;
+            let area_covered = self.area_covered.fetch_add(block_area, Ordering::SeqCst) + block_area;
             StatsReporting {
-                area_covered: self.area_covered.load(Ordering::SeqCst),
+                area_covered,
                 total_area: self.image_size_squared,
+                mappings_computed: self.mappings_computed.load(Ordering::Relaxed),
+            }
+        }
+
+        /// Decides whether `progress` should be delivered to the progress reporter, taking
+        /// the minimal report interval into account. Finishing progress is always delivered.
+        pub fn should_deliver(&self, progress: &StatsReporting) -> bool {
+            let Some(min_interval) = self.min_report_interval else {
+                return true;
+            };
+
+            if progress.finished() {
+                return true;
             }
+
+            let now = self.created.elapsed().as_nanos() as u64;
+            let last_report = self.last_report.load(Ordering::Relaxed);
+            now.saturating_sub(last_report) >= min_interval.as_nanos() as u64
+                && self.last_report
+                .compare_exchange(last_report, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        }
+
+        pub fn report_mapping_computed(&self, within_saturation_bounds: bool) {
+            self.mappings_computed.fetch_add(1, Ordering::Relaxed);
+            if !within_saturation_bounds {
+                self.saturation_rejections.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        pub fn reset(&self) {
+            self.area_covered.store(0, Ordering::SeqCst);
+        }
+
+        /// Summarizes the recorded statistics. The amount of blocks per level is taken
+        /// from `compressed`, since multiple compressions may have been recorded
+        /// while searching for a target.
+        pub fn summary(&self, compressed: &Compressed, duration: Duration) -> CompressionStats {
+            let blocks_per_level = compressed
+                .transformations
+                .iter()
+                .map(|t| t.range.block_size)
+                .counts()
+                .into_iter()
+                .map(|(block_size, count)| (block_size, count as u64))
+                .sorted_by(|(a, _), (b, _)| b.cmp(a))
+                .collect();
+
+            CompressionStats {
+                duration,
+                mappings_computed: self.mappings_computed.load(Ordering::Relaxed),
+                saturation_rejections: self.saturation_rejections.load(Ordering::Relaxed),
+                blocks_per_level,
+                psnr: None,
+                source_entropy: 0.0,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Maps all pixels of an image as 1x1 blocks and counts the delivered reports.
+        fn count_delivered_reports(stats: &Stats) -> (u32, bool) {
+            let mut delivered = 0;
+            let mut finished = false;
+            for _ in 0..stats.image_size_squared {
+                let progress = stats.report_block_mapped(1);
+                if stats.should_deliver(&progress) {
+                    delivered += 1;
+                    finished = progress.finished();
+                }
+            }
+            (delivered, finished)
+        }
+
+        #[test]
+        fn unthrottled_stats_deliver_every_report() {
+            let stats = Stats::new(32);
+            assert_eq!(count_delivered_reports(&stats), (32 * 32, true));
+        }
+
+        #[test]
+        fn throttled_stats_deliver_fewer_reports() {
+            let (unthrottled, _) = count_delivered_reports(&Stats::with_min_report_interval(32, Duration::ZERO));
+            let (throttled, finished) = count_delivered_reports(&Stats::with_min_report_interval(32, Duration::from_secs(60)));
+
+            assert_eq!(unthrottled, 32 * 32);
+            assert!(throttled < unthrottled, "Expected fewer than {} reports, got {}", unthrottled, throttled);
+            assert!(finished);
+        }
+
+        #[test]
+        fn throttled_stats_always_deliver_the_final_report() {
+            let stats = Stats::with_min_report_interval(32, Duration::from_secs(3600));
+            assert_eq!(count_delivered_reports(&stats), (1, true));
         }
     }
 }
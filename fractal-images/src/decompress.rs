@@ -1,18 +1,147 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::mem;
+use std::path::Path;
 use std::sync::Arc;
 
-use tracing::instrument;
+use image::{DynamicImage, ImageFormat, ImageResult};
+use thiserror::Error;
+use tracing::{debug, instrument};
 
-use crate::image::{Image, MutableImage};
-use crate::image::SquaredBlock;
-use crate::image::IntoDownscaled;
-use crate::image::OwnedImage;
+use crate::image::{average, Coords, Image, MutableImage, Pixel, Size};
+use crate::image::{DownscaledBy, ExtendedBlock, SquaredBlock};
+use crate::image::{IntoDownscaled, IntoUpscaled};
+use crate::image::{IntoOwnedImage, OwnedImage};
 use crate::image::IntoRotated;
-use crate::model::{Compressed, Transformation};
+use crate::compress::color::YCbCrPlanes;
+use crate::metrics;
+use crate::postprocess;
+use crate::postprocess::DeblockStrength;
+use crate::preprocessing::AsDynamicImage;
+use crate::coords;
+use crate::model::{Block, ColorCompressed, Compressed, LosslessCompressed, Rotation, Transformation, ValidationError};
+use fixed_point::FixedPointMapping;
+
+mod coverage;
+mod fixed_point;
+mod region;
+
+pub use coverage::Coverage;
+pub use region::decompress_region;
+
+/// Called with the number of an iteration and the image after it, see [Options::on_iteration].
+pub type IterationCallback = Box<dyn FnMut(u8, &OwnedImage)>;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Options {
     pub iterations: u8,
+
+    /// Keeps a copy of the image after each iteration in [Decompressed::iterations].
+    /// Prefer [Options::on_iteration], which does not need to keep the copies.
     pub keep_each_iteration: bool,
+
+    /// The margin by which range blocks are extended on each side. Overlapping pixels
+    /// are blended, with a weight decreasing linearly towards the outer edge of the margin.
+    /// Should equal the overlap the image was compressed with.
+    pub overlap: u32,
+
+    /// The image the first iteration is applied to.
+    pub initial_image: InitialImage,
+
+    /// If set, the decompression stops as soon as the [MSE](metrics::mse) between two
+    /// consecutive iterations falls below this threshold. [Options::iterations] is then an
+    /// upper bound of the iterations.
+    pub convergence: Option<f64>,
+
+    /// A function which is called with the initial image (as iteration 0) and with the
+    /// image after each iteration. [decompress_rgb] calls it for the luma plane only.
+    pub on_iteration: Option<IterationCallback>,
+
+    /// The order in which the transformations are applied within an iteration.
+    pub application_order: ApplicationOrder,
+
+    /// If set, the boundaries of the range blocks are smoothed with [postprocess::deblock]
+    /// after the last iteration. The images of the iterations are not smoothed.
+    pub deblock: Option<DeblockStrength>,
+
+    /// Reports in [Decompressed::coverage] which pixels are not written by any range block.
+    pub track_coverage: bool,
+
+    /// Runs the first half of the iterations at a reduced resolution, which is faster and
+    /// converges to a similar image, and upscales the result for the remaining iterations.
+    /// The resolution is quartered if every block allows it and halved otherwise, where
+    /// transformations whose blocks can not be halved are skipped until the full resolution.
+    /// Observers receive the upscaled images of the reduced iterations.
+    pub pyramid: bool,
+}
+
+/// The order in which transformations are applied within an iteration.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ApplicationOrder {
+    /// Transformations with larger range blocks are applied first, ties are applied row by
+    /// row from their range block origin. The transformations are applied in place, i.e. each
+    /// one reads the blocks the previous transformations of the same iteration wrote. The
+    /// smaller blocks thereby refine the larger ones within one iteration, which converges
+    /// faster than [ApplicationOrder::FileOrder].
+    ///
+    /// With an [overlap](Options::overlap), the blended contributions are computed from the
+    /// previous iteration like for [ApplicationOrder::FileOrder].
+    LargestBlockFirst,
+
+    /// The order of the compressed file, where each transformation reads the image of the
+    /// previous iteration
+    FileOrder,
+}
+
+impl ApplicationOrder {
+    fn sorted(self, transformations: &[Transformation]) -> Vec<Transformation> {
+        let mut transformations = transformations.to_vec();
+        if self == ApplicationOrder::LargestBlockFirst {
+            transformations.sort_by_key(|transformation| {
+                let range = transformation.range;
+                (Reverse(range.block_size), range.origin.y, range.origin.x)
+            });
+        }
+        transformations
+    }
+}
+
+impl Debug for Options {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Options")
+            .field("iterations", &self.iterations)
+            .field("keep_each_iteration", &self.keep_each_iteration)
+            .field("overlap", &self.overlap)
+            .field("initial_image", &self.initial_image)
+            .field("convergence", &self.convergence)
+            .field("on_iteration", &self.on_iteration.is_some())
+            .field("application_order", &self.application_order)
+            .field("deblock", &self.deblock)
+            .field("track_coverage", &self.track_coverage)
+            .field("pyramid", &self.pyramid)
+            .finish()
+    }
+}
+
+impl Options {
+    /// The same options without a [callback](Options::on_iteration).
+    fn without_callback(&self) -> Self {
+        Self {
+            initial_image: self.initial_image.clone(),
+            on_iteration: None,
+            ..*self
+        }
+    }
+
+    fn validate(&self) -> Result<(), DecompressionError> {
+        if self.iterations == 0 {
+            return Err(DecompressionError::InvalidOptions("At least one iteration is required"));
+        }
+        if self.convergence.is_some_and(|threshold| threshold.is_nan() || threshold < 0.0) {
+            return Err(DecompressionError::InvalidOptions("The convergence threshold needs to be a non-negative number"));
+        }
+        Ok(())
+    }
 }
 
 impl Default for Options {
@@ -20,55 +149,675 @@ impl Default for Options {
         Options {
             iterations: 10,
             keep_each_iteration: false,
+            overlap: 0,
+            initial_image: InitialImage::FlatGray(128),
+            convergence: None,
+            on_iteration: None,
+            application_order: ApplicationOrder::LargestBlockFirst,
+            deblock: None,
+            track_coverage: false,
+            pyramid: false,
+        }
+    }
+}
+
+/// The image a decompression starts with. As the transformations are contractive, the
+/// decompression converges independently of it, but a start close to the original
+/// image converges faster.
+#[derive(Clone, Eq, PartialEq)]
+pub enum InitialImage {
+    /// An image where every pixel has the given value
+    FlatGray(Pixel),
+
+    /// Random noise, which is the same for the same seed
+    RandomSeeded(u64),
+
+    /// A custom image, which needs to have the size of the compressed image
+    Custom(OwnedImage),
+}
+
+impl InitialImage {
+    fn create(&self, size: Size) -> Result<OwnedImage, DecompressionError> {
+        match self {
+            InitialImage::FlatGray(value) => Ok(OwnedImage::filled(size, *value)),
+            InitialImage::RandomSeeded(seed) => Ok(OwnedImage::random_with_seed(size, *seed)),
+            InitialImage::Custom(image) if image.get_size() == size => Ok(image.clone()),
+            InitialImage::Custom(image) => Err(DecompressionError::InitialImageSizeMismatch {
+                expected: size,
+                actual: image.get_size(),
+            }),
+        }
+    }
+}
+
+impl Debug for InitialImage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InitialImage::FlatGray(value) => write!(f, "FlatGray({})", value),
+            InitialImage::RandomSeeded(seed) => write!(f, "RandomSeeded({})", seed),
+            InitialImage::Custom(image) => write!(f, "Custom({})", image.get_size()),
         }
     }
 }
 
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DecompressionError {
+    #[error("The initial image has a size of {actual}, but the compressed image has a size of {expected}")]
+    InitialImageSizeMismatch { expected: Size, actual: Size },
+
+    #[error("The scale factor needs to be a power of two which keeps the scaled image within u32, but was {0}")]
+    InvalidScale(u32),
+
+    #[error("The reference image has a size of {actual}, but the compressed image has a size of {expected}")]
+    ReferenceSizeMismatch { expected: Size, actual: Size },
+    #[error("The target image has a size of {actual}, but the compressed image has a size of {expected}")]
+    TargetSizeMismatch { expected: Size, actual: Size },
+
+    #[error("The region of size {size} at {origin} exceeds the image")]
+    RegionOutOfBounds { origin: Coords, size: Size },
+
+    #[error("Invalid options: {0}")]
+    InvalidOptions(&'static str),
+
+    #[error("The residual has {actual} values, but the image has {expected} pixels")]
+    ResidualSizeMismatch { expected: usize, actual: usize },
+
+    #[error("The block {block:?} exceeds the image of size {size}")]
+    BlockOutOfBounds { block: Block, size: Size },
+
+    #[error(transparent)]
+    Invalid(#[from] ValidationError),
+}
+
 pub struct Decompressed {
     pub image: OwnedImage,
     pub iterations: Option<Vec<OwnedImage>>,
+
+    /// The amount of iterations which were applied, which is lower than [Options::iterations]
+    /// if the decompression [converged](Options::convergence) early.
+    pub executed_iterations: u8,
+
+    /// The quality of each iteration, if the decompression was compared to a
+    /// [reference](decompress_with_reference).
+    pub iteration_metrics: Vec<IterationMetrics>,
+
+    /// The pixels written by a range block, if [Options::track_coverage] is set.
+    pub coverage: Option<Coverage>,
+}
+
+impl Decompressed {
+    /// Converts the decompressed image to a grayscale [DynamicImage].
+    pub fn to_dynamic_image(&self) -> DynamicImage {
+        self.image.as_dynamic_image()
+    }
+
+    /// Saves the decompressed image to `path` in the given format.
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ImageFormat) -> ImageResult<()> {
+        self.to_dynamic_image().save_with_format(path, format)
+    }
+}
+
+/// The quality of an iteration compared to a reference image.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct IterationMetrics {
+    /// The number of the iteration, starting with 1
+    pub iteration: u8,
+    pub mse: f64,
+    pub psnr: f64,
 }
 
 #[instrument(level = "debug", skip(compressed))]
-pub fn decompress(compressed: Compressed, options: Options) -> Decompressed {
-    let mut image = OwnedImage::random(compressed.size);
+pub fn decompress(compressed: Compressed, options: Options) -> Result<Decompressed, DecompressionError> {
+    decompress_observed(compressed, options, None)
+}
+
+/// Decompresses the image like [decompress] and compares each iteration to `reference`,
+/// which needs to have the size of the compressed image.
+#[instrument(level = "debug", skip(compressed, reference))]
+pub fn decompress_with_reference<R: Image>(compressed: Compressed, options: Options, reference: &R) -> Result<Decompressed, DecompressionError> {
+    if reference.get_size() != compressed.size {
+        return Err(DecompressionError::ReferenceSizeMismatch {
+            expected: compressed.size,
+            actual: reference.get_size(),
+        });
+    }
+
+    let mut iteration_metrics = vec![];
+    let mut decompressed = decompress_observed(compressed, options, Some(&mut |iteration, image: &OwnedImage| {
+        iteration_metrics.push(IterationMetrics {
+            iteration,
+            mse: metrics::mse(reference, image).expect("Reference has the size of the image"),
+            psnr: metrics::psnr(reference, image).expect("Reference has the size of the image"),
+        });
+    }))?;
+    decompressed.iteration_metrics = iteration_metrics;
+    Ok(decompressed)
+}
+
+/// Observes the number and the result of each iteration, see [decompress_observed].
+type Observer<'a> = &'a mut dyn FnMut(u8, &OwnedImage);
+
+/// Decompresses the image, calling `observe`, if given, with the number and the result of
+/// each iteration.
+fn decompress_observed(
+    compressed: Compressed,
+    mut options: Options,
+    mut observe: Option<Observer>,
+) -> Result<Decompressed, DecompressionError> {
+    options.validate()?;
+    compressed.validate()?;
+    let initial_image = options.initial_image.create(compressed.size)?;
     let mut image_per_iteration: Option<Vec<OwnedImage>> = match options.keep_each_iteration {
         false => None,
-        true => Some(vec![image.clone()]),
+        true => Some(vec![initial_image.clone()]),
     };
-    for _ in 0..options.iterations {
-        let previous_pass = Arc::new(image.clone());
-        for transformation in compressed.transformations.iter() {
-            transformation.apply_to(previous_pass.clone(), &mut image);
-        }
+    let mut on_iteration = options.on_iteration.take();
+    if let Some(on_iteration) = on_iteration.as_mut() {
+        on_iteration(0, &initial_image);
+    }
 
+    let observed = observe.is_some() || image_per_iteration.is_some() || on_iteration.is_some();
+    let mut report = |iteration: u8, image: &OwnedImage| {
         match image_per_iteration.as_mut() {
             None => (),
             Some(it) => it.push(image.clone()),
         }
+        if let Some(observe) = observe.as_mut() {
+            observe(iteration, image);
+        }
+        if let Some(on_iteration) = on_iteration.as_mut() {
+            on_iteration(iteration, image);
+        }
+    };
+
+    let start = iterate_reduced(&compressed, &options, initial_image, observed.then_some(&mut report))?;
+    let mut image = start.image;
+    let executed_iterations = start.executed_iterations + iterate(
+        &compressed.transformations,
+        start.remaining_iterations,
+        options.overlap,
+        &options,
+        &mut image,
+        |iteration, image| report(start.executed_iterations + iteration, image),
+    )?;
+    if let Some(strength) = options.deblock {
+        postprocess::deblock(&mut image, &compressed, strength);
     }
 
-    Decompressed {
+    Ok(Decompressed {
         image,
         iterations: image_per_iteration,
+        executed_iterations,
+        iteration_metrics: vec![],
+        coverage: options.track_coverage.then(|| Coverage::of(compressed.size, &compressed.transformations)),
+    })
+}
+
+/// Decompresses the image into `target`, which needs to have the size of the compressed image.
+///
+/// The iterations run on an internal pair of buffers, `target` receives the final image.
+/// [Options::keep_each_iteration] is ignored.
+#[instrument(level = "debug", skip(compressed, target))]
+pub fn decompress_into<M: Image + MutableImage>(compressed: Compressed, mut options: Options, target: &mut M) -> Result<(), DecompressionError> {
+    if target.get_size() != compressed.size {
+        return Err(DecompressionError::TargetSizeMismatch {
+            expected: compressed.size,
+            actual: target.get_size(),
+        });
+    }
+    options.validate()?;
+    compressed.validate()?;
+
+    let initial_image = options.initial_image.create(compressed.size)?;
+    let mut on_iteration = options.on_iteration.take();
+    if let Some(on_iteration) = on_iteration.as_mut() {
+        on_iteration(0, &initial_image);
+    }
+
+    let start = iterate_reduced(&compressed, &options, initial_image, on_iteration.as_mut())?;
+    let mut image = start.image;
+
+    iterate(&compressed.transformations, start.remaining_iterations, options.overlap, &options, &mut image, |iteration, image| {
+        if let Some(on_iteration) = on_iteration.as_mut() {
+            on_iteration(start.executed_iterations + iteration, image);
+        }
+    })?;
+    if let Some(strength) = options.deblock {
+        postprocess::deblock(&mut image, &compressed, strength);
+    }
+    copy_into(&image, target);
+    Ok(())
+}
+
+/// Applies up to `iterations` iterations with the given `overlap` to `image`, which initially
+/// contains the initial image, and calls `observe` with the number and the result of each
+/// iteration. Returns the amount of executed iterations, or an error if a block exceeds the image.
+fn iterate(
+    transformations: &[Transformation],
+    iterations: u8,
+    overlap: u32,
+    options: &Options,
+    image: &mut OwnedImage,
+    mut observe: impl FnMut(u8, &OwnedImage),
+) -> Result<u8, DecompressionError> {
+    let transformations = options.application_order.sorted(transformations);
+    // The buffer which is read while the next iteration is written to `image`. Pixels which
+    // are not covered by any range block are never written and keep their initial value.
+    // The domain blocks share it, and release it by the end of each iteration.
+    let mut previous_pass = Arc::new(image.clone());
+    let released = "The domain blocks of the previous iteration are dropped";
+
+    let in_place = overlap == 0 && options.application_order == ApplicationOrder::LargestBlockFirst;
+    // The downscaled and rotated domain block of a transformation applied in place
+    let mut domain = Vec::new();
+
+    let mut executed_iterations = 0;
+    for _ in 0..iterations {
+        if in_place {
+            if options.convergence.is_some() {
+                Arc::get_mut(&mut previous_pass).expect(released).as_mut_slice().copy_from_slice(image.as_slice());
+            }
+            for transformation in transformations.iter() {
+                transformation.apply_in_place(image, &mut domain)?;
+            }
+        } else {
+            mem::swap(image, Arc::get_mut(&mut previous_pass).expect(released));
+            if overlap == 0 {
+                for transformation in transformations.iter() {
+                    transformation.apply_to(&previous_pass, image)?;
+                }
+            } else {
+                apply_blended(&transformations, &previous_pass, image, overlap)?;
+            }
+        }
+
+        executed_iterations += 1;
+        observe(executed_iterations, image);
+
+        if let Some(threshold) = options.convergence {
+            let delta = metrics::mse(&*previous_pass, &*image).expect("Iterations have the same size");
+            if delta < threshold {
+                debug!("Converged after {} iterations (MSE {})", executed_iterations, delta);
+                break;
+            }
+        }
+    }
+
+    Ok(executed_iterations)
+}
+
+/// The image the iterations at full resolution start from.
+struct Start {
+    image: OwnedImage,
+    /// The iterations executed at a reduced resolution
+    executed_iterations: u8,
+    /// The iterations left for the full resolution
+    remaining_iterations: u8,
+}
+
+/// Runs the first half of the iterations at a reduced resolution if [Options::pyramid] is set
+/// and upscales the result. Calls `observe`, if given, with the number and the upscaled result
+/// of each iteration.
+fn iterate_reduced(
+    compressed: &Compressed,
+    options: &Options,
+    image: OwnedImage,
+    mut observe: Option<impl FnMut(u8, &OwnedImage)>,
+) -> Result<Start, DecompressionError> {
+    let factor = pyramid_factor(compressed);
+    if !options.pyramid || factor == 1 || options.iterations < 2 {
+        return Ok(Start { image, executed_iterations: 0, remaining_iterations: options.iterations });
+    }
+
+    let reduced_iterations = options.iterations / 2;
+    let reducible = Compressed {
+        size: compressed.size,
+        transformations: compressed.transformations
+            .iter()
+            .filter(|transformation| is_divisible(transformation, factor))
+            .copied()
+            .collect(),
+    };
+    let reduced = rescaled(&reducible, |value| value.checked_div(factor)).ok_or(DecompressionError::InvalidScale(factor))?;
+    let mut reduced_image = DownscaledBy::new(&image, factor).to_owned_image();
+    let upscaled = |image: &OwnedImage| image.upscale_by(factor).to_owned_image();
+
+    let executed_iterations = iterate(
+        &reduced.transformations,
+        reduced_iterations,
+        options.overlap / factor,
+        options,
+        &mut reduced_image,
+        |iteration, image| {
+            if let Some(observe) = observe.as_mut() {
+                observe(iteration, &upscaled(image));
+            }
+        },
+    )?;
+    debug!("Executed {} iterations at 1/{} of the resolution", executed_iterations, factor);
+
+    Ok(Start {
+        image: upscaled(&reduced_image),
+        executed_iterations,
+        remaining_iterations: options.iterations - reduced_iterations,
+    })
+}
+
+/// The factor by which [Options::pyramid] reduces the resolution: four if it divides the
+/// image size and every block, two if it divides the image size, and one otherwise.
+fn pyramid_factor(compressed: &Compressed) -> u32 {
+    let divides_size = |factor: u32| compressed.size.get_width().is_multiple_of(factor) && compressed.size.get_height().is_multiple_of(factor);
+    if divides_size(4) && compressed.transformations.iter().all(|transformation| is_divisible(transformation, 4)) {
+        4
+    } else if divides_size(2) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Whether the size and position of both blocks of `transformation` are multiples of `factor`.
+fn is_divisible(transformation: &Transformation, factor: u32) -> bool {
+    [transformation.range, transformation.domain]
+        .iter()
+        .all(|block| [block.block_size, block.origin.x, block.origin.y].iter().all(|value| value % factor == 0))
+}
+
+/// Maps the image size and the size and position of every block of `compressed` with `scale`,
+/// while brightness and saturation stay unchanged. Returns `None` if `scale` fails for any value,
+/// e.g. because it overflows.
+fn rescaled(compressed: &Compressed, scale: impl Fn(u32) -> Option<u32>) -> Option<Compressed> {
+    let scale_block = |block: Block| -> Option<Block> {
+        Some(Block {
+            block_size: scale(block.block_size)?,
+            origin: coords!(x=scale(block.origin.x)?, y=scale(block.origin.y)?),
+        })
+    };
+    Some(Compressed {
+        size: Size::new(scale(compressed.size.get_width())?, scale(compressed.size.get_height())?),
+        transformations: compressed.transformations
+            .iter()
+            .map(|transformation| -> Option<Transformation> {
+                Some(Transformation {
+                    range: scale_block(transformation.range)?,
+                    domain: scale_block(transformation.domain)?,
+                    ..*transformation
+                })
+            })
+            .collect::<Option<_>>()?,
+    })
+}
+
+/// Copies all pixels of `source` to `target`, which needs to have the same size.
+fn copy_into<I: Image, M: MutableImage>(source: &I, target: &mut M) {
+    for (pixel, coords) in source.pixels_enumerated() {
+        target.set_pixel(coords.x, coords.y, pixel);
+    }
+}
+
+/// Decompresses the image at `scale` times its size, which needs to be a power of two.
+///
+/// Every range and domain block is scaled by `scale`, while brightness and saturation apply
+/// unchanged. The [overlap](Options::overlap) is scaled as well, a
+/// [custom initial image](InitialImage::Custom) needs to have the scaled size. Fails with
+/// [DecompressionError::InvalidScale] if a scaled size or position exceeds `u32`.
+#[instrument(level = "debug", skip(compressed))]
+pub fn decompress_scaled(compressed: Compressed, options: Options, scale: u32) -> Result<Decompressed, DecompressionError> {
+    if !scale.is_power_of_two() {
+        return Err(DecompressionError::InvalidScale(scale));
+    }
+
+    let scaled = rescaled(&compressed, |value| scale.checked_mul(value)).ok_or(DecompressionError::InvalidScale(scale))?;
+
+    let options = Options {
+        overlap: scale.checked_mul(options.overlap).ok_or(DecompressionError::InvalidScale(scale))?,
+        ..options
+    };
+    decompress(scaled, options)
+}
+
+/// Decompresses each plane of a color image with the given options and converts them back to RGB.
+///
+/// A [custom initial image](InitialImage::Custom) is applied to every plane, which fails if
+/// the chroma planes are subsampled.
+#[instrument(level = "debug", skip(compressed))]
+pub fn decompress_rgb(compressed: ColorCompressed, options: Options) -> Result<DynamicImage, DecompressionError> {
+    let planes = YCbCrPlanes {
+        blue_chroma: decompress(compressed.blue_chroma, options.without_callback())?.image,
+        red_chroma: decompress(compressed.red_chroma, options.without_callback())?.image,
+        luma: decompress(compressed.luma, options)?.image,
+    };
+    Ok(DynamicImage::ImageRgb8(planes.into_rgb()))
+}
+
+/// Decompresses the image and applies the residual, which reproduces the compressed image exactly.
+#[instrument(level = "debug", skip(compressed))]
+pub fn decompress_lossless(compressed: LosslessCompressed) -> Result<OwnedImage, DecompressionError> {
+    let options = Options {
+        iterations: compressed.iterations,
+        overlap: compressed.overlap,
+        ..Default::default()
+    };
+    let expected = compressed.compressed.size.area() as usize;
+    if compressed.residual.len() != expected {
+        return Err(DecompressionError::ResidualSizeMismatch { expected, actual: compressed.residual.len() });
+    }
+    let mut image = decompress(compressed.compressed, options)?.image;
+
+    let width = image.get_width();
+    for (i, residual) in compressed.residual.iter().enumerate() {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        let value = image.pixel(x, y) as i16 + residual;
+        image.set_pixel(x, y, value.clamp(0, 255) as u8);
     }
+    Ok(image)
+}
+
+/// Applies all `transformations` with range blocks extended by `margin` and blends the
+/// contributions to each pixel by their weights.
+fn apply_blended(
+    transformations: &[Transformation],
+    previous_pass: &Arc<OwnedImage>,
+    image: &mut OwnedImage,
+    margin: u32,
+) -> Result<(), DecompressionError> {
+    let mut blended = Blended {
+        width: image.get_width(),
+        height: image.get_height(),
+        sums: vec![0.0; image.get_size().area() as usize],
+        weights: vec![0.0; image.get_size().area() as usize],
+    };
+
+    for transformation in transformations {
+        transformation.accumulate_to(previous_pass, margin, &mut blended)?;
+    }
+
+    for y in 0..blended.height {
+        for x in 0..blended.width {
+            let index = (y * blended.width + x) as usize;
+            if blended.weights[index] > 0.0 {
+                let value = blended.sums[index] / blended.weights[index];
+                image.set_pixel(x, y, value.clamp(0.0, 255.0) as u8);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The weighted sums of pixel values and the sums of their weights.
+struct Blended {
+    width: u32,
+    height: u32,
+    sums: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+/// Maps a pixel of a domain block to the range block with floating point arithmetic.
+fn map_pixel(pixel: Pixel, saturation: f64, brightness: i16) -> Pixel {
+    (pixel as f64 * saturation + brightness as f64).clamp(0.0, 255.0) as Pixel
+}
+
+/// Fails if `block` does not lie within `image`. Checked without overflowing, as the blocks
+/// of a compressed file are not trusted.
+fn check_within(block: Block, image: &impl Image) -> Result<(), DecompressionError> {
+    let last = |origin: u32| origin.checked_add(block.block_size.checked_sub(1)?);
+    match (last(block.origin.x), last(block.origin.y)) {
+        (Some(x), Some(y)) if image.try_pixel(x, y).is_some() => Ok(()),
+        _ => Err(DecompressionError::BlockOutOfBounds { block, size: image.get_size() }),
+    }
+}
+
+/// The weight of a pixel `distance` pixels outside of a range block extended by `margin`.
+fn ramp(distance: u32, margin: u32) -> f64 {
+    (margin + 1 - distance) as f64 / (margin + 1) as f64
 }
 
 impl Transformation {
-    fn apply_to(&self, previous_pass: Arc<OwnedImage>, image: &mut OwnedImage) {
+    fn accumulate_to(&self, previous_pass: &Arc<OwnedImage>, margin: u32, blended: &mut Blended) -> Result<(), DecompressionError> {
+        check_within(self.domain, &**previous_pass)?;
+        check_within(self.range, &**previous_pass)?;
+        let scale = self.domain.block_size / self.range.block_size;
         let domain_block = SquaredBlock {
-            image: previous_pass,
+            image: previous_pass.clone(),
             origin: self.domain.origin,
             size: self.domain.block_size,
         };
+        let domain_block = ExtendedBlock::around(&domain_block, scale * margin)
+            .downscale_by(scale)
+            .rot(self.rotation);
+
+        // The distance of an extended coordinate to the range block along one axis
+        let distance = |e: u32| match e {
+            e if e < margin => margin - e,
+            e if e >= margin + self.range.block_size => e + 1 - margin - self.range.block_size,
+            _ => 0,
+        };
+
+        let extended_size = self.range.block_size + 2 * margin;
+        for ey in 0..extended_size {
+            for ex in 0..extended_size {
+                let x = self.range.origin.x as i64 + ex as i64 - margin as i64;
+                let y = self.range.origin.y as i64 + ey as i64 - margin as i64;
+                if x < 0 || y < 0 || x >= blended.width as i64 || y >= blended.height as i64 {
+                    continue;
+                }
+
+                let weight = ramp(distance(ex), margin) * ramp(distance(ey), margin);
+                let value = (domain_block.pixel(ex, ey) as f64 * self.saturation + self.brightness as f64)
+                    .clamp(0.0, 255.0);
+                let index = (y as u32 * blended.width + x as u32) as usize;
+                blended.sums[index] += weight * value;
+                blended.weights[index] += weight;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the transformation to `image`, reading the domain block from `image` itself.
+    /// `domain` is a buffer for the domain block, which is reused between transformations.
+    fn apply_in_place(&self, image: &mut OwnedImage, domain: &mut Vec<Pixel>) -> Result<(), DecompressionError> {
+        check_within(self.domain, image)?;
+        check_within(self.range, image)?;
+        self.read_domain(image, domain);
+        let mapping = FixedPointMapping::new(self.saturation, self.brightness);
+
+        for ((_, coords), &db_pixel) in self.range.indices(image.get_width()).zip(domain.iter()) {
+            let new_pixel_value = match mapping {
+                Some(mapping) => mapping.map(db_pixel),
+                None => map_pixel(db_pixel, self.saturation, self.brightness),
+            };
+            image.set_pixel(coords.x, coords.y, new_pixel_value);
+        }
+        Ok(())
+    }
 
-        let domain_block = domain_block.downscale_2x2().rot(self.rotation);
-        let indices = self.range.indices(image.get_width(), image.get_height());
+    /// Reads the domain block from `source` into `domain`, downscaled to the size of the range
+    /// block and rotated, in row-major order. Reads the same pixels as the [SquaredBlock],
+    /// [DownscaledBy] and [Rotated](crate::image::Rotated) adapters in [Transformation::apply_to],
+    /// without borrowing `source` beyond this call.
+    fn read_domain(&self, source: &OwnedImage, domain: &mut Vec<Pixel>) {
+        let size = self.range.block_size;
+        let factor = self.domain.block_size / size;
+        let last = size - 1;
+        domain.clear();
+        for y in 0..size {
+            for x in 0..size {
+                let (dx, dy) = match self.rotation {
+                    Rotation::By0 => (x, y),
+                    Rotation::By90 => (y, last - x),
+                    Rotation::By180 => (last - x, last - y),
+                    Rotation::By270 => (last - y, x),
+                };
+                let (origin_x, origin_y) = (self.domain.origin.x + factor * dx, self.domain.origin.y + factor * dy);
+                let mut sum = 0u32;
+                for oy in 0..factor {
+                    for ox in 0..factor {
+                        sum += source.pixel(origin_x + ox, origin_y + oy) as u32;
+                    }
+                }
+                domain.push(average(sum, factor * factor));
+            }
+        }
+    }
+
+    /// Applies the transformation to `image`, reading the domain block from `previous_pass`.
+    ///
+    /// Pixels are mapped with integer arithmetic, unless the saturation can not be represented
+    /// precisely enough as a [fixed-point number](FixedPointMapping).
+    fn apply_to(&self, previous_pass: &Arc<OwnedImage>, image: &mut OwnedImage) -> Result<(), DecompressionError> {
+        check_within(self.domain, &**previous_pass)?;
+        check_within(self.range, image)?;
+        let domain_pixels = SquaredBlock {
+            image: previous_pass.clone(),
+            origin: self.domain.origin,
+            size: self.domain.block_size,
+        }
+            .downscale_by(self.domain.block_size / self.range.block_size)
+            .rot(self.rotation);
+        let indices = self.range.indices(image.get_width());
+        let mapping = FixedPointMapping::new(self.saturation, self.brightness);
 
-        for ((_, coords), db_pixel) in indices.zip(domain_block.pixels()) {
-            let new_pixel_value: f64 = db_pixel as f64 * self.saturation + self.brightness as f64;
-            let new_pixel_value = new_pixel_value.min(255.0).max(0.0) as u8;
+        for ((_, coords), db_pixel) in indices.zip(domain_pixels.pixels()) {
+            let new_pixel_value = match mapping {
+                Some(mapping) => mapping.map(db_pixel),
+                None => map_pixel(db_pixel, self.saturation, self.brightness),
+            };
             image.set_pixel(coords.x, coords.y, new_pixel_value);
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn out_of_bounds() -> Transformation {
+        Transformation {
+            range: Block { block_size: 4, origin: coords!(x=0, y=0) },
+            domain: Block { block_size: 8, origin: coords!(x=u32::MAX - 4, y=0) },
+            rotation: Rotation::By0,
+            brightness: 0,
+            saturation: 0.5,
+        }
+    }
+
+    #[test]
+    fn blocks_exceeding_the_image_are_rejected_in_each_application_order() {
+        for (application_order, overlap) in [
+            (ApplicationOrder::LargestBlockFirst, 0),
+            (ApplicationOrder::FileOrder, 0),
+            (ApplicationOrder::LargestBlockFirst, 1),
+        ] {
+            let options = Options { application_order, overlap, ..Default::default() };
+            let mut image = OwnedImage::filled(Size::squared(16), 128);
+
+            let result = iterate(&[out_of_bounds()], 1, overlap, &options, &mut image, |_, _| {});
+
+            assert_eq!(
+                result,
+                Err(DecompressionError::BlockOutOfBounds { block: out_of_bounds().domain, size: Size::squared(16) })
+            );
+        }
     }
 }
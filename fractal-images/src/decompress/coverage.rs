@@ -0,0 +1,143 @@
+use crate::coords;
+use crate::image::{Coords, Size};
+use crate::model::Transformation;
+
+/// Which pixels of a decompressed image are written by a range block. Pixels outside of any
+/// range block keep the value of the [initial image](crate::decompress::InitialImage), which
+/// happens if the compressor could not map some blocks. With
+/// [overlap](crate::decompress::Options::overlap), the extended margins of neighbouring range
+/// blocks may still blend into such pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    size: Size,
+    covered: Vec<bool>,
+}
+
+impl Coverage {
+    pub(crate) fn of(size: Size, transformations: &[Transformation]) -> Self {
+        let mut covered = vec![false; size.area() as usize];
+        for transformation in transformations {
+            for (index, _) in transformation.range.indices(size.get_width()) {
+                covered[index] = true;
+            }
+        }
+        Self { size, covered }
+    }
+
+    pub fn is_covered(&self, x: u32, y: u32) -> bool {
+        self.covered[(y * self.size.get_width() + x) as usize]
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.covered.iter().all(|covered| *covered)
+    }
+
+    pub fn uncovered_pixels(&self) -> u64 {
+        self.covered.iter().filter(|covered| !**covered).count() as u64
+    }
+
+    /// The share of covered pixels, between 0 and 1.
+    pub fn ratio(&self) -> f64 {
+        1.0 - self.uncovered_pixels() as f64 / self.covered.len() as f64
+    }
+
+    /// Disjoint rectangles which together form the uncovered pixels. Consecutive rows with
+    /// uncovered runs of the same columns are merged into one rectangle.
+    pub fn uncovered_regions(&self) -> Vec<(Coords, Size)> {
+        let mut regions: Vec<(Coords, Size)> = vec![];
+        // Indices into `regions` of the rectangles reaching the previous row
+        let mut open: Vec<usize> = vec![];
+
+        for y in 0..self.size.get_height() {
+            let mut still_open = vec![];
+            for (start, end) in self.uncovered_runs(y) {
+                let continued = open.iter().copied().find(|&i| {
+                    let (origin, size) = regions[i];
+                    origin.x == start && size.get_width() == end - start
+                });
+                match continued {
+                    Some(i) => {
+                        let (origin, size) = regions[i];
+                        regions[i] = (origin, Size::new(size.get_width(), size.get_height() + 1));
+                        still_open.push(i);
+                    }
+                    None => {
+                        regions.push((coords!(x=start, y=y), Size::new(end - start, 1)));
+                        still_open.push(regions.len() - 1);
+                    }
+                }
+            }
+            open = still_open;
+        }
+
+        regions
+    }
+
+    /// The uncovered runs of row `y` as columns `start..end`.
+    fn uncovered_runs(&self, y: u32) -> Vec<(u32, u32)> {
+        let mut runs = vec![];
+        let mut start = None;
+        for x in 0..self.size.get_width() {
+            match (start, self.is_covered(x, y)) {
+                (None, false) => start = Some(x),
+                (Some(run_start), true) => {
+                    runs.push((run_start, x));
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        if let Some(run_start) = start {
+            runs.push((run_start, self.size.get_width()));
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Block, Rotation};
+
+    use super::*;
+
+    fn range(block_size: u32, x: u32, y: u32) -> Transformation {
+        Transformation {
+            range: Block { block_size, origin: coords!(x=x, y=y) },
+            domain: Block { block_size: 2 * block_size, origin: coords!(x=0, y=0) },
+            rotation: Rotation::By0,
+            brightness: 0,
+            saturation: 0.5,
+        }
+    }
+
+    #[test]
+    fn fully_covered_image_has_no_uncovered_regions() {
+        let coverage = Coverage::of(Size::squared(4), &[range(2, 0, 0), range(2, 2, 0), range(2, 0, 2), range(2, 2, 2)]);
+
+        assert!(coverage.is_complete());
+        assert_eq!(coverage.uncovered_pixels(), 0);
+        assert_eq!(coverage.ratio(), 1.0);
+        assert!(coverage.uncovered_regions().is_empty());
+    }
+
+    #[test]
+    fn uncovered_rows_with_the_same_columns_are_merged() {
+        // Covers the left half and the bottom right quadrant of an 8x8 image
+        let coverage = Coverage::of(Size::squared(8), &[range(4, 0, 0), range(4, 0, 4), range(4, 4, 4)]);
+
+        assert_eq!(coverage.uncovered_pixels(), 16);
+        assert_eq!(coverage.ratio(), 0.75);
+        assert_eq!(coverage.uncovered_regions(), vec![(coords!(x=4, y=0), Size::squared(4))]);
+    }
+
+    #[test]
+    fn uncovered_runs_of_different_columns_form_separate_regions() {
+        // Leaves the top left and the bottom right quadrant uncovered
+        let coverage = Coverage::of(Size::squared(4), &[range(2, 2, 0), range(2, 0, 2)]);
+
+        assert_eq!(coverage.uncovered_regions(), vec![
+            (coords!(x=0, y=0), Size::squared(2)),
+            (coords!(x=2, y=2), Size::squared(2)),
+        ]);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::image::Pixel;
+
+/// The fractional bits of [FixedPointMapping::multiplier].
+const FRACTIONAL_BITS: u32 = 14;
+
+/// Maps pixels like a transformation with integer arithmetic only, by quantizing the
+/// saturation to a multiple of `2^-14`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) struct FixedPointMapping {
+    multiplier: i64,
+    /// The brightness, shifted by the fractional bits
+    offset: i64,
+}
+
+impl FixedPointMapping {
+    /// Returns `None` if the quantized saturation changes the mapping of some pixel by more
+    /// than half a gray level, in which case pixels need to be mapped with floating point
+    /// arithmetic.
+    pub(super) fn new(saturation: f64, brightness: i16) -> Option<Self> {
+        let one = (1i64 << FRACTIONAL_BITS) as f64;
+        let multiplier = (saturation * one).round();
+        if !multiplier.is_finite() || multiplier.abs() > i32::MAX as f64 {
+            return None;
+        }
+
+        let quantization_error = Pixel::MAX as f64 * (saturation - multiplier / one).abs();
+        (quantization_error <= 0.5).then_some(Self {
+            multiplier: multiplier as i64,
+            offset: (brightness as i64) << FRACTIONAL_BITS,
+        })
+    }
+
+    pub(super) fn map(self, pixel: Pixel) -> Pixel {
+        ((pixel as i64 * self.multiplier + self.offset) >> FRACTIONAL_BITS).clamp(0, Pixel::MAX as i64) as Pixel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decompress::map_pixel;
+
+    use super::*;
+
+    #[test]
+    fn differs_from_floating_point_by_at_most_one_gray_level() {
+        for saturation in (-100..=100).map(|i| i as f64 / 97.0) {
+            for brightness in (-255..=255).step_by(7) {
+                let mapping = FixedPointMapping::new(saturation, brightness).unwrap();
+                for pixel in 0..=Pixel::MAX {
+                    let (fixed, float) = (mapping.map(pixel), map_pixel(pixel, saturation, brightness));
+                    assert!(
+                        fixed.abs_diff(float) <= 1,
+                        "Saturation {}, brightness {}, pixel {}: {} != {}", saturation, brightness, pixel, fixed, float
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exact_saturations_map_exactly() {
+        let mapping = FixedPointMapping::new(0.5, 10).unwrap();
+        assert_eq!(mapping.map(100), 60);
+        assert_eq!(mapping.map(255), 137);
+    }
+
+    #[test]
+    fn invalid_saturations_fall_back_to_floating_point() {
+        assert_eq!(FixedPointMapping::new(f64::NAN, 0), None);
+        assert_eq!(FixedPointMapping::new(f64::INFINITY, 0), None);
+        assert_eq!(FixedPointMapping::new(1e9, 0), None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+
+use tracing::{debug, instrument};
+
+use crate::decompress::{decompress, DecompressionError, Options};
+use crate::image::{Coords, Image, IntoCropped, OwnedImage, Rect, Size};
+use crate::model::{Block, Compressed, Transformation};
+
+/// Decompresses only the region of size `size` at `origin`, returning an image of the size
+/// of the region.
+///
+/// Only the transformations which contribute to the region are applied: those whose range
+/// block intersects the region, and transitively those whose range block intersects the
+/// domain block of a contributing transformation.
+///
+/// If [Options::convergence] is set, it is measured on the whole image, where only the
+/// contributing transformations were applied.
+#[instrument(level = "debug", skip(compressed))]
+pub fn decompress_region(compressed: Compressed, options: Options, (origin, size): (Coords, Size)) -> Result<OwnedImage, DecompressionError> {
+    let region = Rect::new(origin, size);
+    if !Rect::new(Coords { x: 0, y: 0 }, compressed.size).contains_rect(&region) {
+        return Err(DecompressionError::RegionOutOfBounds { origin, size });
+    }
+    compressed.validate()?;
+
+    let transformations = contributing_transformations(&compressed.transformations, region, options.overlap);
+    debug!("{} of {} transformations contribute to the region", transformations.len(), compressed.transformations.len());
+
+    let decompressed = decompress(Compressed { size: compressed.size, transformations }, options)?.image;
+
+    let region = decompressed.crop(origin, size).expect("Region lies within the image");
+    Ok(OwnedImage::from_pixels(size, region.pixels().collect()).expect("Region has the given size"))
+}
+
+/// Resolves the transformations the pixels of `region` depend on, keeping their order.
+fn contributing_transformations(transformations: &[Transformation], region: Rect, overlap: u32) -> Vec<Transformation> {
+    let mut contributing = vec![false; transformations.len()];
+    let mut required = VecDeque::from([region]);
+
+    while let Some(rect) = required.pop_front() {
+        for (i, transformation) in transformations.iter().enumerate() {
+            if contributing[i] || !extended(&transformation.range, overlap).intersects(&rect) {
+                continue;
+            }
+
+            contributing[i] = true;
+            let scale = transformation.domain.block_size / transformation.range.block_size;
+            required.push_back(extended(&transformation.domain, scale * overlap));
+        }
+    }
+
+    transformations
+        .iter()
+        .zip(contributing)
+        .filter(|(_, contributing)| *contributing)
+        .map(|(transformation, _)| *transformation)
+        .collect()
+}
+
+/// The rectangle of `block`, extended by `margin` on each side and limited to the start of the
+/// image.
+fn extended(block: &Block, margin: u32) -> Rect {
+    let origin = Coords { x: block.origin.x.saturating_sub(margin), y: block.origin.y.saturating_sub(margin) };
+    let end = |start: u32| start + block.block_size + margin;
+    Rect::new(origin, Size::new(end(block.origin.x) - origin.x, end(block.origin.y) - origin.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+    use crate::model::Rotation;
+
+    use super::*;
+
+    fn transformation(range: (u32, u32, u32), domain: (u32, u32, u32)) -> Transformation {
+        Transformation {
+            range: Block { block_size: range.0, origin: coords!(x=range.1, y=range.2) },
+            domain: Block { block_size: domain.0, origin: coords!(x=domain.1, y=domain.2) },
+            rotation: Rotation::By0,
+            brightness: 0,
+            saturation: 0.5,
+        }
+    }
+
+    #[test]
+    fn domains_of_contributing_transformations_are_resolved_transitively() {
+        let transformations = [
+            // Maps into the region, reads from the third transformation's range
+            transformation((2, 0, 0), (4, 4, 4)),
+            // Unrelated
+            transformation((2, 2, 0), (4, 0, 4)),
+            // Reads from the fourth transformation's range
+            transformation((4, 4, 4), (8, 0, 4)),
+            transformation((4, 0, 4), (8, 8, 8)),
+        ];
+
+        let region = Rect::new(coords!(x=0, y=0), Size::squared(2));
+        let contributing = contributing_transformations(&transformations, region, 0);
+
+        assert_eq!(contributing, vec![transformations[0], transformations[2], transformations[3]]);
+    }
+
+    #[test]
+    fn overlap_extends_the_range_blocks() {
+        let transformations = [transformation((2, 2, 0), (4, 4, 4))];
+        let region = Rect::new(coords!(x=0, y=0), Size::squared(2));
+
+        assert!(contributing_transformations(&transformations, region, 0).is_empty());
+        assert_eq!(contributing_transformations(&transformations, region, 1).len(), 1);
+    }
+}
@@ -2,22 +2,42 @@ use derive_more::Display;
 use std::ops::{Add, Div, Mul};
 
 mod block;
+mod crop;
+mod diff;
 mod downscale;
+pub mod eq;
+mod extended;
+mod fn_image;
+mod map;
 mod owned;
+mod pad;
 mod rotate;
 mod square;
+pub mod stats;
+mod threshold;
+mod upscale;
 mod fake;
 mod power_of_two;
+mod rect;
 #[cfg(feature = "generators")]
 pub mod gen;
 
 pub use block::*;
+pub use crop::*;
+pub use diff::*;
 pub use downscale::*;
+pub use extended::*;
+pub use fn_image::*;
+pub use map::*;
 pub use owned::*;
+pub use pad::*;
 pub use rotate::*;
 pub use square::*;
+pub use threshold::*;
+pub use upscale::*;
 pub use fake::*;
 pub use power_of_two::*;
+pub use rect::*;
 use crate::image::iter::PixelIterator;
 
 /// A representation for a gray scale pixel value
@@ -63,6 +83,20 @@ impl Size {
     pub fn transpose(&self) -> Self {
         Size::new(self.get_height(), self.get_width())
     }
+
+    /// Divides width and height by `rhs`, returning `None` if `rhs` is zero or does not
+    /// divide both of them.
+    /// # Example
+    /// ```rust
+    /// use fractal_image::image::Size;
+    ///
+    /// assert_eq!(Size::new(6, 4).checked_div(2), Some(Size::new(3, 2)));
+    /// assert_eq!(Size::new(6, 4).checked_div(4), None);
+    /// ```
+    pub fn checked_div(&self, rhs: u32) -> Option<Self> {
+        let divides = |length: u32| rhs != 0 && length.is_multiple_of(rhs);
+        (divides(self.width) && divides(self.height)).then(|| *self / rhs)
+    }
 }
 
 impl Div<u32> for Size {
@@ -71,7 +105,7 @@ impl Div<u32> for Size {
     fn div(self, rhs: u32) -> Self::Output {
         Self {
             width: self.width / rhs,
-            height: self.width / rhs,
+            height: self.height / rhs,
         }
     }
 }
@@ -82,7 +116,7 @@ impl Mul<u32> for Size {
     fn mul(self, rhs: u32) -> Self::Output {
         Self::Output {
             width: self.width * rhs,
-            height: self.width * rhs,
+            height: self.height * rhs,
         }
     }
 }
@@ -93,7 +127,7 @@ impl Mul<Size> for u32 {
     fn mul(self, rhs: Size) -> Self::Output {
         Self::Output {
             width: rhs.width * self,
-            height: rhs.width * self,
+            height: rhs.height * self,
         }
     }
 }
@@ -116,7 +150,7 @@ macro_rules! size {
 }
 
 /// Represents the coordinates of a pixel
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
 #[display(fmt = "(x={}, y={})", x, y)]
 pub struct Coords {
     pub x: u32,
@@ -156,6 +190,34 @@ pub trait Image: Send + Sync {
 
     fn pixel(&self, x: u32, y: u32) -> Pixel;
 
+    /// Returns the pixel at `x` and `y`, or `None` if the coordinates lie outside of the image,
+    /// where [Image::pixel] panics.
+    fn try_pixel(&self, x: u32, y: u32) -> Option<Pixel> {
+        let size = self.get_size();
+        (x < size.get_width() && y < size.get_height()).then(|| self.pixel(x, y))
+    }
+
+    /// Returns the pixels of row `y` as a contiguous slice, if the image stores them as such.
+    /// Enables faster computations, which otherwise fall back to [Image::pixel].
+    fn pixel_row(&self, _y: u32) -> Option<&[Pixel]> {
+        None
+    }
+
+    /// Copies the pixels of row `y` into `out`, which needs to be as long as the image is wide.
+    /// Copies the [row](Image::pixel_row) at once if the image exposes it, and reads each
+    /// [pixel](Image::pixel) otherwise.
+    fn copy_row_into(&self, y: u32, out: &mut [Pixel]) {
+        assert_eq!(out.len(), self.get_width() as usize, "The row buffer needs to be as long as the image is wide");
+        match self.pixel_row(y) {
+            Some(row) => out.copy_from_slice(row),
+            None => {
+                for (x, pixel) in out.iter_mut().enumerate() {
+                    *pixel = self.pixel(x as u32, y);
+                }
+            }
+        }
+    }
+
     fn pixels_enumerated(&self) -> impl Iterator<Item=(Pixel, Coords)> where Self: Sized {
         PixelIterator::new(self)
     }
@@ -165,6 +227,28 @@ pub trait Image: Send + Sync {
     }
 }
 
+impl<I: Image + ?Sized> Image for &I {
+    fn get_size(&self) -> Size {
+        (**self).get_size()
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        (**self).pixel(x, y)
+    }
+
+    fn try_pixel(&self, x: u32, y: u32) -> Option<Pixel> {
+        (**self).try_pixel(x, y)
+    }
+
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        (**self).pixel_row(y)
+    }
+
+    fn copy_row_into(&self, y: u32, out: &mut [Pixel]) {
+        (**self).copy_row_into(y, out)
+    }
+}
+
 pub trait MutableImage {
     fn set_pixel(&mut self, x: u32, y: u32, value: Pixel);
 }
@@ -248,4 +332,68 @@ mod tests {
             size!(w=2, h=1)
         )
     }
+
+    #[test]
+    fn try_pixel_within_adapters() {
+        let image = OwnedImage::from_pixels(Size::new(4, 2), (0..8).collect()).unwrap();
+
+        let rotated = (&image).rot_90();
+        assert_eq!(rotated.get_size(), size!(w=2, h=4));
+        assert_eq!(rotated.try_pixel(1, 3), Some(rotated.pixel(1, 3)));
+        assert_eq!(rotated.try_pixel(2, 0), None);
+        assert_eq!(rotated.try_pixel(0, 4), None);
+
+        let cropped = (&image).crop(Coords { x: 1, y: 0 }, size!(w=2, h=2)).unwrap();
+        assert_eq!(cropped.try_pixel(1, 1), Some(6));
+        assert_eq!(cropped.try_pixel(2, 0), None);
+
+        let upscaled = (&image).upscale_by(2);
+        assert_eq!(upscaled.try_pixel(7, 3), Some(7));
+        assert_eq!(upscaled.try_pixel(8, 3), None);
+
+        let downscaled = DownscaledBy::new(&image, 2);
+        assert_eq!(downscaled.try_pixel(1, 0), Some(downscaled.pixel(1, 0)));
+        assert_eq!(downscaled.try_pixel(0, 1), None);
+    }
+
+    #[test]
+    fn divide_rectangular_size() {
+        assert_eq!(size!(w=4, h=8) / 2, size!(w=2, h=4));
+        assert_eq!(size!(w=9, h=3) / 3, size!(w=3, h=1));
+    }
+
+    #[test]
+    fn multiply_rectangular_size() {
+        assert_eq!(size!(w=4, h=8) * 2, size!(w=8, h=16));
+        assert_eq!(2 * size!(w=4, h=8), size!(w=8, h=16));
+        assert_eq!(size!(w=3, h=1) * 3, size!(w=9, h=3));
+    }
+
+    #[test]
+    fn checked_division_needs_to_be_exact() {
+        assert_eq!(size!(w=6, h=4).checked_div(2), Some(size!(w=3, h=2)));
+        assert_eq!(size!(w=6, h=4).checked_div(3), None);
+        assert_eq!(size!(w=6, h=4).checked_div(4), None);
+        assert_eq!(size!(w=6, h=4).checked_div(0), None);
+    }
+
+    #[test]
+    fn copied_rows_equal_pixels() {
+        let image = OwnedImage::random(Size::new(5, 3));
+        let rotated = (&image).rot_180();
+        let mut row = vec![0; 5];
+        for y in 0..3 {
+            image.copy_row_into(y, &mut row);
+            assert_eq!(row, (0..5).map(|x| image.pixel(x, y)).collect::<Vec<_>>());
+
+            rotated.copy_row_into(y, &mut row);
+            assert_eq!(row, (0..5).map(|x| rotated.pixel(x, y)).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn copied_row_needs_a_buffer_of_the_image_width() {
+        OwnedImage::random(Size::new(5, 3)).copy_row_into(0, &mut [0; 4]);
+    }
 }
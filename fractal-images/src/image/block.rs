@@ -34,6 +34,19 @@ impl<I> SquaredBlock<I> {
     }
 }
 
+impl<I> SquaredBlock<SquaredBlock<I>> {
+    /// Turns a block of a block into a block of the inner image, with the origins composed,
+    /// such that reading a pixel does not walk through both blocks. Blocks created with
+    /// [IntoSquaredBlocks] from a block are flat already.
+    pub fn flatten(&self) -> SquaredBlock<I> {
+        SquaredBlock {
+            image: self.image.as_inner(),
+            size: self.size,
+            origin: self.origin + self.image.origin,
+        }
+    }
+}
+
 impl<I: Image> Image for SquaredBlock<I> {
     fn get_size(&self) -> Size {
         Size::squared(self.size)
@@ -44,6 +57,14 @@ impl<I: Image> Image for SquaredBlock<I> {
         assert!(y < self.size);
         self.image.pixel(self.origin.x + x, self.origin.y + y)
     }
+
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        assert!(y < self.size);
+        let start = self.origin.x as usize;
+        self.image
+            .pixel_row(self.origin.y + y)
+            .map(|row| &row[start..start + self.size as usize])
+    }
 }
 
 /// Logic to turn something into [SquaredBlock]s.
@@ -57,7 +78,21 @@ mod conversion {
     use crate::model::Block;
 
     pub trait IntoSquaredBlocks<I> {
-        fn squared_blocks(self, size: u32) -> Result<Vec<SquaredBlock<I>>, SquareSizeDoesNotDivideImageSize>;
+        /// Lazily partitions the image into blocks of `size`x`size` pixels, row by row.
+        fn squared_blocks_iter(self, size: u32) -> Result<impl Iterator<Item=SquaredBlock<I>>, SquareSizeDoesNotDivideImageSize>;
+
+        /// Partitions the image into blocks of `size`x`size` pixels, row by row.
+        fn squared_blocks(self, size: u32) -> Result<Vec<SquaredBlock<I>>, SquareSizeDoesNotDivideImageSize>
+        where
+            Self: Sized,
+        {
+            self.squared_blocks_iter(size).map(Iterator::collect)
+        }
+
+        /// Lists the blocks of `size`x`size` pixels whose origins advance by `stride` pixels,
+        /// row by row. Blocks overlap if `stride` is smaller than `size`, and blocks which
+        /// would exceed the image at its right or bottom edge are skipped.
+        fn squared_blocks_with_stride(self, size: u32, stride: u32) -> Result<Vec<SquaredBlock<I>>, NoBlockFits>;
     }
 
     #[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
@@ -66,19 +101,37 @@ mod conversion {
     )]
     pub struct SquareSizeDoesNotDivideImageSize(Size, u32);
 
-    type IntoSquaredBlocksResult<I> = Result<Vec<SquaredBlock<I>>, SquareSizeDoesNotDivideImageSize>;
+    #[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+    #[error("No block of size {size}x{size} with a stride of {stride} fits into the image with size {image_size}")]
+    pub struct NoBlockFits {
+        pub image_size: Size,
+        pub size: u32,
+        pub stride: u32,
+    }
 
     impl<I> IntoSquaredBlocks<I> for &Square<I>
     where
         I: Image,
     {
-        fn squared_blocks(self, size: u32) -> IntoSquaredBlocksResult<I> {
+        fn squared_blocks_iter(self, size: u32) -> Result<impl Iterator<Item=SquaredBlock<I>>, SquareSizeDoesNotDivideImageSize> {
+            let image = self.as_inner();
             create_blocks(self.get_size(), size).map(|blocks| {
+                blocks.map(move |block| SquaredBlock {
+                    image: image.clone(),
+                    size,
+                    origin: block.origin,
+                })
+            })
+        }
+
+        fn squared_blocks_with_stride(self, size: u32, stride: u32) -> Result<Vec<SquaredBlock<I>>, NoBlockFits> {
+            let image = self.as_inner();
+            create_blocks_with_stride(self.get_size(), size, stride).map(|blocks| {
                 blocks.map(|block| SquaredBlock {
-                    image: self.as_inner(),
+                    image: image.clone(),
                     size,
                     origin: block.origin,
-                }).collect::<Vec<_>>()
+                }).collect()
             })
         }
     }
@@ -87,19 +140,30 @@ mod conversion {
     where
         I: Image,
     {
-        fn squared_blocks(self, size: u32) -> IntoSquaredBlocksResult<I> {
+        fn squared_blocks_iter(self, size: u32) -> Result<impl Iterator<Item=SquaredBlock<I>>, SquareSizeDoesNotDivideImageSize> {
+            let (image, origin) = (self.as_inner(), self.origin);
             create_blocks(self.get_size(), size).map(|blocks| {
+                blocks.map(move |block| SquaredBlock {
+                    image: image.clone(),
+                    size,
+                    origin: block.origin + origin,
+                })
+            })
+        }
+
+        fn squared_blocks_with_stride(self, size: u32, stride: u32) -> Result<Vec<SquaredBlock<I>>, NoBlockFits> {
+            create_blocks_with_stride(self.get_size(), size, stride).map(|blocks| {
                 blocks.map(|block| SquaredBlock {
                     image: self.as_inner(),
                     size,
                     origin: block.origin + self.origin,
-                }).collect::<Vec<_>>()
+                }).collect()
             })
         }
     }
 
     fn create_blocks(image_size: Size, size: u32) -> Result<impl Iterator<Item=Block>, SquareSizeDoesNotDivideImageSize> {
-        if image_size.get_width() % size != 0 || image_size.get_height() % size != 0 {
+        if !image_size.get_width().is_multiple_of(size) || !image_size.get_height().is_multiple_of(size) {
             return Err(SquareSizeDoesNotDivideImageSize(image_size, size));
         }
 
@@ -111,6 +175,24 @@ mod conversion {
             origin: coords!(x=size * y, y=size * x),
         }))
     }
+
+    fn create_blocks_with_stride(image_size: Size, size: u32, stride: u32) -> Result<impl Iterator<Item=Block>, NoBlockFits> {
+        // The amount of blocks along an axis of `length` pixels
+        let count = |length: u32| if size == 0 || stride == 0 || size > length {
+            0
+        } else {
+            (length - size) / stride + 1
+        };
+        let (columns, rows) = (count(image_size.get_width()), count(image_size.get_height()));
+        if columns == 0 || rows == 0 {
+            return Err(NoBlockFits { image_size, size, stride });
+        }
+
+        Ok((0..rows).cartesian_product(0..columns).map(move |(row, column)| Block {
+            block_size: size,
+            origin: coords!(x=stride * column, y=stride * row),
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -263,4 +345,108 @@ mod tests {
         assert_eq!(third_block.pixel(0, 1), 28);
         assert_eq!(third_block.pixel(1, 1), 29);
     }
+
+    #[test]
+    fn blocks_of_blocks_refer_to_the_root_image() {
+        let image = FakeImage::squared(16);
+        let root = image.as_inner();
+
+        let outer = image.squared_blocks(8).unwrap().remove(3);
+        let middle = outer.squared_blocks(4).unwrap().remove(1);
+        let inner = middle.squared_blocks(2).unwrap().remove(2);
+
+        assert!(Arc::ptr_eq(&inner.image, &root));
+        assert_eq!(inner.origin, Coords { x: 8 + 4, y: 8 + 2 });
+        assert_eq!(inner.pixel(1, 1), root.pixel(13, 11));
+    }
+
+    #[test]
+    fn flattened_nested_blocks_keep_their_pixels() {
+        let root = FakeImage::squared(16).as_inner();
+        let outer = SquaredBlock { image: root.clone(), size: 8, origin: Coords { x: 8, y: 0 } };
+        let middle = SquaredBlock { image: Arc::new(outer), size: 4, origin: Coords { x: 0, y: 4 } };
+        let inner = SquaredBlock { image: Arc::new(middle), size: 2, origin: Coords { x: 2, y: 2 } };
+
+        let flattened = inner.flatten().flatten();
+
+        assert!(Arc::ptr_eq(&flattened.image, &root));
+        assert_eq!(flattened.origin, Coords { x: 10, y: 6 });
+        assert_eq!(flattened.pixels().collect::<Vec<_>>(), inner.pixels().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn lazy_blocks_equal_eager_blocks() {
+        let image = FakeImage::squared(16);
+        for size in [1, 2, 4, 8, 16] {
+            let lazy = image.squared_blocks_iter(size).unwrap().collect::<Vec<_>>();
+            let eager = image.squared_blocks(size).unwrap();
+            assert_eq!(lazy, eager);
+            assert_eq!(lazy.len() as u32, (16 / size).pow(2));
+
+            let block = &eager[eager.len() / 2];
+            let lazy_sub_blocks = block.squared_blocks_iter(size.div_ceil(2)).unwrap();
+            assert!(lazy_sub_blocks.eq(block.squared_blocks(size.div_ceil(2)).unwrap()));
+        }
+    }
+
+    #[test]
+    fn lazy_blocks_check_the_size() {
+        assert!(FakeImage::squared(16).squared_blocks_iter(3).is_err());
+    }
+
+    mod stride {
+        use super::*;
+
+        fn origins(blocks: &[SquaredBlock<FakeImage>]) -> Vec<(u32, u32)> {
+            blocks.iter().map(|block| (block.origin.x, block.origin.y)).collect()
+        }
+
+        #[test]
+        fn smaller_stride_than_size_overlaps_blocks() {
+            let blocks = FakeImage::squared(4).squared_blocks_with_stride(2, 1).unwrap();
+            assert_eq!(origins(&blocks), vec![
+                (0, 0), (1, 0), (2, 0),
+                (0, 1), (1, 1), (2, 1),
+                (0, 2), (1, 2), (2, 2),
+            ]);
+            assert_eq!(blocks[4].pixel(1, 1), 10);
+        }
+
+        #[test]
+        fn stride_equal_to_size_tiles_the_image() {
+            let image = FakeImage::squared(8);
+            let strided = image.squared_blocks_with_stride(4, 4).unwrap();
+            assert_eq!(strided, image.squared_blocks(4).unwrap());
+        }
+
+        #[test]
+        fn larger_stride_than_size_skips_pixels_and_partial_blocks() {
+            let blocks = FakeImage::squared(8).squared_blocks_with_stride(2, 3).unwrap();
+            assert_eq!(origins(&blocks), vec![
+                (0, 0), (3, 0), (6, 0),
+                (0, 3), (3, 3), (6, 3),
+                (0, 6), (3, 6), (6, 6),
+            ]);
+
+            let blocks = FakeImage::squared(8).squared_blocks_with_stride(3, 4).unwrap();
+            assert_eq!(origins(&blocks), vec![(0, 0), (4, 0), (0, 4), (4, 4)]);
+        }
+
+        #[test]
+        fn strided_blocks_of_a_block_are_translated() {
+            let image = FakeImage::squared(8);
+            let block = image.squared_blocks(4).unwrap().remove(3);
+            let blocks = block.squared_blocks_with_stride(2, 2).unwrap();
+            assert_eq!(origins(&blocks), vec![(4, 4), (6, 4), (4, 6), (6, 6)]);
+        }
+
+        #[test]
+        fn no_fitting_block_is_an_error() {
+            let image = FakeImage::squared(4);
+            let error = NoBlockFits { image_size: Size::squared(4), size: 5, stride: 1 };
+            assert_eq!(image.squared_blocks_with_stride(5, 1), Err(error));
+            assert!(image.squared_blocks_with_stride(0, 1).is_err());
+            assert!(image.squared_blocks_with_stride(2, 0).is_err());
+        }
+    }
 }
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use thiserror::Error;
+
+pub use conversion::*;
+
+use crate::image::{Coords, Image, Pixel, Rect, Size};
+
+/// A rectangular sub-view of an image.
+pub struct Cropped<I> {
+    image: Arc<I>,
+    origin: Coords,
+    size: Size,
+}
+
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("The rectangle of size {size} at {origin} exceeds the image of size {image_size}")]
+pub struct CropOutOfBounds {
+    pub origin: Coords,
+    pub size: Size,
+    pub image_size: Size,
+}
+
+impl<I> Clone for Cropped<I> {
+    fn clone(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            origin: self.origin,
+            size: self.size,
+        }
+    }
+}
+
+impl<I: Image> Cropped<I> {
+    pub(crate) fn new(image: Arc<I>, origin: Coords, size: Size) -> Result<Self, CropOutOfBounds> {
+        check_bounds(origin, size, image.get_size())?;
+        Ok(Self { image, origin, size })
+    }
+
+    pub fn inner(&self) -> Arc<I> {
+        self.image.clone()
+    }
+
+    /// The position of this view in the [inner](Cropped::inner) image.
+    pub fn origin(&self) -> Coords {
+        self.origin
+    }
+
+    /// Crops this view further. Instead of nesting the views, the returned view refers to the
+    /// same inner image, with `origin` translated by the origin of this view.
+    pub fn crop(self, origin: Coords, size: Size) -> Result<Cropped<I>, CropOutOfBounds> {
+        check_bounds(origin, size, self.size)?;
+        Ok(Self {
+            image: self.image,
+            origin: self.origin + origin,
+            size,
+        })
+    }
+}
+
+fn check_bounds(origin: Coords, size: Size, image_size: Size) -> Result<(), CropOutOfBounds> {
+    if Rect::new(Coords { x: 0, y: 0 }, image_size).contains_rect(&Rect::new(origin, size)) {
+        Ok(())
+    } else {
+        Err(CropOutOfBounds { origin, size, image_size })
+    }
+}
+
+impl<I: Image> Image for Cropped<I> {
+    fn get_size(&self) -> Size {
+        self.size
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        assert!(x < self.size.get_width());
+        assert!(y < self.size.get_height());
+        self.image.pixel(self.origin.x + x, self.origin.y + y)
+    }
+
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        assert!(y < self.size.get_height());
+        let start = self.origin.x as usize;
+        self.image
+            .pixel_row(self.origin.y + y)
+            .map(|row| &row[start..start + self.size.get_width() as usize])
+    }
+}
+
+mod conversion {
+    use std::sync::Arc;
+
+    use crate::image::{Coords, CropOutOfBounds, Cropped, Image, Size};
+
+    pub trait IntoCropped<I>
+    where
+        I: Image,
+    {
+        /// A view of the rectangle of `size` at `origin`, which needs to lie within the image.
+        fn crop(self, origin: Coords, size: Size) -> Result<Cropped<I>, CropOutOfBounds>;
+    }
+
+    impl<I> IntoCropped<I> for I
+    where
+        I: Image,
+    {
+        fn crop(self, origin: Coords, size: Size) -> Result<Cropped<I>, CropOutOfBounds> {
+            Cropped::new(Arc::new(self), origin, size)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+    use crate::image::fake::FakeImage;
+    use crate::image::OwnedImage;
+
+    use super::*;
+
+    /// A 4x2 image with the pixels `0..8`.
+    fn image_4x2() -> OwnedImage {
+        OwnedImage::from_pixels(Size::new(4, 2), (0..8).collect()).unwrap()
+    }
+
+    #[test]
+    fn cropped_pixels_are_translated() {
+        let cropped = image_4x2().crop(coords!(x=1, y=0), Size::new(2, 2)).unwrap();
+
+        assert_eq!(cropped.get_size(), Size::new(2, 2));
+        assert_eq!(cropped.pixels().collect::<Vec<_>>(), vec![1, 2, 5, 6]);
+        assert_eq!(cropped.pixel_row(1), Some([5, 6].as_slice()));
+    }
+
+    #[test]
+    fn rectangles_at_the_boundary_fit() {
+        let image = image_4x2();
+        assert!((&image).crop(coords!(x=0, y=0), Size::new(4, 2)).is_ok());
+        assert!((&image).crop(coords!(x=3, y=1), Size::new(1, 1)).is_ok());
+        assert!((&image).crop(coords!(x=4, y=2), Size::new(0, 0)).is_ok());
+
+        let last_pixel = (&image).crop(coords!(x=3, y=1), Size::new(1, 1)).unwrap();
+        assert_eq!(last_pixel.pixel(0, 0), 7);
+    }
+
+    #[test]
+    fn rectangles_exceeding_the_image_are_out_of_bounds() {
+        let image = image_4x2();
+        assert_eq!(
+            (&image).crop(coords!(x=3, y=0), Size::new(2, 1)).err(),
+            Some(CropOutOfBounds { origin: coords!(x=3, y=0), size: Size::new(2, 1), image_size: Size::new(4, 2) })
+        );
+        assert!((&image).crop(coords!(x=0, y=1), Size::new(1, 2)).is_err());
+        assert!((&image).crop(coords!(x=u32::MAX, y=0), Size::new(2, 1)).is_err());
+    }
+
+    #[test]
+    fn crop_of_a_crop_translates_the_origin() {
+        let image = FakeImage::squared(8);
+        let outer = (&image).crop(coords!(x=2, y=1), Size::new(5, 6)).unwrap();
+
+        let inner = outer.crop(coords!(x=1, y=3), Size::new(2, 2)).unwrap();
+
+        assert_eq!(inner.origin(), coords!(x=3, y=4));
+        assert_eq!(inner.pixel(1, 1), image.pixel(4, 5));
+    }
+
+    #[test]
+    fn crop_of_a_crop_is_bounded_by_the_outer_crop() {
+        let outer = FakeImage::squared(8).crop(coords!(x=2, y=2), Size::squared(4)).unwrap();
+
+        let result = outer.crop(coords!(x=2, y=2), Size::squared(3));
+
+        assert_eq!(result.err().map(|error| error.image_size), Some(Size::squared(4)));
+    }
+}
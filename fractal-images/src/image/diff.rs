@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use crate::image::{Image, Pixel, Size};
+use crate::metrics::ImageSizeMismatch;
+
+/// The absolute difference of two images of the same size, e.g. to visualize where a
+/// decompressed image deviates from the original.
+pub struct DiffImage<A, B> {
+    first: Arc<A>,
+    second: Arc<B>,
+    gain: u32,
+}
+
+impl<A: Image, B: Image> DiffImage<A, B> {
+    pub fn new(first: Arc<A>, second: Arc<B>) -> Result<Self, ImageSizeMismatch> {
+        if first.get_size() != second.get_size() {
+            return Err(ImageSizeMismatch(first.get_size(), second.get_size()));
+        }
+        Ok(Self { first, second, gain: 1 })
+    }
+
+    /// Multiplies each difference by `gain`, saturating at [Pixel::MAX], such that small
+    /// differences become visible.
+    pub fn with_gain(mut self, gain: u32) -> Self {
+        self.gain = gain;
+        self
+    }
+}
+
+impl<A: Image, B: Image> Image for DiffImage<A, B> {
+    fn get_size(&self) -> Size {
+        self.first.get_size()
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        let difference = self.first.pixel(x, y).abs_diff(self.second.pixel(x, y)) as u32;
+        difference.saturating_mul(self.gain).min(Pixel::MAX as u32) as Pixel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::OwnedImage;
+
+    use super::*;
+
+    #[test]
+    fn diff_of_an_image_with_itself_is_zero() {
+        let image = Arc::new(OwnedImage::random(Size::squared(16)));
+        let diff = DiffImage::new(image.clone(), image).unwrap().with_gain(100);
+        assert!(diff.pixels().all(|pixel| pixel == 0));
+    }
+
+    #[test]
+    fn diff_is_absolute() {
+        let first = Arc::new(OwnedImage::from_pixels(Size::new(2, 1), vec![10, 50]).unwrap());
+        let second = Arc::new(OwnedImage::from_pixels(Size::new(2, 1), vec![30, 20]).unwrap());
+        let diff = DiffImage::new(first, second).unwrap();
+        assert_eq!(diff.pixels().collect::<Vec<_>>(), vec![20, 30]);
+    }
+
+    #[test]
+    fn gain_saturates() {
+        let first = Arc::new(OwnedImage::from_pixels(Size::new(3, 1), vec![0, 0, 0]).unwrap());
+        let second = Arc::new(OwnedImage::from_pixels(Size::new(3, 1), vec![1, 100, 255]).unwrap());
+        let diff = DiffImage::new(first, second).unwrap().with_gain(4);
+        assert_eq!(diff.pixels().collect::<Vec<_>>(), vec![4, 255, 255]);
+    }
+
+    #[test]
+    fn images_of_different_sizes_can_not_be_diffed() {
+        let first = Arc::new(OwnedImage::random(Size::squared(4)));
+        let second = Arc::new(OwnedImage::random(Size::new(4, 2)));
+        assert!(DiffImage::new(first, second).is_err());
+    }
+}
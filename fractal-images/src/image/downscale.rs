@@ -32,14 +32,69 @@ impl<I: Image> Image for Downscaled2x2<I> {
             + self.image.pixel(2 * x + 1, 2 * y) as u32
             + self.image.pixel(2 * x, 2 * y + 1) as u32
             + self.image.pixel(2 * x + 1, 2 * y + 1) as u32;
-        (0.25 * sum as f64) as Pixel
+        average(sum, 4)
     }
 }
 
+/// Downscales an image by averaging blocks of `factor`x`factor` pixels. Equals
+/// [Downscaled2x2] for a `factor` of two.
+pub struct DownscaledBy<I> {
+    image: Arc<I>,
+    factor: u32,
+}
+
+impl<I> Clone for DownscaledBy<I> {
+    fn clone(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            factor: self.factor,
+        }
+    }
+}
+
+impl<I: Image> DownscaledBy<I> {
+    pub fn new(image: I, factor: u32) -> Self {
+        Self {
+            image: Arc::new(image),
+            factor,
+        }
+    }
+
+    pub fn inner(&self) -> Arc<I> {
+        self.image.clone()
+    }
+
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+}
+
+impl<I: Image> Image for DownscaledBy<I> {
+    fn get_size(&self) -> Size {
+        self.image.get_size() / self.factor
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        let mut sum = 0u32;
+        for dy in 0..self.factor {
+            for dx in 0..self.factor {
+                sum += self.image.pixel(self.factor * x + dx, self.factor * y + dy) as u32;
+            }
+        }
+        average(sum, self.factor * self.factor)
+    }
+}
+
+/// The average of `count` pixels summing up to `sum`, rounded half up. Truncating instead would
+/// darken the image by half a gray level on average, with each further downscaling.
+pub(crate) fn average(sum: u32, count: u32) -> Pixel {
+    ((sum + count / 2) / count) as Pixel
+}
+
 mod conversion {
     use std::sync::Arc;
 
-    use crate::image::{Downscaled2x2, Image, Square, SquaredBlock};
+    use crate::image::{Downscaled2x2, DownscaledBy, ExtendedBlock, Image, Square, SquaredBlock};
 
     pub trait IntoDownscaled<I>
     where
@@ -48,6 +103,8 @@ mod conversion {
         type Target;
         
         fn downscale_2x2(self) -> Downscaled2x2<Self::Target>;
+
+        fn downscale_by(self, factor: u32) -> DownscaledBy<Self::Target>;
     }
 
     impl<I> IntoDownscaled<I> for &Square<I>
@@ -60,6 +117,13 @@ mod conversion {
                 image: self.as_inner(),
             }
         }
+
+        fn downscale_by(self, factor: u32) -> DownscaledBy<Self::Target> {
+            DownscaledBy {
+                image: self.as_inner(),
+                factor,
+            }
+        }
     }
 
     impl<I> IntoDownscaled<I> for &SquaredBlock<I>
@@ -72,12 +136,39 @@ mod conversion {
                 image: Arc::new(self.clone()),
             }
         }
+
+        fn downscale_by(self, factor: u32) -> DownscaledBy<Self::Target> {
+            DownscaledBy {
+                image: Arc::new(self.clone()),
+                factor,
+            }
+        }
+    }
+
+    impl<I> IntoDownscaled<I> for &ExtendedBlock<I>
+    where
+        I: Image,
+    {
+        type Target = ExtendedBlock<I>;
+        fn downscale_2x2(self) -> Downscaled2x2<Self::Target> {
+            Downscaled2x2 {
+                image: Arc::new(self.clone()),
+            }
+        }
+
+        fn downscale_by(self, factor: u32) -> DownscaledBy<Self::Target> {
+            DownscaledBy {
+                image: Arc::new(self.clone()),
+                factor,
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::image::fake::FakeImage;
+    use crate::image::{IntoOwnedImage, OwnedImage, Square};
 
     use super::*;
 
@@ -88,6 +179,12 @@ mod tests {
         assert_eq!(image.get_height(), 8);
     }
 
+    #[test]
+    fn downscaled_size_of_rectangular_image() {
+        let image = Downscaled2x2 { image: Arc::new(OwnedImage::random(Size::new(6, 4))) };
+        assert_eq!(image.get_size(), Size::new(3, 2));
+    }
+
     #[test]
     fn groups_2x2_pixels_of_original_image() {
         // Original image
@@ -97,12 +194,80 @@ mod tests {
         // 12 13 14 15
 
         let image = FakeImage::squared(4).downscale_2x2();
-        assert_eq!(image.pixel(0, 0), (1 + 4 + 5) / 4);
-        assert_eq!(image.pixel(1, 0), (2 + 3 + 6 + 7) / 4);
-        assert_eq!(image.pixel(0, 1), (8 + 9 + 12 + 13) / 4);
-        assert_eq!(image.pixel(1, 1), (10 + 11 + 14 + 15) / 4);
+        assert_eq!(image.pixel(0, 0), 3); // 2.5
+        assert_eq!(image.pixel(1, 0), 5); // 4.5
+        assert_eq!(image.pixel(0, 1), 11); // 10.5
+        assert_eq!(image.pixel(1, 1), 13); // 12.5
+    }
+
+    #[test]
+    fn averages_are_rounded_half_up() {
+        let image = |pixels: [Pixel; 4]| Square::new(OwnedImage::from_pixels(Size::squared(2), pixels.to_vec()).unwrap()).unwrap();
+
+        assert_eq!(image([3, 3, 3, 4]).downscale_2x2().pixel(0, 0), 3); // 3.25
+        assert_eq!(image([3, 3, 4, 4]).downscale_2x2().pixel(0, 0), 4); // 3.5
+        assert_eq!(image([3, 4, 4, 4]).downscale_2x2().pixel(0, 0), 4); // 3.75
+        assert_eq!(image([3, 4, 4, 4]).downscale_by(2).pixel(0, 0), 4);
+    }
+
+    #[test]
+    fn repeated_downscaling_is_unbiased() {
+        let image = Square::new(OwnedImage::random_with_seed(Size::squared(64), 5)).unwrap();
+        let mean = |pixels: Vec<Pixel>| pixels.iter().map(|&pixel| pixel as f64).sum::<f64>() / pixels.len() as f64;
+
+        let once = Square::new(image.downscale_2x2().to_owned_image()).unwrap();
+        let twice = once.downscale_2x2();
+
+        let original_mean = mean(image.pixels().collect());
+        let twice_mean = mean(twice.pixels().collect());
+        assert!(
+            (original_mean - twice_mean).abs() < 0.5,
+            "The mean changed from {} to {}", original_mean, twice_mean
+        );
     }
     
+    #[test]
+    fn downscaled_by_two_equals_downscaled_2x2() {
+        let image = FakeImage::squared(8);
+        let by_two = image.downscale_by(2);
+        let by_2x2 = image.downscale_2x2();
+        assert_eq!(by_two.get_size(), by_2x2.get_size());
+        assert!(by_two.pixels().eq(by_2x2.pixels()));
+    }
+
+    #[test]
+    fn downscaled_by_two_equals_downscaled_2x2_for_random_images() {
+        for seed in 0..32 {
+            let size = 2u32.pow(1 + seed as u32 % 5);
+            let image = Square::new(OwnedImage::random_with_seed(Size::squared(size), seed)).unwrap();
+
+            let by_two = image.downscale_by(2);
+            let by_2x2 = image.downscale_2x2();
+
+            assert_eq!(by_two.get_size(), by_2x2.get_size());
+            assert!(by_two.pixels().eq(by_2x2.pixels()), "Images differ for seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn downscaled_by_sixteen_does_not_overflow() {
+        let image = Square::new(OwnedImage::filled(Size::squared(32), Pixel::MAX)).unwrap();
+
+        let downscaled = image.downscale_by(16);
+
+        assert_eq!(downscaled.get_size(), Size::squared(2));
+        assert!(downscaled.pixels().all(|pixel| pixel == Pixel::MAX));
+    }
+
+    #[test]
+    fn downscaled_by_four_groups_4x4_pixels() {
+        let image = FakeImage::squared(8).downscale_by(4);
+        assert_eq!(image.get_size(), Size::squared(2));
+        // Average of 0..4, 8..12, 16..20, 24..28
+        assert_eq!(image.pixel(0, 0), 14); // 13.5
+        // Average of 36..40, 44..48, 52..56, 60..64
+        assert_eq!(image.pixel(1, 1), 50); // 49.5
+    }
 
     #[test]
     #[should_panic]
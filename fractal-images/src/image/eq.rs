@@ -0,0 +1,131 @@
+use thiserror::Error;
+
+use crate::image::{Coords, Image, Pixel, Size};
+
+/// The first difference found when [comparing](compare) two images.
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum ImageDifference {
+    #[error("Images differ in size: {first} vs. {second}")]
+    Size { first: Size, second: Size },
+
+    #[error("Images differ at {coords}: {first} vs. {second}")]
+    Pixel { coords: Coords, first: Pixel, second: Pixel },
+}
+
+/// Returns `true` iff both images have the same size and the same pixels.
+pub fn images_equal<A: Image, B: Image>(first: &A, second: &B) -> bool {
+    compare(first, second, 0).is_ok()
+}
+
+/// Compares two images row by row, and returns the first difference where the size or a pixel
+/// differs by more than `max_abs_diff`.
+pub fn compare<A: Image, B: Image>(first: &A, second: &B, max_abs_diff: Pixel) -> Result<(), ImageDifference> {
+    let size = first.get_size();
+    if size != second.get_size() {
+        return Err(ImageDifference::Size { first: size, second: second.get_size() });
+    }
+
+    let mut first_row = vec![0; size.get_width() as usize];
+    let mut second_row = vec![0; size.get_width() as usize];
+    for y in 0..size.get_height() {
+        first.copy_row_into(y, &mut first_row);
+        second.copy_row_into(y, &mut second_row);
+        let mismatch = first_row
+            .iter()
+            .zip(&second_row)
+            .position(|(a, b)| a.abs_diff(*b) > max_abs_diff);
+        if let Some(x) = mismatch {
+            return Err(ImageDifference::Pixel {
+                coords: Coords { x: x as u32, y },
+                first: first_row[x],
+                second: second_row[x],
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Asserts that two [images](crate::image::Image) have the same size and the same pixels,
+/// reporting the first mismatching pixel otherwise.
+#[macro_export]
+macro_rules! assert_images_equal {
+    ($first:expr, $second:expr $(,)?) => {
+        $crate::assert_images_close!($first, $second, 0)
+    };
+}
+
+/// Asserts that two [images](crate::image::Image) have the same size, and that none of their
+/// pixels differ by more than `max_abs_diff`, reporting the first mismatching pixel otherwise.
+#[macro_export]
+macro_rules! assert_images_close {
+    ($first:expr, $second:expr, $max_abs_diff:expr $(,)?) => {
+        if let Err(difference) = $crate::image::eq::compare(&$first, &$second, $max_abs_diff) {
+            panic!("assertion failed: {}", difference);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{IntoRotated, OwnedImage};
+
+    use super::*;
+
+    fn image(size: Size, pixels: Vec<Pixel>) -> OwnedImage {
+        OwnedImage::from_pixels(size, pixels).unwrap()
+    }
+
+    #[test]
+    fn equal_images() {
+        let image = OwnedImage::random(Size::new(5, 3));
+        let twice_rotated = (&image).rot_180().rot_180();
+        assert!(images_equal(&image, &twice_rotated));
+        assert_images_equal!(image, twice_rotated);
+    }
+
+    #[test]
+    fn reports_first_mismatching_pixel() {
+        let first = image(Size::new(3, 2), vec![0, 1, 2, 3, 4, 5]);
+        let second = image(Size::new(3, 2), vec![0, 1, 2, 3, 9, 0]);
+
+        assert!(!images_equal(&first, &second));
+        assert_eq!(
+            compare(&first, &second, 0),
+            Err(ImageDifference::Pixel { coords: Coords { x: 1, y: 1 }, first: 4, second: 9 })
+        );
+    }
+
+    #[test]
+    fn tolerates_differences_up_to_the_maximum() {
+        let first = image(Size::new(2, 1), vec![10, 20]);
+        let second = image(Size::new(2, 1), vec![13, 17]);
+
+        assert_images_close!(first, second, 3);
+        assert!(compare(&first, &second, 2).is_err());
+    }
+
+    #[test]
+    fn reports_size_mismatch() {
+        let first = OwnedImage::random(Size::new(2, 3));
+        let second = OwnedImage::random(Size::new(3, 2));
+
+        assert_eq!(
+            compare(&first, &second, Pixel::MAX),
+            Err(ImageDifference::Size { first: Size::new(2, 3), second: Size::new(3, 2) })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Images differ at (x=1, y=0): 20 vs. 25")]
+    fn assertion_reports_the_mismatch() {
+        let first = image(Size::new(2, 1), vec![10, 20]);
+        let second = image(Size::new(2, 1), vec![10, 25]);
+        assert_images_close!(first, second, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Images differ in size: 2x1 vs. 1x2")]
+    fn assertion_reports_size_mismatch() {
+        assert_images_equal!(image(Size::new(2, 1), vec![0, 0]), image(Size::new(1, 2), vec![0, 0]));
+    }
+}
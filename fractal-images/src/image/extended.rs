@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use crate::image::{Image, Pixel, Size, SquaredBlock};
+
+/// A [SquaredBlock] which is extended by a margin on each side. Pixels of the margin
+/// which lie outside of the image repeat the closest pixel of the image.
+pub struct ExtendedBlock<I> {
+    image: Arc<I>,
+    size: u32,
+    origin_x: i64,
+    origin_y: i64,
+}
+
+impl<I> Clone for ExtendedBlock<I> {
+    fn clone(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            size: self.size,
+            origin_x: self.origin_x,
+            origin_y: self.origin_y,
+        }
+    }
+}
+
+impl<I: Image> ExtendedBlock<I> {
+    /// Extends `block` by `margin` pixels on each side.
+    pub fn around(block: &SquaredBlock<I>, margin: u32) -> Self {
+        Self {
+            image: block.image.clone(),
+            size: block.size + 2 * margin,
+            origin_x: block.origin.x as i64 - margin as i64,
+            origin_y: block.origin.y as i64 - margin as i64,
+        }
+    }
+}
+
+impl<I: Image> Image for ExtendedBlock<I> {
+    fn get_size(&self) -> Size {
+        Size::squared(self.size)
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        assert!(x < self.size);
+        assert!(y < self.size);
+        let x = (self.origin_x + x as i64).clamp(0, self.image.get_width() as i64 - 1);
+        let y = (self.origin_y + y as i64).clamp(0, self.image.get_height() as i64 - 1);
+        self.image.pixel(x as u32, y as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+    use crate::image::{Coords, FakeImage};
+
+    use super::*;
+
+    fn block(size: u32, x: u32, y: u32) -> SquaredBlock<FakeImage> {
+        SquaredBlock {
+            image: Arc::new(FakeImage::new(Size::squared(4))),
+            size,
+            origin: coords!(x=x, y=y),
+        }
+    }
+
+    #[test]
+    fn inner_pixels_equal_block() {
+        // 0  1  2  3
+        // 4  5  6  7
+        // 8  9  10 11
+        // 12 13 14 15
+        let extended = ExtendedBlock::around(&block(2, 1, 1), 1);
+        assert_eq!(extended.get_size(), Size::squared(4));
+        assert_eq!(extended.pixel(1, 1), 5);
+        assert_eq!(extended.pixel(2, 2), 10);
+        assert_eq!(extended.pixel(0, 0), 0);
+        assert_eq!(extended.pixel(3, 3), 15);
+    }
+
+    #[test]
+    fn margin_outside_of_image_repeats_border() {
+        let extended = ExtendedBlock::around(&block(2, 0, 0), 1);
+        assert_eq!(extended.pixel(0, 0), 0);
+        assert_eq!(extended.pixel(1, 0), 0);
+        assert_eq!(extended.pixel(0, 2), 4);
+        assert_eq!(extended.pixel(3, 3), 10);
+    }
+}
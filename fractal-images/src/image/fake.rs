@@ -39,6 +39,22 @@ impl FakeImage {
     }
 }
 
+/// Hides the rows of the wrapped image, forcing the pixel by pixel path of functions which
+/// otherwise read whole rows.
+#[cfg(any(test, feature = "bench-support"))]
+pub struct WithoutRows<I>(pub I);
+
+#[cfg(any(test, feature = "bench-support"))]
+impl<I: Image> Image for WithoutRows<I> {
+    fn get_size(&self) -> Size {
+        self.0.get_size()
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        self.0.pixel(x, y)
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,72 @@
+use std::fmt::{Debug, Formatter};
+
+use crate::image::{Image, Pixel, Size};
+
+/// An image whose pixels are computed by a function of their coordinates, e.g. to express
+/// patterns in tests without allocating an [OwnedImage](crate::image::OwnedImage).
+///
+/// # Example
+/// ```rust
+/// use fractal_image::image::{FnImage, Image, Size};
+///
+/// let gradient = FnImage::new(Size::new(4, 2), |x, y| (x + 10 * y) as u8);
+///
+/// assert_eq!(gradient.pixel(3, 1), 13);
+/// ```
+#[derive(Clone)]
+pub struct FnImage<F> {
+    size: Size,
+    f: F,
+}
+
+impl<F> FnImage<F>
+where
+    F: Fn(u32, u32) -> Pixel + Send + Sync,
+{
+    pub fn new(size: Size, f: F) -> Self {
+        Self { size, f }
+    }
+}
+
+impl<F> Debug for FnImage<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnImage").field("size", &self.size).finish_non_exhaustive()
+    }
+}
+
+impl<F> Image for FnImage<F>
+where
+    F: Fn(u32, u32) -> Pixel + Send + Sync,
+{
+    fn get_size(&self) -> Size {
+        self.size
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        assert!(x < self.size.get_width() && y < self.size.get_height(), "Pixel ({}, {}) exceeds the image of size {}", x, y, self.size);
+        (self.f)(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_is_kept() {
+        let image = FnImage::new(Size::new(3, 5), |_, _| 0);
+        assert_eq!(image.get_size(), Size::new(3, 5));
+    }
+
+    #[test]
+    fn pixels_are_computed_from_their_coordinates() {
+        let image = FnImage::new(Size::new(3, 2), |x, y| (10 * x + y) as Pixel);
+        assert_eq!(image.pixels().collect::<Vec<_>>(), vec![0, 10, 20, 1, 11, 21]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn pixels_outside_of_the_image_panic() {
+        FnImage::new(Size::squared(2), |_, _| 0).pixel(2, 0);
+    }
+}
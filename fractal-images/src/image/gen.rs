@@ -2,6 +2,12 @@
 
 mod gen_square;
 mod gen_circle;
+mod gen_sierpinski;
+mod gen_noise;
+mod gen_stripes;
 
 pub use gen_square::GenSquare;
-pub use gen_circle::GenCircle;
\ No newline at end of file
+pub use gen_circle::GenCircle;
+pub use gen_sierpinski::GenSierpinski;
+pub use gen_noise::GenNoise;
+pub use gen_stripes::{GenStripes, Orientation};
\ No newline at end of file
@@ -7,14 +7,23 @@ pub struct GenCircle {
     image_size: Size,
     radius: f64,
     center: Coords,
+    softness: f64,
 }
 
 impl GenCircle {
     pub fn new(image_size: u32, radius: f64) -> Square<Self> {
+        Self::new_aa(image_size, radius, 0.0)
+    }
+
+    /// Creates an anti-aliased circle, whose edge ramps from white to black over a band of
+    /// `softness` pixels centered on the radius. A `softness` of zero yields the binary circle
+    /// of [GenCircle::new].
+    pub fn new_aa(image_size: u32, radius: f64, softness: f64) -> Square<Self> {
         let circle = Self {
             image_size: Size::squared(image_size),
             radius,
             center: coords!(x=image_size/2, y = image_size/2),
+            softness: softness.max(0.0),
         };
         Square::new(circle).unwrap()
     }
@@ -31,12 +40,52 @@ impl Image for GenCircle {
         let dx = dx as f64;
         let dy = dy as f64;
 
-        let within_circle = (dx * dx + dy * dy).sqrt() <= self.radius;
+        let distance = (dx * dx + dy * dy).sqrt();
 
-        if within_circle {
+        if self.softness > 0.0 {
+            let coverage = ((self.radius - distance) / self.softness + 0.5).clamp(0.0, 1.0);
+            (coverage * Pixel::MAX as f64).round() as Pixel
+        } else if distance <= self.radius {
             Pixel::MAX
         } else {
             0
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_circle_has_hard_edges() {
+        let circle = GenCircle::new(16, 4.0);
+        assert_eq!(circle.pixel(12, 8), Pixel::MAX);
+        assert_eq!(circle.pixel(13, 8), 0);
+        assert!(circle.pixels().all(|pixel| pixel == 0 || pixel == Pixel::MAX));
+    }
+
+    #[test]
+    fn antialiased_edge_ramps_across_the_boundary() {
+        let circle = GenCircle::new_aa(32, 8.0, 4.0);
+        let row: Vec<_> = (22..28).map(|x| circle.pixel(x, 16)).collect();
+
+        // Distances 6 to 11 from the center
+        assert_eq!(row, vec![255, 191, 128, 64, 0, 0]);
+    }
+
+    #[test]
+    fn antialiased_circle_is_opaque_inside_and_empty_outside() {
+        let circle = GenCircle::new_aa(32, 8.0, 1.0);
+        assert_eq!(circle.pixel(16, 16), Pixel::MAX);
+        assert_eq!(circle.pixel(0, 0), 0);
+        assert_eq!(circle.pixel(24, 16), 128);
+    }
+
+    #[test]
+    fn zero_softness_equals_binary_circle() {
+        let binary = GenCircle::new(16, 5.5);
+        let antialiased = GenCircle::new_aa(16, 5.5, 0.0);
+        assert!(binary.pixels().eq(antialiased.pixels()));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::image::{Image, Pixel, Size, Square};
+
+/// Generates smooth value noise: random values on a lattice, bilinearly interpolated between
+/// the lattice points. Each octave halves the lattice spacing and the amplitude, adding finer
+/// details. Unlike [OwnedImage::random](crate::image::OwnedImage::random), neighbouring pixels
+/// are correlated, as in natural images.
+///
+/// The lattice values are derived from the seed only, hence the same seed always generates
+/// the same image.
+#[derive(Debug)]
+pub struct GenNoise {
+    image_size: Size,
+    seed: u64,
+    octaves: u8,
+}
+
+impl GenNoise {
+    /// Creates noise with at least one octave, where the lattice spacing of the first octave
+    /// is half of `size`.
+    pub fn new(size: u32, seed: u64, octaves: u8) -> Square<Self> {
+        let noise = Self {
+            image_size: Size::squared(size),
+            seed,
+            octaves: octaves.max(1),
+        };
+        Square::new(noise).unwrap()
+    }
+
+    /// The interpolated value of `octave` at `x` and `y`, between 0 and 1.
+    fn octave(&self, octave: u8, x: u32, y: u32) -> f64 {
+        let spacing = (self.image_size.get_width() >> (octave as u32 + 1).min(31)).max(1);
+        let (cell_x, cell_y) = (x / spacing, y / spacing);
+        let tx = (x % spacing) as f64 / spacing as f64;
+        let ty = (y % spacing) as f64 / spacing as f64;
+
+        let lattice = |dx: u32, dy: u32| self.lattice_value(octave, cell_x + dx, cell_y + dy);
+        let top = lerp(lattice(0, 0), lattice(1, 0), tx);
+        let bottom = lerp(lattice(0, 1), lattice(1, 1), tx);
+        lerp(top, bottom, ty)
+    }
+
+    /// A pseudo-random value between 0 and 1 for a lattice point of an octave.
+    fn lattice_value(&self, octave: u8, x: u32, y: u32) -> f64 {
+        let point = ((octave as u64) << 58) ^ ((x as u64) << 29) ^ y as u64;
+        let hash = split_mix(split_mix(self.seed) ^ point);
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl Image for GenNoise {
+    fn get_size(&self) -> Size {
+        self.image_size
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        let mut value = 0.0;
+        let mut amplitudes = 0.0;
+        for octave in 0..self.octaves {
+            let amplitude = 0.5f64.powi(octave as i32);
+            value += amplitude * self.octave(octave, x, y);
+            amplitudes += amplitude;
+        }
+        (value / amplitudes * Pixel::MAX as f64).round() as Pixel
+    }
+}
+
+fn lerp(from: f64, to: f64, t: f64) -> f64 {
+    from + (to - from) * t
+}
+
+/// The SplitMix64 finalizer, which maps similar inputs to uncorrelated outputs.
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_generates_same_image() {
+        let first: Vec<_> = GenNoise::new(32, 7, 4).pixels().collect();
+        let second: Vec<_> = GenNoise::new(32, 7, 4).pixels().collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_seeds_generate_different_images() {
+        let first: Vec<_> = GenNoise::new(32, 7, 4).pixels().collect();
+        let second: Vec<_> = GenNoise::new(32, 8, 4).pixels().collect();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn values_spread_over_the_pixel_range() {
+        let noise = GenNoise::new(64, 3, 4);
+        let min = noise.pixels().min().unwrap();
+        let max = noise.pixels().max().unwrap();
+        assert!(min < 96 && max > 160, "Values only range from {} to {}", min, max);
+    }
+
+    #[test]
+    fn neighbouring_pixels_are_similar() {
+        let noise = GenNoise::new(64, 3, 1);
+        for y in 0..64 {
+            for x in 1..64 {
+                let difference = noise.pixel(x, y).abs_diff(noise.pixel(x - 1, y));
+                assert!(difference <= 16, "Pixels at x={} and x={} in row {} differ by {}", x - 1, x, y, difference);
+            }
+        }
+    }
+
+    #[test]
+    fn octaves_beyond_pixel_resolution_are_supported() {
+        let noise = GenNoise::new(8, 1, 10);
+        assert_eq!(noise.pixels().count(), 64);
+    }
+}
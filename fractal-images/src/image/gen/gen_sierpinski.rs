@@ -0,0 +1,78 @@
+use crate::image::{Image, Pixel, PowerOfTwo, Size, Square};
+
+/// Generates a Sierpinski triangle, which is self-similar: each of the top left, top right and
+/// bottom left quadrants equals the triangle with one level of recursion less at half the size,
+/// while the bottom right quadrant is empty. Unlike the Sierpinski carpet, the quadrants align with the blocks of
+/// power of two images.
+#[derive(Debug)]
+pub struct GenSierpinski {
+    image_size: Size,
+    /// The width of the smallest cells, which are either filled or empty
+    cell_size: u32,
+}
+
+impl GenSierpinski {
+    /// Creates a triangle with `depth` levels of recursion, which is limited by the image size,
+    /// i.e. the smallest cells are at least one pixel wide. Panics if `image_size` is not a
+    /// power of two.
+    pub fn new(image_size: u32, depth: u32) -> PowerOfTwo<Square<Self>> {
+        assert!(image_size.is_power_of_two(), "The image size needs to be a power of two, was {}", image_size);
+        let depth = depth.min(image_size.ilog2());
+        let triangle = Self {
+            image_size: Size::squared(image_size),
+            cell_size: image_size >> depth,
+        };
+        PowerOfTwo::new(Square::new(triangle).unwrap()).unwrap()
+    }
+}
+
+impl Image for GenSierpinski {
+    fn get_size(&self) -> Size {
+        self.image_size
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        let (cell_x, cell_y) = (x / self.cell_size, y / self.cell_size);
+
+        // A cell is empty if it lies in the bottom right quadrant on any level
+        if cell_x & cell_y == 0 {
+            Pixel::MAX
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quadrants_are_triangles_of_one_level_less() {
+        let triangle = GenSierpinski::new(16, 3);
+        let quadrant = GenSierpinski::new(8, 2);
+
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(triangle.pixel(x, y), quadrant.pixel(x, y));
+                assert_eq!(triangle.pixel(8 + x, y), quadrant.pixel(x, y));
+                assert_eq!(triangle.pixel(x, 8 + y), quadrant.pixel(x, y));
+                assert_eq!(triangle.pixel(8 + x, 8 + y), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn depth_is_limited_by_the_image_size() {
+        let triangle = GenSierpinski::new(4, 10);
+
+        // 4x4 cells of a single pixel
+        let pixels: Vec<_> = triangle.pixels().map(|pixel| pixel == Pixel::MAX).collect();
+        assert_eq!(pixels, vec![
+            true, true, true, true,
+            true, false, true, false,
+            true, true, false, false,
+            true, false, false, false,
+        ]);
+    }
+}
@@ -0,0 +1,106 @@
+use std::f64::consts::PI;
+
+use crate::image::{Image, Pixel, Size, Square};
+
+/// The direction in which stripes run
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Orientation {
+    /// Stripes run from left to right, i.e. the pixel values change along the y axis
+    Horizontal,
+
+    /// Stripes run from top to bottom, i.e. the pixel values change along the x axis
+    Vertical,
+}
+
+/// Generates periodic stripes, either hard stripes alternating between black and white, or a
+/// smooth sinusoidal grating.
+#[derive(Debug)]
+pub struct GenStripes {
+    image_size: Size,
+    period: u32,
+    orientation: Orientation,
+    smooth: bool,
+}
+
+impl GenStripes {
+    /// Creates hard stripes, where each `period` pixels start with `period / 2` white pixels,
+    /// followed by black ones.
+    pub fn new(image_size: u32, period: u32, orientation: Orientation) -> Square<Self> {
+        Self::create(image_size, period, orientation, false)
+    }
+
+    /// Creates a sinusoidal grating with a wave length of `period` pixels, which is white at
+    /// the start of each period.
+    pub fn sine(image_size: u32, period: u32, orientation: Orientation) -> Square<Self> {
+        Self::create(image_size, period, orientation, true)
+    }
+
+    fn create(image_size: u32, period: u32, orientation: Orientation, smooth: bool) -> Square<Self> {
+        assert!(period > 0, "The period of stripes needs to be positive");
+        let stripes = Self {
+            image_size: Size::squared(image_size),
+            period,
+            orientation,
+            smooth,
+        };
+        Square::new(stripes).unwrap()
+    }
+}
+
+impl Image for GenStripes {
+    fn get_size(&self) -> Size {
+        self.image_size
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        let position = match self.orientation {
+            Orientation::Horizontal => y,
+            Orientation::Vertical => x,
+        } % self.period;
+
+        if self.smooth {
+            let phase = 2.0 * PI * position as f64 / self.period as f64;
+            ((1.0 + phase.cos()) / 2.0 * Pixel::MAX as f64).round() as Pixel
+        } else if position < self.period / 2 {
+            Pixel::MAX
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hard_stripes_alternate_every_half_period() {
+        let stripes = GenStripes::new(8, 4, Orientation::Vertical);
+        let row: Vec<_> = (0..8).map(|x| stripes.pixel(x, 3)).collect();
+        assert_eq!(row, vec![255, 255, 0, 0, 255, 255, 0, 0]);
+    }
+
+    #[test]
+    fn horizontal_stripes_are_transposed_vertical_stripes() {
+        for create in [GenStripes::new, GenStripes::sine] {
+            let horizontal = create(16, 6, Orientation::Horizontal);
+            let vertical = create(16, 6, Orientation::Vertical);
+            for y in 0..16 {
+                for x in 0..16 {
+                    assert_eq!(horizontal.pixel(x, y), vertical.pixel(y, x));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sine_grating_is_periodic_and_spans_the_pixel_range() {
+        let grating = GenStripes::sine(32, 16, Orientation::Vertical);
+        let row: Vec<_> = (0..32).map(|x| grating.pixel(x, 0)).collect();
+
+        assert_eq!(row[0], 255);
+        assert_eq!(row[8], 0);
+        assert_eq!(row[0..16], row[16..32]);
+        assert!(row.windows(2).all(|pair| pair[0].abs_diff(pair[1]) < 64));
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+pub use conversion::*;
+
+use crate::image::{Image, Pixel, Size};
+
+/// Applies a function to each pixel of an image, e.g. to invert it or to correct its gamma.
+pub struct Mapped<I, F> {
+    image: Arc<I>,
+    f: Arc<F>,
+}
+
+impl<I, F> Clone for Mapped<I, F> {
+    fn clone(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+impl<I: Image, F> Mapped<I, F> {
+    pub fn inner(&self) -> Arc<I> {
+        self.image.clone()
+    }
+}
+
+impl<I, F> Image for Mapped<I, F>
+where
+    I: Image,
+    F: Fn(Pixel) -> Pixel + Send + Sync,
+{
+    fn get_size(&self) -> Size {
+        self.image.get_size()
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        (self.f)(self.image.pixel(x, y))
+    }
+}
+
+/// Inverts a pixel, i.e. maps black to white and vice versa.
+fn invert(pixel: Pixel) -> Pixel {
+    Pixel::MAX - pixel
+}
+
+mod conversion {
+    use std::sync::Arc;
+
+    use crate::image::{Image, Mapped, Pixel};
+
+    pub trait IntoMapped<I>
+    where
+        I: Image,
+    {
+        /// Maps each pixel with `f`, when it is read.
+        fn map_pixels<F>(self, f: F) -> Mapped<I, F>
+        where
+            F: Fn(Pixel) -> Pixel + Send + Sync;
+
+        /// Maps each pixel `p` to `255 - p`.
+        fn invert(self) -> Mapped<I, fn(Pixel) -> Pixel>
+        where
+            Self: Sized,
+        {
+            self.map_pixels(super::invert as fn(Pixel) -> Pixel)
+        }
+    }
+
+    impl<I> IntoMapped<I> for I
+    where
+        I: Image,
+    {
+        fn map_pixels<F>(self, f: F) -> Mapped<I, F>
+        where
+            F: Fn(Pixel) -> Pixel + Send + Sync,
+        {
+            Mapped {
+                image: Arc::new(self),
+                f: Arc::new(f),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{FnImage, IntoDownscaled, OwnedImage, Square};
+
+    use super::*;
+
+    /// A 4x2 image with the pixels `0, 30, 60, ..., 210`.
+    fn image_4x2() -> OwnedImage {
+        OwnedImage::from_pixels(Size::new(4, 2), (0..8).map(|i| 30 * i).collect()).unwrap()
+    }
+
+    #[test]
+    fn inverted_pixels_are_mirrored() {
+        let inverted = image_4x2().invert();
+        assert_eq!(inverted.pixels().collect::<Vec<_>>(), vec![255, 225, 195, 165, 135, 105, 75, 45]);
+    }
+
+    #[test]
+    fn inverting_twice_is_the_identity() {
+        let image = image_4x2();
+        let twice = (&image).invert().invert();
+        assert_eq!(twice.pixels().collect::<Vec<_>>(), image.pixels().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn gamma_correction_keeps_black_and_white() {
+        let gamma = |pixel: Pixel| (255.0 * (pixel as f64 / 255.0).powf(2.2)).round() as Pixel;
+        let image = OwnedImage::from_pixels(Size::new(3, 1), vec![0, 128, 255]).unwrap();
+
+        let corrected = image.map_pixels(gamma);
+
+        assert_eq!(corrected.pixels().collect::<Vec<_>>(), vec![0, 56, 255]);
+    }
+
+    #[test]
+    fn map_applies_after_downscaling() {
+        // Each 2x2 block averages to 150, but a thresholding map applied before
+        // downscaling would yield 127
+        let image = Square::new(FnImage::new(Size::squared(4), |_, y| if y % 2 == 0 { 100 } else { 200 })).unwrap();
+
+        let mapped = (&image).downscale_2x2().map_pixels(|pixel| if pixel < 128 { 0 } else { 255 });
+
+        assert_eq!(mapped.get_size(), Size::squared(2));
+        assert!(mapped.pixels().all(|pixel| pixel == 255));
+    }
+}
@@ -1,7 +1,15 @@
 use rand::{Rng, SeedableRng};
+use thiserror::Error;
 
 use crate::image::{Image, MutableImage, Pixel, Size};
 
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("An image of size {size} needs {} pixels, but {pixels} were given", .size.area())]
+pub struct SizeMismatch {
+    pub size: Size,
+    pub pixels: usize,
+}
+
 /// A type which stores pixel values in a `Vec`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct OwnedImage {
@@ -14,6 +22,28 @@ impl OwnedImage {
         Self::random_with_seed(size, size.area() as u64)
     }
     
+    /// Creates an image from row-major `pixels`, whose amount needs to match the size.
+    pub fn from_pixels(size: Size, pixels: Vec<Pixel>) -> Result<Self, SizeMismatch> {
+        if pixels.len() != size.area() as usize {
+            return Err(SizeMismatch { size, pixels: pixels.len() });
+        }
+        Ok(Self { size, data: pixels })
+    }
+
+    /// Creates an image where every pixel has the given value.
+    pub fn filled(size: Size, value: Pixel) -> Self {
+        Self { size, data: vec![value; size.area() as usize] }
+    }
+
+    /// The row-major pixels of the image.
+    pub fn as_slice(&self) -> &[Pixel] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [Pixel] {
+        &mut self.data
+    }
+
     pub fn random_with_seed(size: Size, seed: u64) -> Self {
         let mut data = Vec::with_capacity((size.area()) as usize);
         let mut rng = rand::prelude::StdRng::seed_from_u64(seed);
@@ -25,6 +55,22 @@ impl OwnedImage {
     }
 }
 
+/// Copies the pixels of an image into an [OwnedImage]. Chains of lazy adapters, such as a
+/// rotated and downscaled block, compute each pixel on every access, hence a copy is cheaper
+/// if they are read repeatedly.
+pub trait IntoOwnedImage {
+    fn to_owned_image(&self) -> OwnedImage;
+}
+
+impl<I: Image> IntoOwnedImage for I {
+    fn to_owned_image(&self) -> OwnedImage {
+        let size = self.get_size();
+        let mut data = Vec::with_capacity(size.area() as usize);
+        data.extend(self.pixels());
+        OwnedImage { size, data }
+    }
+}
+
 impl Image for OwnedImage {
     fn get_size(&self) -> Size {
         self.size
@@ -36,6 +82,19 @@ impl Image for OwnedImage {
         let idx = (y * self.get_width() + x) as usize;
         self.data[idx]
     }
+
+    fn try_pixel(&self, x: u32, y: u32) -> Option<Pixel> {
+        if x >= self.get_width() {
+            return None;
+        }
+        self.data.get((y as usize) * self.get_width() as usize + x as usize).copied()
+    }
+
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        assert!(y < self.get_height());
+        let start = (y * self.get_width()) as usize;
+        Some(&self.data[start..start + self.get_width() as usize])
+    }
 }
 
 impl MutableImage for OwnedImage {
@@ -49,8 +108,24 @@ impl MutableImage for OwnedImage {
 
 #[cfg(test)]
 mod tests {
+    use crate::coords;
+    use crate::image::{Coords, FakeImage, IntoDownscaled, IntoRotated, SquaredBlock};
+    use crate::model::Rotation;
+
     use super::*;
 
+    #[test]
+    fn pixel_row_equals_pixels() {
+        let image = OwnedImage::random(Size::new(5, 3));
+        for y in 0..3 {
+            let row = image.pixel_row(y).unwrap();
+            assert_eq!(row.len(), 5);
+            for x in 0..5 {
+                assert_eq!(row[x as usize], image.pixel(x, y));
+            }
+        }
+    }
+
     #[test]
     fn create_random_owned_image() {
         let image = OwnedImage::random(Size::squared(16));
@@ -58,4 +133,52 @@ mod tests {
         assert_eq!(16, image.get_width());
         assert_eq!(16, image.get_height());
     }
+
+    #[test]
+    fn owned_copy_equals_rotated_downscaled_block() {
+        let image = FakeImage::squared(8);
+        let view = SquaredBlock { image: image.as_inner(), size: 4, origin: coords!(x=4, y=0) }
+            .downscale_2x2()
+            .rot(Rotation::By90);
+
+        let copy = view.to_owned_image();
+
+        assert_eq!(copy.get_size(), view.get_size());
+        assert!(copy.pixels().eq(view.pixels()));
+    }
+
+    #[test]
+    fn try_pixel_at_the_edges() {
+        let image = OwnedImage::from_pixels(Size::new(3, 2), vec![0, 1, 2, 3, 4, 5]).unwrap();
+        assert_eq!(image.try_pixel(0, 0), Some(0));
+        assert_eq!(image.try_pixel(2, 1), Some(5));
+        assert_eq!(image.try_pixel(3, 0), None);
+        assert_eq!(image.try_pixel(0, 2), None);
+        assert_eq!(image.try_pixel(u32::MAX, u32::MAX), None);
+    }
+
+    #[test]
+    fn pixels_of_wrong_length_are_rejected() {
+        assert_eq!(
+            OwnedImage::from_pixels(Size::new(3, 2), vec![0; 5]),
+            Err(SizeMismatch { size: Size::new(3, 2), pixels: 5 })
+        );
+    }
+
+    #[test]
+    fn pixels_are_addressed_row_major_on_non_square_image() {
+        let image = OwnedImage::from_pixels(Size::new(3, 2), vec![0, 1, 2, 3, 4, 5]).unwrap();
+        assert_eq!(image.pixel(2, 0), 2);
+        assert_eq!(image.pixel(0, 1), 3);
+        assert_eq!(image.pixel(2, 1), 5);
+    }
+
+    #[test]
+    fn slices_expose_pixels_without_copying() {
+        let mut image = OwnedImage::filled(Size::new(2, 3), 7);
+        assert_eq!(image.as_slice(), &[7; 6]);
+
+        image.as_mut_slice()[3] = 42;
+        assert_eq!(image.pixel(1, 1), 42);
+    }
 }
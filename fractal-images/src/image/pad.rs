@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use crate::image::{Image, Pixel, Size};
+
+/// How [Padded] fills the pixels beyond the padded image.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PadMode {
+    /// Fills the padding with a constant value
+    Constant(Pixel),
+
+    /// Repeats the pixels at the right and bottom edge of the image
+    Edge,
+
+    /// Mirrors the image at its right and bottom edge, including the edge pixels
+    Mirror,
+}
+
+/// Extends an image to a larger size by padding it at its right and bottom edge. The pixels of
+/// the image keep their coordinates.
+pub struct Padded<I> {
+    image: Arc<I>,
+    size: Size,
+    mode: PadMode,
+}
+
+impl<I> Clone for Padded<I> {
+    fn clone(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            size: self.size,
+            mode: self.mode,
+        }
+    }
+}
+
+impl<I: Image> Padded<I> {
+    /// Pads `image` to `size`. Panics if `size` is smaller than the image in any dimension.
+    pub fn new(image: I, size: Size, mode: PadMode) -> Self {
+        let original = image.get_size();
+        assert!(
+            original.get_width() <= size.get_width() && original.get_height() <= size.get_height(),
+            "Can not pad an image of size {} to the smaller size {}", original, size
+        );
+        Self { image: Arc::new(image), size, mode }
+    }
+
+    pub fn inner(&self) -> Arc<I> {
+        self.image.clone()
+    }
+
+    /// The size of the image before padding.
+    pub fn original_size(&self) -> Size {
+        self.image.get_size()
+    }
+}
+
+impl<I: Image> Image for Padded<I> {
+    fn get_size(&self) -> Size {
+        self.size
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        assert!(x < self.size.get_width() && y < self.size.get_height(), "Pixel ({}, {}) exceeds the image", x, y);
+        let (width, height) = (self.image.get_width(), self.image.get_height());
+        if x < width && y < height {
+            return self.image.pixel(x, y);
+        }
+
+        match self.mode {
+            PadMode::Constant(value) => value,
+            PadMode::Edge => self.image.pixel(x.min(width - 1), y.min(height - 1)),
+            PadMode::Mirror => self.image.pixel(mirrored(x, width), mirrored(y, height)),
+        }
+    }
+
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        if self.image.get_width() == self.size.get_width() && y < self.image.get_height() {
+            self.image.pixel_row(y)
+        } else {
+            None
+        }
+    }
+}
+
+/// Reflects `position` back into `0..length`, repeating the edge pixel.
+fn mirrored(position: u32, length: u32) -> u32 {
+    let position = position % (2 * length);
+    if position < length {
+        position
+    } else {
+        2 * length - 1 - position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::OwnedImage;
+
+    use super::*;
+
+    /// A 3x2 image with the pixels `0..6`.
+    fn image_3x2() -> OwnedImage {
+        OwnedImage::from_pixels(Size::new(3, 2), (0..6).collect()).unwrap()
+    }
+
+    fn rows<I: Image>(image: &I) -> Vec<Vec<Pixel>> {
+        (0..image.get_height())
+            .map(|y| (0..image.get_width()).map(|x| image.pixel(x, y)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn constant_padding() {
+        let padded = Padded::new(image_3x2(), Size::new(4, 3), PadMode::Constant(9));
+        assert_eq!(rows(&padded), vec![vec![0, 1, 2, 9], vec![3, 4, 5, 9], vec![9, 9, 9, 9]]);
+    }
+
+    #[test]
+    fn edge_padding() {
+        let padded = Padded::new(image_3x2(), Size::new(4, 3), PadMode::Edge);
+        assert_eq!(rows(&padded), vec![vec![0, 1, 2, 2], vec![3, 4, 5, 5], vec![3, 4, 5, 5]]);
+    }
+
+    #[test]
+    fn mirror_padding() {
+        let padded = Padded::new(image_3x2(), Size::new(8, 4), PadMode::Mirror);
+        assert_eq!(rows(&padded), vec![
+            vec![0, 1, 2, 2, 1, 0, 0, 1],
+            vec![3, 4, 5, 5, 4, 3, 3, 4],
+            vec![3, 4, 5, 5, 4, 3, 3, 4],
+            vec![0, 1, 2, 2, 1, 0, 0, 1],
+        ]);
+    }
+
+    #[test]
+    fn rows_are_only_exposed_without_horizontal_padding() {
+        let padded = Padded::new(image_3x2(), Size::new(3, 4), PadMode::Edge);
+        assert_eq!(padded.pixel_row(1), Some([3, 4, 5].as_slice()));
+        assert_eq!(padded.pixel_row(2), None);
+
+        let padded = Padded::new(image_3x2(), Size::new(4, 2), PadMode::Edge);
+        assert_eq!(padded.pixel_row(0), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn padding_to_a_smaller_size_panics() {
+        Padded::new(image_3x2(), Size::new(2, 2), PadMode::Edge);
+    }
+}
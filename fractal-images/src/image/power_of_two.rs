@@ -1,7 +1,7 @@
 use std::sync::Arc;
 use derive_more::Display;
 use thiserror::Error;
-use crate::image::{Coords, Image, Pixel, Size};
+use crate::image::{Coords, Cropped, Image, PadMode, Padded, Pixel, Size, Square};
 
 /// Represents an image with dimensions that are powers of two.
 ///
@@ -43,6 +43,12 @@ pub struct PowerOfTwo<I> (Arc<I>);
 )]
 pub struct NoPowerOfTwo(Size);
 
+/// An image without any pixel, which can not be [padded](PowerOfTwo::pad) as there is no pixel
+/// to repeat.
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("The provided image is empty, width = {}, height = {}", .0.get_width(), .0.get_height())]
+pub struct EmptyImage(Size);
+
 impl<I> PowerOfTwo<I>
 where
     I: Image,
@@ -66,6 +72,46 @@ where
     pub fn into_inner(self) -> Arc<I> {
         self.0
     }
+
+    /// Pads the width and the height of `image` to the next power of two. See
+    /// [PowerOfTwo::crop_to_original] to restore the original size. Fails if `image` is empty.
+    pub fn pad(image: I, mode: PadMode) -> Result<PowerOfTwo<Padded<I>>, EmptyImage> {
+        let size = non_empty_size(&image)?;
+        let padded_size = Size::new(size.get_width().next_power_of_two(), size.get_height().next_power_of_two());
+        Ok(PowerOfTwo::new(Padded::new(image, padded_size, mode)).expect("Padded to a power of two"))
+    }
+
+    /// Pads `image` to a square whose size is the next power of two of its larger dimension,
+    /// as required by the [compressor](crate::compress::quadtree::Compressor). Fails if `image`
+    /// is empty.
+    pub fn pad_to_square(image: I, mode: PadMode) -> Result<PowerOfTwo<Square<Padded<I>>>, EmptyImage> {
+        let size = non_empty_size(&image)?;
+        let length = size.get_width().max(size.get_height()).next_power_of_two();
+        let padded = Square::new(Padded::new(image, Size::squared(length), mode)).unwrap_or_else(|_| unreachable!("Padded to a square"));
+        Ok(PowerOfTwo::new(padded).expect("Padded to a power of two"))
+    }
+}
+
+fn non_empty_size<I: Image>(image: &I) -> Result<Size, EmptyImage> {
+    match image.get_size() {
+        size if size.area() == 0 => Err(EmptyImage(size)),
+        size => Ok(size),
+    }
+}
+
+impl<I> PowerOfTwo<Padded<I>>
+where
+    I: Image,
+{
+    /// The size of the image before it was [padded](PowerOfTwo::pad).
+    pub fn original_size(&self) -> Size {
+        self.0.original_size()
+    }
+
+    /// A view of the original image without the padding.
+    pub fn crop_to_original(&self) -> Cropped<Padded<I>> {
+        Cropped::new(self.as_inner(), Coords { x: 0, y: 0 }, self.original_size()).expect("The original image lies within the padded one")
+    }
 }
 
 impl<I> Image for PowerOfTwo<I>
@@ -80,6 +126,10 @@ where
         self.0.pixel(x, y)
     }
 
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        self.0.pixel_row(y)
+    }
+
     fn pixels_enumerated(&self) -> impl Iterator<Item=(Pixel, Coords)>
     where
         Self: Sized,
@@ -130,4 +180,50 @@ mod tests {
             size!(w=3,h=3)
         )).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn padding_extends_to_the_next_power_of_two() {
+        let image = FakeImage::new(size!(w=5, h=8));
+        let padded = PowerOfTwo::pad(image, PadMode::Constant(0)).unwrap();
+
+        assert_eq!(padded.get_size(), size!(w=8, h=8));
+        assert_eq!(padded.original_size(), size!(w=5, h=8));
+        for y in 0..8 {
+            for x in 0..5 {
+                assert_eq!(padded.pixel(x, y), image.pixel(x, y));
+            }
+        }
+        assert_eq!(padded.pixel(5, 0), 0);
+    }
+
+    #[test]
+    fn padding_keeps_powers_of_two() {
+        let padded = PowerOfTwo::pad(FakeImage::new(size!(w=4, h=16)), PadMode::Edge).unwrap();
+        assert_eq!(padded.get_size(), size!(w=4, h=16));
+    }
+
+    #[test]
+    fn padding_to_a_square_uses_the_larger_dimension() {
+        let padded = PowerOfTwo::pad_to_square(FakeImage::new(size!(w=9, h=3)), PadMode::Edge).unwrap();
+        assert_eq!(padded.get_size(), Size::squared(16));
+    }
+
+    #[test]
+    fn cropping_restores_the_original_image() {
+        let image = FakeImage::new(size!(w=3, h=5));
+        let cropped = PowerOfTwo::pad(image, PadMode::Mirror).unwrap().crop_to_original();
+
+        assert_eq!(cropped.get_size(), size!(w=3, h=5));
+        assert_eq!(cropped.pixels().collect::<Vec<_>>(), image.pixels().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn empty_images_can_not_be_padded() {
+        for size in [size!(w=0, h=4), size!(w=4, h=0)] {
+            for mode in [PadMode::Constant(0), PadMode::Edge, PadMode::Mirror] {
+                assert_eq!(PowerOfTwo::pad(FakeImage::new(size), mode).err(), Some(EmptyImage(size)));
+                assert_eq!(PowerOfTwo::pad_to_square(FakeImage::new(size), mode).err(), Some(EmptyImage(size)));
+            }
+        }
+    }
+}
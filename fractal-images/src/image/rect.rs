@@ -0,0 +1,135 @@
+use crate::image::{Coords, Size};
+use crate::model::Block;
+
+/// An axis-aligned rectangle of `size` at `origin`, which includes its origin and excludes its
+/// end, i.e. rectangles which only touch at an edge do not intersect.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rect {
+    pub origin: Coords,
+    pub size: Size,
+}
+
+impl Rect {
+    pub fn new(origin: Coords, size: Size) -> Self {
+        Self { origin, size }
+    }
+
+    /// Whether the rectangle has no area.
+    pub fn is_empty(&self) -> bool {
+        self.size.area() == 0
+    }
+
+    /// The first column to the right of the rectangle. Does not overflow, unlike `u32`.
+    fn end_x(&self) -> u64 {
+        self.origin.x as u64 + self.size.get_width() as u64
+    }
+
+    /// The first row below the rectangle. Does not overflow, unlike `u32`.
+    fn end_y(&self) -> u64 {
+        self.origin.y as u64 + self.size.get_height() as u64
+    }
+
+    pub fn contains_point(&self, point: Coords) -> bool {
+        self.origin.x <= point.x && (point.x as u64) < self.end_x()
+            && self.origin.y <= point.y && (point.y as u64) < self.end_y()
+    }
+
+    /// Whether `other` lies completely within this rectangle, including its edges.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        self.origin.x <= other.origin.x && other.end_x() <= self.end_x()
+            && self.origin.y <= other.origin.y && other.end_y() <= self.end_y()
+    }
+
+    /// Whether both rectangles share at least one pixel.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The pixels both rectangles share, or `None` if there are none.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let start_x = self.origin.x.max(other.origin.x);
+        let start_y = self.origin.y.max(other.origin.y);
+        let end_x = self.end_x().min(other.end_x());
+        let end_y = self.end_y().min(other.end_y());
+        if end_x <= start_x as u64 || end_y <= start_y as u64 {
+            return None;
+        }
+
+        // The intersection is at most as large as either rectangle, so its size fits into `u32`
+        let size = Size::new((end_x - start_x as u64) as u32, (end_y - start_y as u64) as u32);
+        Some(Rect::new(Coords { x: start_x, y: start_y }, size))
+    }
+}
+
+impl From<Block> for Rect {
+    fn from(block: Block) -> Self {
+        Rect::new(block.origin, Size::squared(block.block_size))
+    }
+}
+
+impl From<&Block> for Rect {
+    fn from(block: &Block) -> Self {
+        Rect::from(*block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+
+    use super::*;
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect::new(coords!(x=x, y=y), Size::new(width, height))
+    }
+
+    #[test]
+    fn overlapping_rectangles_intersect() {
+        let (first, second) = (rect(0, 0, 4, 4), rect(2, 3, 4, 4));
+        assert!(first.intersects(&second));
+        assert_eq!(first.intersection(&second), Some(rect(2, 3, 2, 1)));
+        assert_eq!(second.intersection(&first), Some(rect(2, 3, 2, 1)));
+    }
+
+    #[test]
+    fn adjacent_rectangles_do_not_intersect() {
+        let rectangle = rect(2, 2, 2, 2);
+        for neighbour in [rect(4, 2, 2, 2), rect(0, 2, 2, 2), rect(2, 4, 2, 2), rect(2, 0, 2, 2), rect(4, 4, 1, 1)] {
+            assert!(!rectangle.intersects(&neighbour), "{:?} intersects {:?}", rectangle, neighbour);
+            assert_eq!(rectangle.intersection(&neighbour), None);
+        }
+    }
+
+    #[test]
+    fn empty_rectangles_intersect_nothing() {
+        let empty = rect(1, 1, 0, 3);
+        assert!(empty.is_empty());
+        assert!(!empty.intersects(&rect(0, 0, 4, 4)));
+        assert!(!rect(0, 0, 4, 4).intersects(&empty));
+    }
+
+    #[test]
+    fn points_on_the_end_are_not_contained() {
+        let rectangle = rect(1, 1, 2, 2);
+        assert!(rectangle.contains_point(coords!(x=1, y=1)));
+        assert!(rectangle.contains_point(coords!(x=2, y=2)));
+        assert!(!rectangle.contains_point(coords!(x=3, y=2)));
+        assert!(!rectangle.contains_point(coords!(x=2, y=3)));
+        assert!(!rectangle.contains_point(coords!(x=0, y=1)));
+    }
+
+    #[test]
+    fn rectangles_at_the_edge_are_contained() {
+        let image = rect(0, 0, 8, 8);
+        assert!(image.contains_rect(&rect(0, 0, 8, 8)));
+        assert!(image.contains_rect(&rect(6, 4, 2, 4)));
+        assert!(!image.contains_rect(&rect(6, 4, 3, 4)));
+        assert!(!image.contains_rect(&rect(u32::MAX, 0, 2, 2)));
+    }
+
+    #[test]
+    fn blocks_are_converted_to_squares() {
+        let block = Block { block_size: 4, origin: coords!(x=2, y=6) };
+        assert_eq!(Rect::from(block), rect(2, 6, 4, 4));
+    }
+}
@@ -77,6 +77,10 @@ where
         self.0.pixel(x, y)
     }
 
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        self.0.pixel_row(y)
+    }
+
     fn pixels_enumerated(&self) -> impl Iterator<Item=(Pixel, Coords)> {
         self.0.pixels_enumerated()
     }
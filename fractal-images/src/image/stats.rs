@@ -0,0 +1,133 @@
+use crate::image::{Image, Pixel};
+
+/// Counts how often each pixel value occurs in `image`.
+pub fn histogram<I: Image>(image: &I) -> [u64; 256] {
+    let mut histogram = [0u64; 256];
+    let mut row = vec![0; image.get_width() as usize];
+    for y in 0..image.get_height() {
+        image.copy_row_into(y, &mut row);
+        for &pixel in &row {
+            histogram[pixel as usize] += 1;
+        }
+    }
+    histogram
+}
+
+/// The mean pixel value of `image`, or NaN for an empty image.
+pub fn mean<I: Image>(image: &I) -> f64 {
+    Stats::of(image).mean
+}
+
+/// The variance of the pixel values of `image`, or NaN for an empty image.
+pub fn variance<I: Image>(image: &I) -> f64 {
+    Stats::of(image).variance
+}
+
+/// The smallest pixel value of `image`, or `None` for an empty image.
+pub fn min<I: Image>(image: &I) -> Option<Pixel> {
+    Stats::of(image).min
+}
+
+/// The largest pixel value of `image`, or `None` for an empty image.
+pub fn max<I: Image>(image: &I) -> Option<Pixel> {
+    Stats::of(image).max
+}
+
+/// Basic statistics of the pixel values of an image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub count: u64,
+    pub mean: f64,
+    pub variance: f64,
+    pub min: Option<Pixel>,
+    pub max: Option<Pixel>,
+}
+
+impl Stats {
+    pub fn of<I: Image>(image: &I) -> Self {
+        Self::from_histogram(&histogram(image))
+    }
+
+    /// Computes the statistics from a [histogram], accumulating sums as integers such that,
+    /// e.g., the variance of a constant image is exactly zero.
+    pub fn from_histogram(histogram: &[u64; 256]) -> Self {
+        let (mut count, mut sum, mut squared_sum) = (0u64, 0u64, 0u128);
+        for (value, &occurrences) in histogram.iter().enumerate() {
+            count += occurrences;
+            sum += value as u64 * occurrences;
+            squared_sum += (value * value) as u128 * occurrences as u128;
+        }
+
+        // n² * variance = n * Σx² - (Σx)², which is exact in integers
+        let n = count as u128;
+        let scaled_variance = n * squared_sum - sum as u128 * sum as u128;
+        let occurring = || (0..=Pixel::MAX).filter(|&value| histogram[value as usize] > 0);
+
+        Self {
+            count,
+            mean: sum as f64 / count as f64,
+            variance: scaled_variance as f64 / (n * n) as f64,
+            min: occurring().next(),
+            max: occurring().next_back(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{FakeImage, OwnedImage, Size};
+
+    use super::*;
+
+    fn image(size: Size, pixels: Vec<Pixel>) -> OwnedImage {
+        OwnedImage::from_pixels(size, pixels).unwrap()
+    }
+
+    #[test]
+    fn histogram_counts_pixel_values() {
+        let histogram = histogram(&image(Size::new(3, 2), vec![0, 7, 7, 255, 7, 0]));
+        assert_eq!(histogram[0], 2);
+        assert_eq!(histogram[7], 3);
+        assert_eq!(histogram[255], 1);
+        assert_eq!(histogram.iter().sum::<u64>(), 6);
+    }
+
+    #[test]
+    fn stats_of_consecutive_values() {
+        // Pixels 0, 1, ..., 15
+        let stats = Stats::of(&FakeImage::squared(4));
+        assert_eq!(stats.count, 16);
+        assert_eq!(stats.mean, 7.5);
+        assert_eq!(stats.variance, 21.25);
+        assert_eq!(stats.min, Some(0));
+        assert_eq!(stats.max, Some(15));
+    }
+
+    #[test]
+    fn stats_of_two_values() {
+        let image = image(Size::squared(2), vec![10, 10, 20, 20]);
+        assert_eq!(mean(&image), 15.0);
+        assert_eq!(variance(&image), 25.0);
+        assert_eq!(min(&image), Some(10));
+        assert_eq!(max(&image), Some(20));
+    }
+
+    #[test]
+    fn variance_of_constant_images_is_zero() {
+        for value in [0, 1, 127, 254, Pixel::MAX] {
+            let image = OwnedImage::filled(Size::new(7, 5), value);
+            assert_eq!(variance(&image), 0.0, "Variance of {} is not zero", value);
+            assert_eq!(mean(&image), value as f64);
+        }
+    }
+
+    #[test]
+    fn stats_of_empty_image() {
+        let stats = Stats::of(&OwnedImage::filled(Size::squared(0), 0));
+        assert_eq!(stats.count, 0);
+        assert!(stats.mean.is_nan());
+        assert!(stats.variance.is_nan());
+        assert_eq!(stats.min, None);
+        assert_eq!(stats.max, None);
+    }
+}
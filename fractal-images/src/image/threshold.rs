@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+pub use conversion::*;
+
+use crate::image::{Image, Pixel, Size};
+
+/// Binarizes an image: pixels below the level become black, all others white.
+pub struct Thresholded<I> {
+    image: Arc<I>,
+    level: Pixel,
+}
+
+impl<I> Clone for Thresholded<I> {
+    fn clone(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            level: self.level,
+        }
+    }
+}
+
+impl<I: Image> Thresholded<I> {
+    pub fn inner(&self) -> Arc<I> {
+        self.image.clone()
+    }
+
+    pub fn level(&self) -> Pixel {
+        self.level
+    }
+}
+
+impl<I: Image> Image for Thresholded<I> {
+    fn get_size(&self) -> Size {
+        self.image.get_size()
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        if self.image.pixel(x, y) < self.level {
+            0
+        } else {
+            Pixel::MAX
+        }
+    }
+}
+
+mod conversion {
+    use std::sync::Arc;
+
+    use crate::image::{Image, Pixel, Thresholded};
+
+    pub trait IntoThresholded<I>
+    where
+        I: Image,
+    {
+        /// Maps pixels below `level` to 0 and all others to [Pixel::MAX].
+        fn threshold(self, level: Pixel) -> Thresholded<I>;
+    }
+
+    impl<I> IntoThresholded<I> for I
+    where
+        I: Image,
+    {
+        fn threshold(self, level: Pixel) -> Thresholded<I> {
+            Thresholded {
+                image: Arc::new(self),
+                level,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+    use crate::image::{Coords, IntoCropped, IntoRotated, OwnedImage};
+
+    use super::*;
+
+    /// A 4x2 image with the pixels `0, 40, 80, ..., 280`, saturated at 255.
+    fn image_4x2() -> OwnedImage {
+        OwnedImage::from_pixels(Size::new(4, 2), (0..8).map(|i| (40 * i).min(255) as Pixel).collect()).unwrap()
+    }
+
+    #[test]
+    fn pixels_below_the_level_become_black() {
+        let thresholded = image_4x2().threshold(100);
+        assert_eq!(thresholded.pixels().collect::<Vec<_>>(), vec![0, 0, 0, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn level_zero_makes_every_pixel_white() {
+        let thresholded = image_4x2().threshold(0);
+        assert!(thresholded.pixels().all(|pixel| pixel == Pixel::MAX));
+    }
+
+    #[test]
+    fn level_255_keeps_only_white_pixels_white() {
+        let thresholded = image_4x2().threshold(Pixel::MAX);
+        assert_eq!(thresholded.pixels().collect::<Vec<_>>(), vec![0, 0, 0, 0, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn thresholds_compose_with_crops_and_rotations() {
+        let image = image_4x2();
+
+        let cropped = (&image).crop(coords!(x=1, y=0), Size::new(2, 2)).unwrap().threshold(100);
+        assert_eq!(cropped.pixels().collect::<Vec<_>>(), vec![0, 0, 255, 255]);
+
+        let rotated = (&image).threshold(100).rot_90();
+        assert_eq!(rotated.get_size(), Size::new(2, 4));
+        for y in 0..4 {
+            for x in 0..2 {
+                assert_eq!(rotated.pixel(x, y), (&image).rot_90().threshold(100).pixel(x, y));
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+pub use conversion::*;
+
+use crate::image::{Image, Pixel, Size};
+
+/// Upscales an image by `factor` with bilinear interpolation between the centers of the
+/// original pixels. Pixels beyond the outermost centers repeat the edge of the image.
+pub struct UpscaledBy<I> {
+    image: Arc<I>,
+    factor: u32,
+}
+
+impl<I> Clone for UpscaledBy<I> {
+    fn clone(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            factor: self.factor,
+        }
+    }
+}
+
+impl<I: Image> UpscaledBy<I> {
+    pub fn inner(&self) -> Arc<I> {
+        self.image.clone()
+    }
+
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    /// The two neighbouring source positions of `position` along an axis of `length` pixels,
+    /// and the weight of the second one.
+    fn neighbours(&self, position: u32, length: u32) -> (u32, u32, f64) {
+        let source = ((position as f64 + 0.5) / self.factor as f64 - 0.5).clamp(0.0, (length - 1) as f64);
+        let first = source.floor() as u32;
+        let second = (first + 1).min(length - 1);
+        (first, second, source - first as f64)
+    }
+}
+
+impl<I: Image> Image for UpscaledBy<I> {
+    fn get_size(&self) -> Size {
+        self.image.get_size() * self.factor
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        assert!(x < self.get_width() && y < self.get_height(), "Pixel ({}, {}) exceeds the image", x, y);
+        let (x0, x1, wx) = self.neighbours(x, self.image.get_width());
+        let (y0, y1, wy) = self.neighbours(y, self.image.get_height());
+
+        let pixel = |x, y| self.image.pixel(x, y) as f64;
+        let top = (1.0 - wx) * pixel(x0, y0) + wx * pixel(x1, y0);
+        let bottom = (1.0 - wx) * pixel(x0, y1) + wx * pixel(x1, y1);
+        ((1.0 - wy) * top + wy * bottom).round() as Pixel
+    }
+}
+
+mod conversion {
+    use std::sync::Arc;
+
+    use crate::image::{Image, UpscaledBy};
+
+    pub trait IntoUpscaled<I>
+    where
+        I: Image,
+    {
+        fn upscale_by(self, factor: u32) -> UpscaledBy<I>;
+    }
+
+    impl<I> IntoUpscaled<I> for I
+    where
+        I: Image,
+    {
+        fn upscale_by(self, factor: u32) -> UpscaledBy<I> {
+            UpscaledBy {
+                image: Arc::new(self),
+                factor,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::fake::FakeImage;
+    use crate::image::OwnedImage;
+
+    use super::*;
+
+    #[test]
+    fn upscaled_size() {
+        let image = FakeImage::squared(4).upscale_by(4);
+        assert_eq!(image.get_size(), Size::squared(16));
+    }
+
+    #[test]
+    fn upscaled_by_one_equals_original() {
+        let image = FakeImage::squared(8);
+        assert!((&image).upscale_by(1).pixels().eq(image.pixels()));
+    }
+
+    #[test]
+    fn flat_image_stays_flat() {
+        let image = OwnedImage::filled(Size::squared(4), 77).upscale_by(2);
+        assert!(image.pixels().all(|pixel| pixel == 77));
+    }
+
+    #[test]
+    fn interpolates_between_pixel_centers() {
+        // 0   100
+        // 100 200
+        let image = OwnedImage::from_pixels(Size::squared(2), vec![0, 100, 100, 200]).unwrap().upscale_by(2);
+
+        // The outer pixels repeat the edge, the inner ones lie a quarter between two centers
+        assert_eq!(image.pixel(0, 0), 0);
+        assert_eq!(image.pixel(1, 0), 25);
+        assert_eq!(image.pixel(2, 0), 75);
+        assert_eq!(image.pixel(3, 0), 100);
+        assert_eq!(image.pixel(1, 1), 50);
+        assert_eq!(image.pixel(3, 3), 200);
+    }
+
+    #[test]
+    #[should_panic]
+    fn overflow_x() {
+        let image = FakeImage::squared(2).upscale_by(2);
+        image.pixel(4, 0);
+    }
+}
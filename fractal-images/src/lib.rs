@@ -3,5 +3,10 @@ pub mod decompress;
 pub mod image;
 pub mod model;
 pub mod persistence;
+pub mod postprocess;
 pub mod preprocessing;
+pub mod resize;
 pub mod metrics;
+pub mod visualize;
+#[cfg(feature = "bench-support")]
+pub mod bench_support;
@@ -1,10 +1,23 @@
 use std::cmp::max;
+use std::fmt::{Display, Formatter};
+use rayon::prelude::*;
 use thiserror::Error;
-use crate::image::{Image, Size};
+use crate::image::{Coords, Image, OwnedImage, Pixel, Rect, Size};
+use crate::image::stats::histogram;
+use crate::model::Compressed;
 
 #[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
-#[error("Can not compare images with different sizes ({} != {})", 0, 1)]
-pub struct ImageSizeMismatch(Size, Size);
+#[error("Can not compare images with different sizes ({} != {})", .0, .1)]
+pub struct ImageSizeMismatch(pub(crate) Size, pub(crate) Size);
+
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RegionError {
+    #[error(transparent)]
+    SizeMismatch(#[from] ImageSizeMismatch),
+
+    #[error("The region {:?} does not lie within the images of size {}", .0, .1)]
+    OutOfBounds(Rect, Size),
+}
 
 /// Computes the [MSE](https://en.wikipedia.org/wiki/Mean_squared_error) metric of two images.
 pub fn mse<A: Image, B: Image>(first: &A, second: &B) -> Result<f64, ImageSizeMismatch> {
@@ -13,33 +26,500 @@ pub fn mse<A: Image, B: Image>(first: &A, second: &B) -> Result<f64, ImageSizeMi
     }
 
     let area = first.get_size().area();
+    let width = first.get_width() as usize;
+    let (mut row_a, mut row_b) = (vec![0; width], vec![0; width]);
 
-    let sum: f64 = first.pixels().zip(second.pixels())
-        .map(|(px_a, px_b)| (px_a as i64 - px_b as i64).pow(2) as f64)
-        .sum();
+    let mut sum = 0u64;
+    for y in 0..first.get_height() {
+        first.copy_row_into(y, &mut row_a);
+        second.copy_row_into(y, &mut row_b);
+        sum += squared_error_sum(&row_a, &row_b);
+    }
+
+    Ok(sum as f64 / area as f64)
+}
+
+/// Computes the [MSE](mse) like [mse], but processes the rows in parallel. Faster for large
+/// images, and returns exactly the same result.
+pub fn mse_par<A: Image, B: Image>(first: &A, second: &B) -> Result<f64, ImageSizeMismatch> {
+    let (sum, _) = par_sums(first, second)?;
+    Ok(sum as f64 / first.get_size().area() as f64)
+}
+
+/// Computes the [PSNR](psnr) like [psnr], but processes the rows in parallel. Faster for large
+/// images, and returns exactly the same result.
+pub fn psnr_par<A: Image, B: Image>(first: &A, second: &B) -> Result<f64, ImageSizeMismatch> {
+    let (sum, max_pixel) = par_sums(first, second)?;
+    let mse = sum as f64 / first.get_size().area() as f64;
+    Ok(20f64 * (max_pixel as f64).log10() - 10f64 * mse.log10())
+}
+
+/// Returns the sum of the squared errors and the largest pixel of both images, computed per row
+/// in parallel. The sums are integers, hence their order does not change the result.
+fn par_sums<A: Image, B: Image>(first: &A, second: &B) -> Result<(u64, Pixel), ImageSizeMismatch> {
+    if first.get_size() != second.get_size() {
+        return Err(ImageSizeMismatch(first.get_size(), second.get_size()));
+    }
+
+    let width = first.get_width() as usize;
+    Ok((0..first.get_height())
+        .into_par_iter()
+        .map_init(
+            || (vec![0; width], vec![0; width]),
+            |(row_a, row_b), y| {
+                first.copy_row_into(y, row_a);
+                second.copy_row_into(y, row_b);
+                let max_pixel = row_a.iter().chain(row_b.iter()).copied().max().unwrap_or(0);
+                (squared_error_sum(row_a, row_b), max_pixel)
+            },
+        )
+        .reduce(|| (0, 0), |(sum_a, max_a), (sum_b, max_b)| (sum_a + sum_b, max(max_a, max_b))))
+}
+
+fn squared_error_sum(row_a: &[Pixel], row_b: &[Pixel]) -> u64 {
+    row_a.iter()
+        .zip(row_b)
+        .map(|(&px_a, &px_b)| (px_a as i64 - px_b as i64).pow(2) as u64)
+        .sum()
+}
+
+/// Computes the [MAE](https://en.wikipedia.org/wiki/Mean_absolute_error) metric of two images.
+pub fn mae<A: Image, B: Image>(first: &A, second: &B) -> Result<f64, ImageSizeMismatch> {
+    if first.get_size() != second.get_size() {
+        return Err(ImageSizeMismatch(first.get_size(), second.get_size()));
+    }
 
-    Ok(sum / area as f64)
+    let area = first.get_size().area();
+    let width = first.get_width() as usize;
+    let (mut row_a, mut row_b) = (vec![0; width], vec![0; width]);
+
+    let mut sum = 0u64;
+    for y in 0..first.get_height() {
+        first.copy_row_into(y, &mut row_a);
+        second.copy_row_into(y, &mut row_b);
+        sum += row_a.iter()
+            .zip(&row_b)
+            .map(|(&px_a, &px_b)| px_a.abs_diff(px_b) as u64)
+            .sum::<u64>();
+    }
+
+    Ok(sum as f64 / area as f64)
 }
 
 /// Computes the [PSNR](https://en.wikipedia.org/wiki/Peak_signal-to-noise_ratio) metric of two images.
 pub fn psnr<A: Image, B: Image>(first: &A, second: &B) -> Result<f64, ImageSizeMismatch> {
     let mse = mse(first, second)?;
-    let max_a = first.pixels().max().unwrap_or(0);
-    let max_b = second.pixels().max().unwrap_or(0);
-    let max = max(max_a, max_b) as f64;
+    let max = max(max_pixel(first), max_pixel(second)) as f64;
 
     Ok(20f64 * max.log10() - 10f64 * mse.log10())
 }
 
+/// The side length of the windows over which [ssim] compares the images.
+const SSIM_WINDOW: usize = 11;
+
+/// The standard deviation of the Gaussian weights within a window of [ssim].
+const SSIM_SIGMA: f64 = 1.5;
+
+/// Stabilizes the luminance and contrast terms of [ssim] if the denominators are close to zero.
+const SSIM_C1: f64 = (0.01 * Pixel::MAX as f64) * (0.01 * Pixel::MAX as f64);
+const SSIM_C2: f64 = (0.03 * Pixel::MAX as f64) * (0.03 * Pixel::MAX as f64);
+
+/// Computes the mean [SSIM](https://en.wikipedia.org/wiki/Structural_similarity) index of two
+/// images, which ranges from -1 to 1, and is 1 for identical images.
+///
+/// Follows Wang et al. (2004): the index is computed for every 11x11 window with Gaussian weights
+/// (σ = 1.5) lying within the images, and averaged. Images smaller than a window are compared in
+/// correspondingly smaller windows.
+pub fn ssim<A: Image, B: Image>(first: &A, second: &B) -> Result<f64, ImageSizeMismatch> {
+    if first.get_size() != second.get_size() {
+        return Err(ImageSizeMismatch(first.get_size(), second.get_size()));
+    }
+    if first.get_size().area() == 0 {
+        return Ok(f64::NAN);
+    }
+
+    let (width, height) = (first.get_width() as usize, first.get_height() as usize);
+    Ok(ssim_of_pixels(&pixels_as_f64(first), &pixels_as_f64(second), width, height))
+}
+
+fn ssim_of_pixels(a: &[f64], b: &[f64], width: usize, height: usize) -> f64 {
+    let window = gaussian_window(SSIM_WINDOW.min(width).min(height));
+    let filter = |values: Vec<f64>| filter_valid(&values, width, height, &window);
+    let mean_a = filter(a.to_vec());
+    let mean_b = filter(b.to_vec());
+    let mean_aa = filter(a.iter().map(|a| a * a).collect());
+    let mean_bb = filter(b.iter().map(|b| b * b).collect());
+    let mean_ab = filter(a.iter().zip(b).map(|(a, b)| a * b).collect());
+
+    let sum: f64 = (0..mean_a.len())
+        .map(|i| {
+            let (mu_a, mu_b) = (mean_a[i], mean_b[i]);
+            let variance_a = mean_aa[i] - mu_a * mu_a;
+            let variance_b = mean_bb[i] - mu_b * mu_b;
+            let covariance = mean_ab[i] - mu_a * mu_b;
+
+            ((2.0 * mu_a * mu_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                / ((mu_a * mu_a + mu_b * mu_b + SSIM_C1) * (variance_a + variance_b + SSIM_C2))
+        })
+        .sum();
+
+    sum / mean_a.len() as f64
+}
+
+fn pixels_as_f64<I: Image>(image: &I) -> Vec<f64> {
+    let mut row = vec![0; image.get_width() as usize];
+    let mut result = Vec::with_capacity(image.get_size().area() as usize);
+    for y in 0..image.get_height() {
+        image.copy_row_into(y, &mut row);
+        result.extend(row.iter().map(|&pixel| pixel as f64));
+    }
+    result
+}
+
+/// Normalized one-dimensional Gaussian weights of `length` values.
+fn gaussian_window(length: usize) -> Vec<f64> {
+    let center = (length - 1) as f64 / 2.0;
+    let weights: Vec<f64> = (0..length)
+        .map(|i| (-(i as f64 - center).powi(2) / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp())
+        .collect();
+    let sum: f64 = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / sum).collect()
+}
+
+/// Computes the weighted mean of `values` within every square window that lies within the
+/// image, using `window` as separable weights in both directions.
+fn filter_valid(values: &[f64], width: usize, height: usize, window: &[f64]) -> Vec<f64> {
+    let n = window.len();
+    let (out_width, out_height) = (width - n + 1, height - n + 1);
+
+    let horizontal: Vec<f64> = (0..height)
+        .flat_map(|y| {
+            let row = &values[y * width..(y + 1) * width];
+            (0..out_width).map(move |x| row[x..x + n].iter().zip(window).map(|(v, w)| v * w).sum::<f64>())
+        })
+        .collect();
+
+    (0..out_height)
+        .flat_map(|y| {
+            let horizontal = &horizontal;
+            (0..out_width).map(move |x| {
+                window.iter()
+                    .enumerate()
+                    .map(|(dy, w)| horizontal[(y + dy) * out_width + x] * w)
+                    .sum::<f64>()
+            })
+        })
+        .collect()
+}
+
+/// The metrics of two images, as computed by [compare].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct QualityReport {
+    pub mse: f64,
+    pub psnr: f64,
+    pub mae: f64,
+    pub ssim: f64,
+}
+
+impl Display for QualityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "MSE: {:.2}", self.mse)?;
+        writeln!(f, "PSNR: {:.2} dB", self.psnr)?;
+        writeln!(f, "MAE: {:.2}", self.mae)?;
+        write!(f, "SSIM: {:.4}", self.ssim)
+    }
+}
+
+/// Computes [mse], [psnr], [mae] and [ssim] at once, reading the pixels of each image only once.
+pub fn compare<A: Image, B: Image>(first: &A, second: &B) -> Result<QualityReport, ImageSizeMismatch> {
+    if first.get_size() != second.get_size() {
+        return Err(ImageSizeMismatch(first.get_size(), second.get_size()));
+    }
+
+    let area = first.get_size().area();
+    let (width, height) = (first.get_width() as usize, first.get_height() as usize);
+    let (mut row_a, mut row_b) = (vec![0; width], vec![0; width]);
+    let (mut pixels_a, mut pixels_b) = (Vec::with_capacity(area as usize), Vec::with_capacity(area as usize));
+
+    let (mut squared_sum, mut absolute_sum, mut max_pixel) = (0u64, 0u64, 0);
+    for y in 0..first.get_height() {
+        first.copy_row_into(y, &mut row_a);
+        second.copy_row_into(y, &mut row_b);
+        for (&px_a, &px_b) in row_a.iter().zip(&row_b) {
+            let difference = px_a.abs_diff(px_b) as u64;
+            squared_sum += difference * difference;
+            absolute_sum += difference;
+            max_pixel = max(max_pixel, max(px_a, px_b));
+        }
+        pixels_a.extend(row_a.iter().map(|&pixel| pixel as f64));
+        pixels_b.extend(row_b.iter().map(|&pixel| pixel as f64));
+    }
+
+    let mse = squared_sum as f64 / area as f64;
+    Ok(QualityReport {
+        mse,
+        psnr: 20f64 * (max_pixel as f64).log10() - 10f64 * mse.log10(),
+        mae: absolute_sum as f64 / area as f64,
+        ssim: if area == 0 { f64::NAN } else { ssim_of_pixels(&pixels_a, &pixels_b, width, height) },
+    })
+}
+
+/// Renders the [RMS](https://en.wikipedia.org/wiki/Root_mean_square) error of every square block
+/// of `block_size` as a grayscale image of the same size, where the block with the largest error
+/// is white. Blocks at the right and bottom border are cut off by the image.
+///
+/// Panics if `block_size` is zero.
+pub fn error_map<A: Image, B: Image>(first: &A, second: &B, block_size: u32) -> Result<OwnedImage, ImageSizeMismatch> {
+    assert!(block_size > 0, "The blocks of an error map need to be at least one pixel large");
+    let size = first.get_size();
+    let cells = (0..size.get_height())
+        .step_by(block_size as usize)
+        .flat_map(|y| (0..size.get_width()).step_by(block_size as usize).map(move |x| (x, y, block_size)));
+    render_error_map(first, second, cells)
+}
+
+/// Renders the RMS error like [error_map], but per range block of `compressed` instead of a
+/// fixed grid. Pixels outside of any range block are black.
+pub fn error_map_of_partition<A: Image, B: Image>(first: &A, second: &B, compressed: &Compressed) -> Result<OwnedImage, ImageSizeMismatch> {
+    let cells = compressed.transformations
+        .iter()
+        .map(|transformation| (transformation.range.origin.x, transformation.range.origin.y, transformation.range.block_size));
+    render_error_map(first, second, cells)
+}
+
+/// Renders the RMS error within each of the square `cells` given as `(x, y, size)`.
+fn render_error_map<A: Image, B: Image>(
+    first: &A,
+    second: &B,
+    cells: impl Iterator<Item=(u32, u32, u32)>,
+) -> Result<OwnedImage, ImageSizeMismatch> {
+    if first.get_size() != second.get_size() {
+        return Err(ImageSizeMismatch(first.get_size(), second.get_size()));
+    }
+
+    let size = first.get_size();
+    let width = size.get_width() as usize;
+    let (mut row_a, mut row_b) = (vec![0; width], vec![0; width]);
+    let mut squared_errors = Vec::with_capacity(size.area() as usize);
+    for y in 0..size.get_height() {
+        first.copy_row_into(y, &mut row_a);
+        second.copy_row_into(y, &mut row_b);
+        squared_errors.extend(row_a.iter().zip(&row_b).map(|(&px_a, &px_b)| (px_a.abs_diff(px_b) as u64).pow(2)));
+    }
+
+    // The cells cut off by the image, with their RMS error
+    let cells: Vec<_> = cells
+        .filter_map(|(x, y, block_size)| {
+            let (end_x, end_y) = ((x + block_size).min(size.get_width()), (y + block_size).min(size.get_height()));
+            if x >= end_x || y >= end_y {
+                return None;
+            }
+            let sum: u64 = (y..end_y)
+                .map(|y| squared_errors[y as usize * width + x as usize..y as usize * width + end_x as usize].iter().sum::<u64>())
+                .sum();
+            let area = (end_x - x) as u64 * (end_y - y) as u64;
+            Some(((x, y, end_x, end_y), (sum as f64 / area as f64).sqrt()))
+        })
+        .collect();
+
+    let max_error = cells.iter().map(|&(_, error)| error).fold(0.0, f64::max);
+    let mut map = OwnedImage::filled(size, 0);
+    for ((x, y, end_x, end_y), error) in cells {
+        let level = if max_error > 0.0 { (error / max_error * Pixel::MAX as f64).round() as Pixel } else { 0 };
+        for y in y..end_y {
+            map.as_mut_slice()[y as usize * width + x as usize..y as usize * width + end_x as usize].fill(level);
+        }
+    }
+    Ok(map)
+}
+
+/// The size of a persisted compression, as computed by [compression_report].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CompressionReport {
+    pub image_size: Size,
+    pub serialized_bytes: u64,
+    pub transformations: usize,
+    pub bits_per_pixel: f64,
+    /// Zero if there are no transformations.
+    pub bytes_per_transformation: f64,
+    /// The size of the same image in another encoding, see [CompressionReport::with_reference].
+    pub reference_bytes: Option<u64>,
+}
+
+impl CompressionReport {
+    /// Compares the compression to another encoding of the same image, such as a PNG file of
+    /// `reference_bytes`.
+    pub fn with_reference(self, reference_bytes: u64) -> Self {
+        Self { reference_bytes: Some(reference_bytes), ..self }
+    }
+
+    /// The size of the compression relative to the [reference](CompressionReport::with_reference),
+    /// which is below 1 if the compression is smaller.
+    pub fn ratio(&self) -> Option<f64> {
+        self.reference_bytes.map(|reference| self.serialized_bytes as f64 / reference as f64)
+    }
+}
+
+impl Display for CompressionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Size: {} bytes", self.serialized_bytes)?;
+        writeln!(f, "Bits per pixel: {:.3}", self.bits_per_pixel)?;
+        write!(f, "Transformations: {} ({:.2} bytes each)", self.transformations, self.bytes_per_transformation)?;
+        if let (Some(reference), Some(ratio)) = (self.reference_bytes, self.ratio()) {
+            write!(f, "\nRatio: {:.3} (reference: {} bytes)", ratio, reference)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes the size of `compressed` when persisted with `serialized_bytes`, as returned by
+/// [Compressed::persist_as_binary_v1] for example.
+pub fn compression_report(compressed: &Compressed, serialized_bytes: u64) -> CompressionReport {
+    let transformations = compressed.transformations.len();
+    let bytes_per_transformation = if transformations == 0 {
+        0.0
+    } else {
+        serialized_bytes as f64 / transformations as f64
+    };
+
+    CompressionReport {
+        image_size: compressed.size,
+        serialized_bytes,
+        transformations,
+        bits_per_pixel: (serialized_bytes * 8) as f64 / compressed.size.area() as f64,
+        bytes_per_transformation,
+        reference_bytes: None,
+    }
+}
+
+/// Computes the [MSE](mse) of two images within `region` only.
+pub fn mse_region<A: Image, B: Image>(first: &A, second: &B, region: Rect) -> Result<f64, RegionError> {
+    let (sum, _) = region_sums(first, second, region)?;
+    Ok(sum as f64 / region.size.area() as f64)
+}
+
+/// Computes the [PSNR](psnr) of two images within `region` only, where the peak is the largest
+/// pixel within the region.
+pub fn psnr_region<A: Image, B: Image>(first: &A, second: &B, region: Rect) -> Result<f64, RegionError> {
+    let (sum, max_pixel) = region_sums(first, second, region)?;
+    let mse = sum as f64 / region.size.area() as f64;
+    Ok(20f64 * (max_pixel as f64).log10() - 10f64 * mse.log10())
+}
+
+/// Returns the sum of the squared errors within `region`, and the largest pixel of both images
+/// within it.
+fn region_sums<A: Image, B: Image>(first: &A, second: &B, region: Rect) -> Result<(u64, Pixel), RegionError> {
+    let size = first.get_size();
+    if size != second.get_size() {
+        return Err(ImageSizeMismatch(size, second.get_size()).into());
+    }
+    if !Rect::new(Coords { x: 0, y: 0 }, size).contains_rect(&region) {
+        return Err(RegionError::OutOfBounds(region, size));
+    }
+
+    let width = size.get_width() as usize;
+    let (mut row_a, mut row_b) = (vec![0; width], vec![0; width]);
+    let columns = region.origin.x as usize..region.origin.x as usize + region.size.get_width() as usize;
+
+    let (mut sum, mut max_pixel) = (0u64, 0);
+    for y in region.origin.y..region.origin.y + region.size.get_height() {
+        first.copy_row_into(y, &mut row_a);
+        second.copy_row_into(y, &mut row_b);
+        for (&px_a, &px_b) in row_a[columns.clone()].iter().zip(&row_b[columns.clone()]) {
+            sum += (px_a.abs_diff(px_b) as u64).pow(2);
+            max_pixel = max(max_pixel, max(px_a, px_b));
+        }
+    }
+    Ok((sum, max_pixel))
+}
+
+/// Splits the images into non-overlapping square windows of `window_size`, which are cut off at
+/// the right and bottom border, and returns the window with the largest [MSE](mse) together with
+/// its MSE. Returns an empty window at the origin for empty images.
+///
+/// Panics if `window_size` is zero.
+pub fn worst_window<A: Image, B: Image>(first: &A, second: &B, window_size: u32) -> Result<(Rect, f64), ImageSizeMismatch> {
+    assert!(window_size > 0, "The windows need to be at least one pixel large");
+    let size = first.get_size();
+    if size != second.get_size() {
+        return Err(ImageSizeMismatch(size, second.get_size()));
+    }
+
+    let bounds = Rect::new(Coords { x: 0, y: 0 }, size);
+    let windows = (0..size.get_height())
+        .step_by(window_size as usize)
+        .flat_map(|y| (0..size.get_width()).step_by(window_size as usize).map(move |x| (x, y)))
+        .filter_map(|(x, y)| Rect::new(Coords { x, y }, Size::squared(window_size)).intersection(&bounds));
+
+    let mut worst = (Rect::new(Coords { x: 0, y: 0 }, Size::squared(0)), 0.0);
+    for window in windows {
+        let mse = mse_region(first, second, window).expect("The window lies within the images");
+        if mse > worst.1 || worst.0.is_empty() {
+            worst = (window, mse);
+        }
+    }
+    Ok(worst)
+}
+
+/// Computes the [Shannon entropy](https://en.wikipedia.org/wiki/Entropy_(information_theory)) of
+/// the pixel values of `image` in bits per pixel, which ranges from 0 for a constant image to 8
+/// if all values occur equally often. A lower entropy hints at a better compressible image.
+/// Returns 0 for an empty image.
+pub fn entropy<I: Image>(image: &I) -> f64 {
+    let area = image.get_size().area() as f64;
+    histogram(image)
+        .iter()
+        .filter(|&&occurrences| occurrences > 0)
+        .map(|&occurrences| {
+            let probability = occurrences as f64 / area;
+            -probability * probability.log2()
+        })
+        .sum()
+}
+
+/// Computes the intersection of the normalized histograms of two images, which ranges from 0 if
+/// no pixel value occurs in both to 1 if the values are distributed equally. The images may have
+/// different sizes. Returns NaN if an image is empty.
+pub fn histogram_intersection<A: Image, B: Image>(first: &A, second: &B) -> f64 {
+    let (area_a, area_b) = (first.get_size().area() as f64, second.get_size().area() as f64);
+    histogram(first)
+        .iter()
+        .zip(histogram(second).iter())
+        .map(|(&occurrences_a, &occurrences_b)| (occurrences_a as f64 / area_a).min(occurrences_b as f64 / area_b))
+        .sum()
+}
+
+fn max_pixel<I: Image>(image: &I) -> Pixel {
+    let mut row = vec![0; image.get_width() as usize];
+    (0..image.get_height())
+        .filter_map(|y| {
+            image.copy_row_into(y, &mut row);
+            row.iter().copied().max()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     mod mse {
         use fluid::prelude::ShouldExtension;
-        use crate::image::FakeImage;
+        use crate::image::{FakeImage, IntoRotated, OwnedImage};
         use super::*;
 
+        #[test]
+        fn mse_of_rows_equals_mse_of_pixels() {
+            let first = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let second = OwnedImage::random_with_seed(Size::new(32, 16), 2);
+            let (first_rotated, second_rotated) = (first.clone().rot_0(), second.clone().rot_0());
+            assert!(first_rotated.pixel_row(0).is_none());
+
+            assert_eq!(mse(&first, &second), mse(&first_rotated, &second_rotated));
+            assert_eq!(mse(&first, &second_rotated), mse(&first, &second));
+        }
+
         #[test]
         fn mse_for_images_with_different_sizes_returns_error() {
             let first = FakeImage::squared(4);
@@ -56,7 +536,7 @@ mod tests {
 
     mod psnr {
         use fluid::prelude::ShouldExtension;
-        use crate::image::FakeImage;
+        use crate::image::{FakeImage, IntoRotated, OwnedImage};
         use super::*;
 
         #[test]
@@ -72,6 +552,14 @@ mod tests {
                 .because("two images with inequal sizes are not comparable");
         }
 
+        #[test]
+        fn psnr_of_rows_equals_psnr_of_pixels() {
+            let first = OwnedImage::random_with_seed(Size::squared(16), 1);
+            let second = OwnedImage::random_with_seed(Size::squared(16), 2);
+
+            assert_eq!(psnr(&first, &second), psnr(&first.clone().rot_0(), &second.clone().rot_0()));
+        }
+
         #[test]
         fn psnr_for_same_images_returns_infinity() {
             let first = FakeImage::squared(5);
@@ -85,4 +573,437 @@ mod tests {
             result.should().be_equal_to(Ok(f64::INFINITY)).because("two equal images have an infinity PSNR");
         }
     }
-}
\ No newline at end of file
+
+    mod ssim {
+        use crate::image::{FakeImage, IntoRotated, OwnedImage};
+        use super::*;
+
+        fn inverted(image: &OwnedImage) -> OwnedImage {
+            OwnedImage::from_pixels(
+                image.get_size(),
+                image.as_slice().iter().map(|&pixel| Pixel::MAX - pixel).collect(),
+            ).unwrap()
+        }
+
+        #[test]
+        fn ssim_for_same_images_returns_one() {
+            let image = OwnedImage::random_with_seed(Size::squared(32), 1);
+            let result = ssim(&image, &image.clone()).unwrap();
+            assert!((result - 1.0).abs() < 1e-12, "{}", result);
+        }
+
+        #[test]
+        fn ssim_for_inverted_image_is_close_to_minus_one() {
+            let image = OwnedImage::random_with_seed(Size::squared(32), 1);
+            let result = ssim(&image, &inverted(&image)).unwrap();
+            assert!((-1.0..-0.8).contains(&result), "{}", result);
+        }
+
+        #[test]
+        fn ssim_decreases_with_noise() {
+            let image = OwnedImage::random_with_seed(Size::squared(32), 1);
+            let noise = OwnedImage::random_with_seed(Size::squared(32), 2);
+            let noisy = |amount: u32| OwnedImage::from_pixels(
+                image.get_size(),
+                image.as_slice().iter()
+                    .zip(noise.as_slice())
+                    .map(|(&pixel, &noise)| ((pixel as u32 * (8 - amount) + noise as u32 * amount) / 8) as Pixel)
+                    .collect(),
+            ).unwrap();
+
+            let slightly = ssim(&image, &noisy(1)).unwrap();
+            let heavily = ssim(&image, &noisy(6)).unwrap();
+            assert!(1.0 > slightly && slightly > heavily, "{} {}", slightly, heavily);
+        }
+
+        #[test]
+        fn ssim_of_rows_equals_ssim_of_pixels() {
+            let first = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let second = OwnedImage::random_with_seed(Size::new(32, 16), 2);
+
+            assert_eq!(ssim(&first, &second), ssim(&first.clone().rot_0(), &second.clone().rot_0()));
+        }
+
+        #[test]
+        fn ssim_of_images_smaller_than_a_window() {
+            let image = OwnedImage::random_with_seed(Size::new(5, 3), 1);
+            let result = ssim(&image, &inverted(&image)).unwrap();
+            assert!((-1.0..0.0).contains(&result), "{}", result);
+        }
+
+        #[test]
+        fn ssim_for_images_with_different_sizes_returns_error() {
+            let result = ssim(&FakeImage::squared(4), &FakeImage::squared(5));
+            assert_eq!(result, Err(ImageSizeMismatch(Size::squared(4), Size::squared(5))));
+        }
+    }
+
+    mod compare {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use crate::image::{FakeImage, OwnedImage};
+        use super::*;
+
+        /// Counts how often its rows and pixels are read.
+        struct CountingImage {
+            image: OwnedImage,
+            rows_read: AtomicUsize,
+            pixels_read: AtomicUsize,
+        }
+
+        impl CountingImage {
+            fn new(image: OwnedImage) -> Self {
+                Self { image, rows_read: AtomicUsize::new(0), pixels_read: AtomicUsize::new(0) }
+            }
+        }
+
+        impl Image for CountingImage {
+            fn get_size(&self) -> Size {
+                self.image.get_size()
+            }
+
+            fn pixel(&self, x: u32, y: u32) -> Pixel {
+                self.pixels_read.fetch_add(1, Ordering::Relaxed);
+                self.image.pixel(x, y)
+            }
+
+            fn copy_row_into(&self, y: u32, out: &mut [Pixel]) {
+                self.rows_read.fetch_add(1, Ordering::Relaxed);
+                self.image.copy_row_into(y, out)
+            }
+        }
+
+        #[test]
+        fn compare_equals_the_individual_metrics() {
+            let first = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let second = OwnedImage::random_with_seed(Size::new(32, 16), 2);
+
+            let report = compare(&first, &second).unwrap();
+            assert_eq!(report.mse, mse(&first, &second).unwrap());
+            assert_eq!(report.psnr, psnr(&first, &second).unwrap());
+            assert_eq!(report.mae, mae(&first, &second).unwrap());
+            assert_eq!(report.ssim, ssim(&first, &second).unwrap());
+        }
+
+        #[test]
+        fn compare_reads_each_row_once() {
+            let size = Size::new(32, 16);
+            let first = CountingImage::new(OwnedImage::random_with_seed(size, 1));
+            let second = CountingImage::new(OwnedImage::random_with_seed(size, 2));
+
+            compare(&first, &second).unwrap();
+            for image in [&first, &second] {
+                assert_eq!(image.rows_read.load(Ordering::Relaxed), size.get_height() as usize);
+                assert_eq!(image.pixels_read.load(Ordering::Relaxed), 0);
+            }
+        }
+
+        #[test]
+        fn mae_of_known_images() {
+            let first = OwnedImage::filled(Size::squared(4), 10);
+            let second = OwnedImage::from_pixels(Size::squared(4), (0..16).map(|i| if i < 8 { 4 } else { 20 }).collect()).unwrap();
+            assert_eq!(mae(&first, &second), Ok(8.0));
+            assert_eq!(mae(&second, &first), Ok(8.0));
+        }
+
+        #[test]
+        fn report_is_displayed_per_metric() {
+            let report = QualityReport { mse: 4.0, psnr: 42.1234, mae: 1.5, ssim: 0.98766 };
+            assert_eq!(report.to_string(), "MSE: 4.00\nPSNR: 42.12 dB\nMAE: 1.50\nSSIM: 0.9877");
+        }
+
+        #[test]
+        fn compare_for_images_with_different_sizes_returns_error() {
+            assert!(compare(&FakeImage::squared(4), &FakeImage::squared(5)).is_err());
+            assert!(mae(&FakeImage::squared(4), &FakeImage::squared(5)).is_err());
+        }
+    }
+
+    mod error_map {
+        use crate::coords;
+        use crate::image::{Coords, MutableImage, OwnedImage};
+        use crate::model::{Block, Rotation, Transformation};
+        use super::*;
+
+        /// Returns a copy of `image` where the pixels of the 4x4 block at `x` and `y` are changed.
+        fn corrupted(image: &OwnedImage, x: u32, y: u32) -> OwnedImage {
+            let mut corrupted = image.clone();
+            for (dx, dy) in (0..4).flat_map(|dx| (0..4).map(move |dy| (dx, dy))) {
+                let pixel = corrupted.pixel(x + dx, y + dy);
+                corrupted.set_pixel(x + dx, y + dy, pixel.wrapping_add(100));
+            }
+            corrupted
+        }
+
+        fn nonzero_cells(map: &OwnedImage, block_size: u32) -> Vec<(u32, u32)> {
+            (0..map.get_height())
+                .step_by(block_size as usize)
+                .flat_map(|y| (0..map.get_width()).step_by(block_size as usize).map(move |x| (x, y)))
+                .filter(|&(x, y)| map.pixel(x, y) != 0)
+                .collect()
+        }
+
+        #[test]
+        fn error_map_of_same_images_is_black() {
+            let image = OwnedImage::random_with_seed(Size::new(16, 12), 1);
+            let map = error_map(&image, &image.clone(), 4).unwrap();
+
+            assert_eq!(map.get_size(), image.get_size());
+            assert!(map.as_slice().iter().all(|&pixel| pixel == 0));
+        }
+
+        #[test]
+        fn corrupted_block_lights_up_one_cell() {
+            let image = OwnedImage::random_with_seed(Size::new(16, 12), 1);
+            let map = error_map(&image, &corrupted(&image, 8, 4), 4).unwrap();
+
+            assert_eq!(nonzero_cells(&map, 4), vec![(8, 4)]);
+            assert_eq!(map.pixel(8, 4), Pixel::MAX);
+            assert!((8..12).all(|x| (4..8).all(|y| map.pixel(x, y) == Pixel::MAX)));
+        }
+
+        #[test]
+        fn cells_are_cut_off_by_the_image() {
+            let image = OwnedImage::random_with_seed(Size::new(12, 6), 1);
+            let map = error_map(&image, &corrupted(&image, 8, 2), 8).unwrap();
+
+            assert_eq!(nonzero_cells(&map, 8), vec![(8, 0)]);
+            assert_eq!(map.pixel(11, 5), Pixel::MAX);
+            assert_eq!(map.pixel(7, 5), 0);
+        }
+
+        #[test]
+        fn error_map_of_partition_uses_the_range_blocks() {
+            let transformation = |block_size: u32, x: u32, y: u32| Transformation {
+                range: Block { block_size, origin: coords!(x=x, y=y) },
+                domain: Block { block_size: 2 * block_size, origin: coords!(x=0, y=0) },
+                rotation: Rotation::By0,
+                brightness: 0,
+                saturation: 0.0,
+            };
+            let image = OwnedImage::random_with_seed(Size::squared(8), 1);
+
+            // A corrupted quarter of a single block lights up the whole block
+            let compressed = Compressed { size: Size::squared(8), transformations: vec![transformation(8, 0, 0)] };
+            let map = error_map_of_partition(&image, &corrupted(&image, 4, 4), &compressed).unwrap();
+            assert!(map.as_slice().iter().all(|&pixel| pixel == Pixel::MAX));
+
+            // Pixels outside of any range block stay black
+            let compressed = Compressed { size: Size::squared(8), transformations: vec![transformation(4, 0, 0)] };
+            let map = error_map_of_partition(&image, &corrupted(&image, 0, 0), &compressed).unwrap();
+            assert_eq!(nonzero_cells(&map, 4), vec![(0, 0)]);
+        }
+
+        #[test]
+        fn error_map_for_images_with_different_sizes_returns_error() {
+            let first = OwnedImage::random(Size::squared(4));
+            let second = OwnedImage::random(Size::squared(5));
+            assert_eq!(error_map(&first, &second, 2), Err(ImageSizeMismatch(Size::squared(4), Size::squared(5))));
+        }
+    }
+
+    mod compression_report {
+        use crate::coords;
+        use crate::image::Coords;
+        use crate::model::{Block, Rotation, Transformation};
+        use super::*;
+
+        fn compressed(size: Size, transformations: usize) -> Compressed {
+            let transformation = Transformation {
+                range: Block { block_size: 4, origin: coords!(x=0, y=0) },
+                domain: Block { block_size: 8, origin: coords!(x=0, y=0) },
+                rotation: Rotation::By0,
+                brightness: 0,
+                saturation: 0.0,
+            };
+            Compressed { size, transformations: vec![transformation; transformations] }
+        }
+
+        #[test]
+        fn bits_per_pixel_of_known_sizes() {
+            let report = compression_report(&compressed(Size::squared(16), 4), 64);
+            assert_eq!(report.bits_per_pixel, 2.0);
+            assert_eq!(report.bytes_per_transformation, 16.0);
+            assert_eq!(report.transformations, 4);
+
+            let report = compression_report(&compressed(Size::new(32, 8), 10), 5);
+            assert_eq!(report.bits_per_pixel, 40.0 / 256.0);
+            assert_eq!(report.bytes_per_transformation, 0.5);
+        }
+
+        #[test]
+        fn ratio_relative_to_the_reference() {
+            let report = compression_report(&compressed(Size::squared(16), 4), 64);
+            assert_eq!(report.ratio(), None);
+            assert_eq!(report.with_reference(256).ratio(), Some(0.25));
+        }
+
+        #[test]
+        fn report_without_transformations() {
+            let report = compression_report(&compressed(Size::squared(16), 0), 12);
+            assert_eq!(report.bytes_per_transformation, 0.0);
+        }
+
+        #[test]
+        fn report_is_displayed_with_the_ratio() {
+            let report = compression_report(&compressed(Size::squared(16), 4), 64).with_reference(128);
+            assert_eq!(
+                report.to_string(),
+                "Size: 64 bytes\nBits per pixel: 2.000\nTransformations: 4 (16.00 bytes each)\nRatio: 0.500 (reference: 128 bytes)"
+            );
+        }
+    }
+
+    mod regions {
+        use crate::image::{MutableImage, OwnedImage};
+        use super::*;
+
+        fn rect(x: u32, y: u32, width: u32, height: u32) -> Rect {
+            Rect::new(Coords { x, y }, Size::new(width, height))
+        }
+
+        /// An image with an artifact of 3x2 pixels at (21, 9).
+        fn with_artifact(image: &OwnedImage) -> OwnedImage {
+            let mut result = image.clone();
+            for (x, y) in (21..24).flat_map(|x| (9..11).map(move |y| (x, y))) {
+                result.set_pixel(x, y, image.pixel(x, y).wrapping_add(128));
+            }
+            result
+        }
+
+        #[test]
+        fn region_covering_the_image_equals_the_global_metrics() {
+            let first = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let second = OwnedImage::random_with_seed(Size::new(32, 16), 2);
+            let whole = rect(0, 0, 32, 16);
+
+            assert_eq!(mse_region(&first, &second, whole).unwrap(), mse(&first, &second).unwrap());
+            assert_eq!(psnr_region(&first, &second, whole).unwrap(), psnr(&first, &second).unwrap());
+        }
+
+        #[test]
+        fn region_only_considers_its_pixels() {
+            let image = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let artifact = with_artifact(&image);
+
+            assert_eq!(mse_region(&image, &artifact, rect(0, 0, 21, 16)), Ok(0.0));
+            assert_eq!(mse_region(&image, &artifact, rect(21, 9, 3, 2)), Ok(128.0 * 128.0));
+            assert_eq!(psnr_region(&image, &artifact, rect(24, 0, 8, 16)), Ok(f64::INFINITY));
+        }
+
+        #[test]
+        fn region_outside_of_the_images_returns_error() {
+            let image = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let region = rect(30, 0, 4, 4);
+            assert_eq!(mse_region(&image, &image, region), Err(RegionError::OutOfBounds(region, Size::new(32, 16))));
+            assert!(matches!(
+                psnr_region(&image, &OwnedImage::random(Size::squared(4)), rect(0, 0, 1, 1)),
+                Err(RegionError::SizeMismatch(_))
+            ));
+        }
+
+        #[test]
+        fn worst_window_contains_the_artifact() {
+            let image = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let (window, mse) = worst_window(&image, &with_artifact(&image), 8).unwrap();
+
+            assert_eq!(window, rect(16, 8, 8, 8));
+            assert_eq!(mse, 6.0 * 128.0 * 128.0 / 64.0);
+        }
+
+        #[test]
+        fn worst_window_is_cut_off_by_the_image() {
+            let image = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let (window, _) = worst_window(&image, &with_artifact(&image), 12).unwrap();
+            assert_eq!(window, rect(12, 0, 12, 12));
+
+            let (window, mse) = worst_window(&image, &with_artifact(&image), 20).unwrap();
+            assert_eq!(window, rect(20, 0, 12, 16));
+            assert_eq!(mse, 6.0 * 128.0 * 128.0 / (12.0 * 16.0));
+        }
+
+        #[test]
+        fn worst_window_of_same_images_has_no_error() {
+            let image = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let (window, mse) = worst_window(&image, &image.clone(), 8).unwrap();
+            assert_eq!(window, rect(0, 0, 8, 8));
+            assert_eq!(mse, 0.0);
+        }
+    }
+
+    mod parallel {
+        use crate::image::{FakeImage, OwnedImage, WithoutRows};
+        use super::*;
+
+        #[test]
+        fn parallel_metrics_equal_the_serial_ones() {
+            for (size, seed) in [(Size::new(301, 97), 1), (Size::squared(256), 2), (Size::new(1, 1), 3)] {
+                let first = OwnedImage::random_with_seed(size, seed);
+                let second = OwnedImage::random_with_seed(size, seed + 10);
+
+                assert_eq!(mse_par(&first, &second), mse(&first, &second));
+                assert_eq!(psnr_par(&first, &second), psnr(&first, &second));
+            }
+        }
+
+        #[test]
+        fn parallel_metrics_of_images_without_rows_equal_the_serial_ones() {
+            let first = WithoutRows(OwnedImage::random_with_seed(Size::new(64, 48), 1));
+            let second = WithoutRows(OwnedImage::random_with_seed(Size::new(64, 48), 2));
+
+            assert_eq!(mse_par(&first, &second), mse(&first, &second));
+            assert_eq!(psnr_par(&first, &second), psnr(&first, &second));
+        }
+
+        #[test]
+        fn parallel_psnr_of_same_images_is_infinite() {
+            let image = OwnedImage::random_with_seed(Size::squared(64), 1);
+            assert_eq!(psnr_par(&image, &image.clone()), Ok(f64::INFINITY));
+        }
+
+        #[test]
+        fn parallel_metrics_for_images_with_different_sizes_return_error() {
+            assert!(mse_par(&FakeImage::squared(4), &FakeImage::squared(5)).is_err());
+            assert!(psnr_par(&FakeImage::squared(4), &FakeImage::squared(5)).is_err());
+        }
+    }
+
+    mod histograms {
+        use crate::image::OwnedImage;
+        use super::*;
+
+        #[test]
+        fn entropy_of_constant_image_is_zero() {
+            assert_eq!(entropy(&OwnedImage::filled(Size::squared(16), 42)), 0.0);
+        }
+
+        #[test]
+        fn entropy_of_noise_approaches_eight_bits() {
+            let entropy = entropy(&OwnedImage::random_with_seed(Size::squared(256), 1));
+            assert!(7.99 < entropy && entropy <= 8.0, "{}", entropy);
+        }
+
+        #[test]
+        fn entropy_of_two_equally_frequent_values_is_one_bit() {
+            let image = OwnedImage::from_pixels(Size::new(4, 2), vec![0, 255, 0, 255, 255, 0, 255, 0]).unwrap();
+            assert_eq!(entropy(&image), 1.0);
+        }
+
+        #[test]
+        fn histogram_intersection_with_itself_is_one() {
+            let image = OwnedImage::random_with_seed(Size::new(32, 16), 1);
+            let intersection = histogram_intersection(&image, &image);
+            assert!((intersection - 1.0).abs() < 1e-12, "{}", intersection);
+        }
+
+        #[test]
+        fn histogram_intersection_of_known_images() {
+            let black = OwnedImage::filled(Size::squared(4), 0);
+            let white = OwnedImage::filled(Size::squared(8), 255);
+            let half = OwnedImage::from_pixels(Size::new(2, 1), vec![0, 255]).unwrap();
+
+            assert_eq!(histogram_intersection(&black, &white), 0.0);
+            assert_eq!(histogram_intersection(&black, &half), 0.5);
+            assert_eq!(histogram_intersection(&half, &white), 0.5);
+        }
+    }
+}
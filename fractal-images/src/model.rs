@@ -2,8 +2,16 @@ mod block;
 mod transformation;
 mod compressed;
 mod rotation;
+mod quadtree;
+mod validation;
+mod partition;
 
 pub use block::Block;
-pub use compressed::Compressed;
+pub use compressed::{ColorCompressed, Compressed, LosslessCompressed};
 pub use transformation::Transformation;
-pub use rotation::{Rotation, RotationInvalidError};
\ No newline at end of file
+pub use rotation::{Rotation, RotationInvalidError};
+pub use quadtree::QuadtreeNode;
+pub use validation::ValidationError;
+pub use partition::PartitionStats;
+#[cfg(any(feature = "persist-as-binary-v1", feature = "persist-as-binary-v2"))]
+pub(crate) use validation::validate_transformation;
\ No newline at end of file
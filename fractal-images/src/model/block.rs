@@ -10,16 +10,14 @@ pub struct Block {
 }
 
 impl Block {
-    pub fn indices(
-        &self,
-        image_width: u32,
-        image_height: u32,
-    ) -> impl Iterator<Item = (usize, Coords)> {
+    /// Returns the index of each pixel of the block in the row-major pixels of an image,
+    /// together with its coordinates.
+    pub fn indices(&self, image_width: u32) -> impl Iterator<Item = (usize, Coords)> {
         let mut indices: Vec<(usize, Coords)> = Vec::with_capacity(self.block_size.pow(2) as usize);
         for i in 0..self.block_size {
             for j in 0..self.block_size {
                 let index =
-                    (self.origin.y * image_width + self.origin.x + image_height * i + j) as usize;
+                    ((self.origin.y + i) * image_width + self.origin.x + j) as usize;
                 indices.push((index, coords!(x=self.origin.x + j, y=self.origin.y + i)))
             }
         }
@@ -63,7 +61,61 @@ mod tests {
                 (53, coords!(x=3, y=5)),
                 (54, coords!(x=4, y=5))
             ],
-            block.indices(10,10).collect::<Vec<_>>()
+            block.indices(10).collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn get_indices_of_non_square_image_at_right_edge() {
+        //  0   1   2   3   4   5   6   7   8   9
+        // 10  11  12  13  14  15  16  17  18  19
+        // 20  21  22  23  24  25  26  27  28  29
+        // 30  31  32  33  34  35  36  37  38  39
+        // 40  41  42  43  44  45  46  47  48  49
+        // 50  51  52  53  54  55  56  57  58  59
+
+        let block = Block {
+            block_size: 2,
+            origin: coords!(x=8, y=1),
+        };
+
+        assert_eq!(
+            vec![
+                (18, coords!(x=8, y=1)),
+                (19, coords!(x=9, y=1)),
+                (28, coords!(x=8, y=2)),
+                (29, coords!(x=9, y=2)),
+            ],
+            block.indices(10).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn get_indices_of_non_square_image_at_bottom_edge() {
+        let block = Block {
+            block_size: 2,
+            origin: coords!(x=3, y=4),
+        };
+
+        assert_eq!(
+            vec![
+                (43, coords!(x=3, y=4)),
+                (44, coords!(x=4, y=4)),
+                (53, coords!(x=3, y=5)),
+                (54, coords!(x=4, y=5)),
+            ],
+            block.indices(10).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn get_indices_of_tall_image() {
+        let block = Block {
+            block_size: 2,
+            origin: coords!(x=0, y=8),
+        };
+
+        let indices = block.indices(4).map(|(index, _)| index).collect::<Vec<_>>();
+        assert_eq!(vec![32, 33, 36, 37], indices);
+    }
 }
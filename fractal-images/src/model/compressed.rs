@@ -8,4 +8,44 @@ pub struct Compressed {
     
     /// All [transformations](Transformation) to reconstruct the image
     pub transformations: Vec<Transformation>,
-}
\ No newline at end of file
+}
+
+/// A color image, compressed as separate planes of the YCbCr color space.
+#[derive(Debug, Clone)]
+pub struct ColorCompressed {
+    /// Whether the chroma planes have half the width and height of the luma plane
+    pub chroma_subsampled: bool,
+
+    /// The luma (Y) plane, which has the size of the image
+    pub luma: Compressed,
+
+    /// The blue-difference chroma (Cb) plane
+    pub blue_chroma: Compressed,
+
+    /// The red-difference chroma (Cr) plane
+    pub red_chroma: Compressed,
+}
+
+impl ColorCompressed {
+    /// The size of the compressed image
+    pub fn size(&self) -> Size {
+        self.luma.size
+    }
+}
+
+/// A compressed image together with the residual between the image and its decompression,
+/// which allows to reproduce the image exactly.
+#[derive(Debug, Clone)]
+pub struct LosslessCompressed {
+    /// The fractal compression of the image
+    pub compressed: Compressed,
+
+    /// The amount of iterations the residual was computed with
+    pub iterations: u8,
+
+    /// The overlap the residual was computed with
+    pub overlap: u32,
+
+    /// The difference of each pixel of the image to the decompressed pixel, in row-major order
+    pub residual: Vec<i16>,
+}
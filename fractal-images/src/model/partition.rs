@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+
+use crate::image::{Coords, Rect};
+use crate::model::{Compressed, Transformation};
+
+/// Describes how the range blocks of a [Compressed] partition the image, see
+/// [Compressed::partition_stats].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartitionStats {
+    /// The amount of transformations
+    pub transformations: usize,
+
+    /// The amount of range blocks per block size, ordered from the largest to the smallest
+    /// block size
+    pub blocks_per_size: Vec<(u32, usize)>,
+
+    /// The fraction of pixels covered by at least one range block
+    pub covered_fraction: f64,
+
+    /// The smallest range block size, or `None` without transformations
+    pub min_block_size: Option<u32>,
+
+    /// The largest range block size, or `None` without transformations
+    pub max_block_size: Option<u32>,
+
+    /// The mean range block size, or `None` without transformations
+    pub mean_block_size: Option<f64>,
+
+    /// The depth of the smallest range block within a quadtree whose root covers the image,
+    /// i.e. how often the image was split, or `None` without transformations
+    pub quadtree_depth: Option<u32>,
+
+    /// Pairs of indices of transformations whose range blocks share pixels, in ascending order.
+    /// A pixel covered by more than two blocks only pairs the first of them with the others.
+    pub overlaps: Vec<(usize, usize)>,
+}
+
+impl PartitionStats {
+    /// Whether every pixel is covered by exactly one range block.
+    pub fn is_exact_partition(&self) -> bool {
+        self.covered_fraction == 1.0 && self.overlaps.is_empty()
+    }
+}
+
+impl Display for PartitionStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Transformations: {}", self.transformations)?;
+        writeln!(f, "Blocks per size: {}", self.blocks_per_size
+            .iter()
+            .map(|(block_size, count)| format!("{}x{}: {}", block_size, block_size, count))
+            .join(", "))?;
+        if let (Some(min), Some(max), Some(mean), Some(depth)) =
+            (self.min_block_size, self.max_block_size, self.mean_block_size, self.quadtree_depth) {
+            writeln!(f, "Block sizes: {} to {} (mean {:.2})", min, max, mean)?;
+            writeln!(f, "Quadtree depth: {}", depth)?;
+        }
+        write!(f, "Covered: {:.2}%", 100.0 * self.covered_fraction)?;
+        if let Some((first, second)) = self.overlaps.first() {
+            write!(f, "\nOverlapping pairs: {} (e.g. transformations {} and {})", self.overlaps.len(), first, second)?;
+        }
+        Ok(())
+    }
+}
+
+impl Compressed {
+    /// Computes statistics about the range blocks, without needing the original image. Parts of
+    /// blocks outside of the image are ignored.
+    pub fn partition_stats(&self) -> PartitionStats {
+        let range_sizes = || self.transformations.iter().map(|t| t.range.block_size);
+        let blocks_per_size = range_sizes()
+            .counts()
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| b.cmp(a))
+            .collect();
+        let min_block_size = range_sizes().min();
+        let max_block_size = range_sizes().max();
+        let mean_block_size = (!self.transformations.is_empty())
+            .then(|| range_sizes().map(|size| size as f64).sum::<f64>() / self.transformations.len() as f64);
+        let side = self.size.get_width().max(self.size.get_height());
+        let quadtree_depth = min_block_size.map(|min| (side / min.max(1)).max(1).ilog2());
+
+        let (covered, overlaps) = coverage(&self.transformations, Rect::new(Coords { x: 0, y: 0 }, self.size));
+        let area = self.size.get_width() as u64 * self.size.get_height() as u64;
+
+        PartitionStats {
+            transformations: self.transformations.len(),
+            blocks_per_size,
+            covered_fraction: covered as f64 / area as f64,
+            min_block_size,
+            max_block_size,
+            mean_block_size,
+            quadtree_depth,
+            overlaps: overlaps.into_iter().collect(),
+        }
+    }
+}
+
+/// The amount of pixels within `bounds` covered by the range blocks, and the pairs of
+/// overlapping transformations as described in [PartitionStats::overlaps].
+///
+/// The size of a compression is not trusted, hence the pixels are not visited one by one.
+/// Instead, the image is swept in horizontal bands between the upper and lower edges of the
+/// range blocks, where each band is covered by the same blocks in all of its rows.
+fn coverage(transformations: &[Transformation], bounds: Rect) -> (u64, BTreeSet<(usize, usize)>) {
+    let ranges: Vec<(usize, Rect)> = transformations
+        .iter()
+        .enumerate()
+        .filter_map(|(index, transformation)| Some((index, Rect::from(transformation.range).intersection(&bounds)?)))
+        .collect();
+    let top = |range: &Rect| range.origin.y as u64;
+    let bottom = |range: &Rect| range.origin.y as u64 + range.size.get_height() as u64;
+
+    // The indices of the ranges starting and ending at each edge
+    let mut edges: BTreeMap<u64, (Vec<usize>, Vec<usize>)> = BTreeMap::new();
+    for (position, (_, range)) in ranges.iter().enumerate() {
+        edges.entry(top(range)).or_default().0.push(position);
+        edges.entry(bottom(range)).or_default().1.push(position);
+    }
+
+    let mut covered = 0;
+    let mut overlaps = BTreeSet::new();
+    // The ranges covering the current band, by the index of their transformation
+    let mut active: BTreeMap<usize, Rect> = BTreeMap::new();
+    let mut edges = edges.into_iter().peekable();
+    while let Some((y, (starting, ending))) = edges.next() {
+        for position in ending {
+            active.remove(&ranges[position].0);
+        }
+        for position in starting {
+            active.insert(ranges[position].0, ranges[position].1);
+        }
+        let Some(&(next_y, _)) = edges.peek() else {
+            break;
+        };
+
+        // The painted intervals of the band by their start, with their end and the first
+        // transformation covering them
+        let mut owners: BTreeMap<u64, (u64, usize)> = BTreeMap::new();
+        for (&index, range) in &active {
+            let (start, end) = (range.origin.x as u64, range.origin.x as u64 + range.size.get_width() as u64);
+            let overlapping: Vec<(u64, u64, usize)> = owners
+                .range(..=start)
+                .next_back()
+                .filter(|(_, &(owned_end, _))| owned_end > start)
+                .into_iter()
+                .chain(owners.range(start + 1..end))
+                .map(|(&owned_start, &(owned_end, owner))| (owned_start, owned_end, owner))
+                .collect();
+
+            let mut position = start;
+            for (owned_start, owned_end, owner) in overlapping {
+                overlaps.insert((owner, index));
+                if owned_start > position {
+                    owners.insert(position, (owned_start, index));
+                }
+                position = position.max(owned_end);
+            }
+            if position < end {
+                owners.insert(position, (end, index));
+            }
+        }
+        let covered_width: u64 = owners.iter().map(|(start, (end, _))| end - start).sum();
+        covered += covered_width * (next_y - y);
+    }
+
+    (covered, overlaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+    use crate::image::Size;
+    use crate::model::{Block, Rotation, Transformation};
+
+    use super::*;
+
+    fn transformation(block_size: u32, x: u32, y: u32) -> Transformation {
+        Transformation {
+            range: Block { block_size, origin: coords!(x=x, y=y) },
+            domain: Block { block_size: 2 * block_size, origin: coords!(x=0, y=0) },
+            rotation: Rotation::By0,
+            brightness: 0,
+            saturation: 0.5,
+        }
+    }
+
+    fn compressed(transformations: Vec<Transformation>) -> Compressed {
+        Compressed { size: Size::squared(8), transformations }
+    }
+
+    /// A 4x4 block on the left top, two 4x4 blocks on the right top and the left bottom, and
+    /// four 2x2 blocks on the right bottom.
+    fn partition() -> Vec<Transformation> {
+        vec![
+            transformation(4, 0, 0),
+            transformation(4, 4, 0),
+            transformation(4, 0, 4),
+            transformation(2, 4, 4),
+            transformation(2, 6, 4),
+            transformation(2, 4, 6),
+            transformation(2, 6, 6),
+        ]
+    }
+
+    #[test]
+    fn stats_of_a_partition() {
+        let stats = compressed(partition()).partition_stats();
+
+        assert_eq!(stats.transformations, 7);
+        assert_eq!(stats.blocks_per_size, vec![(4, 3), (2, 4)]);
+        assert_eq!(stats.covered_fraction, 1.0);
+        assert_eq!(stats.min_block_size, Some(2));
+        assert_eq!(stats.max_block_size, Some(4));
+        assert_eq!(stats.mean_block_size, Some(20.0 / 7.0));
+        assert_eq!(stats.quadtree_depth, Some(2));
+        assert!(stats.overlaps.is_empty());
+        assert!(stats.is_exact_partition());
+    }
+
+    #[test]
+    fn gap_is_not_covered() {
+        let mut transformations = partition();
+        transformations.remove(6);
+        let stats = compressed(transformations).partition_stats();
+
+        assert_eq!(stats.covered_fraction, 60.0 / 64.0);
+        assert!(stats.overlaps.is_empty());
+        assert!(!stats.is_exact_partition());
+    }
+
+    #[test]
+    fn overlapping_blocks_are_reported() {
+        let mut transformations = partition();
+        transformations.push(transformation(4, 2, 2));
+        transformations.push(transformation(2, 6, 6));
+        let stats = compressed(transformations).partition_stats();
+
+        assert_eq!(stats.covered_fraction, 1.0);
+        assert_eq!(stats.overlaps, vec![(0, 7), (1, 7), (2, 7), (3, 7), (6, 8)]);
+        assert!(!stats.is_exact_partition());
+        assert!(stats.to_string().contains("Overlapping pairs: 5 (e.g. transformations 0 and 7)"), "{}", stats);
+    }
+
+    #[test]
+    fn blocks_outside_of_the_image_are_cut_off() {
+        let stats = compressed(vec![transformation(8, 4, 4), transformation(4, 8, 0)]).partition_stats();
+        assert_eq!(stats.covered_fraction, 16.0 / 64.0);
+        assert!(stats.overlaps.is_empty());
+    }
+
+    #[test]
+    fn overlaps_equal_those_of_single_pixels() {
+        let transformations: Vec<_> = (0..40u32)
+            .map(|i| transformation(1 << (i % 4), (i * 7) % 13, (i * 11) % 17))
+            .collect();
+        let size = Size::squared(16);
+
+        // The index of the first transformation covering each pixel
+        let mut owners = vec![None; size.area() as usize];
+        let mut expected_overlaps = BTreeSet::new();
+        for (index, transformation) in transformations.iter().enumerate() {
+            let range = transformation.range;
+            for y in range.origin.y..(range.origin.y + range.block_size).min(16) {
+                for x in range.origin.x..(range.origin.x + range.block_size).min(16) {
+                    match owners[(16 * y + x) as usize] {
+                        Some(other) => {
+                            expected_overlaps.insert((other, index));
+                        }
+                        None => owners[(16 * y + x) as usize] = Some(index),
+                    }
+                }
+            }
+        }
+        let expected_covered = owners.iter().filter(|owner| owner.is_some()).count();
+
+        let stats = Compressed { size, transformations }.partition_stats();
+        assert_eq!(stats.covered_fraction, expected_covered as f64 / 256.0);
+        assert_eq!(stats.overlaps, expected_overlaps.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn huge_images_are_not_allocated() {
+        let compressed = Compressed { size: Size::squared(1 << 31), transformations: vec![transformation(4, 0, 0)] };
+
+        let stats = compressed.partition_stats();
+
+        assert_eq!(stats.covered_fraction, 16.0 / 2f64.powi(62));
+    }
+
+    #[test]
+    fn stats_without_transformations() {
+        let stats = compressed(vec![]).partition_stats();
+
+        assert_eq!(stats.covered_fraction, 0.0);
+        assert_eq!(stats.min_block_size, None);
+        assert_eq!(stats.mean_block_size, None);
+        assert_eq!(stats.quadtree_depth, None);
+        assert_eq!(stats.to_string(), "Transformations: 0\nBlocks per size: \nCovered: 0.00%");
+    }
+}
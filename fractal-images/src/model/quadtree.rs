@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use crate::coords;
+use crate::image::Coords;
+use crate::model::{Block, Compressed};
+
+/// A node of the quadtree which describes how an image was split into range blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuadtreeNode {
+    /// A range block which is mapped by the transformation at index `transformation`
+    /// of [Compressed::transformations].
+    Leaf { block: Block, transformation: usize },
+
+    /// A block which was split into four quadrants, ordered top left, top right,
+    /// bottom left and bottom right.
+    Split { block: Block, children: Box<[QuadtreeNode; 4]> },
+
+    /// A block which is not covered by any transformation, e.g. because its pixels
+    /// could not be mapped.
+    Empty { block: Block },
+}
+
+impl QuadtreeNode {
+    /// The region of the image this node describes.
+    pub fn block(&self) -> &Block {
+        match self {
+            QuadtreeNode::Leaf { block, .. } => block,
+            QuadtreeNode::Split { block, .. } => block,
+            QuadtreeNode::Empty { block } => block,
+        }
+    }
+
+    /// Returns all [leaves](QuadtreeNode::Leaf) below this node, in depth-first order.
+    pub fn leaves(&self) -> Vec<&QuadtreeNode> {
+        match self {
+            QuadtreeNode::Leaf { .. } => vec![self],
+            QuadtreeNode::Split { children, .. } => children.iter().flat_map(|child| child.leaves()).collect(),
+            QuadtreeNode::Empty { .. } => vec![],
+        }
+    }
+
+    /// Builds the node of `block`, where `ranges` are the range blocks whose origin lies
+    /// within `block`. Blocks without any range block are not split any further.
+    fn build(block: Block, ranges: &[Block], transformations: &HashMap<(u32, Coords), usize>) -> Self {
+        if let Some(&transformation) = transformations.get(&(block.block_size, block.origin)) {
+            return QuadtreeNode::Leaf { block, transformation };
+        }
+
+        if block.block_size <= 1 || ranges.is_empty() {
+            return QuadtreeNode::Empty { block };
+        }
+
+        let size = block.block_size / 2;
+        let Coords { x, y } = block.origin;
+        let children = [coords!(x=x, y=y), coords!(x=x + size, y=y), coords!(x=x, y=y + size), coords!(x=x + size, y=y + size)]
+            .map(|origin| {
+                let child = Block { block_size: size, origin };
+                let within = |offset: u32, start: u32| (start..start + size).contains(&offset);
+                let ranges = ranges
+                    .iter()
+                    .filter(|range| within(range.origin.x, origin.x) && within(range.origin.y, origin.y))
+                    .copied()
+                    .collect::<Vec<_>>();
+                Self::build(child, &ranges, transformations)
+            });
+
+        if children.iter().all(|child| matches!(child, QuadtreeNode::Empty { .. })) {
+            QuadtreeNode::Empty { block }
+        } else {
+            QuadtreeNode::Split { block, children: Box::new(children) }
+        }
+    }
+}
+
+impl Compressed {
+    /// Reconstructs the quadtree of range blocks from the [transformations](Compressed::transformations).
+    ///
+    /// Returns `None` if the image is not a square whose size is a power of two, or if
+    /// the range blocks do not form a quadtree, e.g. because they overlap.
+    pub fn as_quadtree(&self) -> Option<QuadtreeNode> {
+        if !self.size.is_squared() || !self.size.get_width().is_power_of_two() {
+            return None;
+        }
+
+        let mut transformations = HashMap::with_capacity(self.transformations.len());
+        for (i, transformation) in self.transformations.iter().enumerate() {
+            let range = transformation.range;
+            if transformations.insert((range.block_size, range.origin), i).is_some() {
+                return None;
+            }
+        }
+
+        let ranges = self.transformations.iter().map(|transformation| transformation.range).collect::<Vec<_>>();
+        let root = QuadtreeNode::build(
+            Block { block_size: self.size.get_width(), origin: coords!(x=0, y=0) },
+            &ranges,
+            &transformations,
+        );
+
+        // Range blocks which are not aligned to the quadtree or lie within a leaf are not reached
+        match root.leaves().len() == self.transformations.len() {
+            true => Some(root),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::Size;
+    use crate::model::{Rotation, Transformation};
+
+    use super::*;
+
+    fn transformation(block_size: u32, x: u32, y: u32) -> Transformation {
+        Transformation {
+            range: Block { block_size, origin: coords!(x=x, y=y) },
+            domain: Block { block_size: 2 * block_size, origin: coords!(x=0, y=0) },
+            rotation: Rotation::By0,
+            brightness: 0,
+            saturation: 0.0,
+        }
+    }
+
+    fn compressed(transformations: Vec<Transformation>) -> Compressed {
+        Compressed { size: Size::squared(4), transformations }
+    }
+
+    #[test]
+    fn split_blocks_become_inner_nodes() {
+        let compressed = compressed(vec![
+            transformation(2, 0, 0),
+            transformation(2, 2, 0),
+            transformation(2, 0, 2),
+            transformation(1, 2, 2),
+            transformation(1, 3, 2),
+            transformation(1, 2, 3),
+            transformation(1, 3, 3),
+        ]);
+
+        let tree = compressed.as_quadtree().unwrap();
+        let QuadtreeNode::Split { children, .. } = &tree else {
+            panic!("Expected the root to be split, was {:?}", tree);
+        };
+        assert!(matches!(children[0], QuadtreeNode::Leaf { transformation: 0, .. }));
+        assert!(matches!(children[3], QuadtreeNode::Split { .. }));
+        assert_eq!(tree.leaves().len(), 7);
+    }
+
+    #[test]
+    fn uncovered_blocks_are_empty() {
+        let compressed = compressed(vec![transformation(2, 0, 0)]);
+
+        let tree = compressed.as_quadtree().unwrap();
+        let QuadtreeNode::Split { children, .. } = &tree else {
+            panic!("Expected the root to be split, was {:?}", tree);
+        };
+        assert!(matches!(children[1], QuadtreeNode::Empty { .. }));
+    }
+
+    #[test]
+    fn blocks_without_range_blocks_are_not_split() {
+        let compressed = Compressed { size: Size::squared(65536), transformations: vec![transformation(2, 0, 0)] };
+
+        let tree = compressed.as_quadtree().unwrap();
+
+        assert_eq!(tree.leaves().len(), 1);
+        let QuadtreeNode::Split { children, .. } = &tree else {
+            panic!("Expected the root to be split, was {:?}", tree);
+        };
+        assert_eq!(children[3], QuadtreeNode::Empty { block: Block { block_size: 32768, origin: coords!(x=32768, y=32768) } });
+    }
+
+    #[test]
+    fn overlapping_blocks_are_no_quadtree() {
+        let compressed = compressed(vec![transformation(2, 0, 0), transformation(1, 1, 1)]);
+        assert_eq!(compressed.as_quadtree(), None);
+    }
+
+    #[test]
+    fn unaligned_blocks_are_no_quadtree() {
+        let compressed = compressed(vec![transformation(2, 1, 0)]);
+        assert_eq!(compressed.as_quadtree(), None);
+    }
+}
@@ -0,0 +1,153 @@
+use thiserror::Error;
+
+use crate::image::{Coords, Rect, Size};
+use crate::model::{Block, Compressed, Transformation};
+
+/// Describes why a [Compressed] image can not be decompressed.
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("Transformation {transformation} contains a block of size zero")]
+    EmptyBlock { transformation: usize },
+
+    #[error("Transformation {transformation} contains the block {block:?}, which exceeds the image size {size}")]
+    BlockOutOfBounds { transformation: usize, block: Block, size: Size },
+
+    #[error("Transformation {transformation} maps a domain block of size {domain_size} to a range block of size {range_size}, \
+    but the domain block size needs to be a power of two multiple of the range block size")]
+    InvalidDomainBlockSize { transformation: usize, range_size: u32, domain_size: u32 },
+}
+
+impl ValidationError {
+    /// The index of the invalid transformation.
+    pub fn transformation(&self) -> usize {
+        match *self {
+            ValidationError::EmptyBlock { transformation }
+            | ValidationError::BlockOutOfBounds { transformation, .. }
+            | ValidationError::InvalidDomainBlockSize { transformation, .. } => transformation,
+        }
+    }
+}
+
+impl Compressed {
+    /// Checks that every block lies within the image and that every domain block is larger
+    /// than its range block by a power of two, which is required to decompress the image.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for (i, transformation) in self.transformations.iter().enumerate() {
+            validate_transformation(i, transformation, self.size)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks a single transformation of an image of the given size like [Compressed::validate],
+/// where `index` is the index reported in errors.
+pub(crate) fn validate_transformation(index: usize, transformation: &Transformation, size: Size) -> Result<(), ValidationError> {
+    let (range, domain) = (transformation.range, transformation.domain);
+
+    for block in [range, domain] {
+        if block.block_size == 0 {
+            return Err(ValidationError::EmptyBlock { transformation: index });
+        }
+        if !block.fits_into(size) {
+            return Err(ValidationError::BlockOutOfBounds { transformation: index, block, size });
+        }
+    }
+
+    let is_power_of_two_multiple = domain.block_size > range.block_size
+        && domain.block_size % range.block_size == 0
+        && (domain.block_size / range.block_size).is_power_of_two();
+    if !is_power_of_two_multiple {
+        return Err(ValidationError::InvalidDomainBlockSize {
+            transformation: index,
+            range_size: range.block_size,
+            domain_size: domain.block_size,
+        });
+    }
+
+    Ok(())
+}
+
+impl Block {
+    fn fits_into(&self, size: Size) -> bool {
+        Rect::new(Coords { x: 0, y: 0 }, size).contains_rect(&Rect::from(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+    use crate::model::{Rotation, Transformation};
+
+    use super::*;
+
+    fn compressed(range: Block, domain: Block) -> Compressed {
+        Compressed {
+            size: Size::squared(8),
+            transformations: vec![Transformation {
+                range,
+                domain,
+                rotation: Rotation::By0,
+                brightness: 0,
+                saturation: 0.5,
+            }],
+        }
+    }
+
+    fn block(block_size: u32, x: u32, y: u32) -> Block {
+        Block { block_size, origin: coords!(x=x, y=y) }
+    }
+
+    #[test]
+    fn blocks_within_image_are_valid() {
+        assert_eq!(compressed(block(4, 4, 4), block(8, 0, 0)).validate(), Ok(()));
+        assert_eq!(compressed(block(2, 6, 0), block(8, 0, 0)).validate(), Ok(()));
+    }
+
+    #[test]
+    fn range_block_exceeding_image_is_invalid() {
+        let range = block(4, 5, 0);
+        assert_eq!(
+            compressed(range, block(8, 0, 0)).validate(),
+            Err(ValidationError::BlockOutOfBounds { transformation: 0, block: range, size: Size::squared(8) })
+        );
+    }
+
+    #[test]
+    fn domain_block_exceeding_image_is_invalid() {
+        let domain = block(8, 0, 1);
+        assert!(matches!(
+            compressed(block(4, 0, 0), domain).validate(),
+            Err(ValidationError::BlockOutOfBounds { block, .. }) if block == domain
+        ));
+    }
+
+    #[test]
+    fn overflowing_origin_is_invalid() {
+        let range = block(4, u32::MAX - 1, 0);
+        assert!(matches!(
+            compressed(range, block(8, 0, 0)).validate(),
+            Err(ValidationError::BlockOutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn domain_block_of_invalid_size_is_invalid() {
+        assert!(matches!(
+            compressed(block(2, 0, 0), block(6, 0, 0)).validate(),
+            Err(ValidationError::InvalidDomainBlockSize { range_size: 2, domain_size: 6, .. })
+        ));
+        assert!(matches!(
+            compressed(block(2, 0, 0), block(2, 0, 0)).validate(),
+            Err(ValidationError::InvalidDomainBlockSize { .. })
+        ));
+    }
+
+    #[test]
+    fn empty_block_is_invalid() {
+        assert_eq!(
+            compressed(block(0, 0, 0), block(2, 0, 0)).validate(),
+            Err(ValidationError::EmptyBlock { transformation: 0 })
+        );
+    }
+}
@@ -1,22 +1,68 @@
 #[cfg(feature = "persist-as-json")]
 mod json;
+#[cfg(feature = "persist-as-cbor")]
+mod cbor;
+#[cfg(any(feature = "persist-as-json", feature = "persist-as-cbor"))]
+mod schema;
 #[cfg(feature = "persist-as-binary-v1")]
 pub mod binary_v1;
+#[cfg(feature = "persist-as-binary-v2")]
+pub mod binary_v2;
+#[cfg(feature = "persist-as-binary-v1")]
+mod varint;
+#[cfg(feature = "persist-as-binary-v1")]
+mod inflate;
+#[cfg(feature = "persist-as-binary-v1")]
+pub mod flags;
+pub mod dump;
 
-use crate::model::Compressed;
-use std::fs::File;
-use std::io::{BufReader, Write};
+use crate::model::{ColorCompressed, Compressed, LosslessCompressed};
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Write};
 use std::path::Path;
 use std::io;
 use thiserror::Error;
 use tracing::debug;
+#[cfg(feature = "persist-compressed")]
+use flate2::{read::MultiGzDecoder, write::GzEncoder};
+
+/// The bytes every [gzip](https://www.rfc-editor.org/rfc/rfc1952) file starts with.
+#[cfg(feature = "persist-compressed")]
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// The amount of bytes a gzip-wrapped file may decompress to, which protects from files
+/// decompressing to huge amounts of data.
+#[cfg(feature = "persist-compressed")]
+pub const MAX_GZIP_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
 
-#[derive(Debug)]
-enum Format {
+/// A format in which a [Compressed] can be persisted, see [PersistOptions] and
+/// [Compressed::to_bytes].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PersistFormat {
     #[cfg(feature = "persist-as-json")]
     Json,
+    #[cfg(feature = "persist-as-cbor")]
+    Cbor,
     #[cfg(feature = "persist-as-binary-v1")]
-    QuadtreeFicV1,
+    BinaryV1,
+    #[cfg(feature = "persist-as-binary-v2")]
+    BinaryV2,
+}
+
+/// Options of [Compressed::persist_with_options].
+#[derive(Debug, Copy, Clone)]
+pub struct PersistOptions {
+    pub format: PersistFormat,
+
+    /// Waits until the file is written to disk before returning. Skipping it is faster, but
+    /// the file may be incomplete if the system crashes shortly after. Enabled by default.
+    pub sync: bool,
+}
+
+impl PersistOptions {
+    pub fn new(format: PersistFormat) -> Self {
+        Self { format, sync: true }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -29,6 +75,14 @@ pub enum PersistenceError {
     #[error("Error while serializing JSON: {0}")]
     JSONSerializationError(#[from] json::SerializationError),
 
+    #[cfg(feature = "persist-as-cbor")]
+    #[error("Error while deserializing CBOR: {0}")]
+    CBORDeserializationError(#[from] cbor::DeserializationError),
+
+    #[cfg(feature = "persist-as-cbor")]
+    #[error("Error while serializing CBOR: {0}")]
+    CBORSerializationError(#[from] cbor::SerializationError),
+
     #[error("IO error: {0}")]
     IO(#[from] io::Error),
 
@@ -39,50 +93,251 @@ pub enum PersistenceError {
     #[cfg(feature = "persist-as-binary-v1")]
     #[error("Error while deserializing as QFIC (v1): {0}")]
     BinaryV1DeserializationError(#[from] binary_v1::DeserializationError),
+
+    #[cfg(feature = "persist-as-binary-v2")]
+    #[error("Error while serializing as QFIC (v2): {0}")]
+    BinaryV2SerializationError(#[from] binary_v2::SerializationError),
+
+    #[cfg(feature = "persist-as-binary-v2")]
+    #[error("Error while deserializing as QFIC (v2): {0}")]
+    BinaryV2DeserializationError(#[from] binary_v2::DeserializationError),
+
+    #[cfg(feature = "persist-compressed")]
+    #[error("Error while decompressing gzip: {0}")]
+    Gzip(io::Error),
+
+    #[cfg(feature = "persist-compressed")]
+    #[error("The gzip-wrapped data decompresses to more than {limit} bytes")]
+    GzipTooLarge { limit: u64 },
+
+    #[error("Unknown file format starting with {:?}", .0)]
+    UnknownFormat(Vec<u8>),
 }
 
 impl Compressed {
     #[cfg(feature = "persist-as-json")]
     pub fn persist_as_json<T: AsRef<Path>>(&self, path: T) -> Result<u64, PersistenceError> {
-        self.persist_with(Format::Json, path.as_ref())
+        self.persist_with(PersistFormat::Json, path.as_ref())
+    }
+
+    /// Persists like [Compressed::persist_as_json], but indented over multiple lines, which is
+    /// easier to inspect. Both are read by [Compressed::read_from_json].
+    #[cfg(feature = "persist-as-json")]
+    pub fn persist_as_json_pretty<T: AsRef<Path>>(&self, path: T) -> Result<u64, PersistenceError> {
+        write_to(path.as_ref(), &json::serialize_pretty(self)?)
+    }
+
+    #[cfg(feature = "persist-as-cbor")]
+    pub fn persist_as_cbor<T: AsRef<Path>>(&self, path: T) -> Result<u64, PersistenceError> {
+        self.persist_with(PersistFormat::Cbor, path.as_ref())
     }
 
     #[cfg(feature = "persist-as-binary-v1")]
     pub fn persist_as_binary_v1<T: AsRef<Path>>(&self, path: T) -> Result<u64, PersistenceError> {
-        self.persist_with(Format::QuadtreeFicV1, path.as_ref())
+        self.persist_with(PersistFormat::BinaryV1, path.as_ref())
     }
 
-    fn persist_with(&self, format: Format, path: &Path) -> Result<u64, PersistenceError> {
-        debug!("Persisting as {:?}", format);
-        let serialized: Vec<u8> = match format {
+    #[cfg(feature = "persist-as-binary-v2")]
+    pub fn persist_as_binary_v2<T: AsRef<Path>>(&self, path: T) -> Result<u64, PersistenceError> {
+        self.persist_with(PersistFormat::BinaryV2, path.as_ref())
+    }
+
+    /// Persists like [Compressed::persist_as_binary_v1], but wrapped in gzip.
+    /// [Compressed::read_from_path] unwraps it transparently.
+    ///
+    /// The binary v1 payload is already DEFLATE-compressed, so the wrapper hardly shrinks it and
+    /// exists only for transport, e.g. to serve the file with `Content-Encoding: gzip`.
+    #[cfg(feature = "persist-compressed")]
+    pub fn persist_as_binary_v1_deflate<T: AsRef<Path>>(&self, path: T) -> Result<u64, PersistenceError> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(&binary_v1::serialize(self)?)?;
+        write_to(path.as_ref(), &encoder.finish()?)
+    }
+
+    /// Returns the amount of bytes [Compressed::persist_as_binary_v1] would write,
+    /// without touching the file system.
+    #[cfg(feature = "persist-as-binary-v1")]
+    pub fn estimated_size_binary_v1(&self) -> Result<u64, PersistenceError> {
+        Ok(binary_v1::serialize(self)?.len() as u64)
+    }
+
+    /// Writes the compression in the [binary v2](binary_v2) format to `writer`, e.g. to send
+    /// it over a socket, and returns the amount of bytes written. See
+    /// [Compressed::read_from_reader] to read it back.
+    #[cfg(feature = "persist-as-binary-v2")]
+    pub fn persist_to_writer<W: Write>(&self, writer: W) -> Result<u64, PersistenceError> {
+        self.write_with(PersistFormat::BinaryV2, writer)
+    }
+
+    /// Persists the compression to `path` as configured by `options`, and returns the amount
+    /// of bytes written.
+    pub fn persist_with_options<T: AsRef<Path>>(&self, path: T, options: PersistOptions) -> Result<u64, PersistenceError> {
+        let bytes = self.to_bytes(options.format)?;
+        let mut file = File::create(path.as_ref())?;
+        file.write_all(&bytes)?;
+        if options.sync {
+            file.sync_all()?;
+        }
+        Ok(bytes.len() as u64)
+    }
+
+    /// Serializes the compression in `format` without touching the file system, e.g. to send
+    /// it from a web server. See [Compressed::from_bytes] to read it back.
+    pub fn to_bytes(&self, format: PersistFormat) -> Result<Vec<u8>, PersistenceError> {
+        let mut bytes = Vec::new();
+        self.write_with(format, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Deserializes a compression in `format` from `bytes`. Unlike
+    /// [Compressed::read_from_reader], the format is not detected.
+    pub fn from_bytes(format: PersistFormat, bytes: &[u8]) -> Result<Self, PersistenceError> {
+        Ok(match format {
             #[cfg(feature = "persist-as-json")]
-            Format::Json => json::serialize(self)?,
+            PersistFormat::Json => json::deserialize(bytes)?,
+            #[cfg(feature = "persist-as-cbor")]
+            PersistFormat::Cbor => cbor::deserialize(bytes)?,
             #[cfg(feature = "persist-as-binary-v1")]
-            Format::QuadtreeFicV1 => binary_v1::serialize(self)?,
-        };
-        
-        let mut file = File::create(path)?;
-        file.write_all(serialized.as_slice())?;
-        file.sync_all()?;
+            PersistFormat::BinaryV1 => binary_v1::deserialize(bytes)?,
+            #[cfg(feature = "persist-as-binary-v2")]
+            PersistFormat::BinaryV2 => binary_v2::deserialize(bytes)?,
+        })
+    }
 
-        let file_size = file.metadata()?.len();
+    fn persist_with(&self, format: PersistFormat, path: &Path) -> Result<u64, PersistenceError> {
+        self.persist_with_options(path, PersistOptions::new(format))
+    }
 
-        Ok(file_size)
+    fn write_with<W: Write>(&self, format: PersistFormat, mut writer: W) -> Result<u64, PersistenceError> {
+        debug!("Persisting as {:?}", format);
+        let written = match format {
+            #[cfg(feature = "persist-as-json")]
+            PersistFormat::Json => json::serialize_into(self, &mut writer)?,
+            #[cfg(feature = "persist-as-cbor")]
+            PersistFormat::Cbor => cbor::serialize_into(self, &mut writer)?,
+            #[cfg(feature = "persist-as-binary-v1")]
+            PersistFormat::BinaryV1 => binary_v1::serialize_into(self, &mut writer)?,
+            #[cfg(feature = "persist-as-binary-v2")]
+            PersistFormat::BinaryV2 => binary_v2::serialize_into(self, &mut writer)?,
+        };
+        writer.flush()?;
+        Ok(written)
     }
 
     #[cfg(feature = "persist-as-json")]
     pub fn read_from_json(path: &Path) -> Result<Self, PersistenceError> {
+        Self::from_bytes(PersistFormat::Json, &fs::read(path)?)
+    }
+
+    #[cfg(feature = "persist-as-cbor")]
+    pub fn read_from_cbor(path: &Path) -> Result<Self, PersistenceError> {
+        Self::from_bytes(PersistFormat::Cbor, &fs::read(path)?)
+    }
+
+    #[cfg(feature = "persist-as-binary-v1")]
+    pub fn read_from_binary_v1(path: &Path) -> Result<Self, PersistenceError> {
+        Self::from_bytes(PersistFormat::BinaryV1, &fs::read(path)?)
+    }
+
+    #[cfg(feature = "persist-as-binary-v2")]
+    pub fn read_from_binary_v2(path: &Path) -> Result<Self, PersistenceError> {
+        Self::from_bytes(PersistFormat::BinaryV2, &fs::read(path)?)
+    }
+
+    /// Reads a compression in any of the enabled formats, detected by the start of the file:
+    /// binary files since version 2 start with [binary_v2::MAGIC], JSON files with `{`, and
+    /// anything else is attempted to be read as the unversioned binary v1 format.
+    pub fn read_from_path<T: AsRef<Path>>(path: T) -> Result<Self, PersistenceError> {
+        Self::read_from_reader(BufReader::new(File::open(path.as_ref())?))
+    }
+
+    /// Reads a compression in any of the enabled formats from `reader`, detected as in
+    /// [Compressed::read_from_path]. Consumes the whole reader.
+    pub fn read_from_reader<R: Read>(mut reader: R) -> Result<Self, PersistenceError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        #[cfg(feature = "persist-compressed")]
+        if bytes.starts_with(&GZIP_MAGIC) {
+            let mut decompressed = Vec::new();
+            MultiGzDecoder::new(bytes.as_slice())
+                .take(MAX_GZIP_DECOMPRESSED_SIZE + 1)
+                .read_to_end(&mut decompressed)
+                .map_err(PersistenceError::Gzip)?;
+            if decompressed.len() as u64 > MAX_GZIP_DECOMPRESSED_SIZE {
+                return Err(PersistenceError::GzipTooLarge { limit: MAX_GZIP_DECOMPRESSED_SIZE });
+            }
+            bytes = decompressed;
+        }
+
+        Self::detect_and_deserialize(&bytes)
+    }
+
+    fn detect_and_deserialize(bytes: &[u8]) -> Result<Self, PersistenceError> {
+        #[cfg(feature = "persist-as-binary-v2")]
+        if binary_v2::has_magic(bytes) {
+            return Ok(binary_v2::deserialize(bytes)?);
+        }
+
+        // A v1 file may start with `{` by chance, as its DEFLATE stream has no header
+        #[cfg(feature = "persist-as-json")]
+        if bytes.first() == Some(&b'{') {
+            let json = json::deserialize(bytes);
+            #[cfg(feature = "persist-as-binary-v1")]
+            if json.is_err() {
+                if let Ok(compressed) = binary_v1::deserialize(bytes) {
+                    return Ok(compressed);
+                }
+            }
+            return Ok(json?);
+        }
+
+        #[cfg(feature = "persist-as-binary-v1")]
+        match binary_v1::deserialize(bytes) {
+            Ok(compressed) => return Ok(compressed),
+            // The data was read successfully, hence it is a v1 file, but with invalid content
+            Err(error @ binary_v1::DeserializationError::InvalidTransformation { .. }) => return Err(error.into()),
+            Err(_) => {}
+        }
+
+        Err(PersistenceError::UnknownFormat(bytes.iter().take(4).copied().collect()))
+    }
+}
+
+impl ColorCompressed {
+    #[cfg(feature = "persist-as-binary-v1")]
+    pub fn persist_as_binary_v1<T: AsRef<Path>>(&self, path: T) -> Result<u64, PersistenceError> {
+        write_to(path.as_ref(), &binary_v1::serialize_color(self)?)
+    }
+
+    #[cfg(feature = "persist-as-binary-v1")]
+    pub fn read_from_binary_v1(path: &Path) -> Result<Self, PersistenceError> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
-        let compressed = json::deserialize(reader)?;
-        Ok(compressed)
+        Ok(binary_v1::deserialize_color(reader)?)
+    }
+}
+
+impl LosslessCompressed {
+    /// Persists the compression and its residual. If `compress_residual` is set, the
+    /// residual is compressed with zlib.
+    #[cfg(feature = "persist-as-binary-v1")]
+    pub fn persist_as_binary_v1<T: AsRef<Path>>(&self, path: T, compress_residual: bool) -> Result<u64, PersistenceError> {
+        write_to(path.as_ref(), &binary_v1::serialize_lossless(self, compress_residual)?)
     }
 
     #[cfg(feature = "persist-as-binary-v1")]
     pub fn read_from_binary_v1(path: &Path) -> Result<Self, PersistenceError> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
-        let compressed = binary_v1::deserialize(reader)?;
-        Ok(compressed)
+        Ok(binary_v1::deserialize_lossless(reader)?)
     }
 }
+
+/// Writes `serialized` to a file at `path`, and returns the amount of bytes written.
+fn write_to(path: &Path, serialized: &[u8]) -> Result<u64, PersistenceError> {
+    let mut file = File::create(path)?;
+    file.write_all(serialized)?;
+    file.sync_all()?;
+    Ok(serialized.len() as u64)
+}
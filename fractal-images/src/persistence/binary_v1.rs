@@ -9,12 +9,34 @@
 //! `<block> = <range block origin><domain block origin><rotation><brightness><saturation>`
 //!
 //! Furthermore, the binary is compressed with DEFLATE.
+//!
+//! Groups are ordered by ascending range block size, and blocks within a group keep the order
+//! of the transformations. Hence, deserializing yields the transformations sorted stably by
+//! their range block size, and serializing the same compression twice yields identical bytes.
+//!
+//! As the amount of groups is not persisted, a file truncated exactly between two groups can
+//! not be told apart from a complete one. [binary_v2](super::binary_v2) persists it.
+//!
+//! Color images are persisted as
+//!
+//! `<chroma subsampling flag>(<payload length><payload>)*`
+//!
+//! where each of the luma, blue chroma and red chroma planes is an embedded payload in the
+//! format above.
+//!
+//! Lossless compressions are persisted as
+//!
+//! `<payload length><payload><iterations><overlap><residual compression flag><residual length><residual>`
+//!
+//! where the payload is in the format above and the residual consists of little endian `i16`s,
+//! which are optionally compressed with zlib.
 //! 
 //! ## Important
 //! Relies on the fact that every domain block is twice the size of a range block.
 //! Returns a [SerializationError] if this is violated.
 
-use std::io::{Cursor, Read};
+use std::collections::BTreeMap;
+use std::io::{Cursor, ErrorKind, Read, Write};
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use thiserror::Error;
@@ -22,7 +44,10 @@ use tracing::error;
 
 use crate::{coords, model};
 use crate::image::{Coords, Size};
-use crate::model::{Rotation, RotationInvalidError};
+use crate::model::{validate_transformation, ColorCompressed, LosslessCompressed, Rotation, RotationInvalidError, ValidationError};
+use crate::persistence::flags::{Flags, InvalidFlags};
+use crate::persistence::inflate::{is_inflate_failure, InflateReader};
+use crate::persistence::varint::{read_varint, to_u32, unzigzag, write_varint, zigzag};
 
 #[derive(Error, Debug)]
 pub enum SerializationError {
@@ -46,18 +71,79 @@ pub enum DeserializationError {
 
     #[error("Error while inflating compressed image")]
     InflateError,
+
+    #[error("The data is too short to contain a header ({0} bytes)")]
+    MissingHeader(usize),
+
+    #[error("Transformation {index} is invalid: {reason}")]
+    InvalidTransformation { index: usize, reason: ValidationError },
+
+    #[error("The data ends unexpectedly")]
+    Truncated,
+
+    #[error("Found {0} bytes of unexpected data after the end")]
+    TrailingData(usize),
+
+    #[error("A group contains {count} blocks of size {range_size}, but at most {max} fit into the image")]
+    TooManyBlocks { count: u32, range_size: u32, max: u64 },
+
+    #[error(transparent)]
+    InvalidFlags(#[from] InvalidFlags),
+
+    #[error("The block is flipped, which is not supported")]
+    UnsupportedFlip,
+
+    #[error("Invalid chroma subsampling flag: {0}")]
+    InvalidChromaSubsampling(u8),
+
+    #[error("Invalid residual compression flag: {0}")]
+    InvalidResidualCompression(u8),
+
+    #[error("The residual has {actual} values, but the image has {expected} pixels")]
+    ResidualSizeMismatch { expected: usize, actual: usize },
 }
 
 pub fn serialize(compressed: &model::Compressed) -> Result<Vec<u8>, SerializationError> {
+    let mut result = Vec::new();
+    serialize_into(compressed, &mut result)?;
+    Ok(result)
+}
+
+/// Writes `compressed` to `writer` like [serialize], and returns the amount of bytes written.
+pub fn serialize_into<W: Write>(compressed: &model::Compressed, mut writer: W) -> Result<u64, SerializationError> {
+    let payload = serialize_payload(compressed, Layout::V1)?;
+    writer.write_all(&payload)?;
+    Ok(payload.len() as u64)
+}
+
+/// The differences of the payload between the binary versions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(super) enum Layout {
+    /// As described in this module.
+    V1,
+
+    /// Contains the amount of groups after the image size, orders the groups by descending
+    /// range block size and the blocks within a group by their origin, encodes origins as
+    /// varints relative to the previous block, stores the rotation in a [Flags] byte, and the
+    /// saturation of each block as a fixed point number, see [binary_v2](super::binary_v2).
+    V2,
+}
+
+/// Serializes `compressed` in the given [Layout].
+pub(super) fn serialize_payload(compressed: &model::Compressed, layout: Layout) -> Result<Vec<u8>, SerializationError> {
     let mut result: Vec<u8> = Vec::new();
     result.write_u32::<LittleEndian>(compressed.size.get_width())?;
     result.write_u32::<LittleEndian>(compressed.size.get_height())?;
 
-    let rb_to_trans_map = generate_entries(compressed)?;
+    let mut rb_to_trans_map: Vec<_> = generate_entries(compressed)?.into_iter().collect();
+    if layout == Layout::V2 {
+        result.write_u32::<LittleEndian>(rb_to_trans_map.len() as u32)?;
+        rb_to_trans_map.reverse();
+    }
 
     for (rb_size, entry) in rb_to_trans_map {
         result.write_u32::<LittleEndian>(rb_size)?;
-        entry.serialize(&mut result)?;
+        entry.serialize(&mut result, layout)?;
     }
 
     Ok(deflate(&result))
@@ -67,8 +153,9 @@ fn deflate(data: &[u8]) -> Vec<u8> {
     miniz_oxide::deflate::compress_to_vec(data, 1)
 }
 
-fn generate_entries(compressed: &model::Compressed) -> Result<fxhash::FxHashMap<u32, Entry>, SerializationError> {
-    let mut rb_to_trans_map = fxhash::FxHashMap::default();
+/// Groups the transformations by range block size, sorted by ascending size.
+fn generate_entries(compressed: &model::Compressed) -> Result<BTreeMap<u32, Entry>, SerializationError> {
+    let mut rb_to_trans_map = BTreeMap::new();
     for t in &compressed.transformations {
         if t.domain.block_size != 2 * t.range.block_size {
             return Err(SerializationError::InvalidBlockSize { range_size: t.range.block_size, domain_size: t.domain.block_size });
@@ -83,7 +170,7 @@ fn generate_entries(compressed: &model::Compressed) -> Result<fxhash::FxHashMap<
         rb_entry.entries.push(EntryChild {
             rb_origin: t.range.origin,
             db_origin: t.domain.origin,
-            rotation: t.rotation.into(),
+            rotation: t.rotation,
             brightness: t.brightness,
             saturation: t.saturation,
         })
@@ -94,49 +181,304 @@ fn generate_entries(compressed: &model::Compressed) -> Result<fxhash::FxHashMap<
 
 #[tracing::instrument(skip(reader))]
 pub fn deserialize(reader: impl Read) -> Result<model::Compressed, DeserializationError> {
-    let mut reader = inflate(reader)?;
+    deserialize_payload(reader, Layout::V1)
+}
+
+/// Deserializes a payload written by [serialize_payload] in the same [Layout]. Fails if the
+/// payload ends in the middle of a group or contains data after the last group.
+pub(super) fn deserialize_payload(reader: impl Read, layout: Layout) -> Result<model::Compressed, DeserializationError> {
+    let reader = TransformationReader::with_layout(reader, layout)?;
+    let size = reader.size();
+    Ok(model::Compressed {
+        size,
+        transformations: reader.collect::<Result<_, _>>()?,
+    })
+}
 
-    let width = reader.read_u32::<LittleEndian>().unwrap();
-    let height = reader.read_u32::<LittleEndian>().unwrap();
+/// Reads the transformations of a compression in the binary format one by one, such that they
+/// do not need to be held in memory at once. Every transformation is
+/// [validated](model::Compressed::validate) before it is returned.
+///
+/// The first error ends the iteration. [deserialize] collects the transformations of a reader.
+pub struct TransformationReader<R: Read> {
+    reader: InflateReader<R>,
+    layout: Layout,
+    size: Size,
+    remaining_groups: Option<u32>,
+    group: Option<Group>,
+    index: usize,
+    finished: bool,
+}
+
+/// The state of the group whose blocks are being read.
+struct Group {
+    range_size: u32,
+    remaining: u32,
+    previous: Coords,
+}
+
+impl<R: Read> TransformationReader<R> {
+    /// Reads the header from `reader`, which contains the binary format.
+    pub fn new(reader: R) -> Result<Self, DeserializationError> {
+        Self::with_layout(reader, Layout::V1)
+    }
+
+    pub(super) fn with_layout(reader: R, layout: Layout) -> Result<Self, DeserializationError> {
+        let mut reader = InflateReader::new(reader);
+        let header_length = match layout {
+            Layout::V1 => 8,
+            Layout::V2 => 12,
+        };
+
+        let mut header = Vec::with_capacity(header_length);
+        let result = (&mut reader).take(header_length as u64).read_to_end(&mut header);
+        match result {
+            Err(error) if reader.input_read() == 0 && error.kind() == ErrorKind::UnexpectedEof => {
+                return Err(DeserializationError::MissingHeader(0));
+            }
+            result => result.map_err(|error| map_read_error(error.into()))?,
+        };
+        if header.len() < header_length {
+            return Err(DeserializationError::MissingHeader(header.len()));
+        }
+
+        let mut header = Cursor::new(header);
+        let width = header.read_u32::<LittleEndian>()?;
+        let height = header.read_u32::<LittleEndian>()?;
+        let remaining_groups = match layout {
+            Layout::V1 => None,
+            Layout::V2 => Some(header.read_u32::<LittleEndian>()?),
+        };
+
+        Ok(Self {
+            reader,
+            layout,
+            size: Size::new(width, height),
+            remaining_groups,
+            group: None,
+            index: 0,
+            finished: false,
+        })
+    }
 
-    let mut transformations = vec![];
+    /// The size of the compressed image, as read from the header.
+    pub fn size(&self) -> Size {
+        self.size
+    }
 
-    while let Ok(range_size) = reader.read_u32::<LittleEndian>() {
-        let rb_entry = Entry::deserialize(&mut reader)?;
+    fn read_next(&mut self) -> Result<Option<model::Transformation>, DeserializationError> {
+        loop {
+            if let Some(group) = self.group.as_mut().filter(|group| group.remaining > 0) {
+                let child = EntryChild::deserialize(&mut self.reader, self.layout, group.previous)?;
+                group.previous = child.rb_origin;
+                group.remaining -= 1;
 
-        for rb_child in rb_entry.entries {
-            transformations.push(
-                model::Transformation {
+                let transformation = model::Transformation {
                     range: model::Block {
-                        block_size: range_size,
-                        origin: rb_child.rb_origin,
+                        block_size: group.range_size,
+                        origin: child.rb_origin,
                     },
                     domain: model::Block {
-                        block_size: 2 * range_size,
-                        origin: rb_child.db_origin,
+                        // Saturates for crafted range block sizes, which fail the validation
+                        block_size: group.range_size.saturating_mul(2),
+                        origin: child.db_origin,
                     },
-                    rotation: Rotation::try_from(rb_child.rotation)?,
-                    brightness: rb_child.brightness,
-                    saturation: rb_child.saturation,
+                    rotation: child.rotation,
+                    brightness: child.brightness,
+                    saturation: child.saturation,
+                };
+                validate_transformation(self.index, &transformation, self.size)
+                    .map_err(|reason| DeserializationError::InvalidTransformation { index: self.index, reason })?;
+                self.index += 1;
+                return Ok(Some(transformation));
+            }
+
+            if !self.start_group()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Reads the header of the next group, and returns `false` if there is none.
+    fn start_group(&mut self) -> Result<bool, DeserializationError> {
+        let range_size = match self.remaining_groups.as_mut() {
+            Some(0) => {
+                let mut trailing = Vec::new();
+                self.reader.read_to_end(&mut trailing)?;
+                if !trailing.is_empty() {
+                    return Err(DeserializationError::TrailingData(trailing.len()));
+                }
+                return Ok(false);
+            }
+            Some(remaining) => {
+                *remaining -= 1;
+                self.reader.read_u32::<LittleEndian>()?
+            }
+            // Without a group count, the data ends once there are no more bytes for a group
+            None => {
+                let mut bytes = Vec::with_capacity(4);
+                (&mut self.reader).take(4).read_to_end(&mut bytes)?;
+                match bytes.len() {
+                    0 => return Ok(false),
+                    4 => u32::from_le_bytes(bytes.try_into().unwrap()),
+                    length => return Err(DeserializationError::TrailingData(length)),
                 }
-            );
+            }
+        };
+
+        let count = self.reader.read_u32::<LittleEndian>()?;
+        if let Some(max) = max_blocks(self.size, range_size).filter(|&max| count as u64 > max) {
+            return Err(DeserializationError::TooManyBlocks { count, range_size, max });
         }
+
+        self.group = Some(Group { range_size, remaining: count, previous: coords!(x=0, y=0) });
+        Ok(true)
     }
+}
 
-    Ok(model::Compressed {
-        size: Size::new(width, height),
-        transformations,
+impl<R: Read> Iterator for TransformationReader<R> {
+    type Item = Result<model::Transformation, DeserializationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_next() {
+            Ok(Some(transformation)) => Some(Ok(transformation)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(error) => {
+                self.finished = true;
+                Some(Err(map_read_error(error)))
+            }
+        }
+    }
+}
+
+/// Maps errors of the inflated stream to the errors of the format.
+fn map_read_error(error: DeserializationError) -> DeserializationError {
+    match error {
+        DeserializationError::IO(error) if error.kind() == ErrorKind::UnexpectedEof => DeserializationError::Truncated,
+        DeserializationError::IO(error) if is_inflate_failure(&error) => {
+            error!("Error while inflating: {:?}", error);
+            DeserializationError::InflateError
+        }
+        error => error,
+    }
+}
+
+/// The amount of distinct positions of a range block of size `range_size` within an image of
+/// the given size, which bounds the amount of blocks of a valid group. `None` if no such block
+/// fits into the image, which the validation of the blocks reports more precisely.
+fn max_blocks(size: Size, range_size: u32) -> Option<u64> {
+    let (width, height) = (size.get_width(), size.get_height());
+    if range_size == 0 || range_size > width || range_size > height {
+        return None;
+    }
+    Some((width - range_size + 1) as u64 * (height - range_size + 1) as u64)
+}
+
+pub fn serialize_color(compressed: &ColorCompressed) -> Result<Vec<u8>, SerializationError> {
+    let mut result: Vec<u8> = Vec::new();
+    result.write_u8(compressed.chroma_subsampled.into())?;
+    for plane in [&compressed.luma, &compressed.blue_chroma, &compressed.red_chroma] {
+        let payload = serialize(plane)?;
+        result.write_u32::<LittleEndian>(payload.len() as u32)?;
+        result.extend_from_slice(&payload);
+    }
+    Ok(result)
+}
+
+#[tracing::instrument(skip(reader))]
+pub fn deserialize_color(mut reader: impl Read) -> Result<ColorCompressed, DeserializationError> {
+    let chroma_subsampled = match reader.read_u8()? {
+        0 => false,
+        1 => true,
+        flag => return Err(DeserializationError::InvalidChromaSubsampling(flag)),
+    };
+
+    let mut read_plane = || -> Result<model::Compressed, DeserializationError> {
+        deserialize(Cursor::new(read_length_prefixed(&mut reader)?))
+    };
+
+    Ok(ColorCompressed {
+        chroma_subsampled,
+        luma: read_plane()?,
+        blue_chroma: read_plane()?,
+        red_chroma: read_plane()?,
     })
 }
 
-fn inflate(mut read: impl Read) -> Result<impl Read, DeserializationError> {
+pub fn serialize_lossless(compressed: &LosslessCompressed, compress_residual: bool) -> Result<Vec<u8>, SerializationError> {
+    let mut result: Vec<u8> = Vec::new();
+    let payload = serialize(&compressed.compressed)?;
+    result.write_u32::<LittleEndian>(payload.len() as u32)?;
+    result.extend_from_slice(&payload);
+    result.write_u8(compressed.iterations)?;
+    result.write_u32::<LittleEndian>(compressed.overlap)?;
+
+    let mut residual = Vec::with_capacity(2 * compressed.residual.len());
+    for value in &compressed.residual {
+        residual.write_i16::<LittleEndian>(*value)?;
+    }
+    if compress_residual {
+        residual = miniz_oxide::deflate::compress_to_vec_zlib(&residual, 6);
+    }
+
+    result.write_u8(compress_residual.into())?;
+    result.write_u32::<LittleEndian>(residual.len() as u32)?;
+    result.extend_from_slice(&residual);
+    Ok(result)
+}
+
+#[tracing::instrument(skip(reader))]
+pub fn deserialize_lossless(mut reader: impl Read) -> Result<LosslessCompressed, DeserializationError> {
+    let compressed = deserialize(Cursor::new(read_length_prefixed(&mut reader)?))?;
+    let iterations = reader.read_u8()?;
+    let overlap = reader.read_u32::<LittleEndian>()?;
+
+    let compress_residual = reader.read_u8()?;
+    let residual = read_length_prefixed(&mut reader)?;
+    let expected = compressed.size.area() as usize;
+    let residual = match compress_residual {
+        0 => residual,
+        // Inflating fails beyond the expected size, instead of allocating arbitrarily much
+        1 => miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&residual, 2 * expected).map_err(|err| {
+            error!("Error while inflating residual: {:?}", err);
+            DeserializationError::InflateError
+        })?,
+        flag => return Err(DeserializationError::InvalidResidualCompression(flag)),
+    };
+
+    if residual.len() != 2 * expected {
+        return Err(DeserializationError::ResidualSizeMismatch { expected, actual: residual.len() / 2 });
+    }
+    let mut residual_reader = Cursor::new(residual);
+    let residual = (0..expected)
+        .map(|_| residual_reader.read_i16::<LittleEndian>())
+        .collect::<Result<Vec<i16>, _>>()?;
+
+    Ok(LosslessCompressed {
+        compressed,
+        iterations,
+        overlap,
+        residual,
+    })
+}
+
+/// Reads a length followed by as many bytes. The length is not trusted, hence the bytes are
+/// only allocated as far as they are actually read.
+fn read_length_prefixed(mut reader: impl Read) -> Result<Vec<u8>, DeserializationError> {
+    let length = reader.read_u32::<LittleEndian>()?;
     let mut bytes = Vec::new();
-    read.read_to_end(&mut bytes)?;
-    let what = miniz_oxide::inflate::decompress_to_vec(&bytes).map_err(|err| {
-        error!("Error while inflating: {:?}", err);
-        DeserializationError::InflateError
-    })?;
-    Ok(Cursor::new(what))
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < length as usize {
+        return Err(DeserializationError::Truncated);
+    }
+    Ok(bytes)
 }
 
 struct Entry {
@@ -144,59 +486,104 @@ struct Entry {
 }
 
 impl Entry {
-    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), SerializationError> {
+    fn serialize(&self, buf: &mut Vec<u8>, layout: Layout) -> Result<(), SerializationError> {
         buf.write_u32::<LittleEndian>(self.entries.len() as u32)?;
-        for entry in &self.entries {
-            entry.serialize(buf)?;
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        if layout == Layout::V2 {
+            entries.sort_by_key(|entry| (entry.rb_origin.y, entry.rb_origin.x));
         }
-        Ok(())
-    }
 
-    fn deserialize<R: Read>(reader: &mut R) -> Result<Self, DeserializationError> {
-        let entries_count = reader.read_u32::<LittleEndian>()?;
-        let mut entries = Vec::with_capacity(entries_count as usize);
-        for _ in 0..entries_count {
-            let entry = EntryChild::deserialize(reader)?;
-            entries.push(entry);
+        let mut previous = coords!(x=0, y=0);
+        for entry in entries {
+            entry.serialize(buf, layout, previous)?;
+            previous = entry.rb_origin;
         }
-        Ok(Self {
-            entries,
-        })
+        Ok(())
     }
 }
 
 struct EntryChild {
     rb_origin: Coords,
     db_origin: Coords,
-    rotation: u8,
+    rotation: Rotation,
     brightness: i16,
     saturation: f64,
 }
 
 impl EntryChild {
-    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), SerializationError> {
-        buf.write_u32::<LittleEndian>(self.rb_origin.x)?;
-        buf.write_u32::<LittleEndian>(self.rb_origin.y)?;
-        buf.write_u32::<LittleEndian>(self.db_origin.x)?;
-        buf.write_u32::<LittleEndian>(self.db_origin.y)?;
-        buf.write_u8(self.rotation)?;
+    /// Serializes the block, where the [Layout::V2] encodes the range block origin relative to
+    /// the `previous` one, which needs to precede it in row-major order.
+    fn serialize(&self, buf: &mut Vec<u8>, layout: Layout, previous: Coords) -> Result<(), SerializationError> {
+        match layout {
+            Layout::V1 => {
+                buf.write_u32::<LittleEndian>(self.rb_origin.x)?;
+                buf.write_u32::<LittleEndian>(self.rb_origin.y)?;
+                buf.write_u32::<LittleEndian>(self.db_origin.x)?;
+                buf.write_u32::<LittleEndian>(self.db_origin.y)?;
+            }
+            Layout::V2 => {
+                // Within the same row, x is stored relative to the previous block as well
+                let dy = self.rb_origin.y - previous.y;
+                let x = if dy == 0 { self.rb_origin.x - previous.x } else { self.rb_origin.x };
+                write_varint(buf, dy as u64)?;
+                write_varint(buf, x as u64)?;
+                write_varint(buf, zigzag(self.db_origin.x as i64 - self.rb_origin.x as i64))?;
+                write_varint(buf, zigzag(self.db_origin.y as i64 - self.rb_origin.y as i64))?;
+            }
+        }
+        match layout {
+            Layout::V1 => buf.write_u8(self.rotation.into())?,
+            Layout::V2 => buf.write_u8(Flags { rotation: self.rotation, horizontal_flip: false }.to_byte())?,
+        }
         buf.write_i16::<LittleEndian>(self.brightness)?;
-        buf.write_f64::<LittleEndian>(self.saturation)?;
+        match layout {
+            Layout::V1 => buf.write_f64::<LittleEndian>(self.saturation)?,
+            Layout::V2 => buf.write_i16::<LittleEndian>(to_fixed_point(self.saturation))?,
+        }
         Ok(())
     }
 
-    fn deserialize<R: Read>(reader: &mut R) -> Result<Self, DeserializationError> {
-        let rb_origin_x = reader.read_u32::<LittleEndian>()?;
-        let rb_origin_y = reader.read_u32::<LittleEndian>()?;
-        let db_origin_x = reader.read_u32::<LittleEndian>()?;
-        let db_origin_y = reader.read_u32::<LittleEndian>()?;
-        let rotation = reader.read_u8()?;
+    fn deserialize<R: Read>(reader: &mut R, layout: Layout, previous: Coords) -> Result<Self, DeserializationError> {
+        let (rb_origin, db_origin) = match layout {
+            Layout::V1 => {
+                let rb_origin_x = reader.read_u32::<LittleEndian>()?;
+                let rb_origin_y = reader.read_u32::<LittleEndian>()?;
+                let db_origin_x = reader.read_u32::<LittleEndian>()?;
+                let db_origin_y = reader.read_u32::<LittleEndian>()?;
+                (coords!(x=rb_origin_x, y=rb_origin_y), coords!(x=db_origin_x, y=db_origin_y))
+            }
+            Layout::V2 => {
+                let dy = read_varint(reader)?;
+                let x = read_varint(reader)?;
+                let rb_origin = if dy == 0 {
+                    coords!(x=to_u32((previous.x as u64).saturating_add(x))?, y=previous.y)
+                } else {
+                    coords!(x=to_u32(x)?, y=to_u32((previous.y as u64).saturating_add(dy))?)
+                };
+                let db_origin_x = to_u32((rb_origin.x as i64).saturating_add(unzigzag(read_varint(reader)?)))?;
+                let db_origin_y = to_u32((rb_origin.y as i64).saturating_add(unzigzag(read_varint(reader)?)))?;
+                (rb_origin, coords!(x=db_origin_x, y=db_origin_y))
+            }
+        };
+        let rotation = match layout {
+            Layout::V1 => Rotation::try_from(reader.read_u8()?)?,
+            Layout::V2 => {
+                let flags = Flags::from_byte(reader.read_u8()?)?;
+                if flags.horizontal_flip {
+                    return Err(DeserializationError::UnsupportedFlip);
+                }
+                flags.rotation
+            }
+        };
         let brightness = reader.read_i16::<LittleEndian>()?;
-        let saturation = reader.read_f64::<LittleEndian>()?;
+        let saturation = match layout {
+            Layout::V1 => reader.read_f64::<LittleEndian>()?,
+            Layout::V2 => from_fixed_point(reader.read_i16::<LittleEndian>()?),
+        };
 
         Ok(Self {
-            rb_origin: coords!(x=rb_origin_x, y=rb_origin_y),
-            db_origin: coords!(x=db_origin_x, y=db_origin_y),
+            rb_origin,
+            db_origin,
             rotation,
             brightness,
             saturation,
@@ -204,6 +591,18 @@ impl EntryChild {
     }
 }
 
+/// The scale of a saturation in the Q1.14 fixed point format, which covers saturations in
+/// `[-2, 2)` with a precision of `2^-14`.
+const FIXED_POINT_SCALE: f64 = (1 << 14) as f64;
+
+fn to_fixed_point(saturation: f64) -> i16 {
+    (saturation * FIXED_POINT_SCALE).round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+fn from_fixed_point(saturation: i16) -> f64 {
+    saturation as f64 / FIXED_POINT_SCALE
+}
+
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
@@ -215,6 +614,46 @@ mod test {
 
     use super::*;
 
+    fn inflate(reader: impl Read) -> std::io::Result<Vec<u8>> {
+        let mut inflated = Vec::new();
+        InflateReader::new(reader).read_to_end(&mut inflated)?;
+        Ok(inflated)
+    }
+
+    /// Transformations in several groups of different range block sizes.
+    fn multi_group_compressed() -> Compressed {
+        Compressed {
+            size: size!(w=64, h=64),
+            transformations: [16, 8, 4, 8, 16, 2, 4, 8, 1].into_iter().map(with_range_size).collect(),
+        }
+    }
+
+    #[test]
+    fn transformation_reader_reads_like_deserialize() {
+        for layout in [Layout::V1, Layout::V2] {
+            let serialized = serialize_payload(&multi_group_compressed(), layout).unwrap();
+            let batch = deserialize_payload(Cursor::new(serialized.clone()), layout).unwrap();
+
+            let mut reader = TransformationReader::with_layout(Cursor::new(serialized), layout).unwrap();
+            assert_eq!(reader.size(), size!(w=64, h=64));
+            for expected in &batch.transformations {
+                assert_eq!(reader.next().unwrap().unwrap(), *expected);
+            }
+            assert!(reader.next().is_none());
+        }
+    }
+
+    #[test]
+    fn transformation_reader_stops_after_an_error() {
+        let inflated = inflate(Cursor::new(serialize(&multi_group_compressed()).unwrap())).unwrap();
+        let truncated = Cursor::new(deflate(&inflated[..inflated.len() - 1]));
+
+        let results: Vec<_> = TransformationReader::new(truncated).unwrap().collect();
+        assert_eq!(results.len(), multi_group_compressed().transformations.len());
+        assert!(matches!(results.last(), Some(Err(DeserializationError::Truncated))));
+        assert!(results[..results.len() - 1].iter().all(Result::is_ok));
+    }
+
     #[test]
     fn no_transformations() {
         let compressed = Compressed {
@@ -269,6 +708,55 @@ mod test {
         deserialized.transformations[2].should().be_equal_to(t_32_1);
     }
 
+    fn with_range_size(range_size: u32) -> Transformation {
+        let mut transformation = create_transformation();
+        transformation.range.block_size = range_size;
+        transformation.domain.block_size = 2 * range_size;
+        transformation
+    }
+
+    #[test]
+    fn transformations_are_sorted_stably_by_range_size() {
+        let transformations: Vec<_> = [16, 8, 2, 8, 16, 4, 2, 8].into_iter().map(with_range_size).collect();
+        let compressed = Compressed {
+            size: size!(w=64, h=64),
+            transformations: transformations.clone(),
+        };
+
+        let deserialized = deserialize(Cursor::new(serialize(&compressed).unwrap())).unwrap();
+
+        let mut expected = transformations;
+        expected.sort_by_key(|t| t.range.block_size);
+        assert_eq!(deserialized.transformations, expected);
+    }
+
+    #[test]
+    fn serialization_is_deterministic() {
+        let compressed = Compressed {
+            size: size!(w=64, h=64),
+            transformations: [8, 16, 4, 16, 8, 2, 1].into_iter().map(with_range_size).collect(),
+        };
+
+        for layout in [Layout::V1, Layout::V2] {
+            let first = serialize_payload(&compressed, layout).unwrap();
+            let second = serialize_payload(&compressed.clone(), layout).unwrap();
+            assert_eq!(first, second, "{:?} is not deterministic", layout);
+        }
+    }
+
+    #[test]
+    fn sorted_transformations_are_preserved_exactly() {
+        let compressed = Compressed {
+            size: size!(w=64, h=64),
+            transformations: [2, 4, 4, 8, 16, 16].into_iter().map(with_range_size).collect(),
+        };
+
+        let serialized = serialize(&compressed).unwrap();
+        let deserialized = deserialize(Cursor::new(serialized.clone())).unwrap();
+        assert_eq!(deserialized.transformations, compressed.transformations);
+        assert_eq!(serialize(&deserialized).unwrap(), serialized);
+    }
+
     #[fact]
     fn invalid_domain_block_size_returns_error() {
         let mut transformation = create_transformation();
@@ -282,15 +770,299 @@ mod test {
             .because("the domain block size is not twice the range block size");
     }
 
+    #[test]
+    fn color_compressed_is_serializable_and_deserializable() {
+        let plane = |size| Compressed {
+            size: size!(w=size, h=size),
+            transformations: vec![create_transformation()],
+        };
+        let compressed = ColorCompressed {
+            chroma_subsampled: true,
+            luma: plane(128),
+            blue_chroma: plane(64),
+            red_chroma: plane(64),
+        };
+
+        let serialized = serialize_color(&compressed).unwrap();
+        let deserialized = deserialize_color(Cursor::new(serialized)).unwrap();
+        assert!(deserialized.chroma_subsampled);
+        assert_eq!(deserialized.size(), size!(w=128, h=128));
+        assert_eq!(deserialized.blue_chroma.size, size!(w=64, h=64));
+        assert_eq!(deserialized.luma.transformations, compressed.luma.transformations);
+        assert_eq!(deserialized.red_chroma.transformations, compressed.red_chroma.transformations);
+    }
+
+    #[test]
+    fn invalid_chroma_subsampling_flag_returns_error() {
+        let result = deserialize_color(Cursor::new(vec![7]));
+        assert!(matches!(result, Err(DeserializationError::InvalidChromaSubsampling(7))));
+    }
+
+    #[test]
+    fn lossless_compressed_is_serializable_and_deserializable() {
+        for compress_residual in [false, true] {
+            let compressed = LosslessCompressed {
+                compressed: Compressed {
+                    size: size!(w=2, h=2),
+                    transformations: vec![Transformation {
+                        range: Block { block_size: 1, origin: coords!(x=1, y=0) },
+                        domain: Block { block_size: 2, origin: coords!(x=0, y=0) },
+                        ..create_transformation()
+                    }],
+                },
+                iterations: 7,
+                overlap: 1,
+                residual: vec![-255, 0, 3, 255],
+            };
+
+            let serialized = serialize_lossless(&compressed, compress_residual).unwrap();
+            let deserialized = deserialize_lossless(Cursor::new(serialized)).unwrap();
+            assert_eq!(deserialized.compressed.transformations, compressed.compressed.transformations);
+            assert_eq!(deserialized.iterations, 7);
+            assert_eq!(deserialized.overlap, 1);
+            assert_eq!(deserialized.residual, compressed.residual);
+        }
+    }
+
+    #[test]
+    fn residual_of_wrong_size_returns_error() {
+        let compressed = LosslessCompressed {
+            compressed: Compressed {
+                size: size!(w=2, h=2),
+                transformations: vec![],
+            },
+            iterations: 1,
+            overlap: 0,
+            residual: vec![1, 2, 3],
+        };
+
+        let serialized = serialize_lossless(&compressed, false).unwrap();
+        let result = deserialize_lossless(Cursor::new(serialized));
+        assert!(matches!(result, Err(DeserializationError::ResidualSizeMismatch { expected: 4, actual: 3 })));
+    }
+
+    #[test]
+    fn lengths_exceeding_the_data_return_error() {
+        let mut color = vec![0];
+        color.extend(u32::MAX.to_le_bytes());
+        assert!(matches!(deserialize_color(Cursor::new(color)), Err(DeserializationError::Truncated)));
+
+        let lossless = u32::MAX.to_le_bytes().to_vec();
+        assert!(matches!(deserialize_lossless(Cursor::new(lossless)), Err(DeserializationError::Truncated)));
+    }
+
+    #[test]
+    fn truncated_data_returns_error() {
+        let compressed = Compressed {
+            size: size!(w=64, h=64),
+            transformations: vec![create_transformation(), create_transformation()],
+        };
+
+        let serialized = serialize(&compressed).unwrap();
+        for length in 0..serialized.len() {
+            assert!(deserialize(Cursor::new(&serialized[..length])).is_err(), "Truncated to {} bytes", length);
+        }
+    }
+
+    #[test]
+    fn trailing_data_shorter_than_a_group_returns_error() {
+        let compressed = Compressed {
+            size: size!(w=64, h=64),
+            transformations: vec![create_transformation()],
+        };
+
+        let mut inflated = inflate(Cursor::new(serialize(&compressed).unwrap())).unwrap();
+        inflated.extend([1, 2, 3]);
+        let result = deserialize(Cursor::new(deflate(&inflated)));
+        assert!(matches!(result, Err(DeserializationError::TrailingData(3))));
+    }
+
+    #[test]
+    fn counted_payload_truncated_between_groups_returns_error() {
+        let mut t_8 = create_transformation();
+        t_8.range.block_size = 8;
+        t_8.domain.block_size = 16;
+        let compressed = Compressed {
+            size: size!(w=64, h=64),
+            transformations: vec![t_8, create_transformation()],
+        };
+
+        let inflated = inflate(Cursor::new(serialize_payload(&compressed, Layout::V2).unwrap())).unwrap();
+        for length in 12..inflated.len() {
+            let result = deserialize_payload(Cursor::new(deflate(&inflated[..length])), Layout::V2);
+            assert!(matches!(result, Err(DeserializationError::Truncated)), "Truncated to {} bytes", length);
+        }
+        assert!(deserialize_payload(Cursor::new(deflate(&inflated)), Layout::V2).is_ok());
+    }
+
+    fn serialized_group(size: Size, range_size: u32, count: u32) -> Vec<u8> {
+        let mut payload = Vec::new();
+        payload.write_u32::<LittleEndian>(size.get_width()).unwrap();
+        payload.write_u32::<LittleEndian>(size.get_height()).unwrap();
+        payload.write_u32::<LittleEndian>(range_size).unwrap();
+        payload.write_u32::<LittleEndian>(count).unwrap();
+        deflate(&payload)
+    }
+
+    #[test]
+    fn absurd_block_count_returns_error() {
+        let result = deserialize(Cursor::new(serialized_group(Size::squared(16), 4, u32::MAX)));
+        assert!(matches!(
+            result,
+            Err(DeserializationError::TooManyBlocks { count: u32::MAX, range_size: 4, max: 169 })
+        ));
+    }
+
+    #[test]
+    fn count_of_blocks_larger_than_the_image_is_left_to_the_validation() {
+        let result = deserialize(Cursor::new(serialized_group(Size::new(64, 8), 16, u32::MAX)));
+        assert!(matches!(result, Err(DeserializationError::Truncated)));
+    }
+
+    #[test]
+    fn possible_but_missing_blocks_return_truncated() {
+        // Any count fits into the image, but the blocks are not allocated before they are read
+        let result = deserialize(Cursor::new(serialized_group(Size::squared(1 << 16), 1, u32::MAX)));
+        assert!(matches!(result, Err(DeserializationError::Truncated)));
+        assert_eq!(max_blocks(Size::squared(1 << 16), 1), Some(1 << 32));
+    }
+
+    #[test]
+    fn max_blocks_counts_distinct_positions() {
+        assert_eq!(max_blocks(Size::squared(16), 16), Some(1));
+        assert_eq!(max_blocks(Size::new(16, 8), 8), Some(9));
+        assert_eq!(max_blocks(Size::squared(16), 0), None);
+        assert_eq!(max_blocks(Size::squared(16), 17), None);
+    }
+
+    #[test]
+    fn empty_data_returns_missing_header() {
+        let result = deserialize(Cursor::new(vec![]));
+        assert!(matches!(result, Err(DeserializationError::MissingHeader(0))));
+    }
+
+    #[test]
+    fn partial_header_returns_missing_header() {
+        for length in 1..8 {
+            let result = deserialize(Cursor::new(deflate(&vec![0; length])));
+            assert!(matches!(result, Err(DeserializationError::MissingHeader(actual)) if actual == length));
+        }
+        assert!(deserialize(Cursor::new(deflate(&[0; 8]))).is_ok());
+    }
+
+    fn serialized_with(range_size: u32, range_origin: Coords, domain_origin: Coords) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for value in [16, 16, range_size, 1, range_origin.x, range_origin.y, domain_origin.x, domain_origin.y] {
+            payload.write_u32::<LittleEndian>(value).unwrap();
+        }
+        payload.write_u8(0).unwrap();
+        payload.write_i16::<LittleEndian>(0).unwrap();
+        payload.write_f64::<LittleEndian>(0.5).unwrap();
+        deflate(&payload)
+    }
+
+    #[test]
+    fn valid_crafted_data_is_deserialized() {
+        let serialized = serialized_with(4, coords!(x=12, y=12), coords!(x=8, y=8));
+        assert!(deserialize(Cursor::new(serialized)).is_ok());
+    }
+
+    #[test]
+    fn out_of_bounds_origin_returns_error() {
+        let serialized = serialized_with(4, coords!(x=40000, y=40000), coords!(x=0, y=0));
+        let result = deserialize(Cursor::new(serialized));
+        assert!(matches!(
+            result,
+            Err(DeserializationError::InvalidTransformation { index: 0, reason: ValidationError::BlockOutOfBounds { .. } })
+        ));
+    }
+
+    #[test]
+    fn zero_block_size_returns_error() {
+        let serialized = serialized_with(0, coords!(x=0, y=0), coords!(x=0, y=0));
+        let result = deserialize(Cursor::new(serialized));
+        assert!(matches!(
+            result,
+            Err(DeserializationError::InvalidTransformation { index: 0, reason: ValidationError::EmptyBlock { .. } })
+        ));
+    }
+
+    #[test]
+    fn overflowing_domain_block_size_returns_error() {
+        let serialized = serialized_with(1 << 31, coords!(x=0, y=0), coords!(x=0, y=0));
+        let result = deserialize(Cursor::new(serialized));
+        assert!(matches!(result, Err(DeserializationError::InvalidTransformation { index: 0, .. })));
+    }
+
+    #[test]
+    fn every_rotation_roundtrips_in_flags() {
+        for rotation in [Rotation::By0, Rotation::By90, Rotation::By180, Rotation::By270] {
+            let mut transformation = create_transformation();
+            transformation.rotation = rotation;
+            let compressed = Compressed {
+                size: size!(w=64, h=64),
+                transformations: vec![transformation],
+            };
+
+            let serialized = serialize_payload(&compressed, Layout::V2).unwrap();
+            let deserialized = deserialize_payload(Cursor::new(serialized), Layout::V2).unwrap();
+            assert_eq!(deserialized.transformations[0].rotation, rotation);
+        }
+    }
+
+    /// Serializes a single block in the [Layout::V2], and modifies its flags byte.
+    fn serialized_with_flags(modify: impl FnOnce(u8) -> u8) -> Vec<u8> {
+        let compressed = Compressed {
+            size: size!(w=64, h=64),
+            transformations: vec![create_transformation()],
+        };
+        let mut inflated = inflate(Cursor::new(serialize_payload(&compressed, Layout::V2).unwrap())).unwrap();
+
+        // After the header, the group header and four single byte varints
+        let flags = 12 + 8 + 4;
+        inflated[flags] = modify(inflated[flags]);
+        deflate(&inflated)
+    }
+
+    #[test]
+    fn flipped_block_returns_error() {
+        let result = deserialize_payload(Cursor::new(serialized_with_flags(|flags| flags | 0b100)), Layout::V2);
+        assert!(matches!(result, Err(DeserializationError::UnsupportedFlip)));
+    }
+
+    #[test]
+    fn reserved_flags_return_error() {
+        let result = deserialize_payload(Cursor::new(serialized_with_flags(|flags| flags | 0b1000)), Layout::V2);
+        assert!(matches!(result, Err(DeserializationError::InvalidFlags(_))));
+    }
+
+    #[test]
+    fn fixed_point_saturation_is_exact_within_quantization() {
+        for saturation in [-1.0, -0.5, 0.0, 0.3, 0.75, 0.999, 1.0, 1.9] {
+            let roundtrip = from_fixed_point(to_fixed_point(saturation));
+            assert!((roundtrip - saturation).abs() <= 0.5 / FIXED_POINT_SCALE, "{} became {}", saturation, roundtrip);
+        }
+    }
+
+    #[test]
+    fn fixed_point_saturation_saturates_outside_of_its_range() {
+        assert_eq!(to_fixed_point(5.0), i16::MAX);
+        assert_eq!(to_fixed_point(-5.0), i16::MIN);
+        assert_eq!(from_fixed_point(i16::MIN), -2.0);
+    }
+
+    /// A transformation at a random origin, which lies within any image of at least 64x64
+    /// pixels for block sizes up to 32.
     fn create_transformation() -> Transformation {
+        let origin = || coords!(x=rand::random::<u32>() % 4 * 8, y=rand::random::<u32>() % 4 * 8);
         Transformation {
             range: Block {
                 block_size: 16,
-                origin: coords!(x=rand::random(), y=rand::random()),
+                origin: origin(),
             },
             domain: Block {
                 block_size: 32,
-                origin: coords!(x=rand::random(), y=rand::random()),
+                origin: origin(),
             },
             rotation: Rotation::By0,
             brightness: rand::random(),
@@ -0,0 +1,227 @@
+//! Versioned binary compression for quadtree compressed images.
+//!
+//! The binary format prefixes a payload similar to the [binary_v1](super::binary_v1) format
+//! with a header:
+//!
+//! `<magic><version><payload length><payload>`
+//!
+//! where the magic are the four ASCII bytes [MAGIC] and the version is a single byte, such that
+//! files can be told apart from JSON and from future versions. Unlike in version 1, the payload
+//! contains the amount of groups after the image size:
+//!
+//! `<image width><image height><amount of groups>(<range block size><amount of blocks><block>)*`
+//!
+//! Hence, truncated files and data after the end are detected. Furthermore, the saturation of
+//! a block is stored as a signed 16 bit fixed point number with 14 fractional bits (Q1.14)
+//! instead of an `f64`, which saves six bytes per block. Saturations are restored up to
+//! `2^-15`, as long as they lie within `[-2, 2)`.
+//!
+//! Instead of the rotation code, a block contains a [flags](super::flags) byte, which stores the
+//! rotation in its lowest two bits and reserves further bits for flips. Flipped blocks are
+//! rejected, as long as they can not be decompressed.
+
+use std::io::{Read, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use thiserror::Error;
+
+use crate::model;
+use crate::model::ValidationError;
+use crate::persistence::binary_v1;
+use crate::persistence::binary_v1::Layout;
+
+/// The bytes every binary file since version 2 starts with.
+pub const MAGIC: [u8; 4] = *b"QFIC";
+
+/// The version of the format written by [serialize].
+pub const VERSION: u8 = 2;
+
+/// The length of the magic, the version and the payload length.
+const HEADER_LENGTH: usize = 9;
+
+#[derive(Error, Debug)]
+pub enum SerializationError {
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Payload(#[from] binary_v1::SerializationError),
+}
+
+#[derive(Error, Debug)]
+pub enum DeserializationError {
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("Unknown magic bytes {:?}, expected {:?}", .0, MAGIC)]
+    UnknownMagic([u8; 4]),
+
+    #[error("Unsupported version {}, expected {}", .0, VERSION)]
+    UnsupportedVersion(u8),
+
+    #[error("The data is too short to contain a header ({0} bytes)")]
+    MissingHeader(usize),
+
+    #[error("The data ends unexpectedly")]
+    Truncated,
+
+    #[error("Transformation {index} is invalid: {reason}")]
+    InvalidTransformation { index: usize, reason: ValidationError },
+
+    #[error("Found {0} bytes of unexpected data after the end")]
+    TrailingData(usize),
+
+    #[error(transparent)]
+    Payload(binary_v1::DeserializationError),
+}
+
+impl From<binary_v1::DeserializationError> for DeserializationError {
+    fn from(error: binary_v1::DeserializationError) -> Self {
+        match error {
+            binary_v1::DeserializationError::MissingHeader(length) => Self::MissingHeader(length),
+            binary_v1::DeserializationError::Truncated => Self::Truncated,
+            binary_v1::DeserializationError::InvalidTransformation { index, reason } => {
+                Self::InvalidTransformation { index, reason }
+            }
+            binary_v1::DeserializationError::TrailingData(length) => Self::TrailingData(length),
+            error => Self::Payload(error),
+        }
+    }
+}
+
+pub fn serialize(compressed: &model::Compressed) -> Result<Vec<u8>, SerializationError> {
+    let mut result = Vec::new();
+    serialize_into(compressed, &mut result)?;
+    Ok(result)
+}
+
+/// Writes `compressed` to `writer` like [serialize], and returns the amount of bytes written.
+pub fn serialize_into<W: Write>(compressed: &model::Compressed, mut writer: W) -> Result<u64, SerializationError> {
+    let payload = binary_v1::serialize_payload(compressed, Layout::V2)?;
+    writer.write_all(&MAGIC)?;
+    writer.write_u8(VERSION)?;
+    writer.write_u32::<LittleEndian>(payload.len() as u32)?;
+    writer.write_all(&payload)?;
+    Ok((HEADER_LENGTH + payload.len()) as u64)
+}
+
+#[tracing::instrument(skip(reader))]
+pub fn deserialize(mut reader: impl Read) -> Result<model::Compressed, DeserializationError> {
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    (&mut reader).take(HEADER_LENGTH as u64).read_to_end(&mut header)?;
+    if header.len() < HEADER_LENGTH {
+        return Err(DeserializationError::MissingHeader(header.len()));
+    }
+
+    let magic: [u8; 4] = header[0..4].try_into().unwrap();
+    if magic != MAGIC {
+        return Err(DeserializationError::UnknownMagic(magic));
+    }
+
+    let version = header[4];
+    if version != VERSION {
+        return Err(DeserializationError::UnsupportedVersion(version));
+    }
+
+    let length = u32::from_le_bytes(header[5..9].try_into().unwrap());
+    let mut payload = Vec::new();
+    (&mut reader).take(length as u64).read_to_end(&mut payload)?;
+    if payload.len() < length as usize {
+        return Err(DeserializationError::Truncated);
+    }
+
+    let mut trailing = Vec::new();
+    reader.read_to_end(&mut trailing)?;
+    if !trailing.is_empty() {
+        return Err(DeserializationError::TrailingData(trailing.len()));
+    }
+
+    Ok(binary_v1::deserialize_payload(payload.as_slice(), Layout::V2)?)
+}
+
+/// Returns `true` iff `bytes` start with the [MAGIC] of a versioned binary file.
+pub fn has_magic(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use crate::coords;
+    use crate::image::{Coords, Size};
+    use crate::model::{Block, Compressed, Rotation, Transformation};
+    use crate::size;
+
+    use super::*;
+
+    fn compressed() -> Compressed {
+        Compressed {
+            size: size!(w=64, h=32),
+            transformations: vec![Transformation {
+                range: Block { block_size: 8, origin: coords!(x=8, y=16) },
+                domain: Block { block_size: 16, origin: coords!(x=0, y=16) },
+                rotation: Rotation::By270,
+                brightness: -12,
+                saturation: 0.75,
+            }],
+        }
+    }
+
+    #[test]
+    fn starts_with_magic_and_version() {
+        let serialized = serialize(&compressed()).unwrap();
+        assert!(has_magic(&serialized));
+        assert_eq!(serialized[4], VERSION);
+    }
+
+    #[test]
+    fn is_serializable_and_deserializable() {
+        let serialized = serialize(&compressed()).unwrap();
+        let deserialized = deserialize(Cursor::new(serialized)).unwrap();
+        assert_eq!(deserialized.size, compressed().size);
+        assert_eq!(deserialized.transformations, compressed().transformations);
+    }
+
+    #[test]
+    fn unknown_magic_returns_error() {
+        let result = deserialize(Cursor::new(b"QFIX\x02\x00\x00\x00\x00".to_vec()));
+        assert!(matches!(result, Err(DeserializationError::UnknownMagic(magic)) if &magic == b"QFIX"));
+    }
+
+    #[test]
+    fn unsupported_version_returns_error() {
+        let mut serialized = serialize(&compressed()).unwrap();
+        serialized[4] = 9;
+        let result = deserialize(Cursor::new(serialized));
+        assert!(matches!(result, Err(DeserializationError::UnsupportedVersion(9))));
+    }
+
+    #[test]
+    fn truncated_data_returns_error() {
+        let serialized = serialize(&compressed()).unwrap();
+        for length in HEADER_LENGTH..serialized.len() {
+            let result = deserialize(&serialized[..length]);
+            assert!(matches!(result, Err(DeserializationError::Truncated)), "Truncated to {} bytes: {:?}", length, result);
+        }
+    }
+
+    #[test]
+    fn partial_header_returns_missing_header() {
+        let serialized = serialize(&compressed()).unwrap();
+        for length in 0..HEADER_LENGTH {
+            let result = deserialize(&serialized[..length]);
+            assert!(matches!(result, Err(DeserializationError::MissingHeader(actual)) if actual == length));
+        }
+    }
+
+    #[test]
+    fn trailing_data_returns_error() {
+        for trailing in 1..6 {
+            let mut serialized = serialize(&compressed()).unwrap();
+            serialized.extend(vec![0; trailing]);
+            let result = deserialize(Cursor::new(serialized));
+            assert!(matches!(result, Err(DeserializationError::TrailingData(length)) if length == trailing));
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Persistence as [CBOR](https://www.rfc-editor.org/rfc/rfc8949), a compact binary format which
+//! is self-describing like JSON and hence readable in other languages without a custom parser.
+//! The contents follow the same schema as the [json](super::json) format.
+
+use std::io::{Read, Write};
+
+use thiserror::Error;
+
+use crate::model;
+use crate::model::{RotationInvalidError, ValidationError};
+use crate::persistence::schema::Contents;
+
+#[derive(Error, Debug)]
+pub enum SerializationError {
+    #[error("An error occurred while serializing: {0}")]
+    Serialization(#[from] ciborium::ser::Error<std::io::Error>),
+}
+
+/// Writes `compressed` to `writer` and returns the amount of bytes written.
+pub fn serialize_into<W: Write>(compressed: &model::Compressed, writer: W) -> Result<u64, SerializationError> {
+    let mut writer = CountingWriter { inner: writer, written: 0 };
+    ciborium::into_writer(&Contents::from(compressed.clone()), &mut writer)?;
+    Ok(writer.written)
+}
+
+#[derive(Error, Debug)]
+pub enum DeserializationError {
+    #[error("An error occurred while deserializing: {0}")]
+    Deserialization(#[from] ciborium::de::Error<std::io::Error>),
+
+    #[error(transparent)]
+    InvalidRotation(#[from] RotationInvalidError),
+
+    #[error("Transformation {index} is invalid: {reason}")]
+    InvalidTransformation { index: usize, reason: ValidationError },
+}
+
+impl From<ValidationError> for DeserializationError {
+    fn from(reason: ValidationError) -> Self {
+        Self::InvalidTransformation { index: reason.transformation(), reason }
+    }
+}
+
+pub fn deserialize(reader: impl Read) -> Result<model::Compressed, DeserializationError> {
+    let contents: Contents = ciborium::from_reader(reader)?;
+    contents.into_compressed()
+}
+
+struct CountingWriter<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use crate::coords;
+    use crate::image::{Coords, Size};
+    use crate::model::{Block, Compressed, Rotation, Transformation};
+
+    use super::*;
+
+    fn compressed(range_x: u32) -> Compressed {
+        Compressed {
+            size: Size::squared(16),
+            transformations: vec![Transformation {
+                range: Block { block_size: 4, origin: coords!(x=range_x, y=12) },
+                domain: Block { block_size: 8, origin: coords!(x=8, y=0) },
+                rotation: Rotation::By270,
+                brightness: -7,
+                saturation: 0.625,
+            }],
+        }
+    }
+
+    fn serialize(compressed: &Compressed) -> Vec<u8> {
+        let mut serialized = Vec::new();
+        serialize_into(compressed, &mut serialized).unwrap();
+        serialized
+    }
+
+    #[test]
+    fn is_serializable_and_deserializable() {
+        let serialized = serialize(&compressed(4));
+        let deserialized = deserialize(Cursor::new(serialized)).unwrap();
+        assert_eq!(deserialized.size, compressed(4).size);
+        assert_eq!(deserialized.transformations, compressed(4).transformations);
+    }
+
+    #[test]
+    fn reports_the_amount_of_bytes_written() {
+        let mut serialized = Vec::new();
+        let written = serialize_into(&compressed(4), &mut serialized).unwrap();
+        assert_eq!(written, serialized.len() as u64);
+    }
+
+    #[test]
+    fn out_of_bounds_origin_returns_error() {
+        let serialized = serialize(&compressed(40_000));
+        let result = deserialize(Cursor::new(serialized));
+        assert!(matches!(
+            result,
+            Err(DeserializationError::InvalidTransformation { index: 0, reason: ValidationError::BlockOutOfBounds { .. } })
+        ));
+    }
+
+    #[test]
+    fn truncated_data_returns_error() {
+        let serialized = serialize(&compressed(4));
+        let result = deserialize(&serialized[..serialized.len() - 1]);
+        assert!(matches!(result, Err(DeserializationError::Deserialization(_))));
+    }
+}
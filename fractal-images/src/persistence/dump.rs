@@ -0,0 +1,211 @@
+//! Human-readable listings of the transformations of a [Compressed] image, for debugging. The
+//! [CSV](DumpFormat::Csv) listing can be edited by hand and read back with
+//! [Compressed::from_csv].
+
+use std::io::{self, BufRead, Write};
+
+use thiserror::Error;
+
+use crate::coords;
+use crate::image::{Coords, Size};
+use crate::model::{Block, Compressed, Rotation, Transformation, ValidationError};
+
+/// The columns of the CSV listing.
+const CSV_HEADER: [&str; 9] = [
+    "range_x", "range_y", "range_size",
+    "domain_x", "domain_y", "domain_size",
+    "rotation", "brightness", "saturation",
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DumpFormat {
+    /// Aligned columns with a header, meant to be read by humans.
+    Table,
+
+    /// Comma separated values with a header, where rotations are given in degrees.
+    Csv,
+}
+
+#[derive(Error, Debug)]
+pub enum CsvError {
+    #[error("IO error: {0}")]
+    IO(#[from] io::Error),
+
+    #[error("Line {line} has {found} fields, but {expected} are expected")]
+    FieldCount { line: usize, found: usize, expected: usize },
+
+    #[error("Line {line} contains the invalid {field} {value:?}")]
+    InvalidValue { line: usize, field: &'static str, value: String },
+
+    #[error("Transformation {index} is invalid: {reason}")]
+    InvalidTransformation { index: usize, reason: ValidationError },
+}
+
+impl Compressed {
+    /// Writes one line per transformation to `writer`, preceded by a header line.
+    pub fn dump<W: Write>(&self, mut writer: W, format: DumpFormat) -> io::Result<()> {
+        match format {
+            DumpFormat::Table => {
+                writeln!(
+                    writer,
+                    "{:>5} {:>5} {:>5} {:>8} {:>8} {:>8} {:>8} {:>10} {:>12}",
+                    "x", "y", "size", "domain x", "domain y", "size", "rotation", "brightness", "saturation"
+                )?;
+                for t in &self.transformations {
+                    writeln!(
+                        writer,
+                        "{:>5} {:>5} {:>5} {:>8} {:>8} {:>8} {:>8} {:>10} {:>12.6}",
+                        t.range.origin.x, t.range.origin.y, t.range.block_size,
+                        t.domain.origin.x, t.domain.origin.y, t.domain.block_size,
+                        degrees(t.rotation), t.brightness, t.saturation
+                    )?;
+                }
+            }
+            DumpFormat::Csv => {
+                writeln!(writer, "{}", CSV_HEADER.join(","))?;
+                for t in &self.transformations {
+                    writeln!(
+                        writer,
+                        "{},{},{},{},{},{},{},{},{}",
+                        t.range.origin.x, t.range.origin.y, t.range.block_size,
+                        t.domain.origin.x, t.domain.origin.y, t.domain.block_size,
+                        degrees(t.rotation), t.brightness, t.saturation
+                    )?;
+                }
+            }
+        }
+        writer.flush()
+    }
+
+    /// Reads transformations from a [CSV](DumpFormat::Csv) listing as written by
+    /// [Compressed::dump], e.g. after editing it by hand. The header, empty lines and lines
+    /// starting with `#` are skipped. The resulting compression is
+    /// [validated](Compressed::validate) against `size`.
+    pub fn from_csv<R: BufRead>(reader: R, size: Size) -> Result<Self, CsvError> {
+        let mut transformations = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line == CSV_HEADER.join(",") {
+                continue;
+            }
+            transformations.push(parse_transformation(line, index + 1)?);
+        }
+
+        let compressed = Compressed { size, transformations };
+        compressed.validate().map_err(|reason| CsvError::InvalidTransformation {
+            index: reason.transformation(),
+            reason,
+        })?;
+        Ok(compressed)
+    }
+}
+
+fn parse_transformation(line: &str, line_number: usize) -> Result<Transformation, CsvError> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() != CSV_HEADER.len() {
+        return Err(CsvError::FieldCount { line: line_number, found: fields.len(), expected: CSV_HEADER.len() });
+    }
+
+    let invalid = |column: usize| CsvError::InvalidValue {
+        line: line_number,
+        field: CSV_HEADER[column],
+        value: fields[column].to_owned(),
+    };
+    let number = |column: usize| fields[column].parse::<u32>().map_err(|_| invalid(column));
+
+    Ok(Transformation {
+        range: Block { block_size: number(2)?, origin: coords!(x=number(0)?, y=number(1)?) },
+        domain: Block { block_size: number(5)?, origin: coords!(x=number(3)?, y=number(4)?) },
+        rotation: fields[6].parse().ok().and_then(from_degrees).ok_or_else(|| invalid(6))?,
+        brightness: fields[7].parse().map_err(|_| invalid(7))?,
+        saturation: fields[8].parse().map_err(|_| invalid(8))?,
+    })
+}
+
+fn degrees(rotation: Rotation) -> u16 {
+    match rotation {
+        Rotation::By0 => 0,
+        Rotation::By90 => 90,
+        Rotation::By180 => 180,
+        Rotation::By270 => 270,
+    }
+}
+
+fn from_degrees(degrees: u16) -> Option<Rotation> {
+    match degrees {
+        0 => Some(Rotation::By0),
+        90 => Some(Rotation::By90),
+        180 => Some(Rotation::By180),
+        270 => Some(Rotation::By270),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn compressed() -> Compressed {
+        let rotations = [Rotation::By0, Rotation::By90, Rotation::By180, Rotation::By270];
+        let transformations = (0..16)
+            .map(|index| Transformation {
+                range: Block { block_size: 4, origin: coords!(x=index % 4 * 4, y=index / 4 * 4) },
+                domain: Block { block_size: 8, origin: coords!(x=index % 3 * 4, y=8) },
+                rotation: rotations[index as usize % 4],
+                brightness: index as i16 * 17 - 128,
+                saturation: 1.0 / (index as f64 + 3.0) - 0.2,
+            })
+            .collect();
+        Compressed { size: Size::squared(16), transformations }
+    }
+
+    fn dumped(format: DumpFormat) -> String {
+        let mut output = Vec::new();
+        compressed().dump(&mut output, format).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn csv_roundtrip() {
+        let csv = dumped(DumpFormat::Csv);
+        let read = Compressed::from_csv(csv.as_bytes(), Size::squared(16)).unwrap();
+        assert_eq!(read.size, compressed().size);
+        assert_eq!(read.transformations, compressed().transformations);
+    }
+
+    #[test]
+    fn table_has_a_row_per_transformation() {
+        let table = dumped(DumpFormat::Table);
+        assert_eq!(table.lines().count(), 1 + compressed().transformations.len());
+        assert!(table.lines().next().unwrap().contains("rotation"));
+        assert!(table.lines().nth(2).unwrap().contains(" 90 "));
+    }
+
+    #[test]
+    fn csv_with_comments_and_spaces_is_read() {
+        let csv = "# An edited listing\nrange_x,range_y,range_size,domain_x,domain_y,domain_size,rotation,brightness,saturation\n\n 4, 8, 4, 0, 0, 8, 270, -3, 0.5\n";
+        let read = Compressed::from_csv(csv.as_bytes(), Size::squared(16)).unwrap();
+        assert_eq!(read.transformations.len(), 1);
+        assert_eq!(read.transformations[0].rotation, Rotation::By270);
+        assert_eq!(read.transformations[0].range.origin, coords!(x=4, y=8));
+    }
+
+    #[test]
+    fn invalid_rotation_returns_error() {
+        let result = Compressed::from_csv("0,0,4,0,0,8,45,0,0.5".as_bytes(), Size::squared(16));
+        assert!(matches!(result, Err(CsvError::InvalidValue { line: 1, field: "rotation", .. })));
+    }
+
+    #[test]
+    fn missing_field_returns_error() {
+        let result = Compressed::from_csv("0,0,4,0,0,8,0,0".as_bytes(), Size::squared(16));
+        assert!(matches!(result, Err(CsvError::FieldCount { line: 1, found: 8, expected: 9 })));
+    }
+
+    #[test]
+    fn out_of_bounds_block_returns_error() {
+        let result = Compressed::from_csv("12,12,4,12,12,8,0,0,0.5".as_bytes(), Size::squared(16));
+        assert!(matches!(result, Err(CsvError::InvalidTransformation { index: 0, .. })));
+    }
+}
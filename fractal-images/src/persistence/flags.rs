@@ -0,0 +1,96 @@
+//! The flags byte of a block in the [binary_v2](super::binary_v2) format, which stores how the
+//! domain block is transformed onto the range block:
+//!
+//! | Bits | Meaning                                             |
+//! |------|-----------------------------------------------------|
+//! | 0–1  | The [Rotation] code                                 |
+//! | 2    | Whether the domain block is flipped horizontally    |
+//! | 3–7  | Reserved for future transformations, need to be `0` |
+
+use thiserror::Error;
+
+use crate::model::Rotation;
+
+const ROTATION_MASK: u8 = 0b0000_0011;
+const HORIZONTAL_FLIP: u8 = 0b0000_0100;
+const RESERVED_MASK: u8 = !(ROTATION_MASK | HORIZONTAL_FLIP);
+
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+#[error("Invalid flags {:#010b}, where the reserved bits {:#010b} need to be zero", .0, RESERVED_MASK)]
+pub struct InvalidFlags(pub u8);
+
+/// The transformation of a block, as stored in its flags byte.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Flags {
+    pub rotation: Rotation,
+    pub horizontal_flip: bool,
+}
+
+impl Flags {
+    pub fn to_byte(self) -> u8 {
+        let flip = if self.horizontal_flip { HORIZONTAL_FLIP } else { 0 };
+        u8::from(self.rotation) | flip
+    }
+
+    /// Parses a flags byte, failing if any reserved bit is set.
+    pub fn from_byte(byte: u8) -> Result<Self, InvalidFlags> {
+        if byte & RESERVED_MASK != 0 {
+            return Err(InvalidFlags(byte));
+        }
+
+        // Every rotation code fits into the two bits
+        let rotation = Rotation::try_from(byte & ROTATION_MASK).map_err(|_| InvalidFlags(byte))?;
+        Ok(Self {
+            rotation,
+            horizontal_flip: byte & HORIZONTAL_FLIP != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROTATIONS: [Rotation; 4] = [Rotation::By0, Rotation::By90, Rotation::By180, Rotation::By270];
+
+    fn all_flags() -> impl Iterator<Item = Flags> {
+        ROTATIONS.into_iter().flat_map(|rotation| {
+            [false, true].map(|horizontal_flip| Flags { rotation, horizontal_flip })
+        })
+    }
+
+    #[test]
+    fn every_combination_roundtrips() {
+        for flags in all_flags() {
+            assert_eq!(Flags::from_byte(flags.to_byte()), Ok(flags));
+        }
+    }
+
+    #[test]
+    fn every_combination_has_a_distinct_byte() {
+        let mut bytes: Vec<_> = all_flags().map(Flags::to_byte).collect();
+        bytes.sort();
+        assert_eq!(bytes, (0..8).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn rotation_is_stored_in_the_lowest_bits() {
+        for rotation in ROTATIONS {
+            let flags = Flags { rotation, horizontal_flip: false };
+            assert_eq!(flags.to_byte(), u8::from(rotation));
+        }
+        assert_eq!(Flags { rotation: Rotation::By90, horizontal_flip: true }.to_byte(), 0b101);
+    }
+
+    #[test]
+    fn every_byte_with_reserved_bits_is_invalid() {
+        for byte in 0..=u8::MAX {
+            let result = Flags::from_byte(byte);
+            if byte < 8 {
+                assert!(result.is_ok(), "{:#010b} is valid", byte);
+            } else {
+                assert_eq!(result, Err(InvalidFlags(byte)));
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Inflates raw DEFLATE streams while they are read, such that the inflated data never needs to
+//! be held in memory as a whole.
+
+use std::io::{self, ErrorKind, Read};
+
+use miniz_oxide::inflate::stream::{inflate, InflateState};
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+use thiserror::Error;
+
+/// The amount of compressed bytes read from the inner reader at once.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// The error within the [io::Error] returned for corrupt DEFLATE data.
+#[derive(Error, Debug)]
+#[error("Invalid DEFLATE data")]
+pub struct InflateFailed;
+
+/// A reader of the data inflated from the raw DEFLATE stream in `inner`. Fails with
+/// [ErrorKind::UnexpectedEof] if `inner` ends before the stream does, and with [InflateFailed]
+/// if the stream is corrupt. Data after the end of the stream is not read.
+pub struct InflateReader<R> {
+    inner: R,
+    state: Box<InflateState>,
+    input: Vec<u8>,
+    start: usize,
+    end: usize,
+    input_read: u64,
+    input_ended: bool,
+    finished: bool,
+}
+
+impl<R: Read> InflateReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            state: InflateState::new_boxed(DataFormat::Raw),
+            input: vec![0; BUFFER_SIZE],
+            start: 0,
+            end: 0,
+            input_read: 0,
+            input_ended: false,
+            finished: false,
+        }
+    }
+
+    /// The amount of compressed bytes read from the inner reader so far.
+    pub fn input_read(&self) -> u64 {
+        self.input_read
+    }
+}
+
+impl<R: Read> Read for InflateReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while !self.finished {
+            if self.start == self.end && !self.input_ended {
+                self.end = self.inner.read(&mut self.input)?;
+                self.start = 0;
+                self.input_read += self.end as u64;
+                self.input_ended = self.end == 0;
+            }
+
+            let result = inflate(&mut self.state, &self.input[self.start..self.end], buf, MZFlush::None);
+            self.start += result.bytes_consumed;
+            match result.status {
+                Ok(MZStatus::StreamEnd) => self.finished = true,
+                Ok(_) => {}
+                // More input is needed
+                Err(MZError::Buf) if self.start == self.end => {
+                    if self.input_ended {
+                        return Err(io::Error::new(ErrorKind::UnexpectedEof, "The DEFLATE stream ends unexpectedly"));
+                    }
+                }
+                Err(_) => return Err(io::Error::new(ErrorKind::InvalidData, InflateFailed)),
+            }
+
+            if result.bytes_written > 0 {
+                return Ok(result.bytes_written);
+            }
+        }
+        Ok(0)
+    }
+}
+
+/// Returns `true` iff `error` was caused by corrupt DEFLATE data.
+pub fn is_inflate_failure(error: &io::Error) -> bool {
+    error.get_ref().is_some_and(|inner| inner.is::<InflateFailed>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inflated(deflated: &[u8]) -> io::Result<Vec<u8>> {
+        let mut result = Vec::new();
+        InflateReader::new(deflated).read_to_end(&mut result)?;
+        Ok(result)
+    }
+
+    /// Yields at most one byte per read.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let length = self.0.len().min(buf.len()).min(1);
+            buf[..length].copy_from_slice(&self.0[..length]);
+            self.0 = &self.0[length..];
+            Ok(length)
+        }
+    }
+
+    /// Pseudo random data, which does not compress well.
+    fn data() -> Vec<u8> {
+        let mut state = 1u32;
+        (0..100_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inflates_like_the_batch_decompressor() {
+        let deflated = miniz_oxide::deflate::compress_to_vec(&data(), 6);
+        assert!(deflated.len() > BUFFER_SIZE);
+        assert_eq!(inflated(&deflated).unwrap(), data());
+    }
+
+    #[test]
+    fn inflates_when_reading_single_bytes() {
+        let deflated = miniz_oxide::deflate::compress_to_vec(&data(), 6);
+
+        let mut result = Vec::new();
+        let mut reader = InflateReader::new(Trickle(&deflated));
+        let mut byte = [0; 1];
+        while reader.read(&mut byte).unwrap() == 1 {
+            result.push(byte[0]);
+        }
+        assert_eq!(result, data());
+        assert_eq!(reader.input_read(), deflated.len() as u64);
+    }
+
+    #[test]
+    fn empty_stream_is_inflated() {
+        assert_eq!(inflated(&miniz_oxide::deflate::compress_to_vec(&[], 6)).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn truncated_stream_returns_unexpected_eof() {
+        let deflated = miniz_oxide::deflate::compress_to_vec(&data(), 6);
+        for length in [0, 1, deflated.len() / 2, deflated.len() - 1] {
+            let error = inflated(&deflated[..length]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::UnexpectedEof, "Truncated to {} bytes", length);
+        }
+    }
+
+    #[test]
+    fn corrupt_stream_returns_error() {
+        // A block with the reserved block type 3
+        let error = inflated(&[0xFF, 0xFF, 0xFF]).unwrap_err();
+        assert!(is_inflate_failure(&error));
+    }
+}
@@ -1,15 +1,18 @@
-use std::io::Read;
+use std::io::{Read, Write};
 
-use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{coords, model, size};
-use crate::image::{Coords, Size};
+use crate::model;
+use crate::model::{RotationInvalidError, ValidationError};
+use crate::persistence::schema::Contents;
 
 #[derive(Error, Debug)]
 pub enum SerializationError {
     #[error("An error occurred while serializing: {0}")]
     Serialization(#[from] serde_json::Error),
+
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
 }
 
 pub fn serialize(compressed: &model::Compressed) -> Result<Vec<u8>, SerializationError> {
@@ -18,103 +21,162 @@ pub fn serialize(compressed: &model::Compressed) -> Result<Vec<u8>, Serializatio
     Ok(serialized.into_bytes())
 }
 
+/// Serializes like [serialize], but indented over multiple lines to be read by humans.
+pub fn serialize_pretty(compressed: &model::Compressed) -> Result<Vec<u8>, SerializationError> {
+    let contents = Contents::from(compressed.clone());
+    let serialized = serde_json::to_string_pretty(&contents)?;
+    Ok(serialized.into_bytes())
+}
+
+/// Writes `compressed` to `writer` like [serialize], and returns the amount of bytes written.
+pub fn serialize_into<W: Write>(compressed: &model::Compressed, mut writer: W) -> Result<u64, SerializationError> {
+    let serialized = serialize(compressed)?;
+    writer.write_all(&serialized)?;
+    Ok(serialized.len() as u64)
+}
+
 #[derive(Error, Debug)]
 pub enum DeserializationError {
     #[error("An error occurred while deserializing: {0}")]
     Deserialization(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    InvalidRotation(#[from] RotationInvalidError),
+
+    #[error("Transformation {index} is invalid: {reason}")]
+    InvalidTransformation { index: usize, reason: ValidationError },
+}
+
+impl From<ValidationError> for DeserializationError {
+    fn from(reason: ValidationError) -> Self {
+        Self::InvalidTransformation { index: reason.transformation(), reason }
+    }
 }
 
 pub fn deserialize(reader: impl Read) -> Result<model::Compressed, DeserializationError> {
     let contents: Contents = serde_json::from_reader(reader)?;
-    let transformations = contents
-        .mappings
-        .into_iter()
-        .map(|m| model::Transformation {
-            range: model::Block {
-                block_size: m.range.size,
-                origin: coords!(x=m.range.x, y=m.range.y),
-            },
-            domain: model::Block {
-                block_size: m.domain.size,
-                origin: coords!(x=m.domain.x, y=m.domain.y),
-            },
-            rotation: model::Rotation::try_from(m.rotation.0)
-                .unwrap_or(model::Rotation::By0),
-            brightness: m.brightness,
-            saturation: m.saturation,
-        })
-        .collect();
-
-    Ok(model::Compressed {
-        size: size!(w=contents.width, h=contents.height),
-        transformations,
-    })
+    contents.into_compressed()
 }
 
-#[derive(Serialize, Deserialize)]
-struct Contents {
-    width: u32,
-    height: u32,
-    mappings: Vec<Mapping>,
-}
+#[cfg(test)]
+mod test {
+    use super::*;
 
-impl From<model::Compressed> for Contents {
-    fn from(compressed: model::Compressed) -> Self {
-        Self {
-            width: compressed.size.get_width(),
-            height: compressed.size.get_height(),
-            mappings: compressed
-                .transformations
-                .into_iter()
-                .map(Mapping::from)
-                .collect(),
-        }
+    fn json(range: (u32, u32, u32), domain: (u32, u32, u32)) -> String {
+        json_with_rotation(range, domain, "0")
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct Mapping {
-    domain: Block,
-    range: Block,
-    rotation: Rotation,
-    brightness: i16,
-    saturation: f64,
-}
+    fn json_with_rotation(range: (u32, u32, u32), domain: (u32, u32, u32), rotation: &str) -> String {
+        let mapping = format!(
+            r#"{{"range":{{"x":{},"y":{},"size":{}}},"domain":{{"x":{},"y":{},"size":{}}},"rotation":{},"brightness":0,"saturation":0.5}}"#,
+            range.0, range.1, range.2, domain.0, domain.1, domain.2, rotation
+        );
+        format!(r#"{{"width":16,"height":16,"mappings":[{}]}}"#, mapping)
+    }
 
-impl From<model::Transformation> for Mapping {
-    fn from(value: model::Transformation) -> Self {
-        Self {
-            domain: Block::from(value.domain),
-            range: Block::from(value.range),
-            rotation: Rotation::from(value.rotation),
-            brightness: value.brightness,
-            saturation: value.saturation,
-        }
+    fn rotation_of(json: String) -> model::Rotation {
+        deserialize(json.as_bytes()).unwrap().transformations[0].rotation
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct Block {
-    size: u32,
-    x: u32,
-    y: u32,
-}
+    #[test]
+    fn legacy_numeric_rotation_is_deserialized() {
+        assert_eq!(rotation_of(json_with_rotation((12, 12, 4), (0, 8, 8), "0")), model::Rotation::By0);
+        assert_eq!(rotation_of(json_with_rotation((12, 12, 4), (0, 8, 8), "3")), model::Rotation::By270);
+    }
 
-impl From<model::Block> for Block {
-    fn from(value: model::Block) -> Self {
-        Self {
-            size: value.block_size,
-            x: value.origin.x,
-            y: value.origin.y,
+    #[test]
+    fn named_rotation_is_deserialized() {
+        assert_eq!(rotation_of(json_with_rotation((12, 12, 4), (0, 8, 8), r#""by0""#)), model::Rotation::By0);
+        assert_eq!(rotation_of(json_with_rotation((12, 12, 4), (0, 8, 8), r#""by180""#)), model::Rotation::By180);
+    }
+
+    #[test]
+    fn invalid_rotation_code_returns_error() {
+        let result = deserialize(json_with_rotation((12, 12, 4), (0, 8, 8), "4").as_bytes());
+        assert!(matches!(result, Err(DeserializationError::InvalidRotation(_))));
+    }
+
+    #[test]
+    fn rotation_is_serialized_by_name() {
+        let mut compressed = deserialize(json((12, 12, 4), (0, 8, 8)).as_bytes()).unwrap();
+        compressed.transformations[0].rotation = model::Rotation::By90;
+
+        let serialized = String::from_utf8(serialize(&compressed).unwrap()).unwrap();
+        assert!(serialized.contains(r#""rotation":"by90""#), "{}", serialized);
+        assert!(serialized.contains(r#""version":2"#), "{}", serialized);
+        assert_eq!(rotation_of(serialized), model::Rotation::By90);
+    }
+
+    fn pretty(json: &str) -> String {
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        serde_json::to_string_pretty(&value).unwrap()
+    }
+
+    fn without_version(json: &str) -> String {
+        let mut value: serde_json::Value = serde_json::from_str(json).unwrap();
+        value.as_object_mut().unwrap().remove("version");
+        value.to_string()
+    }
+
+    #[test]
+    fn pretty_serialization_spans_multiple_lines() {
+        let compressed = deserialize(json((12, 12, 4), (0, 8, 8)).as_bytes()).unwrap();
+
+        let compact = String::from_utf8(serialize(&compressed).unwrap()).unwrap();
+        let pretty = String::from_utf8(serialize_pretty(&compressed).unwrap()).unwrap();
+        assert_eq!(compact.lines().count(), 1);
+        assert!(pretty.lines().count() > 1);
+        assert!(pretty.contains(r#""version": 2"#), "{}", pretty);
+    }
+
+    #[test]
+    fn compact_and_pretty_with_and_without_version_are_deserialized() {
+        let compressed = deserialize(json((12, 12, 4), (0, 8, 8)).as_bytes()).unwrap();
+        let compact = String::from_utf8(serialize(&compressed).unwrap()).unwrap();
+        assert!(compact.contains(r#""version":2"#));
+
+        let variants = [
+            ("compact with version", compact.clone()),
+            ("pretty with version", pretty(&compact)),
+            ("compact without version", without_version(&compact)),
+            ("pretty without version", pretty(&without_version(&compact))),
+        ];
+        for (name, json) in variants {
+            let read = deserialize(json.as_bytes()).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(read.transformations, compressed.transformations, "{}", name);
         }
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct Rotation(u8);
+    #[test]
+    fn valid_transformation_is_deserialized() {
+        let compressed = deserialize(json((12, 12, 4), (0, 8, 8)).as_bytes()).unwrap();
+        assert_eq!(compressed.transformations.len(), 1);
+    }
+
+    #[test]
+    fn out_of_bounds_origin_returns_error() {
+        let result = deserialize(json((40000, 40000, 4), (0, 0, 8)).as_bytes());
+        assert!(matches!(
+            result,
+            Err(DeserializationError::InvalidTransformation { index: 0, reason: ValidationError::BlockOutOfBounds { .. } })
+        ));
+    }
 
-impl From<model::Rotation> for Rotation {
-    fn from(value: model::Rotation) -> Self {
-        Self(value.try_into().unwrap_or(0))
+    #[test]
+    fn zero_block_size_returns_error() {
+        let result = deserialize(json((0, 0, 4), (0, 0, 0)).as_bytes());
+        assert!(matches!(
+            result,
+            Err(DeserializationError::InvalidTransformation { index: 0, reason: ValidationError::EmptyBlock { .. } })
+        ));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn mismatched_domain_to_range_ratio_returns_error() {
+        let result = deserialize(json((0, 0, 4), (0, 0, 12)).as_bytes());
+        assert!(matches!(
+            result,
+            Err(DeserializationError::InvalidTransformation { index: 0, reason: ValidationError::InvalidDomainBlockSize { .. } })
+        ));
+    }
+}
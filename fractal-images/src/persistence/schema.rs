@@ -0,0 +1,158 @@
+//! The serde schema shared by the self-describing formats ([json](super::json) and
+//! [cbor](super::cbor)).
+//!
+//! Since [SCHEMA_VERSION] 2, rotations are written as names such as `"by90"` instead of the
+//! numeric codes of version 1. Both forms are accepted when reading.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{coords, model, size};
+use crate::image::{Coords, Size};
+use crate::model::{RotationInvalidError, ValidationError};
+
+/// The version of the schema written by [Contents].
+pub(super) const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct Contents {
+    /// Missing in files of version 1, which did not contain a version.
+    #[serde(default = "legacy_version")]
+    version: u32,
+    width: u32,
+    height: u32,
+    mappings: Vec<Mapping>,
+}
+
+impl From<model::Compressed> for Contents {
+    fn from(compressed: model::Compressed) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            width: compressed.size.get_width(),
+            height: compressed.size.get_height(),
+            mappings: compressed
+                .transformations
+                .into_iter()
+                .map(Mapping::from)
+                .collect(),
+        }
+    }
+}
+
+impl Contents {
+    /// Converts the contents back to a compression, which is [validated](model::Compressed::validate).
+    pub(super) fn into_compressed<E>(self) -> Result<model::Compressed, E>
+    where
+        E: From<RotationInvalidError> + From<ValidationError>,
+    {
+        let transformations = self
+            .mappings
+            .into_iter()
+            .map(|m| -> Result<model::Transformation, E> {
+                Ok(model::Transformation {
+                    range: model::Block {
+                        block_size: m.range.size,
+                        origin: coords!(x=m.range.x, y=m.range.y),
+                    },
+                    domain: model::Block {
+                        block_size: m.domain.size,
+                        origin: coords!(x=m.domain.x, y=m.domain.y),
+                    },
+                    rotation: model::Rotation::try_from(m.rotation)?,
+                    brightness: m.brightness,
+                    saturation: m.saturation,
+                })
+            })
+            .collect::<Result<_, E>>()?;
+
+        let compressed = model::Compressed {
+            size: size!(w=self.width, h=self.height),
+            transformations,
+        };
+        compressed.validate()?;
+        Ok(compressed)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Mapping {
+    domain: Block,
+    range: Block,
+    rotation: Rotation,
+    brightness: i16,
+    saturation: f64,
+}
+
+impl From<model::Transformation> for Mapping {
+    fn from(value: model::Transformation) -> Self {
+        Self {
+            domain: Block::from(value.domain),
+            range: Block::from(value.range),
+            rotation: Rotation::from(value.rotation),
+            brightness: value.brightness,
+            saturation: value.saturation,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Block {
+    size: u32,
+    x: u32,
+    y: u32,
+}
+
+impl From<model::Block> for Block {
+    fn from(value: model::Block) -> Self {
+        Self {
+            size: value.block_size,
+            x: value.origin.x,
+            y: value.origin.y,
+        }
+    }
+}
+
+fn legacy_version() -> u32 {
+    1
+}
+
+/// A rotation, which is written by name and read either by name or by its numeric code.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Rotation {
+    Named(RotationName),
+    Code(u8),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RotationName {
+    By0,
+    By90,
+    By180,
+    By270,
+}
+
+impl From<model::Rotation> for Rotation {
+    fn from(value: model::Rotation) -> Self {
+        Self::Named(match value {
+            model::Rotation::By0 => RotationName::By0,
+            model::Rotation::By90 => RotationName::By90,
+            model::Rotation::By180 => RotationName::By180,
+            model::Rotation::By270 => RotationName::By270,
+        })
+    }
+}
+
+impl TryFrom<Rotation> for model::Rotation {
+    type Error = RotationInvalidError;
+
+    fn try_from(value: Rotation) -> Result<Self, Self::Error> {
+        match value {
+            Rotation::Named(RotationName::By0) => Ok(model::Rotation::By0),
+            Rotation::Named(RotationName::By90) => Ok(model::Rotation::By90),
+            Rotation::Named(RotationName::By180) => Ok(model::Rotation::By180),
+            Rotation::Named(RotationName::By270) => Ok(model::Rotation::By270),
+            Rotation::Code(code) => model::Rotation::try_from(code),
+        }
+    }
+}
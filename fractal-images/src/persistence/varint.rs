@@ -0,0 +1,119 @@
+//! [LEB128](https://en.wikipedia.org/wiki/LEB128) encoded integers, which need fewer bytes the
+//! smaller the value is: seven bits per byte, where the highest bit marks that more bytes
+//! follow. Signed values are [zigzag](zigzag) encoded first.
+
+use std::io::{self, ErrorKind, Read, Write};
+
+/// The maximal amount of bytes of an encoded `u64`.
+const MAX_LENGTH: usize = 10;
+
+pub fn write_varint(writer: &mut impl Write, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+pub fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0u64;
+    for index in 0..MAX_LENGTH {
+        let mut byte = [0; 1];
+        reader.read_exact(&mut byte)?;
+        let bits = (byte[0] & 0x7F) as u64;
+        if index == MAX_LENGTH - 1 && bits > 1 {
+            break;
+        }
+        value |= bits << (7 * index);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(io::Error::new(ErrorKind::InvalidData, "Varint exceeds 64 bits"))
+}
+
+/// Maps signed to unsigned values such that values of a small magnitude stay small, i.e.
+/// `0, -1, 1, -2, 2, ...` to `0, 1, 2, 3, 4, ...`.
+pub fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// The inverse of [zigzag].
+pub fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Converts a decoded value to a `u32`, failing for values which do not fit.
+pub fn to_u32(value: impl TryInto<u32>) -> io::Result<u32> {
+    value.try_into().map_err(|_| io::Error::new(ErrorKind::InvalidData, "Value exceeds 32 bits"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn encoded(value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, value).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn small_values_need_one_byte() {
+        assert_eq!(encoded(0), vec![0]);
+        assert_eq!(encoded(1), vec![1]);
+        assert_eq!(encoded(127), vec![127]);
+    }
+
+    #[test]
+    fn larger_values_are_split_into_groups_of_seven_bits() {
+        assert_eq!(encoded(128), vec![0x80, 0x01]);
+        assert_eq!(encoded(300), vec![0xAC, 0x02]);
+        assert_eq!(encoded(u64::MAX).len(), MAX_LENGTH);
+    }
+
+    #[test]
+    fn roundtrip() {
+        for value in [0, 1, 127, 128, 255, 16_384, u32::MAX as u64, u64::MAX - 1, u64::MAX] {
+            let mut reader = Cursor::new(encoded(value));
+            assert_eq!(read_varint(&mut reader).unwrap(), value);
+            assert_eq!(reader.position() as usize, reader.get_ref().len());
+        }
+    }
+
+    #[test]
+    fn truncated_varint_returns_error() {
+        let result = read_varint(&mut Cursor::new(vec![0x80, 0x80]));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn overlong_varint_returns_error() {
+        let result = read_varint(&mut Cursor::new(vec![0xFF; 11]));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let mut too_large = vec![0xFF; MAX_LENGTH - 1];
+        too_large.push(0x02);
+        assert_eq!(read_varint(&mut Cursor::new(too_large)).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn u32_varint_needs_to_fit() {
+        let read_u32 = |value| to_u32(read_varint(&mut Cursor::new(encoded(value))).unwrap());
+        assert_eq!(read_u32(u32::MAX as u64).unwrap(), u32::MAX);
+        assert!(read_u32(u32::MAX as u64 + 1).is_err());
+    }
+
+    #[test]
+    fn zigzag_keeps_small_magnitudes_small() {
+        assert_eq!([0, -1, 1, -2, 2].map(zigzag), [0, 1, 2, 3, 4]);
+        for value in [0, 1, -1, 1000, -1000, i64::MAX, i64::MIN] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Post-processing of decompressed images.
+
+use std::collections::HashSet;
+
+use crate::image::{Image, MutableImage, OwnedImage, Pixel};
+use crate::model::Compressed;
+
+/// How many pixels on each side of a block boundary are smoothed by [deblock].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DeblockStrength {
+    /// Smooths the pixel directly next to the boundary
+    Light,
+
+    /// Smooths two pixels on each side of the boundary
+    Strong,
+}
+
+impl DeblockStrength {
+    fn width(self) -> u32 {
+        match self {
+            DeblockStrength::Light => 1,
+            DeblockStrength::Strong => 2,
+        }
+    }
+}
+
+/// Smooths the pixels along the boundaries of the range blocks of `compressed`, which are
+/// visible if the image was compressed with a lenient error threshold.
+///
+/// Every pixel within the [width](DeblockStrength) of a boundary is replaced by a weighted
+/// average of itself and its two neighbours across the boundary, with weights `1, 2, 1`.
+/// Vertical boundaries are smoothed first, then horizontal ones. Pixels further away from a
+/// boundary are not changed.
+pub fn deblock(image: &mut OwnedImage, compressed: &Compressed, strength: DeblockStrength) {
+    let width = strength.width();
+
+    // A boundary at (x, y) lies between (x - 1, y) and (x, y), respectively (x, y - 1) and (x, y)
+    let mut vertical_boundaries = HashSet::new();
+    let mut horizontal_boundaries = HashSet::new();
+    for transformation in &compressed.transformations {
+        let range = transformation.range;
+        for i in 0..range.block_size {
+            vertical_boundaries.insert((range.origin.x, range.origin.y + i));
+            vertical_boundaries.insert((range.origin.x + range.block_size, range.origin.y + i));
+            horizontal_boundaries.insert((range.origin.x + i, range.origin.y));
+            horizontal_boundaries.insert((range.origin.x + i, range.origin.y + range.block_size));
+        }
+    }
+
+    let (image_width, image_height) = (image.get_width(), image.get_height());
+    let source = image.clone();
+    for &(x, y) in &vertical_boundaries {
+        if x == 0 || x >= image_width || y >= image_height {
+            continue;
+        }
+        for px in pixels_around(x, width, image_width) {
+            let (previous, next) = (px.saturating_sub(1), (px + 1).min(image_width - 1));
+            image.set_pixel(px, y, smoothed(source.pixel(previous, y), source.pixel(px, y), source.pixel(next, y)));
+        }
+    }
+
+    let source = image.clone();
+    for &(x, y) in &horizontal_boundaries {
+        if y == 0 || y >= image_height || x >= image_width {
+            continue;
+        }
+        for py in pixels_around(y, width, image_height) {
+            let (previous, next) = (py.saturating_sub(1), (py + 1).min(image_height - 1));
+            image.set_pixel(x, py, smoothed(source.pixel(x, previous), source.pixel(x, py), source.pixel(x, next)));
+        }
+    }
+}
+
+/// The positions within `width` of a boundary at `boundary`, limited to `0..length`.
+fn pixels_around(boundary: u32, width: u32, length: u32) -> impl Iterator<Item=u32> {
+    (0..width).flat_map(move |distance| {
+        let before = boundary.checked_sub(distance + 1);
+        let after = Some(boundary + distance).filter(|position| *position < length);
+        before.into_iter().chain(after)
+    })
+}
+
+fn smoothed(previous: Pixel, pixel: Pixel, next: Pixel) -> Pixel {
+    ((previous as u32 + 2 * pixel as u32 + next as u32 + 2) / 4) as Pixel
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+    use crate::image::{Coords, Size};
+    use crate::model::{Block, Rotation, Transformation};
+
+    use super::*;
+
+    /// An 8x8 image of four flat 4x4 blocks.
+    fn four_blocks() -> (OwnedImage, Compressed) {
+        let values = [0, 100, 100, 200];
+        let pixels = (0..8)
+            .flat_map(|y| (0..8).map(move |x| values[(2 * (y / 4) + x / 4) as usize]))
+            .collect();
+        let transformations = [(0, 0), (4, 0), (0, 4), (4, 4)]
+            .map(|(x, y)| Transformation {
+                range: Block { block_size: 4, origin: coords!(x=x, y=y) },
+                domain: Block { block_size: 8, origin: coords!(x=0, y=0) },
+                rotation: Rotation::By0,
+                brightness: 0,
+                saturation: 0.0,
+            })
+            .to_vec();
+        (OwnedImage::from_pixels(Size::squared(8), pixels).unwrap(), Compressed { size: Size::squared(8), transformations })
+    }
+
+    /// The mean absolute difference of the pixels next to the block boundaries.
+    fn gradient_across_boundaries(image: &OwnedImage) -> f64 {
+        let mut sum = 0.0;
+        for i in 0..8 {
+            sum += (image.pixel(4, i) as f64 - image.pixel(3, i) as f64).abs();
+            sum += (image.pixel(i, 4) as f64 - image.pixel(i, 3) as f64).abs();
+        }
+        sum / 16.0
+    }
+
+    #[test]
+    fn deblocking_reduces_gradient_across_boundaries() {
+        let (mut image, compressed) = four_blocks();
+        let before = gradient_across_boundaries(&image);
+
+        deblock(&mut image, &compressed, DeblockStrength::Light);
+
+        assert!(gradient_across_boundaries(&image) < before);
+    }
+
+    #[test]
+    fn pixels_beyond_filter_width_are_unchanged() {
+        for strength in [DeblockStrength::Light, DeblockStrength::Strong] {
+            let (original, compressed) = four_blocks();
+            let mut image = original.clone();
+
+            deblock(&mut image, &compressed, strength);
+
+            let width = strength.width();
+            for y in 0..8 {
+                for x in 0..8 {
+                    let near_boundary = |position: u32| position + width >= 4 && position < 4 + width;
+                    if !near_boundary(x) && !near_boundary(y) {
+                        assert_eq!(image.pixel(x, y), original.pixel(x, y), "Pixel ({}, {}) changed", x, y);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pixels_around_boundary_are_limited_to_image() {
+        assert_eq!(pixels_around(1, 2, 8).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(pixels_around(7, 2, 8).collect::<Vec<_>>(), vec![6, 7, 5]);
+    }
+}
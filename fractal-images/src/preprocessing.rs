@@ -1,44 +1,376 @@
-use crate::image::{Image, Pixel, PowerOfTwo, Size, Square};
+use crate::compress::color::{subsample, YCbCrPlanes};
+use crate::image::{
+    Coords, Cropped, Image, IntoCropped, IntoOwnedImage, MutableImage, OwnedImage, PadMode, Padded, Pixel, PowerOfTwo, Size,
+    SizeMismatch, Square,
+};
+use image::codecs::jpeg::JpegEncoder;
 use image::imageops::FilterType;
-use image::{DynamicImage, GrayImage, ImageFormat};
-use std::cmp::min;
-use std::path::Path;
+use image::{ColorType, DynamicImage, GrayImage, ImageFormat, RgbImage};
+use std::cmp::{max, min};
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Seek};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
 use tracing::debug;
 
+mod exif;
+
+pub use exif::Orientation;
+
+#[derive(Error, Debug)]
+pub enum LoadError {
+    #[error("Could not load the image: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("The image is empty")]
+    Empty,
+
+    #[error("The maximal dimension {0} is not a power of two")]
+    InvalidMaxDimension(u32),
+}
+
+/// How a loaded image is turned into a square whose size is a power of two, as required by the
+/// [compressor](crate::compress::quadtree::Compressor).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Sizing {
+    /// Downscales the image to the largest power of two fitting into its smaller dimension,
+    /// which distorts images that are not square
+    #[default]
+    DownscaleToPreviousPow2,
+
+    /// Pads the image at its right and bottom edge to the next power of two of its larger
+    /// dimension, keeping every pixel
+    PadToNextPow2(PadMode),
+
+    /// Keeps the largest square power of two in the center of the image, cutting off its edges
+    CenterCropToPreviousPow2,
+}
+
+/// The weights of the red, green and blue channel with which colors are converted to gray.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum LumaWeights {
+    /// 0.299, 0.587 and 0.114 of the NTSC standard (ITU-R BT.601)
+    #[default]
+    Ntsc,
+
+    /// 0.2126, 0.7152 and 0.0722 of ITU-R BT.709, which matches the primaries of sRGB
+    Bt709,
+
+    /// The mean of the three channels
+    Average,
+
+    /// Custom weights of red, green and blue, which should sum up to 1
+    Custom(f32, f32, f32),
+}
+
+impl LumaWeights {
+    /// Converts an RGB color to gray.
+    pub fn gray(&self, red: u8, green: u8, blue: u8) -> Pixel {
+        let (red, green, blue) = (red as u32, green as u32, blue as u32);
+        match *self {
+            LumaWeights::Ntsc => ntsc_grayscale(red, green, blue) as Pixel,
+            LumaWeights::Bt709 => ((2126 * red + 7152 * green + 722 * blue) / 10000) as Pixel,
+            LumaWeights::Average => ((red + green + blue) / 3) as Pixel,
+            LumaWeights::Custom(weight_red, weight_green, weight_blue) => {
+                let gray = weight_red * red as f32 + weight_green * green as f32 + weight_blue * blue as f32;
+                gray.round().clamp(0.0, Pixel::MAX as f32) as Pixel
+            }
+        }
+    }
+}
+
+/// How transparent pixels are converted to opaque ones.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Alpha {
+    /// Ignores the alpha channel, such that fully transparent pixels keep their color
+    #[default]
+    Ignore,
+
+    /// Composites the image over a background of the given red, green and blue
+    CompositeOver([u8; 3]),
+}
+
+/// How 16-bit channels are reduced to 8 bits.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum BitDepthReduction {
+    /// Rounds to the nearest 8-bit value
+    #[default]
+    Round,
+
+    /// Keeps the 8 most significant bits
+    Truncate,
+
+    /// Rounds up or down with an ordered 4x4 Bayer pattern, such that smooth gradients do not
+    /// turn into bands. Values which can be represented with 8 bits are kept.
+    Dither,
+}
+
+/// The thresholds of the [BitDepthReduction::Dither] pattern, in sixteenths.
+const BAYER_4X4: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl BitDepthReduction {
+    /// Reduces the 16-bit `value` of the pixel at `x` and `y` to 8 bits.
+    fn reduce(&self, value: u16, x: u32, y: u32) -> u8 {
+        let value = value as u32;
+        match self {
+            BitDepthReduction::Round => ((value + 128) / 257) as u8,
+            BitDepthReduction::Truncate => (value >> 8) as u8,
+            BitDepthReduction::Dither => {
+                // floor(value / 257 + (threshold + 0.5) / 16)
+                let threshold = BAYER_4X4[(y % 4) as usize][(x % 4) as usize];
+                ((32 * value + 257 * (2 * threshold + 1)) / (32 * 257)) as u8
+            }
+        }
+    }
+}
+
+/// Options of [SquaredGrayscaleImage::read_with].
+#[derive(Debug, Copy, Clone)]
+pub struct LoadOptions {
+    pub sizing: Sizing,
+
+    /// The filter with which [Sizing::DownscaleToPreviousPow2] resamples the image.
+    /// [FilterType::Gaussian] by default, which blurs fine details.
+    pub filter: FilterType,
+
+    /// How colors are converted to gray
+    pub luma: LumaWeights,
+
+    /// How transparent pixels are converted
+    pub alpha: Alpha,
+
+    /// How images with 16 bits per channel are reduced to 8 bits
+    pub bit_depth: BitDepthReduction,
+
+    /// Whether photos are turned upright according to the [Orientation] in their Exif metadata,
+    /// as image viewers do. `true` by default.
+    pub respect_exif: bool,
+
+    /// The largest width and height of the loaded square, which needs to be a power of two.
+    /// Larger images are downscaled, which keeps their aspect ratio if they are padded.
+    pub max_dimension: Option<u32>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            sizing: Sizing::default(),
+            filter: FilterType::Gaussian,
+            luma: LumaWeights::default(),
+            alpha: Alpha::default(),
+            bit_depth: BitDepthReduction::default(),
+            respect_exif: true,
+            max_dimension: None,
+        }
+    }
+}
+
+/// An image loaded with [LoadOptions], which remembers its size before it was
+/// [resized](Sizing).
 #[derive(Debug)]
+pub struct LoadedImage {
+    pub image: PowerOfTwo<Square<SquaredGrayscaleImage>>,
+
+    /// The size of the image as it was read
+    pub original_size: Size,
+
+    /// The size of the part of [LoadedImage::image] at its top left which shows the original
+    /// image. This is all of it, unless it was [padded](Sizing::PadToNextPow2).
+    pub content_size: Size,
+
+    pub sizing: Sizing,
+}
+
+impl LoadedImage {
+    /// A view of the part of `decompressed`, a decompression of [LoadedImage::image], which
+    /// shows the original image. Removes the padding of [Sizing::PadToNextPow2], and keeps the
+    /// whole image otherwise.
+    pub fn crop_to_original<I: Image>(&self, decompressed: I) -> Cropped<I> {
+        let size = match self.sizing {
+            Sizing::PadToNextPow2(_) => self.content_size,
+            Sizing::DownscaleToPreviousPow2 | Sizing::CenterCropToPreviousPow2 => decompressed.get_size(),
+        };
+        decompressed.crop(Coords { x: 0, y: 0 }, size).expect("The decompressed image has the size of the loaded image")
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SquaredGrayscaleImage {
     pixels: Vec<u8>,
     size: Size,
 }
 
 impl SquaredGrayscaleImage {
+    /// Creates an image from row-major `pixels`, whose amount needs to match the size. Wrap it
+    /// in [Square] and [PowerOfTwo] to compress it.
+    pub fn from_pixels(size: Size, pixels: Vec<Pixel>) -> Result<Self, SizeMismatch> {
+        if pixels.len() != size.area() as usize {
+            return Err(SizeMismatch { size, pixels: pixels.len() });
+        }
+        Ok(Self { pixels, size })
+    }
+
+    /// The row-major pixels of the image.
+    pub fn as_pixels(&self) -> &[Pixel] {
+        &self.pixels
+    }
+
+    /// Converts the image to an [OwnedImage] without copying its pixels.
+    pub fn into_owned(self) -> OwnedImage {
+        OwnedImage::from_pixels(self.size, self.pixels).expect("One gray value per pixel")
+    }
+
     pub fn read_from(path: &Path) -> PowerOfTwo<Square<Self>> {
-        let image = image::open(path).unwrap_or_else(|_| panic!("Could not load image: {:?}", path));
+        Self::try_read_from(path).unwrap_or_else(|_| panic!("Could not load image: {:?}", path))
+    }
+
+    /// Reads the image at `path` like [SquaredGrayscaleImage::read_from], but returns an error
+    /// instead of panicking if it can not be loaded.
+    pub fn try_read_from(path: &Path) -> Result<PowerOfTwo<Square<Self>>, LoadError> {
+        let options = LoadOptions::default();
+        Self::squared(open(path, &options)?, &options)
+    }
+
+    /// Decodes an encoded image, such as the contents of a PNG file, like
+    /// [SquaredGrayscaleImage::read_from]. The format is detected from the bytes.
+    pub fn read_from_bytes(bytes: &[u8]) -> Result<PowerOfTwo<Square<Self>>, LoadError> {
+        let options = LoadOptions::default();
+        Self::squared(decode(bytes, &options)?, &options)
+    }
+
+    /// Decodes an encoded image from `reader` like [SquaredGrayscaleImage::read_from_bytes].
+    pub fn read_from_reader<R: BufRead + Seek>(mut reader: R) -> Result<PowerOfTwo<Square<Self>>, LoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::read_from_bytes(&bytes)
+    }
+
+    /// Converts `image` to grayscale and downscales it to the largest square power of two.
+    fn squared(image: DynamicImage, options: &LoadOptions) -> Result<PowerOfTwo<Square<Self>>, LoadError> {
         let size = min(image.width(), image.height());
+        if size == 0 {
+            return Err(LoadError::Empty);
+        }
 
         // Ensure size is a multiple of 2
-        let size = (size.ilog2() as f32).exp2() as u32;
+        let size = capped(1 << size.ilog2(), options);
 
-        let image = image.resize(size, size, FilterType::Gaussian);
-        let image = image.to_rgb8();
+        let image = image.resize_exact(size, size, options.filter);
+        let image = Square::new(Self::grayscale(&image, options)).expect("Unable to create a square image");
+
+        Ok(PowerOfTwo::new(image).expect("Unable to downscale image to a power of two"))
+    }
+
+    /// Reads the image at `path` and converts it to a square power of two as configured by
+    /// `options`. [SquaredGrayscaleImage::read_from] reads it like the default options.
+    pub fn read_with(path: &Path, options: LoadOptions) -> Result<LoadedImage, LoadError> {
+        Self::sized(open(path, &options)?, &options)
+    }
+
+    /// Decodes an encoded image like [SquaredGrayscaleImage::read_from_bytes], and converts it
+    /// to a square power of two as configured by `options`.
+    pub fn read_from_bytes_with(bytes: &[u8], options: LoadOptions) -> Result<LoadedImage, LoadError> {
+        Self::sized(decode(bytes, &options)?, &options)
+    }
+
+    /// Converts an already decoded image to a square power of two as configured by `options`.
+    /// The Exif metadata of the image is not known, hence it is not turned upright.
+    pub fn from_dynamic_image_with(image: DynamicImage, options: LoadOptions) -> Result<LoadedImage, LoadError> {
+        Self::sized(image, &options)
+    }
+
+    fn sized(image: DynamicImage, options: &LoadOptions) -> Result<LoadedImage, LoadError> {
+        if let Some(max_dimension) = options.max_dimension.filter(|max_dimension| !max_dimension.is_power_of_two()) {
+            return Err(LoadError::InvalidMaxDimension(max_dimension));
+        }
+
+        let original_size = Size::new(image.width(), image.height());
+        if original_size.area() == 0 {
+            return Err(LoadError::Empty);
+        }
+
+        let sizing = options.sizing;
+        let (image, content_size) = match sizing {
+            Sizing::DownscaleToPreviousPow2 => {
+                let image = Self::squared(image, options)?;
+                let size = image.get_size();
+                (image, size)
+            }
+            Sizing::PadToNextPow2(mode) => {
+                let image = match options.max_dimension {
+                    Some(max_dimension) if max(image.width(), image.height()) > max_dimension => {
+                        image.resize(max_dimension, max_dimension, options.filter)
+                    }
+                    _ => image,
+                };
+                let content_size = Size::new(image.width(), image.height());
+                let padded = PowerOfTwo::pad_to_square(Self::grayscale(&image, options), mode).map_err(|_| LoadError::Empty)?;
+                (Self::copied(&padded), content_size)
+            }
+            Sizing::CenterCropToPreviousPow2 => {
+                let length = 1 << min(image.width(), image.height()).ilog2();
+                let cropped = image.crop_imm((image.width() - length) / 2, (image.height() - length) / 2, length, length);
+                let length = capped(length, options);
+                let cropped = cropped.resize_exact(length, length, options.filter);
+                (Self::copied(&Self::grayscale(&cropped, options)), Size::squared(length))
+            }
+        };
+        Ok(LoadedImage { image, original_size, content_size, sizing })
+    }
+
+    /// Copies `image`, which needs to be a square power of two.
+    fn copied<I: Image>(image: &I) -> PowerOfTwo<Square<Self>> {
+        let width = image.get_width() as usize;
+        let mut pixels = vec![0; image.get_size().area() as usize];
+        for (y, row) in pixels.chunks_exact_mut(width).enumerate() {
+            image.copy_row_into(y as u32, row);
+        }
+        let image = Self { pixels, size: image.get_size() };
+        PowerOfTwo::new(Square::new(image).expect("The image is a square")).expect("The image is a power of two")
+    }
+
+    /// Reads the image at `path` like [SquaredGrayscaleImage::read_from], but pads it to a
+    /// square power of two instead of downscaling it, such that no pixel is lost. The size of
+    /// the image before padding is available with [Padded::original_size].
+    pub fn read_padded_from(path: &Path, mode: PadMode) -> PowerOfTwo<Square<Padded<Self>>> {
+        let options = LoadOptions::default();
+        let image = open(path, &options).unwrap_or_else(|_| panic!("Could not load image: {:?}", path));
+        PowerOfTwo::pad_to_square(Self::grayscale(&image, &options), mode).unwrap_or_else(|_| panic!("Image is empty: {:?}", path))
+    }
+
+    fn grayscale(image: &DynamicImage, options: &LoadOptions) -> Self {
+        let image = image.to_rgba16();
         let grayscale = image
-            .pixels()
-            .map(|pixel| {
-                let red = pixel.0[0];
-                let green = pixel.0[1];
-                let blue = pixel.0[2];
-                let ntsc_grayscale = 299 * red as u32 + 587 * green as u32 + 114 * blue as u32;
-                let ntsc = ntsc_grayscale / 1000;
-                ntsc as u8
+            .enumerate_pixels()
+            .map(|(x, y, pixel)| {
+                let [red, green, blue] = opaque(pixel.0, options.alpha).map(|value| options.bit_depth.reduce(value, x, y));
+                options.luma.gray(red, green, blue)
             })
             .collect::<Vec<_>>();
 
-        let image = Square::new(Self {
+        Self {
             pixels: grayscale,
-            size: Size::squared(size),
-        }).expect("Unable to create a square image");
+            size: Size::new(image.width(), image.height()),
+        }
+    }
+}
 
-        PowerOfTwo::new(image).expect("Unable to downscale image to a power of two")
+/// The red, green and blue of a 16-bit RGBA `pixel`, whose alpha channel is handled by `alpha`.
+fn opaque(pixel: [u16; 4], alpha: Alpha) -> [u16; 3] {
+    let [red, green, blue, opacity] = pixel.map(|value| value as u32);
+    match alpha {
+        Alpha::Ignore => [red, green, blue].map(|value| value as u16),
+        Alpha::CompositeOver(background) => {
+            let max = u16::MAX as u32;
+            let composite = |value: u32, background: u8| {
+                (value * opacity + background as u32 * 257 * (max - opacity) + max / 2) / max
+            };
+            [composite(red, background[0]), composite(green, background[1]), composite(blue, background[2])]
+                .map(|value| value as u16)
+        }
     }
 }
 
@@ -51,6 +383,161 @@ impl Image for SquaredGrayscaleImage {
         let index = self.get_width() * y + x;
         self.pixels[index as usize]
     }
+
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        let start = (self.get_width() * y) as usize;
+        Some(&self.pixels[start..start + self.get_width() as usize])
+    }
+}
+
+impl MutableImage for SquaredGrayscaleImage {
+    fn set_pixel(&mut self, x: u32, y: u32, value: Pixel) {
+        assert!(x < self.get_width());
+        assert!(y < self.get_height());
+        let index = self.get_width() * y + x;
+        self.pixels[index as usize] = value;
+    }
+}
+
+/// Caps the power of two `length` to the [maximal dimension](LoadOptions::max_dimension).
+fn capped(length: u32, options: &LoadOptions) -> u32 {
+    options.max_dimension.map_or(length, |max_dimension| min(length, max_dimension))
+}
+
+/// Reads the image at `path` in the format of its extension, like [image::open].
+fn open(path: &Path, options: &LoadOptions) -> Result<DynamicImage, LoadError> {
+    let format = ImageFormat::from_path(path)?;
+    let bytes = std::fs::read(path)?;
+    Ok(oriented(image::load_from_memory_with_format(&bytes, format)?, &bytes, options))
+}
+
+/// Decodes an encoded image, whose format is detected from `bytes`.
+fn decode(bytes: &[u8], options: &LoadOptions) -> Result<DynamicImage, LoadError> {
+    Ok(oriented(image::load_from_memory(bytes)?, bytes, options))
+}
+
+/// Turns `image`, which was decoded from `bytes`, upright if `options` respect its Exif metadata.
+fn oriented(image: DynamicImage, bytes: &[u8], options: &LoadOptions) -> DynamicImage {
+    match options.respect_exif {
+        true => Orientation::of_encoded(bytes).unwrap_or_default().apply(image),
+        false => image,
+    }
+}
+
+/// A plane of a [ColorImage], such as its luma.
+#[derive(Debug, Clone)]
+pub struct Plane {
+    pixels: Vec<Pixel>,
+    size: Size,
+}
+
+impl Plane {
+    /// Wraps `plane`, which needs to be a square power of two.
+    fn squared(plane: OwnedImage) -> PowerOfTwo<Square<Self>> {
+        let plane = Self { size: plane.get_size(), pixels: plane.as_slice().to_vec() };
+        PowerOfTwo::new(Square::new(plane).expect("The plane is a square")).expect("The plane is a power of two")
+    }
+}
+
+impl Image for Plane {
+    fn get_size(&self) -> Size {
+        self.size
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Pixel {
+        let index = self.get_width() * y + x;
+        self.pixels[index as usize]
+    }
+
+    fn pixel_row(&self, y: u32) -> Option<&[Pixel]> {
+        let start = (self.get_width() * y) as usize;
+        Some(&self.pixels[start..start + self.get_width() as usize])
+    }
+}
+
+/// A color image, loaded as separate planes of the YCbCr color space, which can be compressed
+/// with [compress_rgb](crate::compress::color::compress_rgb).
+///
+/// Like [SquaredGrayscaleImage], the image is downscaled to the largest square power of two.
+/// The chroma planes have the size of the luma plane, or half of it once
+/// [subsampled](ColorImage::with_chroma_subsampling).
+#[derive(Debug)]
+pub struct ColorImage {
+    /// The luma (Y) plane, which has the size of the image
+    pub luma: PowerOfTwo<Square<Plane>>,
+
+    /// The blue-difference chroma (Cb) plane
+    pub blue_chroma: PowerOfTwo<Square<Plane>>,
+
+    /// The red-difference chroma (Cr) plane
+    pub red_chroma: PowerOfTwo<Square<Plane>>,
+}
+
+impl ColorImage {
+    pub fn read_from(path: &Path) -> Result<Self, LoadError> {
+        Self::from_rgb(&open(path, &LoadOptions::default())?.to_rgb8())
+    }
+
+    /// Decodes an encoded image like [SquaredGrayscaleImage::read_from_bytes].
+    pub fn read_from_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        Self::from_rgb(&decode(bytes, &LoadOptions::default())?.to_rgb8())
+    }
+
+    /// Converts `image` to YCbCr and downscales it to the largest square power of two, such as
+    /// the RGB image returned by [decompress_rgb](crate::decompress::decompress_rgb).
+    pub fn from_rgb(image: &RgbImage) -> Result<Self, LoadError> {
+        let size = min(image.width(), image.height());
+        if size == 0 {
+            return Err(LoadError::Empty);
+        }
+        let size = 1 << size.ilog2();
+
+        let image = image::imageops::resize(image, size, size, LoadOptions::default().filter);
+        let planes = YCbCrPlanes::from_rgb(&image);
+        Ok(Self {
+            luma: Plane::squared(planes.luma),
+            blue_chroma: Plane::squared(planes.blue_chroma),
+            red_chroma: Plane::squared(planes.red_chroma),
+        })
+    }
+
+    /// Halves the width and height of the chroma planes, unless they are subsampled already or
+    /// consist of a single pixel.
+    pub fn with_chroma_subsampling(self) -> Self {
+        if self.is_chroma_subsampled() || self.blue_chroma.get_width() == 1 {
+            return self;
+        }
+        Self {
+            blue_chroma: Plane::squared(subsample(&self.blue_chroma.to_owned_image())),
+            red_chroma: Plane::squared(subsample(&self.red_chroma.to_owned_image())),
+            ..self
+        }
+    }
+
+    /// Whether the chroma planes have half the width and height of the luma plane
+    pub fn is_chroma_subsampled(&self) -> bool {
+        self.blue_chroma.get_size() != self.luma.get_size()
+    }
+
+    /// The size of the image
+    pub fn get_size(&self) -> Size {
+        self.luma.get_size()
+    }
+
+    /// Recombines the planes to an RGB image. Subsampled chroma planes are upscaled by
+    /// repeating their pixels.
+    pub fn to_rgb(&self) -> RgbImage {
+        let planes = YCbCrPlanes {
+            luma: self.luma.to_owned_image(),
+            blue_chroma: self.blue_chroma.to_owned_image(),
+            red_chroma: self.red_chroma.to_owned_image(),
+        };
+        planes.into_rgb()
+    }
+
+    pub fn save_color_png<T: AsRef<Path>>(&self, path: T) -> Result<(), SaveError> {
+        Ok(self.to_rgb().save_with_format(path, ImageFormat::Png)?)
+    }
 }
 
 pub trait AsDynamicImage {
@@ -63,29 +550,131 @@ where
 {
     fn as_dynamic_image(&self) -> DynamicImage {
         debug!("Converting image to dynamic image");
-        let pixels: Vec<_> = self.pixels().collect();
+        let width = self.get_width() as usize;
+        let mut pixels = vec![0; width * self.get_height() as usize];
+        for (y, row) in pixels.chunks_exact_mut(width.max(1)).enumerate() {
+            self.copy_row_into(y as u32, row);
+        }
         let image = GrayImage::from_raw(self.get_width(), self.get_height(), pixels)
             .expect("Unable to convert to GrayImage");
         DynamicImage::ImageLuma8(image)
     }
 }
 
+/// Converts an RGB color to gray with the weights of the NTSC standard.
+fn ntsc_grayscale(red: u32, green: u32, blue: u32) -> u32 {
+    (299 * red + 587 * green + 114 * blue) / 1000
+}
+
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("Images with color type {0:?} can not be converted to grayscale")]
+pub struct UnsupportedColorType(pub ColorType);
+
+/// Converts images decoded by the `image` crate to grayscale, keeping their size.
+///
+/// Colors are converted with the weights of the NTSC standard, like
+/// [SquaredGrayscaleImage::read_from] does, 16-bit and floating point channels are scaled to
+/// 8 bits and alpha channels are ignored.
+pub trait FromDynamicImage: Sized {
+    fn from_dynamic_image(image: &DynamicImage) -> Result<Self, UnsupportedColorType>;
+}
+
+impl FromDynamicImage for OwnedImage {
+    fn from_dynamic_image(image: &DynamicImage) -> Result<Self, UnsupportedColorType> {
+        let from_16_bit = |value: u32| (value * Pixel::MAX as u32 + u16::MAX as u32 / 2) / u16::MAX as u32;
+        let from_float = |value: f32| (value.clamp(0.0, 1.0) * Pixel::MAX as f32).round() as u32;
+
+        let pixels: Vec<Pixel> = match image {
+            DynamicImage::ImageLuma8(image) => image.as_raw().clone(),
+            DynamicImage::ImageLumaA8(image) => image.pixels().map(|pixel| pixel.0[0]).collect(),
+            DynamicImage::ImageLuma16(image) => image.pixels()
+                .map(|pixel| from_16_bit(pixel.0[0] as u32) as Pixel)
+                .collect(),
+            DynamicImage::ImageLumaA16(image) => image.pixels()
+                .map(|pixel| from_16_bit(pixel.0[0] as u32) as Pixel)
+                .collect(),
+            DynamicImage::ImageRgb8(image) => image.pixels()
+                .map(|pixel| ntsc_grayscale(pixel.0[0] as u32, pixel.0[1] as u32, pixel.0[2] as u32) as Pixel)
+                .collect(),
+            DynamicImage::ImageRgba8(image) => image.pixels()
+                .map(|pixel| ntsc_grayscale(pixel.0[0] as u32, pixel.0[1] as u32, pixel.0[2] as u32) as Pixel)
+                .collect(),
+            DynamicImage::ImageRgb16(image) => image.pixels()
+                .map(|pixel| from_16_bit(ntsc_grayscale(pixel.0[0] as u32, pixel.0[1] as u32, pixel.0[2] as u32)) as Pixel)
+                .collect(),
+            DynamicImage::ImageRgba16(image) => image.pixels()
+                .map(|pixel| from_16_bit(ntsc_grayscale(pixel.0[0] as u32, pixel.0[1] as u32, pixel.0[2] as u32)) as Pixel)
+                .collect(),
+            DynamicImage::ImageRgb32F(image) => image.pixels()
+                .map(|pixel| ntsc_grayscale(from_float(pixel.0[0]), from_float(pixel.0[1]), from_float(pixel.0[2])) as Pixel)
+                .collect(),
+            DynamicImage::ImageRgba32F(image) => image.pixels()
+                .map(|pixel| ntsc_grayscale(from_float(pixel.0[0]), from_float(pixel.0[1]), from_float(pixel.0[2])) as Pixel)
+                .collect(),
+            other => return Err(UnsupportedColorType(other.color())),
+        };
+
+        let size = Size::new(image.width(), image.height());
+        Ok(OwnedImage::from_pixels(size, pixels).expect("One gray value per pixel"))
+    }
+}
+
+impl TryFrom<DynamicImage> for OwnedImage {
+    type Error = UnsupportedColorType;
+
+    fn try_from(image: DynamicImage) -> Result<Self, Self::Error> {
+        OwnedImage::from_dynamic_image(&image)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SaveError {
+    #[error("Could not save the image: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("The image format can not be inferred from the extension of {0:?}")]
+    UnknownExtension(PathBuf),
+}
+
+/// The format of an image saved to `path`, as inferred from its extension.
+pub fn format_from_extension(path: &Path) -> Result<ImageFormat, SaveError> {
+    ImageFormat::from_path(path).map_err(|_| SaveError::UnknownExtension(path.to_path_buf()))
+}
+
 pub trait SafeableImage {
-    fn save_image(&self, path: &Path, format: ImageFormat);
+    fn save_image(&self, path: &Path, format: ImageFormat) -> Result<(), SaveError>;
 
-    fn save_image_as_png<T: AsRef<Path>>(&self, path: T) {
+    /// Saves the image as JPEG with a `quality` between 1 and 100.
+    fn save_image_as_jpeg<T: AsRef<Path>>(&self, path: T, quality: u8) -> Result<(), SaveError>;
+
+    fn save_image_as_png<T: AsRef<Path>>(&self, path: T) -> Result<(), SaveError> {
         self.save_image(path.as_ref(), ImageFormat::Png)
     }
+
+    fn save_image_as_bmp<T: AsRef<Path>>(&self, path: T) -> Result<(), SaveError> {
+        self.save_image(path.as_ref(), ImageFormat::Bmp)
+    }
+
+    /// Saves the image in the [format of the extension](format_from_extension) of `path`.
+    fn save_image_auto<T: AsRef<Path>>(&self, path: T) -> Result<(), SaveError> {
+        let path = path.as_ref();
+        self.save_image(path, format_from_extension(path)?)
+    }
 }
 
 impl<T> SafeableImage for T
 where
     T: AsDynamicImage,
 {
-    fn save_image(&self, path: &Path, format: ImageFormat) {
-        let image = self.as_dynamic_image();
-        image
-            .save_with_format(path, format)
-            .unwrap_or_else(|_| panic!("Could not save image to {:?}", path));
+    fn save_image(&self, path: &Path, format: ImageFormat) -> Result<(), SaveError> {
+        Ok(self.as_dynamic_image().save_with_format(path, format)?)
+    }
+
+    fn save_image_as_jpeg<P: AsRef<Path>>(&self, path: P, quality: u8) -> Result<(), SaveError> {
+        let writer = BufWriter::new(File::create(path)?);
+        Ok(self.as_dynamic_image().write_with_encoder(JpegEncoder::new_with_quality(writer, quality))?)
     }
 }
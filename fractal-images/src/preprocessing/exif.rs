@@ -0,0 +1,209 @@
+//! Reads the orientation of photos from their [Exif](https://www.cipa.jp/std/documents/e/DC-008-2012_E.pdf)
+//! metadata, which is stored in an APP1 segment of JPEG files and in the `eXIf` chunk of PNG files.
+
+use image::DynamicImage;
+
+const JPEG_MAGIC: [u8; 2] = [0xFF, 0xD8];
+const PNG_MAGIC: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// The JPEG segment markers of Exif metadata, the start of the scan and the end of the image.
+const APP1: u8 = 0xE1;
+const START_OF_SCAN: u8 = 0xDA;
+const END_OF_IMAGE: u8 = 0xD9;
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+const ORIENTATION_TAG: u16 = 0x0112;
+const SHORT: u16 = 3;
+
+/// How an image needs to be transformed to be displayed upright, as given by the Exif
+/// orientation tag. Rotations are clockwise.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Orientation {
+    #[default]
+    Normal,
+    FlipHorizontal,
+    Rotate180,
+    FlipVertical,
+    Rotate90FlipHorizontal,
+    Rotate90,
+    Rotate270FlipHorizontal,
+    Rotate270,
+}
+
+impl Orientation {
+    /// The orientation of the Exif `value`, which is between 1 and 8.
+    pub fn from_exif(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(Orientation::Normal),
+            2 => Some(Orientation::FlipHorizontal),
+            3 => Some(Orientation::Rotate180),
+            4 => Some(Orientation::FlipVertical),
+            5 => Some(Orientation::Rotate90FlipHorizontal),
+            6 => Some(Orientation::Rotate90),
+            7 => Some(Orientation::Rotate270FlipHorizontal),
+            8 => Some(Orientation::Rotate270),
+            _ => None,
+        }
+    }
+
+    /// The orientation stored in the Exif metadata of an encoded JPEG or PNG image, if any.
+    pub fn of_encoded(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&JPEG_MAGIC) {
+            jpeg_exif(bytes).and_then(tiff_orientation)
+        } else if bytes.starts_with(&PNG_MAGIC) {
+            png_exif(bytes).and_then(tiff_orientation)
+        } else {
+            None
+        }
+    }
+
+    /// Transforms `image` such that it is upright.
+    pub fn apply(&self, image: DynamicImage) -> DynamicImage {
+        match self {
+            Orientation::Normal => image,
+            Orientation::FlipHorizontal => image.fliph(),
+            Orientation::Rotate180 => image.rotate180(),
+            Orientation::FlipVertical => image.flipv(),
+            Orientation::Rotate90FlipHorizontal => image.rotate90().fliph(),
+            Orientation::Rotate90 => image.rotate90(),
+            Orientation::Rotate270FlipHorizontal => image.rotate270().fliph(),
+            Orientation::Rotate270 => image.rotate270(),
+        }
+    }
+}
+
+/// The TIFF structure within the APP1 segment of a JPEG file.
+fn jpeg_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut position = JPEG_MAGIC.len();
+    loop {
+        let marker = *bytes.get(position + 1)?;
+        if bytes[position] != 0xFF || marker == START_OF_SCAN || marker == END_OF_IMAGE {
+            return None;
+        }
+        let length = u16::from_be_bytes(bytes.get(position + 2..position + 4)?.try_into().unwrap()) as usize;
+        let segment = bytes.get(position + 4..position + 2 + length)?;
+        if marker == APP1 && segment.starts_with(EXIF_HEADER) {
+            return Some(&segment[EXIF_HEADER.len()..]);
+        }
+        position += 2 + length;
+    }
+}
+
+/// The TIFF structure within the `eXIf` chunk of a PNG file.
+fn png_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut position = PNG_MAGIC.len();
+    loop {
+        let length = u32::from_be_bytes(bytes.get(position..position + 4)?.try_into().unwrap()) as usize;
+        let chunk_type = bytes.get(position + 4..position + 8)?;
+        let data = bytes.get(position + 8..position + 8 + length)?;
+        match chunk_type {
+            b"eXIf" => return Some(data),
+            b"IEND" => return None,
+            _ => position += 12 + length,
+        }
+    }
+}
+
+/// The orientation tag of the first image file directory of a TIFF structure.
+fn tiff_orientation(tiff: &[u8]) -> Option<Orientation> {
+    let little_endian = match tiff.get(..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| {
+        let bytes = tiff.get(offset..offset + 2)?.try_into().unwrap();
+        Some(if little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    };
+    let u32_at = |offset: usize| {
+        let bytes = tiff.get(offset..offset + 4)?.try_into().unwrap();
+        Some(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    };
+
+    let directory = u32_at(4)? as usize;
+    let entries = u16_at(directory)? as usize;
+    (0..entries)
+        .map(|index| directory + 2 + 12 * index)
+        .find(|&entry| u16_at(entry) == Some(ORIENTATION_TAG))
+        .filter(|&entry| u16_at(entry + 2) == Some(SHORT))
+        .and_then(|entry| Orientation::from_exif(u16_at(entry + 8)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A TIFF structure with a single orientation tag.
+    fn tiff(little_endian: bool, orientation: u16) -> Vec<u8> {
+        let u16_bytes = |value: u16| if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+        let u32_bytes = |value: u32| if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+
+        let mut tiff = if little_endian { b"II".to_vec() } else { b"MM".to_vec() };
+        tiff.extend(u16_bytes(42));
+        tiff.extend(u32_bytes(8));
+        tiff.extend(u16_bytes(1));
+        tiff.extend(u16_bytes(ORIENTATION_TAG));
+        tiff.extend(u16_bytes(SHORT));
+        tiff.extend(u32_bytes(1));
+        tiff.extend(u16_bytes(orientation));
+        tiff.extend([0, 0]);
+        tiff.extend(u32_bytes(0));
+        tiff
+    }
+
+    fn jpeg(tiff: &[u8]) -> Vec<u8> {
+        let mut jpeg = JPEG_MAGIC.to_vec();
+        // A comment segment before the Exif segment
+        jpeg.extend([0xFF, 0xFE, 0x00, 0x04, b'h', b'i']);
+        jpeg.extend([0xFF, APP1]);
+        jpeg.extend(((2 + EXIF_HEADER.len() + tiff.len()) as u16).to_be_bytes());
+        jpeg.extend(EXIF_HEADER);
+        jpeg.extend(tiff);
+        jpeg.extend([0xFF, START_OF_SCAN]);
+        jpeg
+    }
+
+    fn png(tiff: &[u8]) -> Vec<u8> {
+        let mut png = PNG_MAGIC.to_vec();
+        for (chunk_type, data) in [(b"IHDR", &[0; 13][..]), (b"eXIf", tiff), (b"IEND", &[])] {
+            png.extend((data.len() as u32).to_be_bytes());
+            png.extend(chunk_type);
+            png.extend(data);
+            // The CRC is not checked
+            png.extend([0; 4]);
+        }
+        png
+    }
+
+    #[test]
+    fn orientation_is_read_from_jpeg() {
+        for little_endian in [true, false] {
+            let orientation = Orientation::of_encoded(&jpeg(&tiff(little_endian, 6)));
+            assert_eq!(orientation, Some(Orientation::Rotate90));
+        }
+    }
+
+    #[test]
+    fn orientation_is_read_from_png() {
+        assert_eq!(Orientation::of_encoded(&png(&tiff(false, 3))), Some(Orientation::Rotate180));
+    }
+
+    #[test]
+    fn missing_or_invalid_orientation_is_none() {
+        assert_eq!(Orientation::of_encoded(&jpeg(&tiff(true, 9))), None);
+        assert_eq!(Orientation::of_encoded(&jpeg(b"XX")), None);
+        assert_eq!(Orientation::of_encoded(&[0xFF, 0xD8, 0xFF, START_OF_SCAN]), None);
+        assert_eq!(Orientation::of_encoded(&jpeg(&tiff(true, 6))[..20]), None);
+        assert_eq!(Orientation::of_encoded(b"not an image"), None);
+    }
+
+    #[test]
+    fn rotation_by_90_is_clockwise() {
+        let image = DynamicImage::ImageLuma8(image::GrayImage::from_raw(2, 1, vec![1, 2]).unwrap());
+
+        let rotated = Orientation::Rotate90.apply(image).to_luma8();
+
+        assert_eq!(rotated.dimensions(), (1, 2));
+        assert_eq!(rotated.as_raw(), &[1, 2]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Resizing of images to arbitrary sizes.
+
+use thiserror::Error;
+
+use crate::image::{Image, OwnedImage, Pixel, Size};
+
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResizeError {
+    #[error("Can not resize an image of size {from} to the larger size {to}, only downscaling is supported")]
+    Upscale { from: Size, to: Size },
+
+    #[error("Can not resize an image of size {from} to the empty size {to}")]
+    Empty { from: Size, to: Size },
+}
+
+/// Downscales `image` to `target` by averaging the area each target pixel covers, which also
+/// works for ratios which are no integers, e.g. from 7x7 to 3x3 pixels. Source pixels which are
+/// partially covered contribute with the covered fraction of their area. Averages are rounded
+/// half up, like [Downscaled2x2](crate::image::Downscaled2x2) does.
+///
+/// Returns an error if `target` is larger than the image in any dimension, or empty.
+pub fn box_resize(image: &impl Image, target: Size) -> Result<OwnedImage, ResizeError> {
+    let from = image.get_size();
+    if target.get_width() > from.get_width() || target.get_height() > from.get_height() {
+        return Err(ResizeError::Upscale { from, to: target });
+    }
+    if target.area() == 0 {
+        return Err(ResizeError::Empty { from, to: target });
+    }
+
+    let columns = weights(from.get_width(), target.get_width());
+    let rows = weights(from.get_height(), target.get_height());
+    // The weights along an axis sum up to the length of the source axis
+    let total = from.get_width() as u64 * from.get_height() as u64;
+
+    let mut pixels = Vec::with_capacity(target.area() as usize);
+    for row in &rows {
+        for column in &columns {
+            let mut sum = 0u64;
+            for &(y, weight_y) in row {
+                for &(x, weight_x) in column {
+                    sum += weight_x * weight_y * image.pixel(x, y) as u64;
+                }
+            }
+            pixels.push(((sum + total / 2) / total) as Pixel);
+        }
+    }
+
+    Ok(OwnedImage::from_pixels(target, pixels).expect("One pixel per target pixel is computed"))
+}
+
+/// For each of the `target` pixels along an axis of `source` pixels, the source pixels it
+/// covers together with the covered area. Areas are measured in units of `1 / target` pixels,
+/// such that they are integers.
+fn weights(source: u32, target: u32) -> Vec<Vec<(u32, u64)>> {
+    let (source, target) = (source as u64, target as u64);
+    (0..target)
+        .map(|t| {
+            let (start, end) = (t * source, (t + 1) * source);
+            (start / target..end.div_ceil(target))
+                .map(|s| {
+                    let covered = end.min((s + 1) * target) - start.max(s * target);
+                    (s as u32, covered)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{IntoDownscaled, Square};
+
+    use super::*;
+
+    #[test]
+    fn arbitrary_ratios_average_the_covered_area() {
+        // Each target pixel covers 7/3 source pixels along each axis, see the weights below
+        let image = OwnedImage::from_pixels(Size::squared(7), (0..49).collect()).unwrap();
+
+        let resized = box_resize(&image, Size::squared(3)).unwrap();
+
+        // The averages of columns are 5/7, 3 and 37/7, the ones of rows seven times these
+        assert_eq!(resized.pixels().collect::<Vec<_>>(), vec![
+            6, 8, 10,
+            22, 24, 26,
+            38, 40, 42,
+        ]);
+    }
+
+    #[test]
+    fn weights_cover_each_source_pixel_once() {
+        let weights = weights(7, 3);
+        assert_eq!(weights, vec![
+            vec![(0, 3), (1, 3), (2, 1)],
+            vec![(2, 2), (3, 3), (4, 2)],
+            vec![(4, 1), (5, 3), (6, 3)],
+        ]);
+    }
+
+    #[test]
+    fn integer_ratios_equal_downscaling() {
+        let image = Square::new(OwnedImage::random_with_seed(Size::squared(16), 3)).unwrap();
+
+        let resized = box_resize(&image, Size::squared(4)).unwrap();
+
+        assert!(resized.pixels().eq(image.downscale_by(4).pixels()));
+    }
+
+    #[test]
+    fn resizing_to_the_same_size_keeps_the_image() {
+        let image = OwnedImage::random_with_seed(Size::new(5, 3), 1);
+        assert_eq!(box_resize(&image, Size::new(5, 3)), Ok(image));
+    }
+
+    #[test]
+    fn rectangular_images_are_resized_per_axis() {
+        let image = OwnedImage::from_pixels(Size::new(4, 2), vec![0, 10, 20, 30, 40, 50, 60, 70]).unwrap();
+        let resized = box_resize(&image, Size::new(2, 1)).unwrap();
+        assert_eq!(resized.pixels().collect::<Vec<_>>(), vec![25, 45]);
+    }
+
+    #[test]
+    fn upscaling_is_an_error() {
+        let image = OwnedImage::random(Size::squared(4));
+        assert_eq!(
+            box_resize(&image, Size::new(5, 2)),
+            Err(ResizeError::Upscale { from: Size::squared(4), to: Size::new(5, 2) })
+        );
+        assert!(matches!(box_resize(&image, Size::new(0, 2)), Err(ResizeError::Empty { .. })));
+    }
+}
@@ -0,0 +1,109 @@
+//! Visualizations for debugging compressions.
+
+use crate::image::{Image, IntoOwnedImage, MutableImage, OwnedImage, Pixel};
+use crate::model::Compressed;
+
+/// Copies `image` and draws the boundaries of the range blocks of `compressed` onto it in white.
+/// See [draw_partition_with].
+pub fn draw_partition(image: &impl Image, compressed: &Compressed) -> OwnedImage {
+    draw_partition_with(image, compressed, Pixel::MAX)
+}
+
+/// Copies `image` and draws the boundaries of the range blocks of `compressed` onto it with
+/// the gray `level`.
+///
+/// Each block is outlined by its top and left edge, and by its bottom and right edge if it lies
+/// at the border of the image, such that neighbouring blocks share a boundary of one pixel.
+pub fn draw_partition_with(image: &impl Image, compressed: &Compressed, level: Pixel) -> OwnedImage {
+    let mut overlay = image.to_owned_image();
+    let (width, height) = (overlay.get_width(), overlay.get_height());
+
+    for transformation in &compressed.transformations {
+        let range = transformation.range;
+        let (start_x, start_y) = (range.origin.x, range.origin.y);
+        let end_x = (start_x + range.block_size).min(width);
+        let end_y = (start_y + range.block_size).min(height);
+        if start_x >= end_x || start_y >= end_y {
+            continue;
+        }
+
+        for x in start_x..end_x {
+            overlay.set_pixel(x, start_y, level);
+            if end_y == height {
+                overlay.set_pixel(x, end_y - 1, level);
+            }
+        }
+        for y in start_y..end_y {
+            overlay.set_pixel(start_x, y, level);
+            if end_x == width {
+                overlay.set_pixel(end_x - 1, y, level);
+            }
+        }
+    }
+
+    overlay
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords;
+    use crate::image::{Coords, Size};
+    use crate::model::{Block, Rotation, Transformation};
+
+    use super::*;
+
+    fn transformation(block_size: u32, x: u32, y: u32) -> Transformation {
+        Transformation {
+            range: Block { block_size, origin: coords!(x=x, y=y) },
+            domain: Block { block_size: 2 * block_size, origin: coords!(x=0, y=0) },
+            rotation: Rotation::By0,
+            brightness: 0,
+            saturation: 0.0,
+        }
+    }
+
+    /// An 8x8 image split into a 4x4 block on the left top, three 4x4 blocks on the right and
+    /// bottom, where the one on the bottom right is split into four 2x2 blocks.
+    fn partition() -> Compressed {
+        Compressed {
+            size: Size::squared(8),
+            transformations: vec![
+                transformation(4, 0, 0),
+                transformation(4, 4, 0),
+                transformation(4, 0, 4),
+                transformation(2, 4, 4),
+                transformation(2, 6, 4),
+                transformation(2, 4, 6),
+                transformation(2, 6, 6),
+            ],
+        }
+    }
+
+    #[test]
+    fn boundaries_are_drawn_and_interiors_untouched() {
+        let image = OwnedImage::filled(Size::squared(8), 100);
+
+        let overlay = draw_partition_with(&image, &partition(), 7);
+
+        let on_boundary = |x: u32, y: u32| {
+            x == 0 || y == 0 || x == 7 || y == 7 || x == 4 || y == 4
+                || (x == 6 && y >= 4) || (y == 6 && x >= 4)
+        };
+        for y in 0..8 {
+            for x in 0..8 {
+                let expected = if on_boundary(x, y) { 7 } else { 100 };
+                assert_eq!(overlay.pixel(x, y), expected, "Pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn boundaries_are_white_by_default() {
+        let image = OwnedImage::filled(Size::squared(8), 0);
+
+        let overlay = draw_partition(&image, &partition());
+
+        assert_eq!(overlay.pixel(4, 2), Pixel::MAX);
+        assert_eq!(overlay.pixel(2, 2), 0);
+    }
+}
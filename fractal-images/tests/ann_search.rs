@@ -0,0 +1,38 @@
+mod common;
+
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::{compress, decompress, metrics};
+use common::random_noise;
+
+fn compress_and_measure(ann_search: Option<usize>) -> (f64, u64) {
+    let image = random_noise(64);
+    let compressor = compress::quadtree::Compressor::new(image.clone())
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0));
+    let compressor = match ann_search {
+        Some(k) => compressor.with_ann_search(k),
+        None => compressor,
+    };
+    let (compressed, stats) = compressor.compress_with_stats().unwrap();
+
+    let decompressed = decompress::decompress(compressed, decompress::Options::default()).unwrap().image;
+    (metrics::psnr(&image, &decompressed).unwrap(), stats.mappings_computed)
+}
+
+#[test]
+fn ann_search_computes_far_fewer_mappings() {
+    let (_, mappings_exact) = compress_and_measure(None);
+    let (_, mappings_ann) = compress_and_measure(Some(16));
+
+    assert!(
+        4 * mappings_ann < mappings_exact,
+        "Expected far fewer than {} mappings, were {}", mappings_exact, mappings_ann
+    );
+}
+
+#[test]
+fn ann_search_loses_little_quality() {
+    let (psnr_exact, _) = compress_and_measure(None);
+    let (psnr_ann, _) = compress_and_measure(Some(16));
+
+    assert!(psnr_ann > psnr_exact - 1.0, "PSNR dropped from {} to {}", psnr_exact, psnr_ann);
+}
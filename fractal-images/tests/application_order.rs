@@ -0,0 +1,73 @@
+#![cfg(feature = "generators")]
+
+mod common;
+
+use fractal_image::coords;
+use fractal_image::decompress::{decompress, ApplicationOrder, InitialImage, Options};
+use fractal_image::image::{Coords, OwnedImage, Size};
+use fractal_image::model::{Block, Compressed, Rotation, Transformation};
+use fractal_image::{compress, metrics};
+use common::soft_circle;
+
+fn psnr_with(application_order: ApplicationOrder, iterations: u8) -> f64 {
+    let compressed = compress::quadtree::Compressor::new(soft_circle()).compress().unwrap();
+    let options = Options { iterations, application_order, ..Default::default() };
+
+    let decompressed = decompress(compressed, options).unwrap().image;
+    metrics::psnr(&soft_circle(), &decompressed).unwrap()
+}
+
+#[test]
+fn largest_block_first_improves_early_iterations() {
+    let file_order = psnr_with(ApplicationOrder::FileOrder, 2);
+    let largest_block_first = psnr_with(ApplicationOrder::LargestBlockFirst, 2);
+
+    assert!(
+        largest_block_first > file_order,
+        "Expected PSNR of largest block first ({}) > file order ({}) after 2 iterations", largest_block_first, file_order
+    );
+}
+
+#[test]
+fn application_orders_converge_to_similar_images() {
+    let file_order = psnr_with(ApplicationOrder::FileOrder, 20);
+    let largest_block_first = psnr_with(ApplicationOrder::LargestBlockFirst, 20);
+
+    assert!(
+        (largest_block_first - file_order).abs() < 0.5,
+        "PSNR of largest block first ({}) and file order ({}) differ", largest_block_first, file_order
+    );
+}
+
+#[test]
+fn a_single_transformation_is_applied_equally_in_both_orders() {
+    for rotation in [Rotation::By0, Rotation::By90, Rotation::By180, Rotation::By270] {
+        for domain_size in [8, 16] {
+            let compressed = Compressed {
+                size: Size::squared(32),
+                transformations: vec![Transformation {
+                    range: Block { block_size: 4, origin: coords!(x=0, y=0) },
+                    domain: Block { block_size: domain_size, origin: coords!(x=12, y=8) },
+                    rotation,
+                    brightness: 20,
+                    saturation: 0.7,
+                }],
+            };
+            let decompressed_with = |application_order| {
+                let options = Options {
+                    iterations: 1,
+                    initial_image: InitialImage::Custom(OwnedImage::random(Size::squared(32))),
+                    application_order,
+                    ..Default::default()
+                };
+                decompress(compressed.clone(), options).unwrap().image
+            };
+
+            assert_eq!(
+                decompressed_with(ApplicationOrder::LargestBlockFirst),
+                decompressed_with(ApplicationOrder::FileOrder),
+                "Images differ for {:?} from a {}x{} domain block", rotation, domain_size, domain_size
+            );
+        }
+    }
+}
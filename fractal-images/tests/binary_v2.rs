@@ -0,0 +1,75 @@
+#![cfg(feature = "persist-as-binary-v2")]
+
+mod common;
+
+use common::temp_path;
+use fractal_image::compress;
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::image::{OwnedImage, PowerOfTwo, Size, Square};
+use fractal_image::model::{Compressed, Transformation};
+
+fn compressed() -> Compressed {
+    let image = OwnedImage::random_with_seed(Size::squared(64), 11);
+    compress::quadtree::Compressor::new(PowerOfTwo::new(Square::new(image).unwrap()).unwrap())
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .compress()
+        .unwrap()
+}
+
+#[test]
+fn binary_v2_is_smaller_than_binary_v1() {
+    let compressed = compressed();
+    let (v1_path, v2_path) = (temp_path("size.v1.qfic"), temp_path("size.v2.qfic"));
+
+    let v1_size = compressed.persist_as_binary_v1(&v1_path).unwrap();
+    let v2_size = compressed.persist_as_binary_v2(&v2_path).unwrap();
+    std::fs::remove_file(&v1_path).unwrap();
+    std::fs::remove_file(&v2_path).unwrap();
+
+    let blocks = compressed.transformations.len() as u64;
+    assert!(blocks > 100, "Only {} blocks", blocks);
+    assert!(
+        v2_size + 2 * blocks < v1_size,
+        "v2 needs {} bytes and v1 {} bytes for {} blocks", v2_size, v1_size, blocks
+    );
+}
+
+#[test]
+fn binary_v2_roundtrip_is_exact_within_quantization() {
+    let compressed = compressed();
+    let path = temp_path("quantization.v2.qfic");
+
+    compressed.persist_as_binary_v2(&path).unwrap();
+    let read = Compressed::read_from_path(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.size, compressed.size);
+    let mut expected = compressed.transformations;
+    let mut actual = read.transformations;
+    let key = |t: &Transformation| (t.range.block_size, t.range.origin.y, t.range.origin.x);
+    expected.sort_by_key(key);
+    actual.sort_by_key(key);
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(&expected) {
+        assert_eq!(
+            (actual.range, actual.domain, actual.rotation, actual.brightness),
+            (expected.range, expected.domain, expected.rotation, expected.brightness)
+        );
+        assert!((actual.saturation - expected.saturation).abs() <= 1.0 / 32768.0);
+    }
+}
+
+#[test]
+#[cfg(feature = "generators")]
+fn varint_origins_shrink_circle_compressions() {
+    use fractal_image::image::gen::GenCircle;
+    use fractal_image::persistence::{binary_v1, binary_v2};
+
+    let compressed = compress::quadtree::Compressor::new(PowerOfTwo::new(GenCircle::new(256, 128.0)).unwrap())
+        .compress()
+        .unwrap();
+
+    let v1_size = binary_v1::serialize(&compressed).unwrap().len();
+    let v2_size = binary_v2::serialize(&compressed).unwrap().len();
+    assert!(v2_size < v1_size, "v2 needs {} bytes, but v1 only {} bytes", v2_size, v1_size);
+}
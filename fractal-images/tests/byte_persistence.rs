@@ -0,0 +1,65 @@
+mod common;
+
+#[cfg(any(feature = "persist-as-json", feature = "persist-as-cbor", feature = "persist-as-binary-v1"))]
+use fractal_image::model::Compressed;
+#[cfg(any(feature = "persist-as-json", feature = "persist-as-cbor", feature = "persist-as-binary-v1"))]
+use fractal_image::persistence::PersistFormat;
+#[cfg(feature = "persist-as-binary-v2")]
+use fractal_image::persistence::PersistenceError;
+#[cfg(any(feature = "persist-as-json", feature = "persist-as-cbor", feature = "persist-as-binary-v1"))]
+use common::compressed;
+#[cfg(feature = "persist-as-binary-v2")]
+use common::temp_path;
+
+#[cfg(any(feature = "persist-as-json", feature = "persist-as-cbor", feature = "persist-as-binary-v1"))]
+fn assert_roundtrip(format: PersistFormat) {
+    let bytes = compressed().to_bytes(format).unwrap();
+    let read = Compressed::from_bytes(format, &bytes).unwrap();
+    assert_eq!(read.size, compressed().size);
+    assert_eq!(read.transformations, compressed().transformations);
+}
+
+#[cfg(feature = "persist-as-json")]
+#[test]
+fn json_roundtrip_over_bytes() {
+    assert_roundtrip(PersistFormat::Json);
+}
+
+#[cfg(feature = "persist-as-cbor")]
+#[test]
+fn cbor_roundtrip_over_bytes() {
+    assert_roundtrip(PersistFormat::Cbor);
+}
+
+#[cfg(feature = "persist-as-binary-v1")]
+#[test]
+fn binary_v1_roundtrip_over_bytes() {
+    assert_roundtrip(PersistFormat::BinaryV1);
+}
+
+#[cfg(feature = "persist-as-binary-v2")]
+#[test]
+fn binary_v2_roundtrip_over_bytes() {
+    assert_roundtrip(PersistFormat::BinaryV2);
+}
+
+#[cfg(feature = "persist-as-binary-v2")]
+#[test]
+fn bytes_match_the_persisted_file() {
+    let path = temp_path("byte-persistence.qfic");
+    let written = compressed().persist_as_binary_v2(&path).unwrap();
+    let contents = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let bytes = compressed().to_bytes(PersistFormat::BinaryV2).unwrap();
+    assert_eq!(bytes, contents);
+    assert_eq!(written, bytes.len() as u64);
+}
+
+#[cfg(feature = "persist-as-binary-v2")]
+#[test]
+fn bytes_are_read_in_the_given_format_only() {
+    let bytes = compressed().to_bytes(PersistFormat::BinaryV2).unwrap();
+    let error = Compressed::from_bytes(PersistFormat::BinaryV1, &bytes).unwrap_err();
+    assert!(matches!(error, PersistenceError::BinaryV1DeserializationError(_)));
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "persist-as-cbor")]
+
+mod common;
+
+use fractal_image::model::Compressed;
+use common::{compressed, temp_path};
+
+#[test]
+fn cbor_roundtrip() {
+    let path = temp_path("roundtrip.cbor");
+    compressed().persist_as_cbor(&path).unwrap();
+    let read = Compressed::read_from_cbor(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let read = read.unwrap();
+    assert_eq!(read.size, compressed().size);
+    assert_eq!(read.transformations, compressed().transformations);
+}
+
+#[test]
+fn cbor_reports_the_file_size() {
+    let path = temp_path("size.cbor");
+    let written = compressed().persist_as_cbor(&path).unwrap();
+    let file_size = std::fs::metadata(&path).unwrap().len();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(written, file_size);
+}
+
+#[cfg(feature = "persist-as-json")]
+#[test]
+fn json_and_cbor_decode_to_equal_compressions() {
+    let json_path = temp_path("cross-check.json");
+    let cbor_path = temp_path("cross-check.cbor");
+    compressed().persist_as_json(&json_path).unwrap();
+    compressed().persist_as_cbor(&cbor_path).unwrap();
+
+    let from_json = Compressed::read_from_json(&json_path);
+    let from_cbor = Compressed::read_from_cbor(&cbor_path);
+    std::fs::remove_file(&json_path).unwrap();
+    std::fs::remove_file(&cbor_path).unwrap();
+
+    let (from_json, from_cbor) = (from_json.unwrap(), from_cbor.unwrap());
+    assert_eq!(from_json.size, from_cbor.size);
+    assert_eq!(from_json.transformations, from_cbor.transformations);
+}
@@ -0,0 +1,152 @@
+use std::io::Cursor;
+
+use fractal_image::compress::color::{compress_rgb, Options};
+use fractal_image::decompress::{decompress_rgb, Options as DecompressionOptions};
+use fractal_image::image::{Image, IntoOwnedImage, OwnedImage, Size};
+use fractal_image::metrics;
+use fractal_image::preprocessing::ColorImage;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+
+fn gradient_32x32() -> RgbImage {
+    RgbImage::from_fn(32, 32, |x, y| Rgb([(8 * x) as u8, (8 * y) as u8, (4 * (x + y)) as u8]))
+}
+
+fn channel(image: &RgbImage, channel: usize) -> OwnedImage {
+    let pixels = image.pixels().map(|pixel| pixel.0[channel]).collect();
+    OwnedImage::from_pixels(Size::new(image.width(), image.height()), pixels).unwrap()
+}
+
+fn assert_roundtrip_psnr(options: Options) {
+    let image = gradient_32x32();
+
+    let compressed = compress_rgb(&image, options).unwrap();
+    let decompressed = decompress_rgb(compressed, DecompressionOptions::default()).unwrap().to_rgb8();
+
+    for c in 0..3 {
+        let psnr = metrics::psnr(&channel(&image, c), &channel(&decompressed, c)).unwrap();
+        assert!(psnr > 25.0, "Expected a PSNR above 25 dB in channel {}, was {} dB", c, psnr);
+    }
+}
+
+#[test]
+fn color_roundtrip_with_chroma_subsampling() {
+    assert_roundtrip_psnr(Options::default());
+}
+
+#[test]
+fn color_roundtrip_without_chroma_subsampling() {
+    assert_roundtrip_psnr(Options { chroma_subsampling: false, ..Default::default() });
+}
+
+#[test]
+fn subsampled_chroma_planes_have_half_the_size() {
+    let compressed = compress_rgb(&gradient_32x32(), Options::default()).unwrap();
+
+    assert!(compressed.chroma_subsampled);
+    assert_eq!(compressed.size(), Size::squared(32));
+    assert_eq!(compressed.blue_chroma.size, Size::squared(16));
+    assert_eq!(compressed.red_chroma.size, Size::squared(16));
+}
+
+#[test]
+#[cfg(feature = "persist-as-binary-v1")]
+fn color_compressed_is_persisted() {
+    let compressed = compress_rgb(&gradient_32x32(), Options::default()).unwrap();
+    let path = std::env::temp_dir().join("fractal-image-color-roundtrip.qfic");
+
+    compressed.persist_as_binary_v1(&path).unwrap();
+    let read = fractal_image::model::ColorCompressed::read_from_binary_v1(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.chroma_subsampled, compressed.chroma_subsampled);
+    assert_eq!(read.luma.transformations, compressed.luma.transformations);
+    assert_eq!(read.blue_chroma.transformations, compressed.blue_chroma.transformations);
+}
+
+/// A smooth sky over a textured ground, which resembles a photo more than a gradient does.
+fn landscape(width: u32, height: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| {
+        if y < height / 2 {
+            Rgb([(100 + x) as u8, (150 + y) as u8, 230])
+        } else {
+            let texture = ((x * 7 + y * 13) % 11) as u8;
+            Rgb([60 + texture, 120 + 2 * texture, 40])
+        }
+    })
+}
+
+fn png(image: &RgbImage) -> Vec<u8> {
+    let mut bytes = Cursor::new(vec![]);
+    DynamicImage::ImageRgb8(image.clone()).write_to(&mut bytes, ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+fn assert_planes_psnr(expected: &ColorImage, actual: &ColorImage, min_psnr: f64) {
+    let planes = [
+        (&expected.luma, &actual.luma),
+        (&expected.blue_chroma, &actual.blue_chroma),
+        (&expected.red_chroma, &actual.red_chroma),
+    ];
+    for (expected, actual) in planes {
+        let psnr = metrics::psnr(&expected.to_owned_image(), &actual.to_owned_image()).unwrap();
+        assert!(psnr > min_psnr, "Expected a PSNR above {} dB, was {} dB", min_psnr, psnr);
+    }
+}
+
+#[test]
+fn color_images_are_loaded_as_square_planes() {
+    let image = ColorImage::read_from_bytes(&png(&landscape(40, 24))).unwrap();
+
+    assert_eq!(image.get_size(), Size::squared(16));
+    assert!(!image.is_chroma_subsampled());
+    for plane in [&image.luma, &image.blue_chroma, &image.red_chroma] {
+        assert_eq!(plane.get_size(), Size::squared(16));
+    }
+}
+
+#[test]
+fn subsampled_chroma_planes_of_loaded_images_have_half_the_size() {
+    let image = ColorImage::read_from_bytes(&png(&landscape(32, 32))).unwrap().with_chroma_subsampling();
+
+    assert!(image.is_chroma_subsampled());
+    assert_eq!(image.luma.get_size(), Size::squared(32));
+    assert_eq!(image.blue_chroma.get_size(), Size::squared(16));
+    assert_eq!(image.red_chroma.get_size(), Size::squared(16));
+    assert_eq!(image.to_rgb().dimensions(), (32, 32));
+}
+
+#[test]
+fn color_images_are_recombined_to_rgb() {
+    let original = landscape(32, 32);
+
+    let recombined = ColorImage::read_from_bytes(&png(&original)).unwrap().to_rgb();
+
+    for c in 0..3 {
+        let psnr = metrics::psnr(&channel(&original, c), &channel(&recombined, c)).unwrap();
+        assert!(psnr > 40.0, "Expected a PSNR above 40 dB in channel {}, was {} dB", c, psnr);
+    }
+}
+
+#[test]
+fn color_images_are_saved_as_png() {
+    let image = ColorImage::read_from_bytes(&png(&landscape(32, 32))).unwrap();
+    let path = std::env::temp_dir().join("fractal-image-color-image.png");
+
+    image.save_color_png(&path).unwrap();
+    let saved = ColorImage::read_from(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(saved.unwrap().to_rgb(), image.to_rgb());
+}
+
+#[test]
+fn color_images_roundtrip_through_the_color_pipeline() {
+    let image = ColorImage::read_from_bytes(&png(&landscape(64, 48))).unwrap();
+
+    let compressed = compress_rgb(&image.to_rgb(), Options::default()).unwrap();
+    let decompressed = decompress_rgb(compressed, DecompressionOptions::default()).unwrap();
+    let decompressed = ColorImage::from_rgb(&decompressed.to_rgb8()).unwrap();
+
+    assert_eq!(decompressed.get_size(), Size::squared(32));
+    assert_planes_psnr(&image, &decompressed, 25.0);
+}
@@ -0,0 +1,91 @@
+//! Fixtures shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use fractal_image::{compress, coords};
+#[cfg(feature = "generators")]
+use fractal_image::image::gen::GenCircle;
+use fractal_image::image::{Coords, FnImage, Image, IntoOwnedImage, OwnedImage, PowerOfTwo, Size, Square};
+use fractal_image::model::{Block, Compressed, Rotation, Transformation};
+
+/// Random noise, which is the same in every run
+pub fn random_noise(size: u32) -> PowerOfTwo<Square<OwnedImage>> {
+    square(OwnedImage::random(Size::squared(size)))
+}
+
+pub fn random_noise_with_seed(size: u32, seed: u64) -> PowerOfTwo<Square<OwnedImage>> {
+    square(OwnedImage::random_with_seed(Size::squared(size), seed))
+}
+
+/// A smooth image, where block boundaries are clearly visible
+pub fn waves_64x64() -> PowerOfTwo<Square<OwnedImage>> {
+    let waves = FnImage::new(Size::squared(64), |x, y| {
+        (128.0 + 60.0 * (x as f64 / 6.0).sin() + 60.0 * (y as f64 / 9.0).cos()) as u8
+    });
+    square(waves.to_owned_image())
+}
+
+/// The compression of 32x32 random noise with the given `seed`
+pub fn compressed_noise(seed: u64, overlap: u32) -> Compressed {
+    compress::quadtree::Compressor::new(random_noise_with_seed(32, seed))
+        .with_overlap(overlap)
+        .compress()
+        .unwrap()
+}
+
+/// The mean absolute difference of neighbouring pixels across the right and bottom edges of range blocks.
+pub fn boundary_discontinuity(compressed: &Compressed, image: &OwnedImage) -> f64 {
+    let mut differences = vec![];
+    for transformation in &compressed.transformations {
+        let range = transformation.range;
+        let (right, bottom) = (range.origin.x + range.block_size, range.origin.y + range.block_size);
+        for i in 0..range.block_size {
+            if right < image.get_width() {
+                let y = range.origin.y + i;
+                differences.push((image.pixel(right, y) as f64 - image.pixel(right - 1, y) as f64).abs());
+            }
+            if bottom < image.get_height() {
+                let x = range.origin.x + i;
+                differences.push((image.pixel(x, bottom) as f64 - image.pixel(x, bottom - 1) as f64).abs());
+            }
+        }
+    }
+    differences.iter().sum::<f64>() / differences.len() as f64
+}
+
+fn square(image: OwnedImage) -> PowerOfTwo<Square<OwnedImage>> {
+    PowerOfTwo::new(Square::new(image).unwrap()).unwrap()
+}
+
+#[cfg(feature = "generators")]
+pub fn circle() -> PowerOfTwo<Square<GenCircle>> {
+    PowerOfTwo::new(GenCircle::new(64, 20.0)).unwrap()
+}
+
+/// An anti-aliased circle, whose soft edge is not reconstructed perfectly
+#[cfg(feature = "generators")]
+pub fn soft_circle() -> PowerOfTwo<Square<GenCircle>> {
+    PowerOfTwo::new(GenCircle::new_aa(64, 20.0, 4.0)).unwrap()
+}
+
+/// A 32x32 image partitioned into 8x8 blocks, which uses every rotation
+pub fn compressed() -> Compressed {
+    let rotations = [Rotation::By0, Rotation::By90, Rotation::By180, Rotation::By270];
+    let transformations = (0..16)
+        .map(|index| Transformation {
+            range: Block { block_size: 8, origin: coords!(x=index % 4 * 8, y=index / 4 * 8) },
+            domain: Block { block_size: 16, origin: coords!(x=index % 3 * 8, y=16) },
+            rotation: rotations[index as usize % 4],
+            brightness: index as i16 * 7 - 50,
+            // Exactly representable in the fixed point format of binary v2
+            saturation: 1.0 - index as f64 / 16.0,
+        })
+        .collect();
+    Compressed { size: Size::squared(32), transformations }
+}
+
+/// A path in the temporary directory, which is unique to the running test binary
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("fractal-image-{}-{}", std::process::id(), name))
+}
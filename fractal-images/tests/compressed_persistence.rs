@@ -0,0 +1,73 @@
+#![cfg(feature = "persist-compressed")]
+
+mod common;
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use flate2::write::GzEncoder;
+use fractal_image::model::Compressed;
+use fractal_image::persistence::{PersistenceError, MAX_GZIP_DECOMPRESSED_SIZE};
+use common::{compressed, temp_path};
+
+fn read_and_remove(path: &PathBuf) -> Compressed {
+    let read = Compressed::read_from_path(path);
+    std::fs::remove_file(path).unwrap();
+    read.unwrap()
+}
+
+#[test]
+fn gzip_wrapped_file_is_read_transparently() {
+    let path = temp_path("v1.qfic.gz");
+    compressed().persist_as_binary_v1_deflate(&path).unwrap();
+
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes[..2], [0x1F, 0x8B]);
+
+    let read = read_and_remove(&path);
+    assert_eq!(read.size, compressed().size);
+    assert_eq!(read.transformations, compressed().transformations);
+}
+
+#[test]
+fn plain_files_are_still_read() {
+    let path = temp_path("v1.qfic");
+    compressed().persist_as_binary_v1(&path).unwrap();
+    assert_eq!(read_and_remove(&path).transformations, compressed().transformations);
+
+    #[cfg(feature = "persist-as-binary-v2")]
+    {
+        let path = temp_path("v2.qfic");
+        compressed().persist_as_binary_v2(&path).unwrap();
+        assert_eq!(read_and_remove(&path).transformations, compressed().transformations);
+    }
+}
+
+#[test]
+fn corrupted_gzip_file_returns_error() {
+    let path = temp_path("corrupted.qfic.gz");
+    compressed().persist_as_binary_v1_deflate(&path).unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.truncate(bytes.len() - 4);
+    std::fs::write(&path, bytes).unwrap();
+
+    let result = Compressed::read_from_path(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(PersistenceError::Gzip(_))), "{:?}", result);
+}
+
+#[test]
+fn gzip_exceeding_the_size_limit_returns_error() {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    let zeros = vec![0; 1024 * 1024];
+    for _ in 0..=MAX_GZIP_DECOMPRESSED_SIZE / zeros.len() as u64 {
+        encoder.write_all(&zeros).unwrap();
+    }
+    let bytes = encoder.finish().unwrap();
+
+    let result = Compressed::read_from_reader(bytes.as_slice());
+    assert!(
+        matches!(result, Err(PersistenceError::GzipTooLarge { limit: MAX_GZIP_DECOMPRESSED_SIZE })),
+        "{:?}", result
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use fractal_image::compress::quadtree::CompressionPreset;
+use fractal_image::{compress, decompress, metrics};
+use common::random_noise_with_seed;
+
+/// Returns the PSNR of the decompressed image and the amount of computed mappings.
+fn compress_with(preset: CompressionPreset) -> (f64, u64) {
+    let image = random_noise_with_seed(32, 11);
+    let (compressed, stats) = compress::quadtree::Compressor::new(image.clone())
+        .with_preset(preset)
+        .compress_with_stats()
+        .unwrap();
+
+    let decompressed = decompress::decompress(compressed, decompress::Options::default()).unwrap().image;
+    (metrics::psnr(&image, &decompressed).unwrap(), stats.mappings_computed)
+}
+
+#[test]
+fn faster_presets_compute_fewer_mappings() {
+    let (_, fast) = compress_with(CompressionPreset::Fast);
+    let (_, balanced) = compress_with(CompressionPreset::Balanced);
+    let (_, best) = compress_with(CompressionPreset::Best);
+
+    assert!(fast < balanced, "Expected fast ({}) < balanced ({})", fast, balanced);
+    assert!(balanced < best, "Expected balanced ({}) < best ({})", balanced, best);
+}
+
+#[test]
+fn better_presets_yield_higher_quality() {
+    let (fast, _) = compress_with(CompressionPreset::Fast);
+    let (best, _) = compress_with(CompressionPreset::Best);
+
+    assert!(fast < best, "Expected PSNR of fast ({}) < best ({})", fast, best);
+}
+
+#[test]
+fn presets_produce_different_compressions() {
+    let compress_with = |preset| compress::quadtree::Compressor::new(random_noise_with_seed(32, 11))
+        .with_preset(preset)
+        .compress()
+        .unwrap()
+        .partition_stats();
+    let fast = compress_with(CompressionPreset::Fast);
+    let balanced = compress_with(CompressionPreset::Balanced);
+    let best = compress_with(CompressionPreset::Best);
+
+    assert_eq!(fast.min_block_size, Some(4));
+    assert_eq!(balanced.min_block_size, Some(2));
+    assert!(
+        fast.transformations < balanced.transformations && balanced.transformations < best.transformations,
+        "Expected fast ({}) < balanced ({}) < best ({}) transformations",
+        fast.transformations, balanced.transformations, best.transformations
+    );
+}
+
+#[test]
+fn individual_settings_override_the_preset() {
+    let stats = compress::quadtree::Compressor::new(random_noise_with_seed(32, 11))
+        .with_preset(CompressionPreset::Fast)
+        .with_min_block_size(1)
+        .with_error_threshold(compress::quadtree::ErrorThreshold::AnyBlockBelowRms(-1.0))
+        .compress()
+        .unwrap()
+        .partition_stats();
+
+    // No mapping has a negative error, hence every block is split down to the minimal size
+    assert_eq!(stats.max_block_size, Some(1));
+    assert!(stats.is_exact_partition());
+}
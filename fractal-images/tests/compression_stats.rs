@@ -0,0 +1,48 @@
+mod common;
+
+use fractal_image::{compress, metrics};
+use fractal_image::compress::quadtree::ErrorThreshold;
+use common::random_noise;
+
+#[test]
+fn stats_report_work_done() {
+    let (_, stats) = compress::quadtree::Compressor::new(random_noise(64))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .compress_with_stats()
+        .unwrap();
+
+    assert!(stats.mappings_computed > 0);
+    assert!(stats.saturation_rejections > 0);
+    assert!(stats.saturation_rejections <= stats.mappings_computed);
+    assert!(!stats.duration.is_zero());
+}
+
+#[test]
+fn stats_blocks_per_level_cover_the_image() {
+    let (compressed, stats) = compress::quadtree::Compressor::new(random_noise(64))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .compress_with_stats()
+        .unwrap();
+
+    let covered_area: u64 = stats.blocks_per_level
+        .iter()
+        .map(|(block_size, count)| (block_size * block_size) as u64 * count)
+        .sum();
+    let block_count: u64 = stats.blocks_per_level.iter().map(|(_, count)| count).sum();
+
+    assert_eq!(covered_area, 64 * 64);
+    assert_eq!(block_count, compressed.transformations.len() as u64);
+}
+
+#[test]
+fn stats_report_the_source_entropy() {
+    let image = random_noise(64);
+    let expected = metrics::entropy(&image);
+    let (_, stats) = compress::quadtree::Compressor::new(image)
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .compress_with_stats()
+        .unwrap();
+
+    assert_eq!(stats.source_entropy, expected);
+    assert!(stats.to_string().contains("Source entropy"));
+}
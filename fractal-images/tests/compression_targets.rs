@@ -0,0 +1,40 @@
+mod common;
+
+use fractal_image::compress;
+use fractal_image::image::{OwnedImage, PowerOfTwo, Square};
+use common::random_noise;
+
+/// A compressor whose domain search is narrowed, as each target is searched with several
+/// compressions of the image.
+fn compressor() -> compress::quadtree::Compressor<PowerOfTwo<Square<OwnedImage>>> {
+    compress::quadtree::Compressor::new(random_noise(256))
+        .with_rotations(false)
+}
+
+#[test]
+fn target_size_is_met_within_tolerance() {
+    let target_size = 16_000;
+
+    let compressed = compressor()
+        .with_target_size_bytes(target_size)
+        .compress()
+        .unwrap();
+
+    let size = compressed.estimated_size_binary_v1().unwrap();
+    let deviation = (size as f64 - target_size as f64).abs() / target_size as f64;
+    assert!(deviation <= 0.15, "Expected a size of {} (+/- 15%), was {}", target_size, size);
+}
+
+#[test]
+fn target_transformation_count_is_met_within_tolerance() {
+    let target_count = 400;
+
+    let compressed = compressor()
+        .with_target_transformation_count(target_count)
+        .compress()
+        .unwrap();
+
+    let count = compressed.transformations.len();
+    let deviation = (count as f64 - target_count as f64).abs() / target_count as f64;
+    assert!(deviation <= 0.15, "Expected {} transformations (+/- 15%), was {}", target_count, count);
+}
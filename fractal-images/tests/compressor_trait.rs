@@ -0,0 +1,53 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use fractal_image::compress;
+use fractal_image::compress::Compressor;
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::model::Compressed;
+use common::random_noise;
+
+fn compress_generic<C: Compressor>(mut compressor: C) -> Compressed {
+    compressor.set_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0));
+    compressor.compress().unwrap()
+}
+
+#[test]
+fn boxed_compressor_compresses_like_the_concrete_compressor() {
+    let boxed: Box<dyn Compressor> = Box::new(compress::quadtree::Compressor::new(random_noise(32)));
+    let concrete = compress::quadtree::Compressor::new(random_noise(32))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0));
+
+    let mut from_boxed = compress_generic(boxed).transformations;
+    let mut from_concrete = concrete.compress().unwrap().transformations;
+
+    // The order of transformations depends on the parallel search
+    from_boxed.sort_by_key(|t| (t.range.origin.y, t.range.origin.x));
+    from_concrete.sort_by_key(|t| (t.range.origin.y, t.range.origin.x));
+    assert_eq!(from_boxed.len(), from_concrete.len());
+    assert_eq!(
+        from_boxed.iter().map(|t| t.range).collect::<Vec<_>>(),
+        from_concrete.iter().map(|t| t.range).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn boxed_compressor_reports_progress() {
+    let finished = Arc::new(AtomicBool::new(false));
+    let finished_in_reporter = finished.clone();
+
+    let mut compressor: Box<dyn Compressor> = Box::new(compress::quadtree::Compressor::new(random_noise(32)));
+    compressor.set_progress_reporter(Arc::new(move |progress| {
+        if progress.finished() {
+            finished_in_reporter.store(true, Ordering::SeqCst);
+        }
+    }));
+
+    let (compressed, stats) = compressor.compress_with_stats().unwrap();
+
+    assert!(finished.load(Ordering::SeqCst));
+    assert!(!compressed.transformations.is_empty());
+    assert!(stats.mappings_computed > 0);
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "generators")]
+
+mod common;
+
+use fractal_image::model::Compressed;
+use fractal_image::{compress, decompress, metrics};
+use common::soft_circle;
+
+fn compressed_circle() -> Compressed {
+    compress::quadtree::Compressor::new(soft_circle()).compress().unwrap()
+}
+
+#[test]
+fn decompression_stops_on_convergence() {
+    let compressed = compressed_circle();
+
+    let full = decompress::decompress(compressed.clone(), decompress::Options::default()).unwrap();
+    let converged = decompress::decompress(
+        compressed,
+        decompress::Options { convergence: Some(1.0), ..Default::default() },
+    ).unwrap();
+
+    assert_eq!(full.executed_iterations, 10);
+    assert!(converged.executed_iterations < 10, "Expected an early stop, executed {} iterations", converged.executed_iterations);
+
+    let full_psnr = metrics::psnr(&soft_circle(), &full.image).unwrap();
+    let converged_psnr = metrics::psnr(&soft_circle(), &converged.image).unwrap();
+    assert!(
+        (full_psnr - converged_psnr).abs() < 1.0,
+        "Expected a PSNR of about {} dB, was {} dB", full_psnr, converged_psnr
+    );
+}
+
+#[test]
+fn decompression_without_convergence_executes_all_iterations() {
+    let options = decompress::Options { iterations: 3, ..Default::default() };
+
+    let decompressed = decompress::decompress(compressed_circle(), options).unwrap();
+
+    assert_eq!(decompressed.executed_iterations, 3);
+}
@@ -0,0 +1,36 @@
+use fractal_image::compress::quadtree::Compressor;
+use fractal_image::decompress;
+use fractal_image::image::{OwnedImage, PowerOfTwo, Size, Square};
+
+#[test]
+fn complete_compression_is_fully_covered() {
+    let image = PowerOfTwo::new(Square::new(OwnedImage::random(Size::squared(16))).unwrap()).unwrap();
+    let compressed = Compressor::new(image).compress().unwrap();
+
+    let options = decompress::Options { track_coverage: true, ..Default::default() };
+    let coverage = decompress::decompress(compressed, options).unwrap().coverage.unwrap();
+
+    assert!(coverage.is_complete());
+}
+
+#[test]
+fn missing_block_is_reported_as_uncovered() {
+    let image = PowerOfTwo::new(Square::new(OwnedImage::random(Size::squared(16))).unwrap()).unwrap();
+    let mut compressed = Compressor::new(image).compress().unwrap();
+    let missing = compressed.transformations.remove(compressed.transformations.len() / 2).range;
+
+    let options = decompress::Options { track_coverage: true, ..Default::default() };
+    let coverage = decompress::decompress(compressed, options).unwrap().coverage.unwrap();
+
+    assert_eq!(coverage.uncovered_pixels(), missing.block_size.pow(2) as u64);
+    assert_eq!(coverage.uncovered_regions(), vec![(missing.origin, Size::squared(missing.block_size))]);
+    assert!(!coverage.is_covered(missing.origin.x, missing.origin.y));
+}
+
+#[test]
+fn coverage_is_not_tracked_by_default() {
+    let image = PowerOfTwo::new(Square::new(OwnedImage::random(Size::squared(16))).unwrap()).unwrap();
+    let compressed = Compressor::new(image).compress().unwrap();
+
+    assert!(decompress::decompress(compressed, Default::default()).unwrap().coverage.is_none());
+}
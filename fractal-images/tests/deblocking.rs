@@ -0,0 +1,25 @@
+mod common;
+
+use fractal_image::postprocess::DeblockStrength;
+use fractal_image::{compress, decompress};
+use common::{boundary_discontinuity, waves_64x64};
+
+#[test]
+fn deblocking_reduces_discontinuities_at_block_boundaries() {
+    let compressed = compress::quadtree::Compressor::new(waves_64x64()).compress().unwrap();
+
+    let decompress_with = |deblock| {
+        let options = decompress::Options { deblock, ..Default::default() };
+        decompress::decompress(compressed.clone(), options).unwrap().image
+    };
+    let blocky = decompress_with(None);
+    let deblocked = decompress_with(Some(DeblockStrength::Strong));
+
+    let (before, after) = (boundary_discontinuity(&compressed, &blocky), boundary_discontinuity(&compressed, &deblocked));
+    assert!(after < before, "Expected a discontinuity below {}, was {}", before, after);
+}
+
+#[test]
+fn deblocking_is_off_by_default() {
+    assert_eq!(decompress::Options::default().deblock, None);
+}
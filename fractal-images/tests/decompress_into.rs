@@ -0,0 +1,31 @@
+mod common;
+
+use fractal_image::decompress::{decompress, decompress_into, DecompressionError, Options};
+use fractal_image::image::{OwnedImage, Size};
+use common::compressed_noise;
+
+#[test]
+fn decompression_into_target_equals_standard_path() {
+    for (overlap, convergence) in [(0, None), (0, Some(0.5)), (1, None)] {
+        let compressed = compressed_noise(29, overlap);
+        let options = || Options { overlap, convergence, ..Default::default() };
+        let mut target = OwnedImage::random(Size::squared(32));
+
+        decompress_into(compressed.clone(), options(), &mut target).unwrap();
+
+        let expected = decompress(compressed, options()).unwrap().image;
+        assert_eq!(target, expected, "Differs with overlap {} and convergence {:?}", overlap, convergence);
+    }
+}
+
+#[test]
+fn target_of_wrong_size_returns_error() {
+    let mut target = OwnedImage::random(Size::new(32, 16));
+
+    let result = decompress_into(compressed_noise(29, 0), Options::default(), &mut target);
+
+    assert_eq!(
+        result,
+        Err(DecompressionError::TargetSizeMismatch { expected: Size::squared(32), actual: Size::new(32, 16) })
+    );
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use fractal_image::decompress::{decompress, DecompressionError, InitialImage, Options};
+use fractal_image::image::{Image, OwnedImage, Size};
+use common::compressed_noise;
+
+#[test]
+fn default_decompression_is_deterministic() {
+    let compressed = compressed_noise(17, 0);
+    let options = || Options { iterations: 1, ..Default::default() };
+
+    let first = decompress(compressed.clone(), options()).unwrap().image;
+    let second = decompress(compressed, options()).unwrap().image;
+
+    assert_eq!(first, second);
+}
+
+#[test]
+fn decompression_starts_with_flat_gray_image() {
+    let compressed = compressed_noise(17, 0);
+    let options = Options { iterations: 1, keep_each_iteration: true, ..Default::default() };
+
+    let iterations = decompress(compressed, options).unwrap().iterations.unwrap();
+
+    assert!(iterations[0].pixels().all(|pixel| pixel == 128));
+}
+
+#[test]
+fn decompression_starts_with_custom_image() {
+    let compressed = compressed_noise(17, 0);
+    let initial = OwnedImage::random_with_seed(Size::squared(32), 3);
+    let options = Options {
+        iterations: 1,
+        keep_each_iteration: true,
+        initial_image: InitialImage::Custom(initial.clone()),
+        ..Default::default()
+    };
+
+    let iterations = decompress(compressed, options).unwrap().iterations.unwrap();
+
+    assert_eq!(iterations[0], initial);
+}
+
+#[test]
+fn custom_image_of_wrong_size_returns_error() {
+    let options = Options {
+        initial_image: InitialImage::Custom(OwnedImage::random(Size::squared(16))),
+        ..Default::default()
+    };
+
+    let result = decompress(compressed_noise(17, 0), options);
+
+    assert_eq!(
+        result.err(),
+        Some(DecompressionError::InitialImageSizeMismatch { expected: Size::squared(32), actual: Size::squared(16) })
+    );
+}
+
+#[test]
+fn zero_iterations_return_error() {
+    let options = Options { iterations: 0, ..Default::default() };
+
+    let result = decompress(compressed_noise(17, 0), options);
+
+    assert!(matches!(result.err(), Some(DecompressionError::InvalidOptions(_))));
+}
+
+#[test]
+fn negative_convergence_threshold_returns_error() {
+    let options = Options { convergence: Some(-1.0), ..Default::default() };
+
+    let result = decompress(compressed_noise(17, 0), options);
+
+    assert!(matches!(result.err(), Some(DecompressionError::InvalidOptions(_))));
+}
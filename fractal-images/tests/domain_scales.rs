@@ -0,0 +1,112 @@
+mod common;
+
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::image::{Image, OwnedImage};
+use fractal_image::{compress, decompress};
+use common::random_noise_with_seed;
+
+#[test]
+fn domain_blocks_have_the_requested_scale() {
+    let compressed = compress::quadtree::Compressor::new(random_noise_with_seed(32, 13))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .with_domain_scales(&[4])
+        .compress()
+        .unwrap();
+
+    assert!(!compressed.transformations.is_empty());
+    for transformation in compressed.transformations {
+        assert_eq!(transformation.domain.block_size, 4 * transformation.range.block_size);
+    }
+}
+
+#[test]
+fn multiple_domain_scales_are_searched() {
+    let (_, single) = compress::quadtree::Compressor::new(random_noise_with_seed(16, 13))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(-1.0))
+        .compress_with_stats()
+        .unwrap();
+    let (_, multiple) = compress::quadtree::Compressor::new(random_noise_with_seed(16, 13))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(-1.0))
+        .with_domain_scales(&[2, 4])
+        .compress_with_stats()
+        .unwrap();
+
+    assert!(single.mappings_computed < multiple.mappings_computed);
+}
+
+#[test]
+fn transformations_with_4x_domain_blocks_are_decompressed() {
+    let compressed = compress::quadtree::Compressor::new(random_noise_with_seed(32, 13))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .with_domain_scales(&[4])
+        .with_rotations(false)
+        .compress()
+        .unwrap();
+
+    let initial = OwnedImage::random(compressed.size);
+    // Each transformation reads the initial image
+    let options = decompress::Options {
+        iterations: 1,
+        initial_image: decompress::InitialImage::Custom(initial.clone()),
+        application_order: decompress::ApplicationOrder::FileOrder,
+        ..Default::default()
+    };
+    let decompressed = decompress::decompress(compressed.clone(), options).unwrap().image;
+
+    for transformation in compressed.transformations {
+        let (range, domain) = (transformation.range, transformation.domain);
+        for y in 0..range.block_size {
+            for x in 0..range.block_size {
+                let mut sum = 0u32;
+                for dy in 0..4 {
+                    for dx in 0..4 {
+                        sum += initial.pixel(domain.origin.x + 4 * x + dx, domain.origin.y + 4 * y + dy) as u32;
+                    }
+                }
+                let downscaled = ((sum + 8) / 16) as u8;
+                let expected = (downscaled as f64 * transformation.saturation + transformation.brightness as f64)
+                    .clamp(0.0, 255.0) as u8;
+
+                assert_eq!(decompressed.pixel(range.origin.x + x, range.origin.y + y), expected);
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "persist-as-binary-v1")]
+fn target_size_requires_the_default_domain_scale() {
+    let result = compress::quadtree::Compressor::new(random_noise_with_seed(32, 13))
+        .with_target_size_bytes(1_000)
+        .with_domain_scales(&[2, 4])
+        .compress();
+
+    assert_eq!(result.unwrap_err(), compress::quadtree::CompressionError::UnsupportedTargetSize);
+}
+
+#[test]
+#[cfg(feature = "persist-as-binary-v2")]
+fn binary_formats_reject_other_domain_scales() {
+    use fractal_image::persistence::{binary_v1, binary_v2};
+
+    let compressed = compress::quadtree::Compressor::new(random_noise_with_seed(32, 13))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .with_domain_scales(&[4])
+        .compress()
+        .unwrap();
+    let range_size = compressed.transformations[0].range.block_size;
+    let expected = (range_size, 4 * range_size);
+
+    match binary_v1::serialize(&compressed) {
+        Err(binary_v1::SerializationError::InvalidBlockSize { range_size, domain_size }) => {
+            assert_eq!((range_size, domain_size), expected)
+        }
+        result => panic!("Expected an invalid block size, was {:?}", result),
+    }
+    match binary_v2::serialize(&compressed) {
+        Err(binary_v2::SerializationError::Payload(binary_v1::SerializationError::InvalidBlockSize { range_size, domain_size })) => {
+            assert_eq!((range_size, domain_size), expected)
+        }
+        result => panic!("Expected an invalid block size, was {:?}", result),
+    }
+}
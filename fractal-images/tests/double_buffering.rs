@@ -0,0 +1,55 @@
+use fractal_image::coords;
+use fractal_image::decompress::{decompress, InitialImage, Options};
+use fractal_image::image::{Coords, Image, OwnedImage, Size};
+use fractal_image::model::{Block, Compressed, Rotation, Transformation};
+
+/// A 4x4 image whose top left quadrant is mapped from the whole image. The other pixels
+/// are not covered by any range block.
+fn top_left_from_whole_image() -> Compressed {
+    Compressed {
+        size: Size::squared(4),
+        transformations: vec![Transformation {
+            range: Block { block_size: 2, origin: coords!(x=0, y=0) },
+            domain: Block { block_size: 4, origin: coords!(x=0, y=0) },
+            rotation: Rotation::By0,
+            brightness: 10,
+            saturation: 0.5,
+        }],
+    }
+}
+
+/// Applies the transformation of [top_left_from_whole_image] to `previous`.
+fn next_iteration(previous: &[u8]) -> Vec<u8> {
+    let mut next = previous.to_vec();
+    for y in 0..2 {
+        for x in 0..2 {
+            let sum: u32 = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                .iter()
+                .map(|(dx, dy)| previous[(4 * (2 * y + dy) + 2 * x + dx) as usize] as u32)
+                .sum();
+            let downscaled = ((sum + 2) / 4) as u8;
+            next[(4 * y + x) as usize] = (downscaled as f64 * 0.5 + 10.0).clamp(0.0, 255.0) as u8;
+        }
+    }
+    next
+}
+
+#[test]
+fn each_iteration_reads_the_previous_iteration() {
+    let initial = OwnedImage::random_with_seed(Size::squared(4), 7);
+    let options = Options {
+        iterations: 3,
+        keep_each_iteration: true,
+        initial_image: InitialImage::Custom(initial.clone()),
+        ..Default::default()
+    };
+
+    let decompressed = decompress(top_left_from_whole_image(), options).unwrap();
+
+    let mut expected = initial.pixels().collect::<Vec<_>>();
+    for (i, iteration) in decompressed.iterations.unwrap().iter().enumerate().skip(1) {
+        expected = next_iteration(&expected);
+        assert_eq!(iteration.pixels().collect::<Vec<_>>(), expected, "Iteration {} differs", i);
+    }
+    assert_eq!(decompressed.image.pixels().collect::<Vec<_>>(), expected);
+}
@@ -0,0 +1,56 @@
+use fractal_image::image::{Image, IntoRotated, OwnedImage, PowerOfTwo, Size, Square};
+use fractal_image::preprocessing::{AsDynamicImage, FromDynamicImage};
+use image::{DynamicImage, ImageBuffer, Luma, Rgba, RgbaImage};
+
+fn gradient() -> OwnedImage {
+    OwnedImage::from_pixels(Size::new(16, 8), (0..128).map(|i| (2 * i) as u8).collect()).unwrap()
+}
+
+#[test]
+fn luma_image_roundtrips_exactly() {
+    let original = gradient();
+
+    let converted = OwnedImage::try_from(original.as_dynamic_image()).unwrap();
+
+    assert_eq!(converted, original);
+}
+
+#[test]
+fn converted_image_can_be_compressed() {
+    let image = OwnedImage::from_pixels(Size::squared(8), (0..64).collect()).unwrap();
+    let dynamic_image = image.as_dynamic_image();
+
+    let converted = OwnedImage::from_dynamic_image(&dynamic_image).unwrap();
+
+    assert!(PowerOfTwo::new(Square::new(converted).unwrap()).is_ok());
+}
+
+#[test]
+fn sixteen_bit_luma_is_scaled_to_eight_bits() {
+    let image: ImageBuffer<Luma<u16>, Vec<u16>> = ImageBuffer::from_raw(3, 1, vec![0, 257 * 100, u16::MAX]).unwrap();
+
+    let converted = OwnedImage::try_from(DynamicImage::ImageLuma16(image)).unwrap();
+
+    assert_eq!(converted.pixels().collect::<Vec<_>>(), vec![0, 100, 255]);
+}
+
+#[test]
+fn alpha_channel_is_ignored() {
+    let image = RgbaImage::from_fn(2, 1, |x, _| Rgba([200, 200, 200, (x * 255) as u8]));
+
+    let converted = OwnedImage::try_from(DynamicImage::ImageRgba8(image)).unwrap();
+
+    assert_eq!(converted.get_size(), Size::new(2, 1));
+    assert_eq!(converted.pixels().collect::<Vec<_>>(), vec![200, 200]);
+}
+
+#[test]
+fn images_without_rows_convert_identically() {
+    let image = OwnedImage::random_with_seed(Size::new(7, 5), 3);
+    let without_rows = image.clone().rot_0();
+
+    let converted = image.as_dynamic_image();
+
+    assert_eq!(converted, without_rows.as_dynamic_image());
+    assert_eq!(converted.as_bytes(), image.pixels().collect::<Vec<_>>().as_slice());
+}
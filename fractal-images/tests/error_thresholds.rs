@@ -0,0 +1,68 @@
+mod common;
+
+use fractal_image::compress;
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::image::{FakeImage, Image, MutableImage, OwnedImage, PowerOfTwo, Size, Square};
+use common::random_noise;
+
+#[test]
+fn per_block_size_threshold_falls_back_to_default() {
+    let threshold = ErrorThreshold::PerBlockSize {
+        thresholds: vec![(128, 1.0), (64, 2.0)],
+        default: 3.0,
+    };
+
+    assert_eq!(threshold.rms_for(&FakeImage::squared(128)), 1.0);
+    assert_eq!(threshold.rms_for(&FakeImage::squared(64)), 2.0);
+    assert_eq!(threshold.rms_for(&FakeImage::squared(32)), 3.0);
+}
+
+#[test]
+fn blocks_of_unmatchable_size_are_split() {
+    // No mapping has a negative error, 128x128 blocks can never be mapped
+    let threshold = ErrorThreshold::PerBlockSize {
+        thresholds: vec![(128, -1.0)],
+        default: 100.0,
+    };
+
+    let compressed = compress::quadtree::Compressor::new(random_noise(256))
+        .with_error_threshold(threshold)
+        .compress()
+        .unwrap();
+
+    assert!(!compressed.transformations.is_empty());
+    for transformation in compressed.transformations {
+        assert!(transformation.range.block_size <= 64, "Unexpected range block {:?}", transformation.range);
+    }
+}
+
+/// An image whose upper half is noise and whose lower half is almost flat.
+fn half_noise_32x32() -> PowerOfTwo<Square<OwnedImage>> {
+    let mut image = OwnedImage::random_with_seed(Size::squared(32), 5);
+    for y in 16..32 {
+        for x in 0..32 {
+            let value = 126 + image.pixel(x, y) % 5;
+            image.set_pixel(x, y, value);
+        }
+    }
+    PowerOfTwo::new(Square::new(image).unwrap()).unwrap()
+}
+
+fn transformations_in_lower_half(error_threshold: ErrorThreshold) -> usize {
+    compress::quadtree::Compressor::new(half_noise_32x32())
+        .with_error_threshold(error_threshold)
+        .compress()
+        .unwrap()
+        .transformations
+        .iter()
+        .filter(|transformation| transformation.range.origin.y >= 16)
+        .count()
+}
+
+#[test]
+fn relative_threshold_splits_flat_blocks_finer() {
+    let absolute = transformations_in_lower_half(ErrorThreshold::AnyBlockBelowRms(10.0));
+    let relative = transformations_in_lower_half(ErrorThreshold::RelativeToVariance(0.5));
+
+    assert!(absolute < relative, "Expected more than {} transformations, were {}", absolute, relative);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "persist-as-binary-v2")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use fractal_image::model::Compressed;
+use fractal_image::persistence::PersistenceError;
+use common::{compressed, temp_path};
+
+fn assert_detected(path: PathBuf) {
+    let read = Compressed::read_from_path(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let read = read.unwrap();
+    assert_eq!(read.size, compressed().size);
+    assert_eq!(read.transformations, compressed().transformations);
+}
+
+#[test]
+fn detects_binary_v2() {
+    let path = temp_path("v2.qfic");
+    compressed().persist_as_binary_v2(&path).unwrap();
+    assert_detected(path);
+}
+
+#[test]
+fn detects_binary_v1() {
+    let path = temp_path("v1.qfic");
+    compressed().persist_as_binary_v1(&path).unwrap();
+    assert_detected(path);
+}
+
+#[test]
+#[cfg(feature = "persist-as-json")]
+fn detects_json() {
+    let path = temp_path("compressed.json");
+    compressed().persist_as_json(&path).unwrap();
+    assert_detected(path);
+}
+
+#[test]
+fn binary_v1_is_not_read_as_binary_v2() {
+    let path = temp_path("v1-as-v2.qfic");
+    compressed().persist_as_binary_v1(&path).unwrap();
+
+    let result = Compressed::read_from_binary_v2(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(PersistenceError::BinaryV2DeserializationError(_))));
+}
+
+#[test]
+fn unknown_magic_returns_error() {
+    let path = temp_path("unknown.qfic");
+    std::fs::write(&path, b"GIF89a, certainly not a compression").unwrap();
+
+    let result = Compressed::read_from_path(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(PersistenceError::UnknownFormat(start)) if start == b"GIF8"));
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use fractal_image::image::{Image, IntoOwnedImage, MutableImage, OwnedImage, PowerOfTwo, Size, SizeMismatch, Square};
+use fractal_image::preprocessing::{AsDynamicImage, SquaredGrayscaleImage};
+use image::ImageFormat;
+
+fn numbered(size: Size) -> SquaredGrayscaleImage {
+    SquaredGrayscaleImage::from_pixels(size, (0..size.area()).map(|i| i as u8).collect()).unwrap()
+}
+
+#[test]
+fn images_are_constructed_from_pixels() {
+    let image = numbered(Size::new(3, 2));
+
+    assert_eq!(image.get_size(), Size::new(3, 2));
+    assert_eq!(image.as_pixels(), &[0, 1, 2, 3, 4, 5]);
+    assert_eq!(image.pixel(2, 1), 5);
+}
+
+#[test]
+fn pixels_not_matching_the_size_are_rejected() {
+    let result = SquaredGrayscaleImage::from_pixels(Size::squared(2), vec![0; 3]);
+
+    assert_eq!(result.unwrap_err(), SizeMismatch { size: Size::squared(2), pixels: 3 });
+}
+
+#[test]
+fn pixels_are_iterated_row_by_row() {
+    let image = numbered(Size::new(4, 3));
+
+    assert!(image.pixels().eq(image.as_pixels().iter().copied()));
+    assert_eq!(image.pixel_row(1), Some(&[4, 5, 6, 7][..]));
+}
+
+#[test]
+fn pixels_are_mutated() {
+    let mut image = numbered(Size::new(3, 2));
+
+    image.set_pixel(1, 1, 99);
+
+    assert_eq!(image.pixel(1, 1), 99);
+    assert_eq!(image.as_pixels(), &[0, 1, 2, 3, 99, 5]);
+}
+
+#[test]
+#[should_panic]
+fn pixels_outside_of_the_image_are_not_mutated() {
+    numbered(Size::new(3, 2)).set_pixel(3, 0, 1);
+}
+
+#[test]
+fn conversion_to_an_owned_image_keeps_the_pixels() {
+    let image = numbered(Size::new(5, 3));
+
+    let owned = image.clone().into_owned();
+
+    assert_eq!(owned, image.to_owned_image());
+    assert_eq!(owned.as_slice(), image.as_pixels());
+}
+
+#[test]
+fn loaded_images_are_preprocessed_in_place() {
+    let original = OwnedImage::random_with_seed(Size::squared(8), 5);
+    let mut bytes = std::io::Cursor::new(vec![]);
+    original.as_dynamic_image().write_to(&mut bytes, ImageFormat::Png).unwrap();
+    let loaded = SquaredGrayscaleImage::read_from_bytes(bytes.get_ref()).unwrap();
+
+    // Inverts the image
+    let mut image = Arc::unwrap_or_clone(Arc::unwrap_or_clone(loaded.into_inner()).into_inner());
+    for (x, y) in (0..8).flat_map(|y| (0..8).map(move |x| (x, y))) {
+        image.set_pixel(x, y, 255 - image.pixel(x, y));
+    }
+    let image = PowerOfTwo::new(Square::new(image).unwrap()).unwrap();
+
+    assert!(image.pixels().zip(original.pixels()).all(|(inverted, pixel)| inverted == 255 - pixel));
+}
@@ -0,0 +1,311 @@
+use std::io::Cursor;
+
+use fractal_image::image::{Image, OwnedImage, PadMode, Size};
+use fractal_image::preprocessing::{
+    Alpha, AsDynamicImage, BitDepthReduction, LoadError, LoadOptions, LoadedImage, LumaWeights, Sizing,
+    SquaredGrayscaleImage,
+};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, ImageFormat, Luma, LumaA, RgbImage};
+
+fn gradient(size: Size) -> OwnedImage {
+    let pixels = (0..size.area()).map(|i| (i * 7 % 256) as u8).collect();
+    OwnedImage::from_pixels(size, pixels).unwrap()
+}
+
+fn png(image: &OwnedImage) -> Vec<u8> {
+    let mut bytes = Cursor::new(vec![]);
+    image.as_dynamic_image().write_to(&mut bytes, ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+fn pixels<I: Image>(image: &I) -> Vec<u8> {
+    let mut row = vec![0; image.get_width() as usize];
+    (0..image.get_height())
+        .flat_map(|y| {
+            image.copy_row_into(y, &mut row);
+            row.clone()
+        })
+        .collect()
+}
+
+#[test]
+fn png_bytes_are_loaded() {
+    let original = gradient(Size::squared(16));
+
+    let loaded = SquaredGrayscaleImage::read_from_bytes(&png(&original)).unwrap();
+
+    assert_eq!(loaded.get_size(), Size::squared(16));
+    assert_eq!(pixels(&loaded), original.as_slice());
+}
+
+#[test]
+fn png_reader_is_loaded() {
+    let original = gradient(Size::squared(16));
+
+    let loaded = SquaredGrayscaleImage::read_from_reader(Cursor::new(png(&original))).unwrap();
+
+    assert_eq!(pixels(&loaded), original.as_slice());
+}
+
+#[test]
+fn loaded_images_are_downscaled_to_a_square_power_of_two() {
+    let bytes = png(&gradient(Size::new(20, 12)));
+
+    assert_eq!(SquaredGrayscaleImage::read_from_bytes(&bytes).unwrap().get_size(), Size::squared(8));
+    assert_eq!(SquaredGrayscaleImage::read_from_reader(Cursor::new(bytes)).unwrap().get_size(), Size::squared(8));
+}
+
+#[test]
+fn bytes_are_loaded_like_a_file() {
+    let bytes = png(&gradient(Size::new(24, 40)));
+    let path = std::env::temp_dir().join("fractal-image-image-loading.png");
+    std::fs::write(&path, &bytes).unwrap();
+    let from_file = SquaredGrayscaleImage::try_read_from(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(pixels(&from_file.unwrap()), pixels(&SquaredGrayscaleImage::read_from_bytes(&bytes).unwrap()));
+}
+
+#[test]
+fn invalid_bytes_return_error() {
+    assert!(matches!(SquaredGrayscaleImage::read_from_bytes(b"not an image"), Err(LoadError::Image(_))));
+    assert!(matches!(
+        SquaredGrayscaleImage::read_from_reader(Cursor::new(b"not an image")),
+        Err(LoadError::Image(_))
+    ));
+}
+
+fn load_20x12(sizing: Sizing) -> (OwnedImage, LoadedImage) {
+    let original = gradient(Size::new(20, 12));
+    let loaded = SquaredGrayscaleImage::read_from_bytes_with(&png(&original), LoadOptions { sizing, ..Default::default() }).unwrap();
+    assert_eq!(loaded.original_size, Size::new(20, 12));
+    (original, loaded)
+}
+
+#[test]
+fn downscaling_is_the_default_sizing() {
+    let (_, loaded) = load_20x12(Sizing::default());
+    let bytes = png(&gradient(Size::new(20, 12)));
+
+    assert_eq!(loaded.image.get_size(), Size::squared(8));
+    assert_eq!(pixels(&loaded.image), pixels(&SquaredGrayscaleImage::read_from_bytes(&bytes).unwrap()));
+    assert_eq!(loaded.crop_to_original(&loaded.image).get_size(), Size::squared(8));
+}
+
+#[test]
+fn padding_keeps_every_pixel() {
+    let (original, loaded) = load_20x12(Sizing::PadToNextPow2(PadMode::Constant(9)));
+
+    assert_eq!(loaded.image.get_size(), Size::squared(32));
+    assert_eq!(pixels(&loaded.crop_to_original(&loaded.image)), original.as_slice());
+    assert_eq!(loaded.image.pixel(20, 0), 9);
+    assert_eq!(loaded.image.pixel(0, 12), 9);
+    assert_eq!(loaded.image.pixel(31, 31), 9);
+}
+
+#[test]
+fn padding_uses_the_requested_mode() {
+    let (original, loaded) = load_20x12(Sizing::PadToNextPow2(PadMode::Edge));
+
+    assert_eq!(loaded.image.pixel(25, 3), original.pixel(19, 3));
+    assert_eq!(loaded.image.pixel(4, 30), original.pixel(4, 11));
+    assert_eq!(loaded.image.pixel(31, 31), original.pixel(19, 11));
+}
+
+#[test]
+fn center_crop_keeps_the_center() {
+    let (original, loaded) = load_20x12(Sizing::CenterCropToPreviousPow2);
+
+    assert_eq!(loaded.image.get_size(), Size::squared(8));
+    for (x, y) in (0..8).flat_map(|x| (0..8).map(move |y| (x, y))) {
+        assert_eq!(loaded.image.pixel(x, y), original.pixel(x + 6, y + 2));
+    }
+}
+
+/// Red, green, blue and orange.
+fn rgb_png() -> Vec<u8> {
+    let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [200, 100, 50]];
+    let image = RgbImage::from_fn(2, 2, |x, y| image::Rgb(colors[(2 * y + x) as usize]));
+    let mut bytes = Cursor::new(vec![]);
+    DynamicImage::ImageRgb8(image).write_to(&mut bytes, ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+fn load(image: DynamicImage, options: LoadOptions) -> Vec<u8> {
+    let mut bytes = Cursor::new(vec![]);
+    image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+    pixels(&SquaredGrayscaleImage::read_from_bytes_with(bytes.get_ref(), options).unwrap().image)
+}
+
+fn load_rgb(luma: LumaWeights) -> Vec<u8> {
+    let options = LoadOptions { luma, ..Default::default() };
+    pixels(&SquaredGrayscaleImage::read_from_bytes_with(&rgb_png(), options).unwrap().image)
+}
+
+#[test]
+fn colors_are_converted_with_the_luma_weights() {
+    assert_eq!(load_rgb(LumaWeights::Ntsc), [76, 149, 29, 124]);
+    assert_eq!(load_rgb(LumaWeights::Bt709), [54, 182, 18, 117]);
+    assert_eq!(load_rgb(LumaWeights::Average), [85, 85, 85, 116]);
+    assert_eq!(load_rgb(LumaWeights::Custom(1.0, 0.0, 0.0)), [255, 0, 0, 200]);
+}
+
+#[test]
+fn default_luma_weights_are_those_of_ntsc() {
+    assert_eq!(pixels(&SquaredGrayscaleImage::read_from_bytes(&rgb_png()).unwrap()), load_rgb(LumaWeights::Ntsc));
+}
+
+#[test]
+fn images_are_downscaled_with_the_filter() {
+    let size = Size::squared(24);
+    let checkerboard = (0..size.area()).map(|i| if (i % 24 + i / 24) % 2 == 0 { 0 } else { 255 }).collect();
+    let bytes = png(&OwnedImage::from_pixels(size, checkerboard).unwrap());
+    let load = |filter| {
+        let options = LoadOptions { filter, ..Default::default() };
+        pixels(&SquaredGrayscaleImage::read_from_bytes_with(&bytes, options).unwrap().image)
+    };
+
+    // Nearest neighbours keep the original colors, while a Gaussian blurs them
+    assert!(load(FilterType::Nearest).iter().all(|&pixel| pixel == 0 || pixel == 255));
+    assert!(load(FilterType::Gaussian).iter().any(|&pixel| pixel != 0 && pixel != 255));
+    assert_eq!(load(FilterType::Lanczos3), load(FilterType::Lanczos3));
+}
+
+/// Opaque, transparent and two half transparent pixels.
+fn gray_alpha() -> DynamicImage {
+    let pixels = [[200, 255], [200, 0], [100, 128], [0, 128]];
+    DynamicImage::ImageLumaA8(GrayAlphaImage::from_fn(2, 2, |x, y| LumaA(pixels[(2 * y + x) as usize])))
+}
+
+fn load_with_alpha(alpha: Alpha) -> Vec<u8> {
+    load(gray_alpha(), LoadOptions { alpha, ..Default::default() })
+}
+
+#[test]
+fn alpha_is_ignored_by_default() {
+    assert_eq!(load_with_alpha(Alpha::default()), [200, 200, 100, 0]);
+}
+
+#[test]
+fn transparent_pixels_are_composited_over_the_background() {
+    assert_eq!(load_with_alpha(Alpha::CompositeOver([255, 255, 255])), [200, 255, 177, 127]);
+    assert_eq!(load_with_alpha(Alpha::CompositeOver([0, 0, 0])), [200, 0, 50, 0]);
+}
+
+fn luma16(size: u32, values: &[u16]) -> DynamicImage {
+    DynamicImage::ImageLuma16(ImageBuffer::from_fn(size, size, |x, y| Luma([values[(size * y + x) as usize]])))
+}
+
+fn load_with_bit_depth(image: DynamicImage, bit_depth: BitDepthReduction) -> Vec<u8> {
+    load(image, LoadOptions { bit_depth, ..Default::default() })
+}
+
+#[test]
+fn sixteen_bit_images_are_rounded_or_truncated() {
+    let image = luma16(2, &[0x12F0, 0xFFFF, 0x0000, 100 * 257]);
+
+    assert_eq!(load_with_bit_depth(image.clone(), BitDepthReduction::Round), [19, 255, 0, 100]);
+    assert_eq!(load_with_bit_depth(image, BitDepthReduction::Truncate), [18, 255, 0, 100]);
+}
+
+#[test]
+fn sixteen_bit_images_are_dithered() {
+    // Halfway between 100 and 101
+    let image = luma16(4, &[25829; 16]);
+
+    let dithered = load_with_bit_depth(image.clone(), BitDepthReduction::Dither);
+
+    assert_eq!(dithered.iter().filter(|&&pixel| pixel == 100).count(), 8);
+    assert_eq!(dithered.iter().filter(|&&pixel| pixel == 101).count(), 8);
+    assert_eq!(dithered, load_with_bit_depth(image, BitDepthReduction::Dither));
+    assert_eq!(load_with_bit_depth(luma16(4, &[100 * 257; 16]), BitDepthReduction::Dither), [100; 16]);
+}
+
+/// A 16x8 JPEG whose left half is black and whose right half is white, with an Exif orientation
+/// of 6, such that it needs to be rotated by 90 degrees clockwise to be upright.
+fn rotated_jpeg() -> Vec<u8> {
+    let image = GrayImage::from_fn(16, 8, |x, _| Luma([if x < 8 { 0 } else { 255 }]));
+    let mut jpeg = vec![];
+    image.write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, 100)).unwrap();
+
+    // A big-endian TIFF structure with the orientation as its only tag
+    let mut exif = b"Exif\0\0MM\0\x2A\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0".to_vec();
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend(((exif.len() + 2) as u16).to_be_bytes());
+    segment.append(&mut exif);
+    jpeg.splice(2..2, segment);
+    jpeg
+}
+
+#[test]
+fn photos_are_turned_upright() {
+    let loaded = SquaredGrayscaleImage::read_from_bytes(&rotated_jpeg()).unwrap();
+
+    // The black half is at the top
+    assert_eq!(loaded.get_size(), Size::squared(8));
+    assert!(loaded.pixel(4, 1) < 20 && loaded.pixel(4, 6) > 235);
+}
+
+#[test]
+fn exif_orientation_is_ignored_if_requested() {
+    let options = |respect_exif| LoadOptions {
+        sizing: Sizing::PadToNextPow2(PadMode::Constant(0)),
+        respect_exif,
+        ..Default::default()
+    };
+
+    let upright = SquaredGrayscaleImage::read_from_bytes_with(&rotated_jpeg(), options(true)).unwrap();
+    let stored = SquaredGrayscaleImage::read_from_bytes_with(&rotated_jpeg(), options(false)).unwrap();
+
+    assert_eq!(upright.original_size, Size::new(8, 16));
+    assert_eq!(stored.original_size, Size::new(16, 8));
+    assert!(stored.image.pixel(2, 4) < 20 && stored.image.pixel(13, 4) > 235);
+}
+
+fn load_capped(width: u32, height: u32, sizing: Sizing, max_dimension: u32) -> Result<LoadedImage, LoadError> {
+    let image = DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| Luma([((x + y) % 256) as u8])));
+    let options = LoadOptions {
+        sizing,
+        max_dimension: Some(max_dimension),
+        filter: FilterType::Nearest,
+        ..Default::default()
+    };
+    SquaredGrayscaleImage::from_dynamic_image_with(image, options)
+}
+
+#[test]
+fn large_images_are_downscaled_to_the_max_dimension() {
+    for sizing in [Sizing::DownscaleToPreviousPow2, Sizing::CenterCropToPreviousPow2] {
+        let loaded = load_capped(3000, 2000, sizing, 256).unwrap();
+
+        assert_eq!(loaded.image.get_size(), Size::squared(256), "{:?}", sizing);
+        assert_eq!(loaded.original_size, Size::new(3000, 2000));
+        assert_eq!(loaded.content_size, Size::squared(256));
+    }
+}
+
+#[test]
+fn large_padded_images_keep_their_aspect_ratio() {
+    let loaded = load_capped(3000, 2000, Sizing::PadToNextPow2(PadMode::Edge), 512).unwrap();
+
+    assert_eq!(loaded.image.get_size(), Size::squared(512));
+    assert_eq!(loaded.content_size, Size::new(512, 341));
+    assert_eq!(loaded.crop_to_original(loaded.image.clone()).get_size(), Size::new(512, 341));
+}
+
+#[test]
+fn images_within_the_max_dimension_are_not_downscaled_further() {
+    assert_eq!(load_capped(100, 60, Sizing::DownscaleToPreviousPow2, 2048).unwrap().image.get_size(), Size::squared(32));
+    assert_eq!(load_capped(100, 60, Sizing::PadToNextPow2(PadMode::Edge), 128).unwrap().image.get_size(), Size::squared(128));
+    assert_eq!(load_capped(100, 60, Sizing::PadToNextPow2(PadMode::Edge), 128).unwrap().content_size, Size::new(100, 60));
+}
+
+#[test]
+fn max_dimensions_which_are_no_power_of_two_are_rejected() {
+    for max_dimension in [0, 1000] {
+        let result = load_capped(16, 16, Sizing::default(), max_dimension);
+        assert!(matches!(result, Err(LoadError::InvalidMaxDimension(dimension)) if dimension == max_dimension));
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use fractal_image::image::{IntoOwnedImage, OwnedImage, Size};
+use fractal_image::preprocessing::{format_from_extension, SafeableImage, SaveError, SquaredGrayscaleImage};
+use image::ImageFormat;
+use common::temp_path;
+
+fn gradient() -> OwnedImage {
+    let size = Size::squared(16);
+    OwnedImage::from_pixels(size, (0..size.area()).map(|i| i as u8).collect()).unwrap()
+}
+
+/// A path in a directory which does not exist
+fn invalid_path(file_name: &str) -> PathBuf {
+    temp_path("missing-directory").join(file_name)
+}
+
+fn saved_format(path: &Path) -> ImageFormat {
+    let bytes = std::fs::read(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    image::guess_format(&bytes).unwrap()
+}
+
+#[test]
+fn formats_are_inferred_from_extensions() {
+    assert_eq!(format_from_extension(Path::new("image.png")).unwrap(), ImageFormat::Png);
+    assert_eq!(format_from_extension(Path::new("image.jpg")).unwrap(), ImageFormat::Jpeg);
+    assert_eq!(format_from_extension(Path::new("image.JPEG")).unwrap(), ImageFormat::Jpeg);
+    assert_eq!(format_from_extension(Path::new("dir/image.bmp")).unwrap(), ImageFormat::Bmp);
+    assert_eq!(format_from_extension(Path::new("image.tif")).unwrap(), ImageFormat::Tiff);
+}
+
+#[test]
+fn unknown_extensions_return_error() {
+    for path in ["image.qfic", "image"] {
+        assert!(matches!(format_from_extension(Path::new(path)), Err(SaveError::UnknownExtension(_))));
+    }
+
+    let path = temp_path("image-saving.unknown");
+    assert!(matches!(gradient().save_image_auto(&path), Err(SaveError::UnknownExtension(_))));
+    assert!(!path.exists());
+}
+
+#[test]
+fn images_are_saved_in_the_format_of_their_extension() {
+    for (file_name, format) in [
+        ("image-saving.png", ImageFormat::Png),
+        ("image-saving.bmp", ImageFormat::Bmp),
+        ("image-saving.jpg", ImageFormat::Jpeg),
+    ] {
+        let path = temp_path(file_name);
+        gradient().save_image_auto(&path).unwrap();
+        assert_eq!(saved_format(&path), format, "Saved {}", file_name);
+    }
+}
+
+#[test]
+fn bmp_images_are_saved_losslessly() {
+    let path = temp_path("image-saving-lossless.bmp");
+
+    gradient().save_image_as_bmp(&path).unwrap();
+    let read = SquaredGrayscaleImage::try_read_from(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.unwrap().to_owned_image().as_slice(), gradient().as_slice());
+}
+
+#[test]
+fn jpeg_quality_is_applied() {
+    let size_with_quality = |quality| {
+        let path = temp_path(&format!("image-saving-{}.jpg", quality));
+        OwnedImage::random_with_seed(Size::squared(64), 3).save_image_as_jpeg(&path, quality).unwrap();
+        let size = std::fs::metadata(&path).unwrap().len();
+        assert_eq!(saved_format(&path), ImageFormat::Jpeg);
+        size
+    };
+
+    assert!(size_with_quality(10) < size_with_quality(95));
+}
+
+#[test]
+fn saving_to_an_invalid_path_returns_error() {
+    let image = gradient();
+
+    assert!(matches!(image.save_image_as_png(invalid_path("image.png")), Err(SaveError::Image(_))));
+    assert!(matches!(image.save_image_as_bmp(invalid_path("image.bmp")), Err(SaveError::Image(_))));
+    assert!(matches!(image.save_image_auto(invalid_path("image.png")), Err(SaveError::Image(_))));
+    assert!(matches!(image.save_image_as_jpeg(invalid_path("image.jpg"), 90), Err(SaveError::IO(_))));
+}
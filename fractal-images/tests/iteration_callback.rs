@@ -0,0 +1,43 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use fractal_image::decompress::{decompress, Options};
+use fractal_image::image::OwnedImage;
+use common::compressed_noise;
+
+#[test]
+fn callback_is_called_with_each_iteration() {
+    let seen = Arc::new(Mutex::new(vec![]));
+    let seen_clone = seen.clone();
+    let options = Options {
+        iterations: 4,
+        on_iteration: Some(Box::new(move |index, image: &OwnedImage| {
+            seen_clone.lock().unwrap().push((index, image.clone()));
+        })),
+        ..Default::default()
+    };
+
+    let decompressed = decompress(compressed_noise(23, 0), options).unwrap();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.iter().map(|(index, _)| *index).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(seen.last().unwrap().1, decompressed.image);
+    assert!(decompressed.iterations.is_none());
+}
+
+#[test]
+fn callback_sees_the_same_images_as_kept_iterations() {
+    let seen = Arc::new(Mutex::new(vec![]));
+    let seen_clone = seen.clone();
+    let options = Options {
+        iterations: 3,
+        keep_each_iteration: true,
+        on_iteration: Some(Box::new(move |_, image: &OwnedImage| seen_clone.lock().unwrap().push(image.clone()))),
+        ..Default::default()
+    };
+
+    let decompressed = decompress(compressed_noise(23, 0), options).unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), decompressed.iterations.unwrap());
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use fractal_image::decompress::{decompress_with_reference, DecompressionError, Options};
+use fractal_image::image::{IntoOwnedImage, OwnedImage, PowerOfTwo, Size, Square};
+use fractal_image::model::Compressed;
+use fractal_image::compress;
+use common::waves_64x64;
+
+fn compress(image: PowerOfTwo<Square<OwnedImage>>) -> Compressed {
+    compress::quadtree::Compressor::new(image).compress().unwrap()
+}
+
+#[test]
+fn psnr_does_not_decrease_with_iterations() {
+    let original = waves_64x64();
+    let compressed = compress(original.clone());
+    let original = original.to_owned_image();
+
+    let decompressed = decompress_with_reference(compressed, Options::default(), &original).unwrap();
+
+    let metrics = decompressed.iteration_metrics;
+    assert_eq!(metrics.len(), 10);
+    assert_eq!(metrics[0].iteration, 1);
+    for pair in metrics.windows(2) {
+        // Allows for rounding noise once the decompression has converged
+        assert!(pair[1].psnr >= pair[0].psnr - 0.05, "PSNR decreased: {:?}", metrics);
+        assert!(pair[1].iteration == pair[0].iteration + 1);
+    }
+}
+
+#[test]
+fn metrics_are_not_computed_without_reference() {
+    let decompressed = fractal_image::decompress::decompress(compress(waves_64x64()), Options::default()).unwrap();
+    assert!(decompressed.iteration_metrics.is_empty());
+}
+
+#[test]
+fn reference_of_wrong_size_returns_error() {
+    let reference = OwnedImage::random(Size::squared(32));
+
+    let result = decompress_with_reference(compress(waves_64x64()), Options::default(), &reference);
+
+    assert_eq!(
+        result.err(),
+        Some(DecompressionError::ReferenceSizeMismatch { expected: Size::squared(64), actual: Size::squared(32) })
+    );
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use fractal_image::assert_images_equal;
+use fractal_image::compress;
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::decompress::{decompress_lossless, DecompressionError};
+use fractal_image::image::{OwnedImage, Size};
+use common::random_noise;
+
+#[test]
+fn lossless_roundtrip_is_bit_exact() {
+    let compressed = compress::quadtree::Compressor::new(random_noise(32))
+        .compress_lossless()
+        .unwrap();
+
+    let decompressed = decompress_lossless(compressed).unwrap();
+
+    let original = OwnedImage::random(Size::squared(32));
+    assert_images_equal!(decompressed, original);
+}
+
+#[test]
+fn lossless_roundtrip_with_overlap_is_bit_exact() {
+    let compressed = compress::quadtree::Compressor::new(random_noise(32))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(40.0))
+        .with_overlap(1)
+        .compress_lossless()
+        .unwrap();
+
+    let decompressed = decompress_lossless(compressed).unwrap();
+
+    assert_images_equal!(decompressed, random_noise(32));
+}
+
+#[test]
+fn residual_of_wrong_size_returns_error() {
+    let mut compressed = compress::quadtree::Compressor::new(random_noise(16))
+        .compress_lossless()
+        .unwrap();
+    compressed.residual.pop();
+
+    let result = decompress_lossless(compressed);
+
+    assert_eq!(result.err(), Some(DecompressionError::ResidualSizeMismatch { expected: 256, actual: 255 }));
+}
+
+#[test]
+#[cfg(feature = "persist-as-binary-v1")]
+fn persisted_lossless_roundtrip_is_bit_exact() {
+    for compress_residual in [false, true] {
+        let compressed = compress::quadtree::Compressor::new(random_noise(16))
+            .compress_lossless()
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("fractal-image-lossless-{}.qfic", compress_residual));
+
+        compressed.persist_as_binary_v1(&path, compress_residual).unwrap();
+        let read = fractal_image::model::LosslessCompressed::read_from_binary_v1(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_images_equal!(decompress_lossless(read).unwrap(), OwnedImage::random(Size::squared(16)));
+    }
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use fractal_image::compress;
+use fractal_image::compress::quadtree::ErrorThreshold;
+use common::random_noise;
+
+#[test]
+fn every_candidate_is_counted() {
+    // No mapping is acceptable, hence every candidate of every range block is compared.
+    // 2x2 range blocks: 4 blocks * 1 domain block * 4 rotations
+    // 1x1 range blocks: 16 blocks * 4 domain blocks * 4 rotations
+    let (_, stats) = compress::quadtree::Compressor::new(random_noise(4))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(-1.0))
+        .compress_with_stats()
+        .unwrap();
+
+    assert_eq!(stats.mappings_computed, 4 * 4 + 16 * 4 * 4);
+}
+
+#[test]
+fn every_candidate_without_rotations_is_counted() {
+    let (_, stats) = compress::quadtree::Compressor::new(random_noise(4))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(-1.0))
+        .with_rotations(false)
+        .compress_with_stats()
+        .unwrap();
+
+    assert_eq!(stats.mappings_computed, 4 + 16 * 4);
+}
+
+#[test]
+fn progress_reports_the_mappings_computed_so_far() {
+    let reported = Arc::new(AtomicU64::new(0));
+    let reported_clone = reported.clone();
+
+    let (_, stats) = compress::quadtree::Compressor::new(random_noise(32))
+        .with_progress_reporter(move |progress| {
+            reported_clone.fetch_max(progress.mappings_computed, Ordering::Relaxed);
+        })
+        .compress_with_stats()
+        .unwrap();
+
+    let reported = reported.load(Ordering::Relaxed);
+    assert!(reported > 0);
+    assert!(reported <= stats.mappings_computed);
+}
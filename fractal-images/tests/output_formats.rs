@@ -0,0 +1,42 @@
+use std::io::Cursor;
+
+use fractal_image::compress::quadtree::Compressor;
+use fractal_image::decompress;
+use fractal_image::image::{OwnedImage, PowerOfTwo, Size, Square};
+use image::ImageFormat;
+
+fn decompressed_16x16() -> decompress::Decompressed {
+    let image = PowerOfTwo::new(Square::new(OwnedImage::random(Size::squared(16))).unwrap()).unwrap();
+    let compressed = Compressor::new(image).compress().unwrap();
+    decompress::decompress(compressed, Default::default()).unwrap()
+}
+
+#[test]
+fn dimensions_survive_a_jpeg_roundtrip() {
+    let decompressed = decompressed_16x16();
+
+    let mut bytes = Cursor::new(vec![]);
+    decompressed.to_dynamic_image().write_to(&mut bytes, ImageFormat::Jpeg).unwrap();
+    let loaded = image::load_from_memory_with_format(bytes.get_ref(), ImageFormat::Jpeg).unwrap();
+
+    assert_eq!((loaded.width(), loaded.height()), (16, 16));
+}
+
+#[test]
+fn saves_in_the_given_format() {
+    let decompressed = decompressed_16x16();
+    let path = std::env::temp_dir().join("fractal-image-output-format.bmp");
+
+    decompressed.save(&path, ImageFormat::Bmp).unwrap();
+    let loaded = image::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!((loaded.width(), loaded.height()), (16, 16));
+}
+
+#[test]
+fn saving_to_a_missing_directory_fails() {
+    let path = std::env::temp_dir().join("fractal-image-missing-directory").join("decompressed.png");
+
+    assert!(decompressed_16x16().save(path, ImageFormat::Png).is_err());
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use fractal_image::{compress, decompress};
+use common::{boundary_discontinuity, waves_64x64};
+
+fn compress_and_measure(overlap: u32) -> f64 {
+    let compressed = compress::quadtree::Compressor::new(waves_64x64())
+        .with_overlap(overlap)
+        .compress()
+        .unwrap();
+
+    let options = decompress::Options { overlap, ..Default::default() };
+    let decompressed = decompress::decompress(compressed.clone(), options).unwrap().image;
+    boundary_discontinuity(&compressed, &decompressed)
+}
+
+#[test]
+fn overlap_reduces_discontinuities_at_block_boundaries() {
+    let without_overlap = compress_and_measure(0);
+    let with_overlap = compress_and_measure(2);
+
+    assert!(
+        with_overlap < without_overlap,
+        "Expected a discontinuity below {}, was {}", without_overlap, with_overlap
+    );
+}
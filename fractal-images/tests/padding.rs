@@ -0,0 +1,20 @@
+use fractal_image::image::{Image, OwnedImage, PadMode, Size};
+use fractal_image::preprocessing::{SafeableImage, SquaredGrayscaleImage};
+
+#[test]
+fn read_image_is_padded_instead_of_downscaled() {
+    let original = OwnedImage::from_pixels(Size::new(5, 3), (0..15).map(|i| 10 * i).collect()).unwrap();
+    let path = std::env::temp_dir().join("fractal-image-padding.png");
+    original.save_image_as_png(&path).unwrap();
+
+    let padded = SquaredGrayscaleImage::read_padded_from(&path, PadMode::Constant(0));
+
+    assert_eq!(padded.get_size(), Size::squared(8));
+    assert_eq!(padded.as_inner().as_inner().original_size(), Size::new(5, 3));
+    for y in 0..3 {
+        for x in 0..5 {
+            assert_eq!(padded.pixel(x, y), original.pixel(x, y));
+        }
+    }
+    assert_eq!(padded.pixel(7, 7), 0);
+}
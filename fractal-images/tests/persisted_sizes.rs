@@ -0,0 +1,47 @@
+#![cfg(feature = "persist-as-binary-v2")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use fractal_image::persistence::{binary_v1, binary_v2, PersistFormat, PersistOptions};
+use common::{compressed, temp_path};
+
+/// Persists to a temporary file, and returns the reported size and the file contents.
+fn persisted(name: &str, persist: impl FnOnce(&PathBuf) -> u64) -> (u64, Vec<u8>) {
+    let path = temp_path(name);
+    let written = persist(&path);
+    let contents = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    (written, contents)
+}
+
+#[test]
+fn binary_v1_reports_the_serialized_length() {
+    let (written, contents) = persisted("v1.qfic", |path| compressed().persist_as_binary_v1(path).unwrap());
+    assert_eq!(written, binary_v1::serialize(&compressed()).unwrap().len() as u64);
+    assert_eq!(written, contents.len() as u64);
+}
+
+#[test]
+fn binary_v2_reports_the_serialized_length() {
+    let (written, contents) = persisted("v2.qfic", |path| compressed().persist_as_binary_v2(path).unwrap());
+    assert_eq!(written, binary_v2::serialize(&compressed()).unwrap().len() as u64);
+    assert_eq!(written, contents.len() as u64);
+}
+
+#[cfg(feature = "persist-as-json")]
+#[test]
+fn json_reports_the_serialized_length() {
+    let (written, contents) = persisted("compressed.json", |path| compressed().persist_as_json(path).unwrap());
+    assert_eq!(written, contents.len() as u64);
+    assert!(written > 0);
+}
+
+#[test]
+fn persisting_without_sync_writes_the_same_file() {
+    let options = PersistOptions { sync: false, ..PersistOptions::new(PersistFormat::BinaryV2) };
+    let (written, contents) = persisted("unsynced.qfic", |path| compressed().persist_with_options(path, options).unwrap());
+    assert_eq!(contents, binary_v2::serialize(&compressed()).unwrap());
+    assert_eq!(written, contents.len() as u64);
+}
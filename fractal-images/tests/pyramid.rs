@@ -0,0 +1,55 @@
+#![cfg(feature = "generators")]
+
+mod common;
+
+use fractal_image::image::{Image, Size};
+use fractal_image::model::Compressed;
+use fractal_image::{compress, decompress, metrics};
+use common::soft_circle;
+
+fn compressed_circle() -> Compressed {
+    compress::quadtree::Compressor::new(soft_circle()).compress().unwrap()
+}
+
+#[test]
+fn pyramid_decoding_reaches_a_similar_quality() {
+    let compressed = compressed_circle();
+
+    let decompress_with = |pyramid| {
+        let options = decompress::Options { pyramid, ..Default::default() };
+        decompress::decompress(compressed.clone(), options).unwrap()
+    };
+    let full = decompress_with(false);
+    let pyramid = decompress_with(true);
+
+    assert_eq!(pyramid.image.get_size(), Size::squared(64));
+    assert_eq!(pyramid.executed_iterations, 10);
+
+    let full_psnr = metrics::psnr(&soft_circle(), &full.image).unwrap();
+    let pyramid_psnr = metrics::psnr(&soft_circle(), &pyramid.image).unwrap();
+    assert!(
+        pyramid_psnr > full_psnr - 1.0,
+        "Expected a PSNR of about {} dB, was {} dB", full_psnr, pyramid_psnr
+    );
+}
+
+#[test]
+fn reduced_iterations_are_reported_at_full_resolution() {
+    let options = decompress::Options { pyramid: true, keep_each_iteration: true, ..Default::default() };
+    let decompressed = decompress::decompress(compressed_circle(), options).unwrap();
+
+    let iterations = decompressed.iterations.unwrap();
+    assert_eq!(iterations.len(), 11);
+    assert!(iterations.iter().all(|image| image.get_size() == Size::squared(64)));
+}
+
+#[test]
+fn single_iteration_runs_at_full_resolution() {
+    let compressed = compressed_circle();
+    let decompress_with = |pyramid| {
+        let options = decompress::Options { iterations: 1, pyramid, ..Default::default() };
+        decompress::decompress(compressed.clone(), options).unwrap().image
+    };
+
+    assert_eq!(decompress_with(true), decompress_with(false));
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use fractal_image::compress;
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::model::QuadtreeNode;
+use common::random_noise;
+
+#[test]
+fn quadtree_leaves_correspond_to_transformations() {
+    let compressed = compress::quadtree::Compressor::new(random_noise(64))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .compress()
+        .unwrap();
+
+    let tree = compressed.as_quadtree().unwrap();
+    let mut leaf_transformations = tree
+        .leaves()
+        .into_iter()
+        .map(|leaf| match leaf {
+            QuadtreeNode::Leaf { block, transformation } => {
+                assert_eq!(*block, compressed.transformations[*transformation].range);
+                *transformation
+            }
+            _ => panic!("Expected a leaf, was {:?}", leaf),
+        })
+        .collect::<Vec<_>>();
+    leaf_transformations.sort();
+
+    assert_eq!(leaf_transformations, (0..compressed.transformations.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn quadtree_covers_the_image_exactly() {
+    let compressed = compress::quadtree::Compressor::new(random_noise(64))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .compress()
+        .unwrap();
+
+    let tree = compressed.as_quadtree().unwrap();
+    assert_eq!(tree.block().block_size, 64);
+
+    let covered_area: u32 = tree.leaves().iter().map(|leaf| leaf.block().block_size.pow(2)).sum();
+    assert_eq!(covered_area, 64 * 64);
+}
@@ -0,0 +1,42 @@
+use fractal_image::coords;
+use fractal_image::decompress::{decompress, Options};
+use fractal_image::image::{Coords, Image, Size};
+use fractal_image::model::{Block, Compressed, Rotation, Transformation};
+
+/// A transformation which maps every pixel of `range` to `brightness`.
+fn flat(range: Block, brightness: i16) -> Transformation {
+    Transformation {
+        range,
+        domain: Block { block_size: 2 * range.block_size, origin: coords!(x=0, y=0) },
+        rotation: Rotation::By0,
+        brightness,
+        saturation: 0.0,
+    }
+}
+
+#[test]
+fn rectangular_image_is_decompressed() {
+    let compressed = Compressed {
+        size: Size::new(10, 6),
+        transformations: vec![
+            flat(Block { block_size: 2, origin: coords!(x=8, y=1) }, 200),
+            flat(Block { block_size: 2, origin: coords!(x=3, y=4) }, 50),
+            flat(Block { block_size: 1, origin: coords!(x=9, y=5) }, 7),
+        ],
+    };
+
+    let decompressed = decompress(compressed, Options { iterations: 1, ..Default::default() }).unwrap().image;
+
+    assert_eq!(decompressed.get_size(), Size::new(10, 6));
+    for y in 0..6 {
+        for x in 0..10 {
+            let expected = match (x, y) {
+                (8..=9, 1..=2) => 200,
+                (3..=4, 4..=5) => 50,
+                (9, 5) => 7,
+                _ => 128,
+            };
+            assert_eq!(decompressed.pixel(x, y), expected, "Pixel ({}, {}) differs", x, y);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use fractal_image::assert_images_equal;
+use fractal_image::compress;
+use fractal_image::coords;
+use fractal_image::decompress::{decompress, decompress_region, DecompressionError, Options};
+use fractal_image::image::{Coords, IntoCropped, Size};
+use fractal_image::model::Compressed;
+use common::waves_64x64;
+
+fn assert_region_equals_crop(compressed: Compressed, options: impl Fn() -> Options, origin: Coords, size: Size) {
+    let full = decompress(compressed.clone(), options()).unwrap().image;
+    let region = decompress_region(compressed, options(), (origin, size)).unwrap();
+
+    assert_images_equal!(region, (&full).crop(origin, size).unwrap());
+}
+
+#[test]
+fn region_equals_crop_of_full_decompression() {
+    let compressed = compress::quadtree::Compressor::new(waves_64x64()).compress().unwrap();
+    assert_region_equals_crop(compressed, Options::default, coords!(x=10, y=20), Size::new(13, 7));
+}
+
+#[test]
+fn region_with_overlap_equals_crop_of_full_decompression() {
+    let compressed = compress::quadtree::Compressor::new(waves_64x64())
+        .with_overlap(2)
+        .compress()
+        .unwrap();
+    let options = || Options { overlap: 2, ..Default::default() };
+    assert_region_equals_crop(compressed, options, coords!(x=40, y=0), Size::new(24, 16));
+}
+
+#[test]
+fn region_exceeding_image_returns_error() {
+    let compressed = compress::quadtree::Compressor::new(waves_64x64()).compress().unwrap();
+
+    let result = decompress_region(compressed, Options::default(), (coords!(x=60, y=0), Size::squared(8)));
+
+    assert_eq!(
+        result.err(),
+        Some(DecompressionError::RegionOutOfBounds { origin: coords!(x=60, y=0), size: Size::squared(8) })
+    );
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use fractal_image::compress;
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::model::Rotation;
+use common::random_noise_with_seed;
+
+#[test]
+fn without_rotations_all_transformations_are_unrotated() {
+    let compressed = compress::quadtree::Compressor::new(random_noise_with_seed(64, 7))
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .with_rotations(false)
+        .compress()
+        .unwrap();
+
+    assert!(!compressed.transformations.is_empty());
+    for transformation in compressed.transformations {
+        assert_eq!(transformation.rotation, Rotation::By0);
+    }
+}
+
+#[test]
+fn without_rotations_fewer_mappings_are_computed() {
+    let compress = |rotations: bool| {
+        compress::quadtree::Compressor::new(random_noise_with_seed(16, 7))
+            // Nothing is accepted, such that all candidates are evaluated
+            .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(-1.0))
+            .with_rotations(rotations)
+            .compress_with_stats()
+            .unwrap()
+            .1
+            .mappings_computed
+    };
+
+    assert_eq!(compress(true), 4 * compress(false));
+}
@@ -34,7 +34,7 @@ fn test_error(image: OwnedImage,
         .with_error_threshold(error_threshold);
     let compressed = compressor.compress().unwrap();
 
-    let decompressed = decompress::decompress(compressed, decompress::Options::default());
+    let decompressed = decompress::decompress(compressed, decompress::Options::default()).unwrap();
     let decompressed_image = decompressed.image;
 
     let mse = metrics::mse(&image, &decompressed_image).unwrap();
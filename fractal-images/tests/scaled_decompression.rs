@@ -0,0 +1,45 @@
+mod common;
+
+use image::imageops::FilterType;
+
+use fractal_image::decompress::{decompress_scaled, DecompressionError, Options};
+use fractal_image::image::{Image, IntoOwnedImage, OwnedImage, Size};
+use fractal_image::preprocessing::AsDynamicImage;
+use fractal_image::{compress, metrics};
+use common::waves_64x64;
+
+fn bicubic_upscaled(image: &OwnedImage, size: u32) -> OwnedImage {
+    let upscaled = image.as_dynamic_image().resize_exact(size, size, FilterType::CatmullRom).to_luma8();
+    OwnedImage::from_pixels(Size::squared(size), upscaled.into_raw()).unwrap()
+}
+
+#[test]
+fn decompression_at_scale_4_resembles_upscaled_original() {
+    let original = waves_64x64();
+    let compressed = compress::quadtree::Compressor::new(original.clone()).compress().unwrap();
+    let original = original.to_owned_image();
+
+    let decompressed = decompress_scaled(compressed, Options::default(), 4).unwrap().image;
+
+    assert_eq!(decompressed.get_size(), Size::squared(256));
+    let psnr = metrics::psnr(&bicubic_upscaled(&original, 256), &decompressed).unwrap();
+    assert!(psnr > 20.0, "Expected a PSNR above 20 dB, was {} dB", psnr);
+}
+
+#[test]
+fn scale_needs_to_be_a_power_of_two() {
+    let compressed = compress::quadtree::Compressor::new(waves_64x64()).compress().unwrap();
+
+    let result = decompress_scaled(compressed, Options::default(), 3);
+
+    assert_eq!(result.err(), Some(DecompressionError::InvalidScale(3)));
+}
+
+#[test]
+fn scale_overflowing_the_image_size_is_invalid() {
+    let compressed = compress::quadtree::Compressor::new(waves_64x64()).compress().unwrap();
+
+    let result = decompress_scaled(compressed, Options::default(), 1 << 31);
+
+    assert_eq!(result.err(), Some(DecompressionError::InvalidScale(1 << 31)));
+}
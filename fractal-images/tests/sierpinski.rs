@@ -0,0 +1,27 @@
+#![cfg(feature = "generators")]
+
+use fractal_image::compress::quadtree::{Compressor, ErrorThreshold};
+use fractal_image::image::gen::GenSierpinski;
+use fractal_image::image::{OwnedImage, PowerOfTwo, Size, Square};
+
+const ERROR_THRESHOLD: ErrorThreshold = ErrorThreshold::AnyBlockBelowRms(10.0);
+
+#[test]
+fn self_similar_image_compresses_far_better_than_noise() {
+    let sierpinski = Compressor::new(GenSierpinski::new(64, 3))
+        .with_error_threshold(ERROR_THRESHOLD)
+        .compress()
+        .unwrap();
+
+    let noise = PowerOfTwo::new(Square::new(OwnedImage::random_with_seed(Size::squared(64), 42)).unwrap()).unwrap();
+    let noise = Compressor::new(noise)
+        .with_error_threshold(ERROR_THRESHOLD)
+        .compress()
+        .unwrap();
+
+    let (sierpinski_size, noise_size) = (sierpinski.estimated_size_binary_v1().unwrap(), noise.estimated_size_binary_v1().unwrap());
+    assert!(
+        10 * sierpinski_size < noise_size,
+        "Expected the triangle ({} bytes) to be far smaller than noise ({} bytes)", sierpinski_size, noise_size
+    );
+}
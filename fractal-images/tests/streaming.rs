@@ -0,0 +1,62 @@
+#![cfg(feature = "persist-as-binary-v2")]
+
+mod common;
+
+use std::collections::VecDeque;
+use std::io::{Cursor, Read};
+
+use fractal_image::model::Compressed;
+use fractal_image::persistence::binary_v2;
+use common::{compressed, temp_path};
+
+/// Hands out at most three bytes per read, like a slow connection.
+struct Trickle<R>(R);
+
+impl<R: Read> Read for Trickle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = buf.len().min(3);
+        self.0.read(&mut buf[..length])
+    }
+}
+
+fn assert_same(read: Compressed) {
+    assert_eq!(read.size, compressed().size);
+    assert_eq!(read.transformations, compressed().transformations);
+}
+
+#[test]
+fn roundtrip_through_cursor() {
+    let mut cursor = Cursor::new(Vec::new());
+    let written = compressed().persist_to_writer(&mut cursor).unwrap();
+
+    assert_eq!(written, cursor.get_ref().len() as u64);
+    cursor.set_position(0);
+    assert_same(Compressed::read_from_reader(cursor).unwrap());
+}
+
+#[test]
+fn roundtrip_through_pipe() {
+    let mut pipe = VecDeque::new();
+    let written = compressed().persist_to_writer(&mut pipe).unwrap();
+
+    assert_eq!(written, pipe.len() as u64);
+    assert_same(Compressed::read_from_reader(Trickle(pipe)).unwrap());
+}
+
+#[test]
+fn written_bytes_equal_serialized_bytes() {
+    let mut written = Vec::new();
+    compressed().persist_to_writer(&mut written).unwrap();
+
+    assert_eq!(written, binary_v2::serialize(&compressed()).unwrap());
+}
+
+#[test]
+fn persisted_file_size_equals_written_bytes() {
+    let path = temp_path("streaming.qfic");
+    let file_size = compressed().persist_as_binary_v2(&path).unwrap();
+    let on_disk = std::fs::metadata(&path).unwrap().len();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(file_size, on_disk);
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "generators")]
+
+use fractal_image::compress::quadtree::{Compressor, ErrorThreshold};
+use fractal_image::image::gen::{GenStripes, Orientation};
+use fractal_image::image::{FnImage, Image, PowerOfTwo, Size, Square};
+use fractal_image::model::{Compressed, Rotation};
+
+/// Horizontal stripes with a period of 8 pixels on the left half and vertical stripes with a
+/// period of 4 pixels on the right half. Downscaled by two and rotated by 90°, a quadrant on
+/// the left equals any 16x16 block on the right.
+fn stripes() -> PowerOfTwo<Square<impl Image>> {
+    let horizontal = GenStripes::new(64, 8, Orientation::Horizontal);
+    let vertical = GenStripes::new(64, 4, Orientation::Vertical);
+    let image = FnImage::new(Size::squared(64), move |x, y| {
+        if x < 32 { horizontal.pixel(x, y) } else { vertical.pixel(x, y) }
+    });
+    PowerOfTwo::new(Square::new(image).unwrap()).unwrap()
+}
+
+fn compress(rotations: bool) -> Compressed {
+    Compressor::new(stripes())
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(10.0))
+        .with_rotations(rotations)
+        .compress()
+        .unwrap()
+}
+
+/// The transformations of 16x16 range blocks on the right half of the image.
+fn right_blocks_of_size_16(compressed: &Compressed) -> Vec<Rotation> {
+    compressed.transformations
+        .iter()
+        .filter(|transformation| transformation.range.origin.x >= 32 && transformation.range.block_size == 16)
+        .map(|transformation| transformation.rotation)
+        .collect()
+}
+
+#[test]
+fn with_rotations_vertical_stripes_map_onto_horizontal_ones() {
+    let rotations = right_blocks_of_size_16(&compress(true));
+
+    assert_eq!(rotations.len(), 8);
+    assert!(
+        rotations.iter().all(|rotation| matches!(rotation, Rotation::By90 | Rotation::By270)),
+        "Expected only rotations by 90° or 270°, were {:?}", rotations
+    );
+}
+
+#[test]
+fn without_rotations_vertical_stripes_are_split() {
+    let compressed = compress(false);
+
+    assert!(right_blocks_of_size_16(&compressed).is_empty());
+    assert!(compressed.transformations
+        .iter()
+        .filter(|transformation| transformation.range.origin.x >= 32)
+        .all(|transformation| transformation.range.block_size < 16));
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use fractal_image::compress;
+use common::waves_64x64;
+
+#[test]
+fn compression_reaches_target_psnr() {
+    let target = 28.0;
+    let (_, stats) = compress::quadtree::Compressor::new(waves_64x64())
+        .with_target_psnr(target)
+        .compress_with_stats()
+        .unwrap();
+
+    let measured = stats.psnr.expect("PSNR should be measured when aiming for a target PSNR");
+    assert!((measured - target).abs() <= 1.0, "Expected a PSNR of about {} dB, was {} dB", target, measured);
+}
+
+#[test]
+fn psnr_is_not_measured_without_target() {
+    let (_, stats) = compress::quadtree::Compressor::new(waves_64x64())
+        .compress_with_stats()
+        .unwrap();
+
+    assert_eq!(stats.psnr, None);
+}
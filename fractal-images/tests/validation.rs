@@ -0,0 +1,82 @@
+use fractal_image::coords;
+use fractal_image::decompress::{decompress, DecompressionError, Options};
+use fractal_image::image::{Coords, Size};
+use fractal_image::model::{Block, Compressed, Rotation, Transformation, ValidationError};
+
+/// A compression of a 16x16 image whose only range block lies at `(x, y)`.
+fn crafted(x: u32, y: u32) -> Compressed {
+    Compressed {
+        size: Size::squared(16),
+        transformations: vec![Transformation {
+            range: Block { block_size: 4, origin: coords!(x=x, y=y) },
+            domain: Block { block_size: 8, origin: coords!(x=0, y=0) },
+            rotation: Rotation::By90,
+            brightness: 10,
+            saturation: 0.5,
+        }],
+    }
+}
+
+#[test]
+fn valid_compression_is_decompressed() {
+    assert!(decompress(crafted(12, 12), Options::default()).is_ok());
+}
+
+#[test]
+fn out_of_bounds_range_block_is_not_decompressed() {
+    let result = decompress(crafted(14, 0), Options::default());
+
+    assert!(matches!(
+        result.err(),
+        Some(DecompressionError::Invalid(ValidationError::BlockOutOfBounds { transformation: 0, .. }))
+    ));
+}
+
+#[test]
+fn out_of_bounds_domain_block_is_not_decompressed() {
+    let mut compressed = crafted(0, 0);
+    compressed.transformations[0].domain.origin = coords!(x=0, y=u32::MAX);
+
+    let result = decompress(compressed, Options::default());
+
+    assert!(matches!(result.err(), Some(DecompressionError::Invalid(ValidationError::BlockOutOfBounds { .. }))));
+}
+
+#[test]
+#[cfg(feature = "persist-as-binary-v1")]
+fn out_of_bounds_block_is_rejected_when_reading() {
+    use fractal_image::persistence::binary_v1::DeserializationError;
+    use fractal_image::persistence::PersistenceError;
+
+    let path = std::env::temp_dir().join("fractal-image-out-of-bounds.qfic");
+    crafted(100, 100).persist_as_binary_v1(&path).unwrap();
+
+    let result = Compressed::read_from_binary_v1(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        result,
+        Err(PersistenceError::BinaryV1DeserializationError(DeserializationError::InvalidTransformation {
+            index: 0,
+            reason: ValidationError::BlockOutOfBounds { .. },
+        }))
+    ));
+}
+
+#[test]
+#[cfg(feature = "persist-as-binary-v2")]
+fn out_of_bounds_block_is_rejected_when_detecting_the_format() {
+    use fractal_image::persistence::binary_v2::DeserializationError;
+    use fractal_image::persistence::PersistenceError;
+
+    let path = std::env::temp_dir().join("fractal-image-out-of-bounds-detected.qfic");
+    crafted(100, 100).persist_as_binary_v2(&path).unwrap();
+
+    let result = Compressed::read_from_path(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        result,
+        Err(PersistenceError::BinaryV2DeserializationError(DeserializationError::InvalidTransformation { index: 0, .. }))
+    ));
+}
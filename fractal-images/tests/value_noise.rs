@@ -0,0 +1,37 @@
+#![cfg(feature = "generators")]
+
+use fractal_image::compress::quadtree::{Compressor, ErrorThreshold};
+use fractal_image::image::gen::GenNoise;
+use fractal_image::image::{IntoOwnedImage, OwnedImage, PowerOfTwo, Size, Square};
+use fractal_image::{decompress, metrics};
+
+const ERROR_THRESHOLD: ErrorThreshold = ErrorThreshold::AnyBlockBelowRms(10.0);
+
+/// The PSNR after a roundtrip and the amount of transformations it needed.
+fn roundtrip(image: OwnedImage) -> (f64, usize) {
+    let compressed = Compressor::new(PowerOfTwo::new(Square::new(image.clone()).unwrap()).unwrap())
+        .with_error_threshold(ERROR_THRESHOLD)
+        .compress()
+        .unwrap();
+    let transformations = compressed.transformations.len();
+    let decompressed = decompress::decompress(compressed, decompress::Options::default()).unwrap();
+    (metrics::psnr(&image, &decompressed.image).unwrap(), transformations)
+}
+
+#[test]
+fn value_noise_roundtrips_with_good_quality() {
+    let (psnr, _) = roundtrip(GenNoise::new(64, 42, 4).to_owned_image());
+
+    assert!(psnr > 25.0, "Expected a PSNR above 25 dB, was {} dB", psnr);
+}
+
+#[test]
+fn value_noise_needs_fewer_transformations_than_white_noise() {
+    let (_, smooth) = roundtrip(GenNoise::new(64, 42, 4).to_owned_image());
+    let (_, white) = roundtrip(OwnedImage::random_with_seed(Size::squared(64), 42));
+
+    assert!(
+        smooth < white / 2,
+        "Expected value noise ({} transformations) to need far fewer transformations than white noise ({})", smooth, white
+    );
+}
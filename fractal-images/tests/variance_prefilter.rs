@@ -0,0 +1,63 @@
+use fractal_image::compress::quadtree::ErrorThreshold;
+use fractal_image::image::{Image, MutableImage, OwnedImage, PowerOfTwo, Size, Square};
+use fractal_image::{compress, decompress, metrics};
+
+/// An image whose upper half is noise and whose lower half is flat.
+fn half_noise_64x64() -> PowerOfTwo<Square<OwnedImage>> {
+    let mut image = OwnedImage::random_with_seed(Size::squared(64), 3);
+    for y in 32..64 {
+        for x in 0..64 {
+            image.set_pixel(x, y, 128);
+        }
+    }
+    PowerOfTwo::new(Square::new(image).unwrap()).unwrap()
+}
+
+fn compress_and_measure(prefilter: Option<f64>) -> (f64, u64) {
+    let image = half_noise_64x64();
+    let compressor = compress::quadtree::Compressor::new(image.clone())
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0));
+    let compressor = match prefilter {
+        Some(tolerance) => compressor.with_variance_prefilter(tolerance),
+        None => compressor,
+    };
+    let (compressed, stats) = compressor.compress_with_stats().unwrap();
+
+    let decompressed = decompress::decompress(compressed, decompress::Options::default()).unwrap().image;
+    assert_eq!(decompressed.get_size(), image.get_size());
+    (metrics::psnr(&image, &decompressed).unwrap(), stats.mappings_computed)
+}
+
+#[test]
+fn prefilter_keeps_quality() {
+    let (psnr_without, _) = compress_and_measure(None);
+    let (psnr_with, _) = compress_and_measure(Some(16.0));
+
+    assert!((psnr_without - psnr_with).abs() < 1.0, "PSNR changed from {} to {}", psnr_without, psnr_with);
+}
+
+#[test]
+fn prefilter_reduces_mappings_computed() {
+    let (_, mappings_without) = compress_and_measure(None);
+    let (_, mappings_with) = compress_and_measure(Some(16.0));
+
+    assert!(
+        4 * mappings_with < 3 * mappings_without,
+        "Expected substantially fewer than {} mappings, were {}", mappings_without, mappings_with
+    );
+}
+
+#[test]
+fn prefilter_rejects_domain_blocks_with_a_much_higher_variance() {
+    let compressed = compress::quadtree::Compressor::new(half_noise_64x64())
+        .with_error_threshold(ErrorThreshold::AnyBlockBelowRms(20.0))
+        .with_variance_prefilter(16.0)
+        .compress()
+        .unwrap();
+
+    let flat_ranges = compressed.transformations.iter().filter(|t| t.range.origin.y >= 32).collect::<Vec<_>>();
+    assert!(!flat_ranges.is_empty());
+    for transformation in flat_ranges {
+        assert!(transformation.domain.origin.y >= 32, "The flat range block is mapped by noise: {:?}", transformation);
+    }
+}